@@ -1,16 +1,14 @@
-use std::{future::Future, sync::Arc};
+use std::sync::Arc;
 
-use futures::StreamExt;
-use oxidrive_pubsub::Publisher;
 use oxidrive_workers::{
-    Dispatch, Process, Worker,
+    Worker,
     queue::{Enqueue, JobQueue},
 };
 
 pub use refresh_collection::*;
 pub use refresh_collections::*;
 
-use crate::file::FileEvent;
+use crate::file::{FileEvent, jobs::start_event_listener};
 
 use super::CollectionEvent;
 
@@ -97,35 +95,3 @@ impl app::Hooks for JobsModule {
         Ok(())
     }
 }
-
-fn start_event_listener<W, E, F, Fut>(
-    ctx: app::context::Context,
-    c: &app::di::Container,
-    mut handler: F,
-) where
-    W: Process,
-    W::Job: Send,
-    E: Clone + Send + 'static,
-    F: FnMut(Dispatch<W::Job>, E) -> Fut + Send + 'static,
-    Fut: Future<Output = ()> + Send,
-{
-    let worker = c.get::<Worker<W>>();
-    let publisher = c.get::<Publisher<E>>();
-    let dispatcher = worker.dispatcher();
-    let mut subscriber = publisher.subscribe();
-
-    worker.clone().start(ctx.clone());
-
-    let run = async move {
-        while let Some(event) = subscriber.next().await {
-            handler(dispatcher.clone(), event).await
-        }
-    };
-
-    tokio::spawn(async move {
-        tokio::select! {
-            _ = run => {},
-            _ = ctx.cancelled() => {},
-        };
-    });
-}
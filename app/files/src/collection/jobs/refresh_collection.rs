@@ -7,7 +7,7 @@ use serde::{Deserialize, Serialize};
 use crate::{
     FileId,
     collection::{ByIdError, CollectionId, CollectionStore, SaveCollectionError},
-    file::{FileMetadata, SearchError},
+    file::{FileMetadata, SearchError, Sort},
 };
 
 #[derive(Clone)]
@@ -40,7 +40,12 @@ impl Process for RefreshCollectionWorker {
         loop {
             let files = self
                 .files
-                .search(collection.owner_id, collection.filter.clone(), paginate)
+                .search(
+                    collection.owner_id,
+                    collection.filter.clone(),
+                    Sort::default(),
+                    paginate,
+                )
                 .await?;
 
             if files.is_empty() || files.next.is_none() {
@@ -4,19 +4,28 @@ use std::str::FromStr;
 
 use oxidrive_accounts::account::AccountId;
 use oxidrive_domain::make_uuid_type;
+use time::{OffsetDateTime, format_description::well_known::Rfc3339};
 
 pub use content::*;
+pub use digest::*;
 pub use event::*;
+pub use sort::*;
 pub use store::*;
 
+use crate::folder::FolderPath;
 use crate::tag;
 use crate::tag::Tag;
-use crate::tag::reserved::SIZE;
+use crate::tag::reserved::{ACCESSED, CREATED, DIR, PATH, SIZE, UPDATED};
 
 mod event;
 
 mod content;
+mod digest;
+pub mod jobs;
+mod sort;
 pub(crate) mod store;
+mod text;
+pub mod version;
 
 pub type Tags = HashMap<String, Tag>;
 
@@ -27,10 +36,23 @@ pub struct File {
     pub id: FileId,
     pub owner_id: AccountId,
     pub name: String,
+    /// The virtual folder the file lives in
+    pub folder: FolderPath,
     pub content_type: String,
+    /// The size of the content as uploaded
     pub size: usize,
+    /// How much space the content takes in the storage backend, once compressed or encrypted
+    pub stored_size: usize,
+    /// How the content was written to the storage backend
+    pub encoding: Encoding,
+    /// The storage tier holding the content, once moved out of the default backend by a lifecycle rule
+    pub tier: Option<String>,
     pub tags: Tags,
     hash: Option<blake3::Hash>,
+    created_at: OffsetDateTime,
+    updated_at: OffsetDateTime,
+    accessed_at: Option<OffsetDateTime>,
+    deleted_at: Option<OffsetDateTime>,
 }
 
 impl File {
@@ -41,15 +63,24 @@ impl File {
     ) -> Self {
         let name = name.into();
         let content_type = content_type.into();
+        let now = now();
 
         let mut this = Self {
             id: FileId::new(),
             owner_id,
             name,
+            folder: FolderPath::root(),
             content_type,
             size: 0,
+            stored_size: 0,
+            encoding: Encoding::default(),
+            tier: None,
             tags: Default::default(),
             hash: None,
+            created_at: now,
+            updated_at: now,
+            accessed_at: None,
+            deleted_at: None,
         };
 
         this.tags = Self::default_tags(&this);
@@ -62,6 +93,21 @@ impl File {
         self.add_tag(Tag::full(SIZE, size.to_string()));
     }
 
+    /// The full path of the file, folder included
+    pub fn path(&self) -> String {
+        self.folder.join(&self.name)
+    }
+
+    pub fn set_folder(&mut self, folder: FolderPath) {
+        self.folder = folder;
+        self.add_tags(self.location_tags());
+    }
+
+    pub fn in_folder(mut self, folder: FolderPath) -> Self {
+        self.set_folder(folder);
+        self
+    }
+
     pub fn with_tags<I>(mut self, tags: I) -> Self
     where
         I: IntoIterator<Item = Tag>,
@@ -101,19 +147,69 @@ impl File {
         self.hash
     }
 
+    /// The digest of the current content, if it has been computed already
+    pub fn digest(&self) -> Option<Digest> {
+        self.hash.map(Digest::Blake3)
+    }
+
     pub(crate) fn set_hash(&mut self, hash: blake3::Hash) {
         self.hash = Some(hash);
     }
 
+    /// When the file was first uploaded or created
+    pub fn created_at(&self) -> OffsetDateTime {
+        self.created_at
+    }
+
+    /// When the name, folder, tags or content of the file last changed
+    pub fn updated_at(&self) -> OffsetDateTime {
+        self.updated_at
+    }
+
+    /// When the content of the file was last downloaded, if it ever was
+    pub fn accessed_at(&self) -> Option<OffsetDateTime> {
+        self.accessed_at
+    }
+
+    pub(crate) fn touch(&mut self) {
+        self.updated_at = now();
+        self.add_tag(time_tag(UPDATED, self.updated_at));
+    }
+
+    pub(crate) fn access(&mut self, at: OffsetDateTime) {
+        self.accessed_at = Some(at);
+        self.add_tag(time_tag(ACCESSED, at));
+    }
+
+    /// When the file was moved to the trash, if it was
+    pub fn deleted_at(&self) -> Option<OffsetDateTime> {
+        self.deleted_at
+    }
+
+    pub fn is_trashed(&self) -> bool {
+        self.deleted_at.is_some()
+    }
+
+    pub(crate) fn trash(&mut self) {
+        self.deleted_at = Some(OffsetDateTime::now_utc());
+    }
+
+    pub(crate) fn restore(&mut self) {
+        self.deleted_at = None;
+    }
+
     pub fn update(&mut self, data: UpdateFile) {
         if let Some(name) = data.name {
             self.name = name;
             self.add_tag(tag!("{}:{}", tag::reserved::NAME, self.name));
+            self.add_tags(self.location_tags());
         }
 
         if let Some(tags) = data.tags {
             self.set_tags(tags);
         }
+
+        self.touch();
     }
 
     pub(self) fn default_tags(file: &File) -> Tags {
@@ -122,6 +218,8 @@ impl File {
                 tag!("{}:{}", tag::reserved::NAME, file.name),
                 tag!("{}:{}", tag::reserved::CONTENT_TYPE, file.content_type),
                 tag!("{}:{}", tag::reserved::SIZE, file.size),
+                time_tag(CREATED, file.created_at),
+                time_tag(UPDATED, file.updated_at),
             ]
             .into_iter()
             .map(|tag| (tag.key.clone(), tag)),
@@ -137,8 +235,47 @@ impl File {
             tags.insert(tag.key.clone(), tag);
         }
 
+        if let Some(accessed_at) = file.accessed_at {
+            let tag = time_tag(ACCESSED, accessed_at);
+            tags.insert(tag.key.clone(), tag);
+        }
+
+        tags.extend(
+            file.location_tags()
+                .into_iter()
+                .map(|tag| (tag.key.clone(), tag)),
+        );
+
         tags
     }
+
+    fn location_tags(&self) -> [Tag; 2] {
+        [
+            Tag::full(DIR, self.folder.as_str()),
+            Tag::full(PATH, self.path()),
+        ]
+    }
+}
+
+/// Timestamps are kept to the second, so that they match their tags
+pub(crate) fn now() -> OffsetDateTime {
+    let now = OffsetDateTime::now_utc();
+    now.replace_nanosecond(0).unwrap_or(now)
+}
+
+/// Formats the instant in UTC, so that the tag values sort chronologically
+pub(crate) fn time_tag(key: &str, at: OffsetDateTime) -> Tag {
+    let at = at.to_offset(time::UtcOffset::UTC);
+    Tag::full(key, at.format(&Rfc3339).unwrap_or_else(|_| at.to_string()))
+}
+
+/// Adds a number to a file name to tell it apart from an existing one, keeping its extension,
+/// e.g. `report (1).pdf`
+pub(crate) fn numbered_name(name: &str, number: usize) -> String {
+    match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => format!("{stem} ({number}).{ext}"),
+        _ => format!("{name} ({number})"),
+    }
 }
 
 #[derive(Debug, Default)]
@@ -227,6 +364,68 @@ mod tests {
         check!(file.tags.get("added") == Some(&tag!("added")));
     }
 
+    #[rstest]
+    fn it_tags_the_location_of_the_file(mut file: File) {
+        check!(file.folder.is_root());
+        check!(file.tags.get(DIR) == Some(&tag!("dir:/")));
+        check!(file.path() == format!("/{}", file.name));
+
+        file.set_folder(FolderPath::parse("/photos/2025").unwrap());
+        file.update(UpdateFile {
+            name: Some("beach.jpg".into()),
+            ..Default::default()
+        });
+
+        check!(file.path() == "/photos/2025/beach.jpg");
+        check!(file.tags.get(DIR) == Some(&tag!("dir:/photos/2025")));
+        check!(file.tags.get(PATH) == Some(&tag!("path:/photos/2025/beach.jpg")));
+    }
+
+    #[rstest]
+    fn it_tags_when_the_file_was_created_updated_and_accessed(mut file: File) {
+        let at = OffsetDateTime::from_unix_timestamp(1736499600).unwrap();
+        file.created_at = at;
+        file.updated_at = at;
+        file.tags = File::default_tags(&file);
+
+        check!(file.tags.get(CREATED) == Some(&tag!("created:2025-01-10T09:00:00Z")));
+        check!(file.tags.get(UPDATED) == Some(&tag!("updated:2025-01-10T09:00:00Z")));
+        check!(file.tags.get(ACCESSED) == None);
+
+        file.access(at + time::Duration::hours(1));
+        check!(file.accessed_at() == Some(at + time::Duration::hours(1)));
+        check!(file.tags.get(ACCESSED) == Some(&tag!("accessed:2025-01-10T10:00:00Z")));
+
+        file.update(UpdateFile {
+            name: Some("renamed.txt".into()),
+            ..Default::default()
+        });
+        check!(file.created_at() == at);
+        check!(file.updated_at() > at);
+    }
+
+    #[rstest]
+    #[case("report.pdf", "report (1).pdf")]
+    #[case("archive.tar.gz", "archive.tar (1).gz")]
+    #[case("README", "README (1)")]
+    #[case(".bashrc", ".bashrc (1)")]
+    fn it_numbers_a_file_name(#[case] name: &str, #[case] expected: &str) {
+        check!(numbered_name(name, 1) == expected);
+    }
+
+    #[rstest]
+    fn it_moves_a_file_to_the_trash_and_restores_it(mut file: File) {
+        check!(!file.is_trashed());
+
+        file.trash();
+        check!(file.is_trashed());
+        check!(file.deleted_at().is_some());
+
+        file.restore();
+        check!(!file.is_trashed());
+        check!(file.deleted_at().is_none());
+    }
+
     #[rstest]
     #[case(UpdateFile { name: Some("test".into()), ..Default::default() })]
     #[case(UpdateFile { tags: Some(vec![tag!("added"), tag!("hello:world")]), ..Default::default() })]
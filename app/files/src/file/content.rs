@@ -1,16 +1,53 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::fmt::Display;
+use std::future::ready;
+use std::ops::RangeBounds;
+use std::pin::pin;
+use std::sync::Arc;
+use std::time::SystemTime;
 
 use bytes::Bytes;
-use futures::{SinkExt, Stream, TryStreamExt};
+use futures::{SinkExt, Stream, StreamExt, TryStreamExt, future::Either};
+use oxidrive_accounts::account::AccountId;
+use uuid::Uuid;
 
-use super::File;
+use crate::upload::UploadId;
 
+use super::{File, FileId, version::FileVersion};
+
+pub use blob::*;
+pub use compression::{Compression, InvalidContentTypeError};
+pub use encryption::*;
+pub use keys::*;
+
+mod blob;
+mod compression;
+mod encryption;
 pub mod fs;
+mod keys;
 pub mod s3;
 
 #[derive(Clone)]
 pub struct FileStorage {
     service: opendal::Operator,
+    /// Backends content can be moved to with [FileStorage::move_to_tier], by name.
+    /// Content is always written to the default backend first
+    tiers: Arc<HashMap<String, opendal::Operator>>,
+    layout: Layout,
+    encryption: Option<Encryption>,
+    compression: Option<Compression>,
+}
+
+/// How content is laid out in the storage backend
+#[derive(Clone)]
+enum Layout {
+    /// Each file owns its content, stored at `{owner_id}/{file_id}`
+    PerFile,
+    /// Content is stored once under its hash, and shared by all the files and versions referring to it.
+    /// Content written before switching to this layout keeps being read from its per-file path
+    /// until it is deduplicated
+    ContentAddressed(Arc<dyn BlobRefs>),
 }
 
 impl FileStorage {
@@ -18,59 +55,1107 @@ impl FileStorage {
         self.service.info().scheme()
     }
 
+    pub fn content_addressed(mut self, refs: Arc<dyn BlobRefs>) -> Self {
+        self.layout = Layout::ContentAddressed(refs);
+        self
+    }
+
+    pub fn is_content_addressed(&self) -> bool {
+        matches!(self.layout, Layout::ContentAddressed(_))
+    }
+
+    /// Encrypts content written from now on with the data key of the account it belongs to.
+    /// Content written before stays readable as is
+    pub fn encrypted(mut self, encryption: Encryption) -> Self {
+        self.encryption = Some(encryption);
+        self
+    }
+
+    /// The same storage, keeping its layout, encryption and compression, with its content in another backend
+    pub fn with_backend(&self, backend: FileStorage) -> Self {
+        Self {
+            service: backend.service,
+            ..self.clone()
+        }
+    }
+
+    /// Adds a backend that content can be moved to with [FileStorage::move_to_tier], under the given name
+    pub fn with_tier(mut self, name: impl Into<String>, backend: FileStorage) -> Self {
+        Arc::make_mut(&mut self.tiers).insert(name.into(), backend.service);
+        self
+    }
+
+    pub fn has_tier(&self, name: &str) -> bool {
+        self.tiers.contains_key(name)
+    }
+
+    /// The same storage, with its content in the given tier, or in the default backend for [None]
+    fn in_tier(&self, tier: Option<&str>) -> Result<Cow<'_, Self>, opendal::Error> {
+        let Some(tier) = tier else {
+            return Ok(Cow::Borrowed(self));
+        };
+
+        let Some(service) = self.tiers.get(tier) else {
+            return Err(opendal::Error::new(
+                opendal::ErrorKind::ConfigInvalid,
+                format!("unknown storage tier '{tier}'"),
+            ));
+        };
+
+        Ok(Cow::Owned(Self {
+            service: service.clone(),
+            ..self.clone()
+        }))
+    }
+
+    /// Compresses content written from now on, if its type is one of those configured.
+    /// Content recorded as compressed is always decompressed when read, even after compression is disabled
+    pub fn compressed(mut self, compression: Compression) -> Self {
+        self.compression = Some(compression);
+        self
+    }
+
+    /// Wraps the data keys of all accounts with the current master key, so that previous master keys
+    /// can be removed from the configuration. Returns how many keys were wrapped again
+    pub async fn rotate_keys(&self) -> Result<usize, RotateKeysError> {
+        let Some(encryption) = &self.encryption else {
+            return Err(RotateKeysError::NotEncrypted);
+        };
+
+        encryption.rotate().await
+    }
+
+    /// Reads the content of the file, or only the requested range of bytes
     pub async fn download(
         &self,
         file: &File,
+        range: impl RangeBounds<u64>,
     ) -> Result<
         Option<impl Stream<Item = Result<Bytes, impl std::error::Error + 'static>> + 'static>,
         DownloadFileError,
     > {
-        let path = path_for(file);
+        let storage = self.in_tier(file.tier.as_deref())?;
+        let path = storage
+            .resolve(file.hash, file.encoding, path_for(file))
+            .await?;
+        storage
+            .download_path(file.owner_id, path, file.encoding, range)
+            .await
+    }
+
+    pub async fn download_version(
+        &self,
+        file: &File,
+        version: &FileVersion,
+        range: impl RangeBounds<u64>,
+    ) -> Result<
+        Option<impl Stream<Item = Result<Bytes, impl std::error::Error + 'static>> + 'static>,
+        DownloadFileError,
+    > {
+        let path = self
+            .resolve(
+                version.hash,
+                version.encoding,
+                version_path(file.owner_id, file.id, version.number),
+            )
+            .await?;
+        self.download_path(file.owner_id, path, version.encoding, range)
+            .await
+    }
 
+    async fn download_path(
+        &self,
+        owner_id: AccountId,
+        path: String,
+        encoding: Encoding,
+        range: impl RangeBounds<u64>,
+    ) -> Result<
+        Option<impl Stream<Item = Result<Bytes, impl std::error::Error + 'static>> + 'static>,
+        DownloadFileError,
+    > {
         if !self.service.exists(&path).await? {
             return Ok(None);
         }
 
-        let reader = self.service.reader(&path).await?;
+        self.read(owner_id, &path, encoding, range).await.map(Some)
+    }
 
-        reader
-            .into_bytes_stream(..)
-            .await
-            .map(Some)
-            .map_err(DownloadFileError)
+    /// Reads stored content, decompressing and decrypting it if it was written so.
+    /// How it was written is recorded with its file or version, so it stays readable
+    /// however the storage is configured later
+    async fn read(
+        &self,
+        owner_id: AccountId,
+        path: &str,
+        encoding: Encoding,
+        range: impl RangeBounds<u64>,
+    ) -> Result<impl Stream<Item = Result<Bytes, std::io::Error>> + 'static, DownloadFileError>
+    {
+        let range = (range.start_bound().cloned(), range.end_bound().cloned());
+
+        let reader = self.service.reader(path).await?;
+
+        // compressed content can only be read from its start, so it is sliced once decompressed
+        let stored = if encoding.encrypted {
+            let Some(encryption) = &self.encryption else {
+                return Err(DownloadFileError::NotEncrypted);
+            };
+
+            let length = self.service.stat(path).await?.content_length();
+            let header = if length > 0 {
+                reader
+                    .read(0..Sealed::HEADER_SIZE.min(length))
+                    .await?
+                    .to_bytes()
+            } else {
+                Bytes::new()
+            };
+
+            let sealed = Sealed::parse(&header, length).ok_or(DownloadFileError::NotSealed)?;
+
+            let key = encryption.data_key(owner_id).await?;
+
+            let plaintext = if encoding.compressed {
+                sealed.range(..)
+            } else {
+                sealed.range(range)
+            };
+
+            let ciphertext = sealed.ciphertext_range(&plaintext);
+            let ciphertext = reader.into_bytes_stream(ciphertext).await?;
+
+            Either::Left(key.open(sealed, plaintext, ciphertext))
+        } else if encoding.compressed {
+            Either::Right(reader.into_bytes_stream(..).await?)
+        } else {
+            Either::Right(reader.into_bytes_stream(range).await?)
+        };
+
+        if !encoding.compressed {
+            return Ok(Either::Left(stored));
+        }
+
+        Ok(Either::Right(compression::slice(
+            compression::decompress(stored),
+            range,
+        )))
     }
 
+    /// Finds where content is stored, preferring its blob when it has already been stored under its hash
+    async fn resolve(
+        &self,
+        hash: Option<blake3::Hash>,
+        encoding: Encoding,
+        path: String,
+    ) -> Result<String, opendal::Error> {
+        if let (Layout::ContentAddressed(_), Some(hash)) = (&self.layout, hash) {
+            let blob = blob_path(&hash, encoding);
+            if self.service.exists(&blob).await? {
+                return Ok(blob);
+            }
+        }
+
+        Ok(path)
+    }
+
+    /// Writes new content for the file to the staging area, returning its size and hash.
+    /// The current content is left untouched until the staged one is promoted with [FileStorage::promote],
+    /// which must only happen once the new content has been saved in the file metadata.
+    /// If that fails, the staged content must be thrown away with [FileStorage::discard]
     pub async fn upload(
         &self,
         file: &File,
         content: impl Stream<Item = Result<Bytes, impl std::error::Error + Send + Sync + 'static>>
         + Unpin,
-    ) -> Result<usize, UploadFileError> {
+    ) -> Result<StagedContent, UploadFileError> {
+        if let Layout::ContentAddressed(refs) = &self.layout {
+            // the file is about to let go of its current content, so it must be counted first
+            self.adopt_current(refs.as_ref(), file).await?;
+        }
+
+        let path = staging_path();
+
+        match self
+            .write(file.owner_id, &path, &file.content_type, content)
+            .await
+        {
+            Ok(staged) => Ok(staged),
+            Err(err) => {
+                // whatever was written is incomplete, and can't be used anyway
+                if let Err(err) = self.service.delete(&path).await {
+                    tracing::warn!(error = %err, path, "failed to delete incomplete staged content");
+                }
+                Err(err)
+            }
+        }
+    }
+
+    /// Stages the content already stored for another file as the new content of the file,
+    /// without it having to be uploaded again. It is then promoted or discarded as with [FileStorage::upload]
+    pub async fn stage_existing(
+        &self,
+        file: &File,
+        source: &File,
+    ) -> Result<StagedContent, CopyContentError> {
+        let hash = match source.hash {
+            Some(hash) => hash,
+            // content stored before it was hashed is hashed now, to be staged like any other
+            None => self
+                .hash_current(source)
+                .await?
+                .ok_or(CopyContentError::NotFound)?,
+        };
+        let mut source = source.clone();
+        source.set_hash(hash);
+        let source = &source;
+
+        let path = staging_path();
+
+        if let Layout::ContentAddressed(refs) = &self.layout {
+            self.adopt_current(refs.as_ref(), file).await?;
+
+            // once the source content is stored under its hash, promoting only takes another reference to it
+            self.adopt_current(refs.as_ref(), source).await?;
+        } else {
+            self.copy_current(source, &path).await?;
+        }
+
+        Ok(StagedContent {
+            path,
+            size: source.size,
+            stored_size: source.stored_size,
+            hash,
+            encoding: source.encoding,
+        })
+    }
+
+    async fn write(
+        &self,
+        owner_id: AccountId,
+        path: &str,
+        content_type: &str,
+        content: impl Stream<Item = Result<Bytes, impl std::error::Error + Send + Sync + 'static>>
+        + Unpin,
+    ) -> Result<StagedContent, UploadFileError> {
         let mut size = 0;
+        let mut stored_size = 0;
+        let mut hasher = blake3::Hasher::new();
+
+        let key = match &self.encryption {
+            Some(encryption) => Some(encryption.data_key(owner_id).await?),
+            None => None,
+        };
+
+        let compression = self
+            .compression
+            .as_ref()
+            .filter(|compression| compression.applies_to(content_type));
+
+        let encoding = Encoding {
+            compressed: compression.is_some(),
+            encrypted: key.is_some(),
+        };
 
         let mut writer = self
             .service
-            .writer_with(&path_for(file))
-            .content_type(&file.content_type)
+            .writer_with(path)
+            .content_type(content_type)
             .await?
             .into_bytes_sink();
 
-        let mut content = content.map_err(std::io::Error::other).inspect_ok(|bytes| {
-            size += bytes.len();
-        });
+        {
+            // size and hash are computed on the content as uploaded, before it is compressed or encrypted
+            let content = content.map_err(std::io::Error::other).inspect_ok(|bytes| {
+                size += bytes.len();
+                hasher.update(bytes);
+            });
+
+            let content = match compression {
+                Some(compression) => Either::Left(compression.compress(content)),
+                None => Either::Right(content),
+            };
+
+            let content = match key {
+                Some(key) => Either::Left(key.seal(content)),
+                None => Either::Right(content),
+            };
+
+            let mut content = pin!(content.inspect_ok(|bytes| stored_size += bytes.len()));
+
+            writer.send_all(&mut content).await?;
+        }
+
+        writer.close().await?;
+
+        Ok(StagedContent {
+            path: path.to_string(),
+            size,
+            stored_size,
+            hash: hasher.finalize(),
+            encoding,
+        })
+    }
+
+    /// Makes staged content the current content of the file
+    pub async fn promote(
+        &self,
+        file: &File,
+        staged: &StagedContent,
+    ) -> Result<(), CopyContentError> {
+        if let Layout::ContentAddressed(refs) = &self.layout {
+            return self
+                .adopt(
+                    refs.as_ref(),
+                    &staged.path,
+                    staged.hash,
+                    staged.encoding,
+                    staged.size,
+                )
+                .await;
+        }
+
+        self.replace(&staged.path, &path_for(file)).await
+    }
+
+    /// Moves staged content over the content at the given path
+    async fn replace(&self, staged: &str, path: &str) -> Result<(), CopyContentError> {
+        // renaming is atomic on backends supporting it, while copies are at least never observed half done
+        // on object stores
+        if self.service.info().full_capability().rename {
+            self.service.rename(staged, path).await?;
+            return Ok(());
+        }
+
+        self.copy(staged, path).await?;
+        self.service.delete(staged).await?;
+
+        Ok(())
+    }
+
+    /// Throws away staged content that will never be promoted
+    pub async fn discard(&self, staged: &StagedContent) -> Result<(), DeleteContentError> {
+        self.service.delete(&staged.path).await?;
+        Ok(())
+    }
+
+    /// Deletes staged content written before the given instant, which belongs to uploads that were abandoned
+    /// before being promoted or discarded, e.g. because the server stopped in the middle of them.
+    /// Returns how many objects were deleted
+    pub async fn delete_staged_before(
+        &self,
+        threshold: SystemTime,
+    ) -> Result<usize, DeleteContentError> {
+        let mut lister = self.service.lister(&format!("{STAGING_PREFIX}/")).await?;
+
+        let mut deleted = 0;
+
+        while let Some(entry) = lister.try_next().await? {
+            if !entry.metadata().is_file() {
+                continue;
+            }
+
+            // not every backend returns the modification time when listing
+            let last_modified = match entry.metadata().last_modified() {
+                Some(last_modified) => Some(last_modified),
+                None => self.service.stat(entry.path()).await?.last_modified(),
+            };
+
+            let is_abandoned = last_modified
+                .and_then(|t| t.timestamp().try_into().ok())
+                .map(|secs| SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(secs))
+                .is_some_and(|t| t <= threshold);
+
+            if is_abandoned {
+                self.service.delete(entry.path()).await?;
+                deleted += 1;
+            }
+        }
+
+        Ok(deleted)
+    }
+
+    /// Lets go of the content the file referred to before being overwritten.
+    /// Content stored per file is overwritten in place, so this only matters for the content addressed layout
+    pub async fn release(&self, previous: &File) -> Result<(), DeleteContentError> {
+        // new content is always written to the default backend, leaving behind content moved to another tier
+        if previous.tier.is_some() {
+            return self.evict(previous).await;
+        }
+
+        let Layout::ContentAddressed(refs) = &self.layout else {
+            return Ok(());
+        };
+
+        match previous.hash {
+            Some(hash) => self.release_blob(refs.as_ref(), hash).await,
+            None => {
+                self.service.delete(&path_for(previous)).await?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Deletes the current content of the file, together with any version still stored per file.
+    /// Versions stored under their hash must be deleted one by one with [FileStorage::delete_version]
+    pub async fn delete(&self, file: &File) -> Result<(), DeleteContentError> {
+        if let (Layout::ContentAddressed(refs), Some(hash)) = (&self.layout, file.hash) {
+            if !self.service.exists(&path_for(file)).await? {
+                self.release_blob(refs.as_ref(), hash).await?;
+            }
+        }
+
+        self.in_tier(file.tier.as_deref())?
+            .service
+            .delete(&path_for(file))
+            .await?;
+        self.service
+            .remove_all(&versions_path(file.owner_id, file.id))
+            .await?;
+        Ok(())
+    }
+
+    /// Stores the current content of the file as the given version
+    pub async fn archive(&self, file: &File, version: u32) -> Result<(), CopyContentError> {
+        let (Layout::ContentAddressed(refs), Some(hash)) = (&self.layout, file.hash) else {
+            return self
+                .copy_current(file, &version_path(file.owner_id, file.id, version))
+                .await;
+        };
+
+        self.adopt_current(refs.as_ref(), file).await?;
+        refs.acquire(hash, file.size).await?;
+
+        Ok(())
+    }
+
+    /// Replaces the current content of the file with the one stored for the given version.
+    /// As with [FileStorage::upload], the previous content must then be let go with [FileStorage::release]
+    pub async fn restore_version(
+        &self,
+        file: &File,
+        version: &FileVersion,
+    ) -> Result<(), CopyContentError> {
+        let path = version_path(file.owner_id, file.id, version.number);
+
+        let (Layout::ContentAddressed(refs), Some(hash)) = (&self.layout, version.hash) else {
+            // as with uploads, the current content is only replaced once the restored one is completely written
+            let staged = staging_path();
+
+            if let Err(err) = self.copy(&path, &staged).await {
+                if let Err(err) = self.service.delete(&staged).await {
+                    tracing::warn!(error = %err, path = staged, "failed to delete incomplete staged content");
+                }
+                return Err(err);
+            }
+
+            return self.replace(&staged, &path_for(file)).await;
+        };
+
+        self.adopt_current(refs.as_ref(), file).await?;
+
+        if self.service.exists(&path).await? {
+            self.adopt(refs.as_ref(), &path, hash, version.encoding, version.size)
+                .await?;
+        }
+
+        refs.acquire(hash, version.size).await?;
+
+        Ok(())
+    }
+
+    pub async fn delete_version(
+        &self,
+        file: &File,
+        version: &FileVersion,
+    ) -> Result<(), DeleteContentError> {
+        let path = version_path(file.owner_id, file.id, version.number);
+
+        if let (Layout::ContentAddressed(refs), Some(hash)) = (&self.layout, version.hash) {
+            if !self.service.exists(&path).await? {
+                return self.release_blob(refs.as_ref(), hash).await;
+            }
+        }
+
+        self.service.delete(&path).await?;
+        Ok(())
+    }
+
+    /// Moves the current content of the file under its hash, if it is still stored per file
+    pub async fn deduplicate(&self, file: &File) -> Result<(), CopyContentError> {
+        let Layout::ContentAddressed(refs) = &self.layout else {
+            return Ok(());
+        };
+
+        self.adopt_current(refs.as_ref(), file).await
+    }
+
+    /// Moves the content of the version under its hash, if it is still stored per file
+    pub async fn deduplicate_version(
+        &self,
+        file: &File,
+        version: &FileVersion,
+    ) -> Result<(), CopyContentError> {
+        let (Layout::ContentAddressed(refs), Some(hash)) = (&self.layout, version.hash) else {
+            return Ok(());
+        };
+
+        let path = version_path(file.owner_id, file.id, version.number);
+
+        if self.service.exists(&path).await? {
+            self.adopt(refs.as_ref(), &path, hash, version.encoding, version.size)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Computes the hash of content found in the storage backend
+    pub async fn hash_stored(
+        &self,
+        content: &StoredContent,
+        encoding: Encoding,
+    ) -> Result<blake3::Hash, HashContentError> {
+        self.hash_path(content.owner_id, &stored_path(content), encoding)
+            .await
+    }
+
+    /// Reads the whole current content of the file to compute its hash,
+    /// returning `None` if the content can't be found
+    pub async fn hash_current(
+        &self,
+        file: &File,
+    ) -> Result<Option<blake3::Hash>, HashContentError> {
+        let storage = self.in_tier(file.tier.as_deref())?;
+        let path = storage
+            .resolve(file.hash, file.encoding, path_for(file))
+            .await?;
+
+        if !storage.service.exists(&path).await? {
+            return Ok(None);
+        }
+
+        storage
+            .hash_path(file.owner_id, &path, file.encoding)
+            .await
+            .map(Some)
+    }
+
+    async fn hash_path(
+        &self,
+        owner_id: AccountId,
+        path: &str,
+        encoding: Encoding,
+    ) -> Result<blake3::Hash, HashContentError> {
+        let mut hasher = blake3::Hasher::new();
+
+        let mut stream = pin!(self.read(owner_id, path, encoding, ..).await?);
+
+        while let Some(bytes) = stream.try_next().await? {
+            hasher.update(&bytes);
+        }
+
+        Ok(hasher.finalize())
+    }
+
+    async fn adopt_current(
+        &self,
+        refs: &dyn BlobRefs,
+        file: &File,
+    ) -> Result<(), CopyContentError> {
+        let Some(hash) = file.hash else {
+            return Ok(());
+        };
+
+        let path = path_for(file);
+
+        if self.service.exists(&path).await? {
+            self.adopt(refs, &path, hash, file.encoding, file.size)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Moves content from its per file path under its hash, taking a reference to it on behalf of whoever
+    /// it belonged to
+    async fn adopt(
+        &self,
+        refs: &dyn BlobRefs,
+        path: &str,
+        hash: blake3::Hash,
+        encoding: Encoding,
+        size: usize,
+    ) -> Result<(), CopyContentError> {
+        // counting the reference first ensures the blob can't be released while it's being written
+        refs.acquire(hash, size).await?;
+
+        let blob = blob_path(&hash, encoding);
+        if !self.service.exists(&blob).await? {
+            self.copy(path, &blob).await?;
+        }
+
+        self.service.delete(path).await?;
+
+        Ok(())
+    }
+
+    async fn release_blob(
+        &self,
+        refs: &dyn BlobRefs,
+        hash: blake3::Hash,
+    ) -> Result<(), DeleteContentError> {
+        if refs.release(hash).await? {
+            // the same content is stored once for each way it was written
+            for encoding in Encoding::ALL {
+                self.service.delete(&blob_path(&hash, encoding)).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn copy(&self, from: &str, to: &str) -> Result<(), CopyContentError> {
+        if self.service.info().full_capability().copy {
+            self.service.copy(from, to).await?;
+            return Ok(());
+        }
+
+        // not every backend supports server side copies, so we stream the content through
+        self.send(from, &self.service, to).await
+    }
+
+    /// Copies the current content of the file to the given path of the default backend, whatever tier holds it
+    async fn copy_current(&self, file: &File, to: &str) -> Result<(), CopyContentError> {
+        match file.tier.as_deref() {
+            None => self.copy(&path_for(file), to).await,
+            tier => {
+                self.in_tier(tier)?
+                    .send(&path_for(file), &self.service, to)
+                    .await
+            }
+        }
+    }
+
+    /// Streams content as stored to another path, possibly of another backend
+    async fn send(
+        &self,
+        from: &str,
+        target: &opendal::Operator,
+        to: &str,
+    ) -> Result<(), CopyContentError> {
+        let content = self
+            .service
+            .reader(from)
+            .await?
+            .into_bytes_stream(..)
+            .await?;
+        let mut content = pin!(content);
+
+        let mut writer = target.writer(to).await?.into_bytes_sink();
 
         writer.send_all(&mut content).await?;
         writer.close().await?;
 
-        Ok(size)
+        Ok(())
+    }
+
+    /// Copies the current content of the file to the given tier, verifying it against its hash once copied.
+    /// The file must then be saved as held by the tier, before its content is deleted from where it was
+    /// with [FileStorage::evict]. Content shared under its hash can't be moved for a single file,
+    /// so tiers are not supported by the content addressed layout
+    pub async fn move_to_tier(
+        &self,
+        file: &File,
+        tier: &str,
+    ) -> Result<Transfer, TransferContentError> {
+        if self.is_content_addressed() {
+            return Err(TransferContentError::ContentAddressed);
+        }
+
+        let source = self.in_tier(file.tier.as_deref())?;
+        let target = self.in_tier(Some(tier))?;
+
+        let path = source
+            .resolve(file.hash, file.encoding, path_for(file))
+            .await?;
+        source
+            .transfer_path(&target, file.owner_id, file.hash, file.encoding, &path)
+            .await
+    }
+
+    /// Deletes the current content of the file from the tier that held it before being moved
+    pub async fn evict(&self, previous: &File) -> Result<(), DeleteContentError> {
+        let storage = self.in_tier(previous.tier.as_deref())?;
+        let path = storage
+            .resolve(previous.hash, previous.encoding, path_for(previous))
+            .await?;
+
+        // content stored under its hash may be shared, so the file only lets go of its reference to it
+        if let (Layout::ContentAddressed(refs), Some(hash)) = (&storage.layout, previous.hash) {
+            if path != path_for(previous) {
+                return storage.release_blob(refs.as_ref(), hash).await;
+            }
+        }
+
+        storage.service.delete(&path).await?;
+        Ok(())
+    }
+
+    /// When the current content of the file was last written to the tier holding it,
+    /// or [None] if it can't be found
+    pub async fn modified_at(&self, file: &File) -> Result<Option<SystemTime>, StatContentError> {
+        let storage = self.in_tier(file.tier.as_deref())?;
+        let path = storage
+            .resolve(file.hash, file.encoding, path_for(file))
+            .await?;
+
+        let metadata = match storage.service.stat(&path).await {
+            Ok(metadata) => metadata,
+            Err(err) if err.kind() == opendal::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+
+        let last_modified = metadata
+            .last_modified()
+            .and_then(|t| t.timestamp().try_into().ok())
+            .map(|secs| SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(secs));
+
+        Ok(last_modified)
+    }
+
+    /// Copies the current content of the file as is to another storage backend, verifying it against its hash
+    /// once copied. Content found in the target with the same hash is assumed to have been copied already
+    pub async fn transfer(
+        &self,
+        target: &FileStorage,
+        file: &File,
+    ) -> Result<Transfer, TransferContentError> {
+        let path = self
+            .resolve(file.hash, file.encoding, path_for(file))
+            .await?;
+        self.transfer_path(target, file.owner_id, file.hash, file.encoding, &path)
+            .await
+    }
+
+    pub async fn transfer_version(
+        &self,
+        target: &FileStorage,
+        file: &File,
+        version: &FileVersion,
+    ) -> Result<Transfer, TransferContentError> {
+        let path = self
+            .resolve(
+                version.hash,
+                version.encoding,
+                version_path(file.owner_id, file.id, version.number),
+            )
+            .await?;
+        self.transfer_path(target, file.owner_id, version.hash, version.encoding, &path)
+            .await
+    }
+
+    async fn transfer_path(
+        &self,
+        target: &FileStorage,
+        owner_id: AccountId,
+        hash: Option<blake3::Hash>,
+        encoding: Encoding,
+        path: &str,
+    ) -> Result<Transfer, TransferContentError> {
+        let length = match self.service.stat(path).await {
+            Ok(metadata) => metadata.content_length(),
+            Err(err) if err.kind() == opendal::ErrorKind::NotFound => return Ok(Transfer::Missing),
+            Err(err) => return Err(err.into()),
+        };
+
+        let expected = match hash {
+            Some(hash) => hash,
+            None => self.hash_path(owner_id, path, encoding).await?,
+        };
+
+        let existing = match target.service.stat(path).await {
+            Ok(metadata) => Some(metadata.content_length()),
+            Err(err) if err.kind() == opendal::ErrorKind::NotFound => None,
+            Err(err) => return Err(err.into()),
+        };
+
+        // blobs shared by several files are only copied once, and an interrupted migration resumes where it stopped.
+        // An interrupted copy may leave content of the right size behind, so it is only trusted if its hash matches
+        if existing == Some(length)
+            && target.hash_path(owner_id, path, encoding).await.ok() == Some(expected)
+        {
+            return Ok(Transfer::Skipped);
+        }
+
+        // content is copied as stored, so it keeps its compression and encryption
+        self.send(path, &target.service, path).await?;
+
+        if target.hash_path(owner_id, path, encoding).await? != expected {
+            target.service.delete(path).await?;
+            return Ok(Transfer::Corrupted);
+        }
+
+        Ok(Transfer::Copied { size: length })
+    }
+
+    /// Writes a chunk of a resumable upload starting at the given offset, returning its size.
+    /// Content received before the stream fails is kept, so the upload can resume right after it
+    pub async fn write_chunk<E: std::error::Error>(
+        &self,
+        owner_id: AccountId,
+        upload_id: UploadId,
+        offset: usize,
+        content: impl Stream<Item = Result<Bytes, E>> + Unpin,
+    ) -> Result<UploadedChunk, UploadFileError> {
+        let mut size = 0;
+
+        let key = match &self.encryption {
+            Some(encryption) => Some(encryption.data_key(owner_id).await?),
+            None => None,
+        };
+
+        let path = chunk_path(
+            upload_id,
+            offset,
+            Encoding {
+                compressed: false,
+                encrypted: key.is_some(),
+            },
+        );
+
+        let mut writer = self.service.writer(&path).await?.into_bytes_sink();
+
+        {
+            let content = content
+                .take_while(|chunk| {
+                    if let Err(err) = chunk {
+                        tracing::debug!(error = %err, %upload_id, "upload chunk interrupted");
+                    }
+                    ready(chunk.is_ok())
+                })
+                .filter_map(|chunk| ready(chunk.ok()))
+                .map(|bytes| {
+                    size += bytes.len();
+                    Ok::<_, std::io::Error>(bytes)
+                });
+
+            // an interrupted chunk simply ends early, so what was received is still sealed as a whole
+            let mut content = pin!(match key {
+                Some(key) => Either::Left(key.seal(content)),
+                None => Either::Right(content),
+            });
+
+            writer.send_all(&mut content).await?;
+        }
+
+        writer.close().await?;
+
+        Ok(UploadedChunk { path, size })
+    }
+
+    /// Reads back all the chunks of a resumable upload, in order
+    pub async fn read_chunks(
+        &self,
+        owner_id: AccountId,
+        upload_id: UploadId,
+    ) -> Result<
+        impl Stream<Item = Result<Bytes, std::io::Error>> + Unpin + Send + 'static,
+        DownloadFileError,
+    > {
+        let mut paths: Vec<String> = self
+            .service
+            .lister(&chunks_path(upload_id))
+            .await?
+            .try_filter(|entry| ready(entry.metadata().is_file()))
+            .map_ok(|entry| entry.path().to_string())
+            .try_collect()
+            .await?;
+
+        paths.sort();
+
+        let storage = self.clone();
+
+        let chunks = futures::stream::iter(paths)
+            .then(move |path| {
+                let storage = storage.clone();
+                async move {
+                    let encoding = Encoding::of_path(&path);
+                    storage.read(owner_id, &path, encoding, ..).await
+                }
+            })
+            .map_err(std::io::Error::other)
+            .try_flatten();
+
+        Ok(Box::pin(chunks))
+    }
+
+    pub async fn delete_chunk(&self, chunk: &UploadedChunk) -> Result<(), DeleteContentError> {
+        self.service.delete(&chunk.path).await?;
+        Ok(())
+    }
+
+    /// Deletes all the chunks of a resumable upload, once it is complete or abandoned
+    pub async fn delete_chunks(&self, upload_id: UploadId) -> Result<(), DeleteContentError> {
+        self.service.remove_all(&chunks_path(upload_id)).await?;
+        Ok(())
     }
+
+    /// Lists every object held by the storage backend, whether or not a [File] still refers to it.
+    /// Objects that do not follow the layout used by [FileStorage] are skipped.
+    pub async fn list(
+        &self,
+    ) -> Result<
+        impl Stream<Item = Result<StoredContent, ListContentError>> + 'static,
+        ListContentError,
+    > {
+        let service = self.service.clone();
+
+        let lister = self.service.lister_with("/").recursive(true).await?;
+
+        let contents = lister
+            .map_err(ListContentError::from)
+            .try_filter_map(move |entry| {
+                let service = service.clone();
+
+                async move {
+                    if !entry.metadata().is_file() {
+                        return Ok(None);
+                    }
+
+                    let Some((owner_id, file_id, version)) = parse_path(entry.path()) else {
+                        return Ok(None);
+                    };
+
+                    // not every backend returns the modification time when listing
+                    let last_modified = match entry.metadata().last_modified() {
+                        Some(last_modified) => Some(last_modified),
+                        None => service.stat(entry.path()).await?.last_modified(),
+                    };
+
+                    let last_modified = last_modified
+                        .and_then(|t| t.timestamp().try_into().ok())
+                        .map(|secs| SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(secs));
+
+                    Ok(Some(StoredContent {
+                        owner_id,
+                        file_id,
+                        version,
+                        last_modified,
+                    }))
+                }
+            });
+
+        Ok(contents)
+    }
+
+    pub async fn delete_stored(&self, content: &StoredContent) -> Result<(), DeleteContentError> {
+        self.service.delete(&stored_path(content)).await?;
+        Ok(())
+    }
+}
+
+/// New content for a file, written with [FileStorage::upload] but not yet promoted to be its current content
+#[derive(Clone, Debug)]
+pub struct StagedContent {
+    path: String,
+    pub size: usize,
+    pub stored_size: usize,
+    pub hash: blake3::Hash,
+    pub encoding: Encoding,
+}
+
+/// How content was written to the storage backend. It is recorded with the file or version the content belongs to,
+/// instead of being guessed from the stored bytes
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Encoding {
+    /// Compressed with zstd, before being encrypted if it also is
+    pub compressed: bool,
+    /// Sealed with the data key of the account owning the content
+    pub encrypted: bool,
+}
+
+impl Encoding {
+    const ALL: [Self; 4] = [
+        Self {
+            compressed: false,
+            encrypted: false,
+        },
+        Self {
+            compressed: true,
+            encrypted: false,
+        },
+        Self {
+            compressed: false,
+            encrypted: true,
+        },
+        Self {
+            compressed: true,
+            encrypted: true,
+        },
+    ];
+
+    /// Tells apart objects holding the same content written differently, like blobs shared by several files
+    fn suffix(&self) -> &'static str {
+        match (self.compressed, self.encrypted) {
+            (false, false) => "",
+            (true, false) => ".zst",
+            (false, true) => ".enc",
+            (true, true) => ".zst.enc",
+        }
+    }
+
+    /// The encoding of an object named with its [Encoding::suffix]
+    fn of_path(path: &str) -> Self {
+        Self::ALL
+            .into_iter()
+            .filter(|encoding| path.ends_with(encoding.suffix()))
+            .max_by_key(|encoding| encoding.suffix().len())
+            .unwrap_or_default()
+    }
+}
+
+/// What happened to content copied to another storage backend with [FileStorage::transfer]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Transfer {
+    /// The content was copied, and matches its hash
+    Copied { size: u64 },
+    /// The content was already found in the target
+    Skipped,
+    /// The content can't be found in the source
+    Missing,
+    /// The copied content does not match its hash, and was deleted from the target
+    Corrupted,
+}
+
+/// A chunk of a resumable upload, written with [FileStorage::write_chunk]
+#[derive(Clone, Debug)]
+pub struct UploadedChunk {
+    path: String,
+    pub size: usize,
+}
+
+/// A piece of content found in the storage backend, stored per file.
+/// Blobs of the content addressed layout are tracked by [BlobRefs] instead
+#[derive(Clone, Debug)]
+pub struct StoredContent {
+    pub owner_id: AccountId,
+    pub file_id: FileId,
+    /// The archived version this content belongs to, or [None] for the current content of the file
+    pub version: Option<u32>,
+    pub last_modified: Option<SystemTime>,
 }
 
 impl FileStorage {
     fn new(cfg: impl opendal::Configurator) -> Self {
         let service = opendal::Operator::from_config(cfg).unwrap().finish();
 
-        Self { service }
+        Self {
+            service,
+            tiers: Default::default(),
+            layout: Layout::PerFile,
+            encryption: None,
+            compression: None,
+        }
     }
 
     pub fn memory() -> Self {
@@ -79,8 +1164,16 @@ impl FileStorage {
 }
 
 #[derive(Debug, thiserror::Error)]
-#[error(transparent)]
-pub struct DownloadFileError(#[from] opendal::Error);
+pub enum DownloadFileError {
+    #[error(transparent)]
+    ServiceError(#[from] opendal::Error),
+    #[error(transparent)]
+    KeyFailed(#[from] DataKeyError),
+    #[error("the content is encrypted, but storage encryption is not enabled")]
+    NotEncrypted,
+    #[error("the content is recorded as encrypted, but is not")]
+    NotSealed,
+}
 
 #[derive(Debug, thiserror::Error)]
 pub enum UploadFileError {
@@ -88,10 +1181,137 @@ pub enum UploadFileError {
     ServiceError(#[from] opendal::Error),
     #[error("failed to write content to storage: {0}")]
     WriteFailed(#[from] std::io::Error),
+    #[error("failed to store content under its hash: {0}")]
+    StoreBlobFailed(#[from] CopyContentError),
+    #[error(transparent)]
+    KeyFailed(#[from] DataKeyError),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum HashContentError {
+    #[error(transparent)]
+    ServiceError(#[from] opendal::Error),
+    #[error("failed to read content: {0}")]
+    ReadFailed(#[from] std::io::Error),
+    #[error(transparent)]
+    DownloadFailed(#[from] DownloadFileError),
 }
 
+#[derive(Debug, thiserror::Error)]
+pub enum CopyContentError {
+    #[error(transparent)]
+    ServiceError(#[from] opendal::Error),
+    #[error("failed to copy content: {0}")]
+    WriteFailed(#[from] std::io::Error),
+    #[error(transparent)]
+    AcquireFailed(#[from] AcquireBlobError),
+    #[error("failed to hash content: {0}")]
+    HashFailed(#[from] HashContentError),
+    #[error("content to copy not found")]
+    NotFound,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum TransferContentError {
+    #[error(transparent)]
+    ServiceError(#[from] opendal::Error),
+    #[error("failed to copy content: {0}")]
+    CopyFailed(#[from] CopyContentError),
+    #[error("failed to verify copied content: {0}")]
+    VerifyFailed(#[from] HashContentError),
+    #[error("content can't be moved to another tier with the content addressed layout")]
+    ContentAddressed,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DeleteContentError {
+    #[error(transparent)]
+    ServiceError(#[from] opendal::Error),
+    #[error(transparent)]
+    ReleaseFailed(#[from] ReleaseBlobError),
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error(transparent)]
+pub struct ListContentError(#[from] opendal::Error);
+
+#[derive(Debug, thiserror::Error)]
+#[error(transparent)]
+pub struct StatContentError(#[from] opendal::Error);
+
 fn path_for(file: &File) -> String {
-    format!("{}/{}", file.owner_id, file.id)
+    object_path(file.owner_id, file.id)
+}
+
+fn object_path(owner_id: AccountId, file_id: FileId) -> String {
+    format!("{owner_id}/{file_id}")
+}
+
+/// Versions live under their own prefix, because on file systems the current content
+/// at `{owner_id}/{file_id}` could not also be a directory
+fn versions_path(owner_id: AccountId, file_id: FileId) -> String {
+    format!("{VERSIONS_PREFIX}/{owner_id}/{file_id}/")
+}
+
+fn version_path(owner_id: AccountId, file_id: FileId, version: u32) -> String {
+    format!("{VERSIONS_PREFIX}/{owner_id}/{file_id}/{version}")
+}
+
+const VERSIONS_PREFIX: &str = "versions";
+
+fn stored_path(content: &StoredContent) -> String {
+    match content.version {
+        Some(version) => version_path(content.owner_id, content.file_id, version),
+        None => object_path(content.owner_id, content.file_id),
+    }
+}
+
+/// Blobs are spread across directories by the first byte of their hash,
+/// to keep the number of entries per directory manageable
+fn blob_path(hash: &blake3::Hash, encoding: Encoding) -> String {
+    let hex = hash.to_hex();
+    format!("{BLOBS_PREFIX}/{}/{hex}{}", &hex[..2], encoding.suffix())
+}
+
+const BLOBS_PREFIX: &str = "blobs";
+
+/// New content is written here first, as its final path in the content addressed layout
+/// is only known once it has been completely hashed
+fn staging_path() -> String {
+    format!("{STAGING_PREFIX}/{}", Uuid::now_v7())
+}
+
+const STAGING_PREFIX: &str = "staging";
+
+/// Chunks of resumable uploads are named after their offset, zero padded so that they sort in order.
+/// The random suffix keeps chunks sent concurrently for the same offset from overwriting each other
+fn chunk_path(upload_id: UploadId, offset: usize, encoding: Encoding) -> String {
+    format!(
+        "{UPLOADS_PREFIX}/{upload_id}/{offset:020}-{}{}",
+        Uuid::now_v7(),
+        encoding.suffix()
+    )
+}
+
+fn chunks_path(upload_id: UploadId) -> String {
+    format!("{UPLOADS_PREFIX}/{upload_id}/")
+}
+
+const UPLOADS_PREFIX: &str = "uploads";
+
+fn parse_path(path: &str) -> Option<(AccountId, FileId, Option<u32>)> {
+    let path = path.trim_start_matches('/');
+
+    if let Some(path) = path.strip_prefix(VERSIONS_PREFIX) {
+        let mut parts = path.trim_start_matches('/').splitn(3, '/');
+        let owner_id = parts.next()?.parse().ok()?;
+        let file_id = parts.next()?.parse().ok()?;
+        let version = parts.next()?.parse().ok()?;
+        return Some((owner_id, file_id, Some(version)));
+    }
+
+    let (owner_id, file_id) = path.split_once('/')?;
+    Some((owner_id.parse().ok()?, file_id.parse().ok()?, None))
 }
 
 #[cfg(test)]
@@ -0,0 +1,59 @@
+use std::{collections::HashMap, sync::Arc};
+
+use async_trait::async_trait;
+use oxidrive_domain::make_error_wrapper;
+use tokio::sync::RwLock;
+
+mod pg;
+mod sqlite;
+
+pub use pg::*;
+pub use sqlite::*;
+
+make_error_wrapper!(AcquireBlobError);
+make_error_wrapper!(ReleaseBlobError);
+
+/// Counts how many files and versions refer to each blob of the content addressed storage layout
+#[async_trait]
+pub trait BlobRefs: Send + Sync + 'static {
+    /// Adds a reference to the blob, starting to track it if it wasn't already
+    async fn acquire(&self, hash: blake3::Hash, size: usize) -> Result<(), AcquireBlobError>;
+
+    /// Removes a reference to the blob, returning true if nothing refers to it anymore
+    /// and it can be deleted. Untracked blobs are never reported as unreferenced
+    async fn release(&self, hash: blake3::Hash) -> Result<bool, ReleaseBlobError>;
+}
+
+#[derive(Clone, Default)]
+pub struct InMemoryBlobRefs {
+    inner: Arc<RwLock<HashMap<blake3::Hash, usize>>>,
+}
+
+#[async_trait]
+impl BlobRefs for InMemoryBlobRefs {
+    async fn acquire(&self, hash: blake3::Hash, _size: usize) -> Result<(), AcquireBlobError> {
+        let mut inner = self.inner.write().await;
+        *inner.entry(hash).or_default() += 1;
+        Ok(())
+    }
+
+    async fn release(&self, hash: blake3::Hash) -> Result<bool, ReleaseBlobError> {
+        let mut inner = self.inner.write().await;
+
+        let Some(refcount) = inner.get_mut(&hash) else {
+            return Ok(false);
+        };
+
+        *refcount = refcount.saturating_sub(1);
+
+        if *refcount > 0 {
+            return Ok(false);
+        }
+
+        inner.remove(&hash);
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,72 @@
+use async_trait::async_trait;
+
+use super::{AcquireBlobError, BlobRefs, ReleaseBlobError};
+
+pub struct SqliteBlobRefs {
+    pool: sqlx::SqlitePool,
+}
+
+impl SqliteBlobRefs {
+    pub fn new(pool: sqlx::SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl BlobRefs for SqliteBlobRefs {
+    async fn acquire(&self, hash: blake3::Hash, size: usize) -> Result<(), AcquireBlobError> {
+        sqlx::query(
+            r#"
+insert into blobs (
+  hash,
+  size,
+  refcount
+) values (
+  $1,
+  $2,
+  1
+) on conflict (hash)
+do update set
+  refcount = blobs.refcount + 1
+"#,
+        )
+        .bind(hash.as_bytes().as_slice())
+        .bind(size as i64)
+        .execute(&self.pool)
+        .await
+        .map_err(AcquireBlobError::wrap)?;
+
+        Ok(())
+    }
+
+    async fn release(&self, hash: blake3::Hash) -> Result<bool, ReleaseBlobError> {
+        let mut tx = self.pool.begin().await.map_err(ReleaseBlobError::wrap)?;
+
+        let refcount: Option<i64> = sqlx::query_scalar(
+            r#"
+update blobs
+set refcount = max(refcount - 1, 0)
+where hash = $1
+returning refcount
+"#,
+        )
+        .bind(hash.as_bytes().as_slice())
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(ReleaseBlobError::wrap)?;
+
+        let unreferenced = refcount == Some(0);
+
+        if unreferenced {
+            sqlx::query("delete from blobs where hash = $1 and refcount = 0")
+                .bind(hash.as_bytes().as_slice())
+                .execute(&mut *tx)
+                .await
+                .map_err(ReleaseBlobError::wrap)?;
+        }
+
+        tx.commit().await.map_err(ReleaseBlobError::wrap)?;
+
+        Ok(unreferenced)
+    }
+}
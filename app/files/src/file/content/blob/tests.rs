@@ -0,0 +1,74 @@
+use assert2::check;
+
+use super::BlobRefs;
+
+async fn count_references<S: BlobRefs>(refs: S) {
+    let hash = blake3::hash(b"hello world");
+
+    refs.acquire(hash, 11).await.unwrap();
+    refs.acquire(hash, 11).await.unwrap();
+
+    check!(!refs.release(hash).await.unwrap());
+    check!(refs.release(hash).await.unwrap());
+
+    // the blob is not tracked anymore
+    check!(!refs.release(hash).await.unwrap());
+}
+
+async fn ignore_untracked_blobs<S: BlobRefs>(refs: S) {
+    let hash = blake3::hash(b"untracked");
+
+    check!(!refs.release(hash).await.unwrap());
+}
+
+mod inmemory {
+    use crate::file::InMemoryBlobRefs;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn it_counts_references() {
+        count_references(InMemoryBlobRefs::default()).await;
+    }
+
+    #[tokio::test]
+    async fn it_ignores_untracked_blobs() {
+        ignore_untracked_blobs(InMemoryBlobRefs::default()).await;
+    }
+}
+
+mod pg {
+    use oxidrive_database::migrate::PG_MIGRATOR;
+
+    use crate::file::PgBlobRefs;
+
+    use super::*;
+
+    #[sqlx::test(migrator = "PG_MIGRATOR")]
+    async fn it_counts_references(pool: sqlx::PgPool) {
+        count_references(PgBlobRefs::new(pool)).await;
+    }
+
+    #[sqlx::test(migrator = "PG_MIGRATOR")]
+    async fn it_ignores_untracked_blobs(pool: sqlx::PgPool) {
+        ignore_untracked_blobs(PgBlobRefs::new(pool)).await;
+    }
+}
+
+mod sqlite {
+    use oxidrive_database::migrate::SQLITE_MIGRATOR;
+
+    use crate::file::SqliteBlobRefs;
+
+    use super::*;
+
+    #[sqlx::test(migrator = "SQLITE_MIGRATOR")]
+    async fn it_counts_references(pool: sqlx::SqlitePool) {
+        count_references(SqliteBlobRefs::new(pool)).await;
+    }
+
+    #[sqlx::test(migrator = "SQLITE_MIGRATOR")]
+    async fn it_ignores_untracked_blobs(pool: sqlx::SqlitePool) {
+        ignore_untracked_blobs(SqliteBlobRefs::new(pool)).await;
+    }
+}
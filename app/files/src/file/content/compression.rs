@@ -0,0 +1,177 @@
+use std::{
+    future::ready,
+    ops::{Bound, RangeBounds},
+};
+
+use async_compression::{
+    Level,
+    tokio::bufread::{ZstdDecoder, ZstdEncoder},
+};
+use bytes::Bytes;
+use futures::{Stream, StreamExt, TryStreamExt};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use tokio_util::io::{ReaderStream, StreamReader};
+
+/// Compresses content of the configured types with zstd before storing it
+#[derive(Clone)]
+pub struct Compression {
+    content_types: GlobSet,
+    level: i32,
+}
+
+impl Compression {
+    /// Compresses content whose type matches any of the given patterns, e.g. `text/*`
+    pub fn new(
+        content_types: &[impl AsRef<str>],
+        level: i32,
+    ) -> Result<Self, InvalidContentTypeError> {
+        let mut builder = GlobSetBuilder::new();
+
+        for content_type in content_types {
+            let content_type = content_type.as_ref();
+            let glob = Glob::new(content_type)
+                .map_err(|err| InvalidContentTypeError(content_type.into(), err))?;
+            builder.add(glob);
+        }
+
+        let content_types = builder
+            .build()
+            .map_err(|err| InvalidContentTypeError(String::new(), err))?;
+
+        Ok(Self {
+            content_types,
+            level,
+        })
+    }
+
+    pub(super) fn applies_to(&self, content_type: &str) -> bool {
+        // parameters like the charset don't change how well content compresses
+        let essence = content_type.split(';').next().unwrap_or_default().trim();
+        self.content_types.is_match(essence)
+    }
+
+    pub(super) fn compress<S>(
+        &self,
+        content: S,
+    ) -> impl Stream<Item = Result<Bytes, std::io::Error>> + use<S>
+    where
+        S: Stream<Item = Result<Bytes, std::io::Error>>,
+    {
+        ReaderStream::new(ZstdEncoder::with_quality(
+            StreamReader::new(content),
+            Level::Precise(self.level),
+        ))
+    }
+}
+
+pub(super) fn decompress<S>(stored: S) -> impl Stream<Item = Result<Bytes, std::io::Error>>
+where
+    S: Stream<Item = Result<Bytes, std::io::Error>>,
+{
+    ReaderStream::new(ZstdDecoder::new(StreamReader::new(stored)))
+}
+
+/// Keeps only the requested range of content that can only be read from its start,
+/// stopping as soon as the range has been read
+pub(super) fn slice<S>(
+    content: S,
+    range: impl RangeBounds<u64>,
+) -> impl Stream<Item = Result<Bytes, std::io::Error>>
+where
+    S: Stream<Item = Result<Bytes, std::io::Error>>,
+{
+    let start = match range.start_bound() {
+        Bound::Included(start) => *start,
+        Bound::Excluded(start) => start + 1,
+        Bound::Unbounded => 0,
+    };
+
+    let end = match range.end_bound() {
+        Bound::Included(end) => Some(end + 1),
+        Bound::Excluded(end) => Some(*end),
+        Bound::Unbounded => None,
+    };
+
+    content
+        .scan(0u64, move |position, bytes| {
+            let bytes = match bytes {
+                Ok(_) if end.is_some_and(|end| *position >= end) => return ready(None),
+                Ok(bytes) => bytes,
+                Err(err) => return ready(Some(Err(err))),
+            };
+
+            let offset = *position;
+            let length = bytes.len() as u64;
+            *position += length;
+
+            let from = start.saturating_sub(offset).min(length);
+            let to = end.map_or(length, |end| end.saturating_sub(offset).min(length));
+
+            ready(Some(Ok(bytes.slice(from as usize..to.max(from) as usize))))
+        })
+        .try_filter(|bytes| ready(!bytes.is_empty()))
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("invalid content type pattern '{0}': {1}")]
+pub struct InvalidContentTypeError(String, globset::Error);
+
+#[cfg(test)]
+mod tests {
+    use assert2::check;
+
+    use super::*;
+
+    async fn collect(stream: impl Stream<Item = Result<Bytes, std::io::Error>>) -> Vec<u8> {
+        let chunks: Vec<Bytes> = stream.try_collect().await.unwrap();
+        chunks.concat()
+    }
+
+    fn pieces(content: &[u8]) -> impl Stream<Item = Result<Bytes, std::io::Error>> {
+        let pieces: Vec<_> = content
+            .chunks(7)
+            .map(|piece| Ok(Bytes::copy_from_slice(piece)))
+            .collect();
+        futures::stream::iter(pieces)
+    }
+
+    #[test]
+    fn it_matches_content_types() {
+        let compression = Compression::new(&["text/*", "application/json"], 3).unwrap();
+
+        check!(compression.applies_to("text/plain"));
+        check!(compression.applies_to("text/csv; charset=utf-8"));
+        check!(compression.applies_to("application/json"));
+        check!(!compression.applies_to("application/zstd"));
+        check!(!compression.applies_to("image/png"));
+    }
+
+    #[test]
+    fn it_rejects_invalid_patterns() {
+        check!(Compression::new(&["text/[*"], 3).is_err());
+    }
+
+    #[tokio::test]
+    async fn it_round_trips_content() {
+        let compression = Compression::new(&["*"], 3).unwrap();
+        let content = "hello world ".repeat(1000).into_bytes();
+
+        let compressed = collect(compression.compress(pieces(&content))).await;
+        check!(compressed.len() < content.len());
+
+        let decompressed = collect(decompress(pieces(&compressed))).await;
+        check!(decompressed == content);
+    }
+
+    #[tokio::test]
+    async fn it_slices_content() {
+        let content = b"hello world, how are you".as_slice();
+
+        check!(collect(slice(pieces(content), 6..11)).await == b"world");
+        check!(collect(slice(pieces(content), ..5)).await == b"hello");
+        check!(collect(slice(pieces(content), 13..)).await == b"how are you");
+        check!(collect(slice(pieces(content), 6..=6)).await == b"w");
+        check!(collect(slice(pieces(content), ..)).await == content);
+        check!(collect(slice(pieces(content), 100..)).await.is_empty());
+    }
+}
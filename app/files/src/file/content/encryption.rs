@@ -0,0 +1,651 @@
+use std::{
+    collections::HashMap,
+    ops::{Bound, Range, RangeBounds},
+    sync::Arc,
+};
+
+use bytes::{Bytes, BytesMut};
+use chacha20poly1305::{
+    AeadCore, Key, KeyInit, XChaCha20Poly1305, XNonce,
+    aead::{Aead, OsRng, Payload, rand_core::RngCore},
+};
+use futures::{Stream, StreamExt, TryStreamExt};
+use oxidrive_accounts::account::AccountId;
+use tokio::sync::RwLock;
+
+use super::{
+    CreateDataKeyError, DataKeyByAccountError, DataKeys, ListDataKeysError, SaveDataKeyError,
+    WrappedKey,
+};
+
+const KEY_ENCRYPTION_CONTEXT: &str = "oxidrive 2025-03-22 storage key encryption key";
+const MASTER_KEY_ID_CONTEXT: &str = "oxidrive 2025-03-22 storage master key id";
+
+/// Marks encrypted objects, so that content recorded as encrypted by mistake is rejected before being opened
+const MAGIC: &[u8; 8] = b"oxidenc1";
+/// Each object gets a random nonce prefix, completed with the index of each chunk
+const NONCE_PREFIX_SIZE: usize = 16;
+const HEADER_SIZE: u64 = (MAGIC.len() + NONCE_PREFIX_SIZE) as u64;
+
+/// Content is sealed in chunks of this many bytes, so that any range can be read
+/// by only decrypting the chunks it spans
+const CHUNK_SIZE: u64 = 64 * 1024;
+const TAG_SIZE: u64 = 16;
+const SEALED_CHUNK_SIZE: u64 = CHUNK_SIZE + TAG_SIZE;
+
+const ROTATE_BATCH_SIZE: usize = 100;
+
+/// Encrypts content at rest, with a data key for each account. Data keys are stored in the database,
+/// wrapped with a key derived from the configured master key
+#[derive(Clone)]
+pub struct Encryption {
+    keys: Arc<dyn DataKeys>,
+    master_key: MasterKey,
+    /// Master keys that were replaced, still needed to unwrap data keys until they are rotated
+    previous_master_keys: Arc<[MasterKey]>,
+    cache: Arc<RwLock<HashMap<AccountId, DataKey>>>,
+}
+
+impl Encryption {
+    pub fn new(keys: Arc<dyn DataKeys>, master_key: &str, previous_master_keys: &[String]) -> Self {
+        Self {
+            keys,
+            master_key: MasterKey::derive(master_key),
+            previous_master_keys: previous_master_keys
+                .iter()
+                .map(|key| MasterKey::derive(key))
+                .collect(),
+            cache: Default::default(),
+        }
+    }
+
+    /// Wraps every data key still wrapped with a previous master key with the current one, returning how many were.
+    /// Data keys themselves don't change, so stored content doesn't need to be encrypted again
+    pub async fn rotate(&self) -> Result<usize, RotateKeysError> {
+        let mut rotated = 0;
+
+        loop {
+            let keys = self
+                .keys
+                .wrapped_with_other_than(&self.master_key.id, ROTATE_BATCH_SIZE)
+                .await?;
+
+            if keys.is_empty() {
+                break;
+            }
+
+            for wrapped in keys {
+                let key = self.unwrap(&wrapped)?;
+                self.keys
+                    .save(self.master_key.wrap(wrapped.account_id, &key))
+                    .await?;
+                rotated += 1;
+            }
+        }
+
+        Ok(rotated)
+    }
+
+    /// Loads the data key of the account, creating it the first time the account stores content
+    pub(super) async fn data_key(&self, account_id: AccountId) -> Result<DataKey, DataKeyError> {
+        if let Some(key) = self.cache.read().await.get(&account_id) {
+            return Ok(key.clone());
+        }
+
+        let wrapped = match self.keys.by_account(account_id).await? {
+            Some(wrapped) => wrapped,
+            None => {
+                let key = XChaCha20Poly1305::generate_key(&mut OsRng);
+                self.keys
+                    .create(self.master_key.wrap(account_id, &key))
+                    .await?
+            }
+        };
+
+        let key = DataKey(XChaCha20Poly1305::new(&self.unwrap(&wrapped)?));
+
+        self.cache.write().await.insert(account_id, key.clone());
+
+        Ok(key)
+    }
+
+    fn unwrap(&self, wrapped: &WrappedKey) -> Result<Key, DataKeyError> {
+        let master_key = std::iter::once(&self.master_key)
+            .chain(self.previous_master_keys.iter())
+            .find(|key| key.id == wrapped.master_key_id)
+            .ok_or_else(|| {
+                DataKeyError::UnknownMasterKey(wrapped.account_id, wrapped.master_key_id.clone())
+            })?;
+
+        master_key.unwrap(wrapped)
+    }
+}
+
+#[derive(Clone)]
+struct MasterKey {
+    id: String,
+    cipher: XChaCha20Poly1305,
+}
+
+impl MasterKey {
+    fn derive(secret: &str) -> Self {
+        let key = blake3::derive_key(KEY_ENCRYPTION_CONTEXT, secret.as_bytes());
+        let id = blake3::Hash::from(blake3::derive_key(MASTER_KEY_ID_CONTEXT, secret.as_bytes()));
+
+        Self {
+            id: id.to_hex()[..16].to_string(),
+            cipher: XChaCha20Poly1305::new(Key::from_slice(&key)),
+        }
+    }
+
+    fn wrap(&self, account_id: AccountId, key: &Key) -> WrappedKey {
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+
+        let sealed = self
+            .cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: key.as_slice(),
+                    aad: account_id.as_uuid().as_bytes(),
+                },
+            )
+            .expect("a data key is always small enough to be encrypted");
+
+        WrappedKey {
+            account_id,
+            master_key_id: self.id.clone(),
+            ciphertext: [nonce.as_slice(), &sealed].concat(),
+        }
+    }
+
+    fn unwrap(&self, wrapped: &WrappedKey) -> Result<Key, DataKeyError> {
+        let corrupted = || DataKeyError::Corrupted(wrapped.account_id);
+
+        let nonce_size = XNonce::default().len();
+        if wrapped.ciphertext.len() < nonce_size {
+            return Err(corrupted());
+        }
+
+        let (nonce, sealed) = wrapped.ciphertext.split_at(nonce_size);
+
+        // binding the key to its account keeps it from being swapped with the key of another account
+        let key = self
+            .cipher
+            .decrypt(
+                XNonce::from_slice(nonce),
+                Payload {
+                    msg: sealed,
+                    aad: wrapped.account_id.as_uuid().as_bytes(),
+                },
+            )
+            .map_err(|_| corrupted())?;
+
+        if key.len() != Key::default().len() {
+            return Err(corrupted());
+        }
+
+        Ok(*Key::from_slice(&key))
+    }
+}
+
+#[derive(Clone)]
+pub(super) struct DataKey(XChaCha20Poly1305);
+
+impl DataKey {
+    /// Encrypts content into a header holding the nonce prefix, followed by each sealed chunk.
+    /// There is always at least one chunk, even for empty content, and the last one is marked as such
+    /// so that truncated content is detected
+    pub(super) fn seal<S>(self, content: S) -> impl Stream<Item = Result<Bytes, std::io::Error>>
+    where
+        S: Stream<Item = Result<Bytes, std::io::Error>> + Unpin,
+    {
+        let mut nonce_prefix = [0; NONCE_PREFIX_SIZE];
+        OsRng.fill_bytes(&mut nonce_prefix);
+
+        let header = Bytes::from([MAGIC.as_slice(), &nonce_prefix].concat());
+
+        let state = SealState {
+            key: self,
+            nonce_prefix,
+            content,
+            buffer: BytesMut::new(),
+            index: 0,
+            done: false,
+        };
+
+        let chunks = futures::stream::try_unfold(state, |mut state| async move {
+            if state.done {
+                return Ok(None);
+            }
+
+            // more than a chunk is buffered before sealing one, to know whether it is the last
+            while state.buffer.len() as u64 <= CHUNK_SIZE {
+                let Some(bytes) = state.content.try_next().await? else {
+                    let chunk = state.buffer.split();
+                    let sealed =
+                        state
+                            .key
+                            .seal_chunk(&state.nonce_prefix, state.index, true, &chunk)?;
+                    state.done = true;
+                    return Ok(Some((sealed, state)));
+                };
+
+                state.buffer.extend_from_slice(&bytes);
+            }
+
+            let chunk = state.buffer.split_to(CHUNK_SIZE as usize);
+            let sealed = state
+                .key
+                .seal_chunk(&state.nonce_prefix, state.index, false, &chunk)?;
+            state.index += 1;
+
+            Ok(Some((sealed, state)))
+        });
+
+        futures::stream::once(async { Ok(header) }).chain(chunks)
+    }
+
+    /// Decrypts the given range of the content of an object, reading `ciphertext` from [Sealed::ciphertext_range]
+    pub(super) fn open<S>(
+        self,
+        sealed: Sealed,
+        range: Range<u64>,
+        ciphertext: S,
+    ) -> impl Stream<Item = Result<Bytes, std::io::Error>>
+    where
+        S: Stream<Item = Result<Bytes, std::io::Error>> + Unpin,
+    {
+        let state = OpenState {
+            key: self,
+            index: sealed.first_chunk(range.start),
+            skip: range.start % CHUNK_SIZE,
+            remaining: range.end.saturating_sub(range.start),
+            sealed,
+            ciphertext,
+            buffer: BytesMut::new(),
+        };
+
+        futures::stream::try_unfold(state, |mut state| async move {
+            if state.remaining == 0 {
+                return Ok(None);
+            }
+
+            let length = state.sealed.sealed_chunk_size(state.index);
+
+            while (state.buffer.len() as u64) < length {
+                let Some(bytes) = state.ciphertext.try_next().await? else {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::UnexpectedEof,
+                        "encrypted content is truncated",
+                    ));
+                };
+
+                state.buffer.extend_from_slice(&bytes);
+            }
+
+            let chunk = state.buffer.split_to(length as usize);
+            let is_last = state.index + 1 == state.sealed.chunks;
+            let chunk =
+                state
+                    .key
+                    .open_chunk(&state.sealed.nonce_prefix, state.index, is_last, &chunk)?;
+
+            let start = state.skip.min(chunk.len() as u64);
+            let end = (start + state.remaining).min(chunk.len() as u64);
+            let chunk = chunk.slice(start as usize..end as usize);
+
+            state.skip = 0;
+            state.remaining -= chunk.len() as u64;
+            state.index += 1;
+
+            if is_last {
+                state.remaining = 0;
+            }
+
+            Ok(Some((chunk, state)))
+        })
+    }
+
+    fn seal_chunk(
+        &self,
+        nonce_prefix: &[u8; NONCE_PREFIX_SIZE],
+        index: u64,
+        is_last: bool,
+        chunk: &[u8],
+    ) -> Result<Bytes, std::io::Error> {
+        self.0
+            .encrypt(
+                &chunk_nonce(nonce_prefix, index),
+                Payload {
+                    msg: chunk,
+                    aad: &[is_last as u8],
+                },
+            )
+            .map(Bytes::from)
+            .map_err(|_| std::io::Error::other("failed to encrypt content"))
+    }
+
+    fn open_chunk(
+        &self,
+        nonce_prefix: &[u8; NONCE_PREFIX_SIZE],
+        index: u64,
+        is_last: bool,
+        chunk: &[u8],
+    ) -> Result<Bytes, std::io::Error> {
+        self.0
+            .decrypt(
+                &chunk_nonce(nonce_prefix, index),
+                Payload {
+                    msg: chunk,
+                    aad: &[is_last as u8],
+                },
+            )
+            .map(Bytes::from)
+            .map_err(|_| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "encrypted content is corrupted or was tampered with",
+                )
+            })
+    }
+}
+
+struct SealState<S> {
+    key: DataKey,
+    nonce_prefix: [u8; NONCE_PREFIX_SIZE],
+    content: S,
+    buffer: BytesMut,
+    index: u64,
+    done: bool,
+}
+
+struct OpenState<S> {
+    key: DataKey,
+    sealed: Sealed,
+    ciphertext: S,
+    buffer: BytesMut,
+    index: u64,
+    /// How many bytes of the next chunk come before the requested range
+    skip: u64,
+    /// How many bytes of the requested range are left to return
+    remaining: u64,
+}
+
+fn chunk_nonce(nonce_prefix: &[u8; NONCE_PREFIX_SIZE], index: u64) -> XNonce {
+    let mut nonce = XNonce::default();
+    nonce[..NONCE_PREFIX_SIZE].copy_from_slice(nonce_prefix);
+    nonce[NONCE_PREFIX_SIZE..].copy_from_slice(&index.to_be_bytes());
+    nonce
+}
+
+/// How the content of an encrypted object is laid out
+#[derive(Clone, Copy, Debug)]
+pub(super) struct Sealed {
+    nonce_prefix: [u8; NONCE_PREFIX_SIZE],
+    /// The size of the encrypted object, header included
+    length: u64,
+    chunks: u64,
+    /// The size of the decrypted content
+    pub(super) size: u64,
+}
+
+impl Sealed {
+    pub(super) const HEADER_SIZE: u64 = HEADER_SIZE;
+
+    /// Parses the header of an object of the given size, returning `None` if it is not a valid encrypted object
+    pub(super) fn parse(header: &[u8], length: u64) -> Option<Self> {
+        let nonce_prefix = header.strip_prefix(MAGIC.as_slice())?.try_into().ok()?;
+
+        let body = length.checked_sub(HEADER_SIZE)?;
+        if body < TAG_SIZE {
+            return None;
+        }
+
+        let chunks = body.div_ceil(SEALED_CHUNK_SIZE);
+
+        Some(Self {
+            nonce_prefix,
+            length,
+            chunks,
+            size: body - chunks * TAG_SIZE,
+        })
+    }
+
+    /// Resolves the requested range of the decrypted content, clamped to its size
+    pub(super) fn range(&self, range: impl RangeBounds<u64>) -> Range<u64> {
+        let start = match range.start_bound() {
+            Bound::Included(start) => *start,
+            Bound::Excluded(start) => start + 1,
+            Bound::Unbounded => 0,
+        };
+
+        let end = match range.end_bound() {
+            Bound::Included(end) => end + 1,
+            Bound::Excluded(end) => *end,
+            Bound::Unbounded => self.size,
+        };
+
+        start.min(self.size)..end.min(self.size)
+    }
+
+    /// The range of the object holding the chunks the given range of decrypted content spans.
+    /// It is never empty, even when the requested range is
+    pub(super) fn ciphertext_range(&self, range: &Range<u64>) -> Range<u64> {
+        let first = self.first_chunk(range.start);
+        let last = (range.end.saturating_sub(1) / CHUNK_SIZE).clamp(first, self.chunks - 1);
+
+        let start = HEADER_SIZE + first * SEALED_CHUNK_SIZE;
+        let end = (HEADER_SIZE + (last + 1) * SEALED_CHUNK_SIZE).min(self.length);
+
+        start..end
+    }
+
+    fn first_chunk(&self, offset: u64) -> u64 {
+        (offset / CHUNK_SIZE).min(self.chunks - 1)
+    }
+
+    fn sealed_chunk_size(&self, index: u64) -> u64 {
+        if index + 1 < self.chunks {
+            return SEALED_CHUNK_SIZE;
+        }
+
+        self.length - HEADER_SIZE - index * SEALED_CHUNK_SIZE
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DataKeyError {
+    #[error("failed to load data key")]
+    LoadFailed(#[from] DataKeyByAccountError),
+    #[error("failed to create data key")]
+    CreateFailed(#[from] CreateDataKeyError),
+    #[error("the data key of account {0} is wrapped with unknown master key {1}")]
+    UnknownMasterKey(AccountId, String),
+    #[error("the data key of account {0} could not be unwrapped")]
+    Corrupted(AccountId),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RotateKeysError {
+    #[error("storage encryption is not enabled")]
+    NotEncrypted,
+    #[error("failed to load data keys")]
+    ListFailed(#[from] ListDataKeysError),
+    #[error(transparent)]
+    UnwrapFailed(#[from] DataKeyError),
+    #[error("failed to save data key")]
+    SaveFailed(#[from] SaveDataKeyError),
+}
+
+#[cfg(test)]
+mod tests {
+    use assert2::{check, let_assert};
+    use oxidrive_accounts::account_id;
+
+    use crate::file::InMemoryDataKeys;
+
+    use super::*;
+
+    const OWNER_ID: AccountId = account_id!("0194327d-becc-7ef3-809c-35dd09f62f45");
+
+    fn key() -> DataKey {
+        DataKey(XChaCha20Poly1305::new(&XChaCha20Poly1305::generate_key(
+            &mut OsRng,
+        )))
+    }
+
+    async fn seal(key: &DataKey, content: &[u8]) -> Vec<u8> {
+        // split in uneven pieces, to exercise the buffering
+        let pieces: Vec<_> = content
+            .chunks(1000)
+            .map(|piece| Ok(Bytes::copy_from_slice(piece)))
+            .collect();
+
+        let sealed: Vec<Bytes> = key
+            .clone()
+            .seal(futures::stream::iter(pieces))
+            .try_collect()
+            .await
+            .unwrap();
+
+        sealed.concat()
+    }
+
+    async fn open(
+        key: &DataKey,
+        object: &[u8],
+        range: impl RangeBounds<u64>,
+    ) -> Result<Vec<u8>, std::io::Error> {
+        let_assert!(
+            Some(sealed) = Sealed::parse(&object[..HEADER_SIZE as usize], object.len() as u64)
+        );
+
+        let range = sealed.range(range);
+        let ciphertext = sealed.ciphertext_range(&range);
+        let ciphertext =
+            Bytes::copy_from_slice(&object[ciphertext.start as usize..ciphertext.end as usize]);
+
+        let opened: Vec<Bytes> = key
+            .clone()
+            .open(sealed, range, futures::stream::iter([Ok(ciphertext)]))
+            .try_collect()
+            .await?;
+
+        Ok(opened.concat())
+    }
+
+    fn content(size: usize) -> Vec<u8> {
+        (0..size).map(|i| (i % 251) as u8).collect()
+    }
+
+    #[tokio::test]
+    async fn it_round_trips_content_of_any_size() {
+        let key = key();
+
+        for size in [0, 1, CHUNK_SIZE as usize, CHUNK_SIZE as usize + 1, 200_000] {
+            let content = content(size);
+            let object = seal(&key, &content).await;
+
+            check!(object.len() != content.len());
+            check!(
+                open(&key, &object, ..).await.unwrap() == content,
+                "size {size}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn it_reads_ranges() {
+        let key = key();
+        let content = content(200_000);
+        let object = seal(&key, &content).await;
+
+        let chunk = CHUNK_SIZE as usize;
+
+        check!(open(&key, &object, 0..10).await.unwrap() == content[0..10]);
+        check!(
+            open(&key, &object, chunk as u64 - 5..chunk as u64 + 5)
+                .await
+                .unwrap()
+                == content[chunk - 5..chunk + 5]
+        );
+        check!(open(&key, &object, 150_000..).await.unwrap() == content[150_000..]);
+        check!(open(&key, &object, 199_999..=199_999).await.unwrap() == content[199_999..]);
+        check!(open(&key, &object, 100..100).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn it_detects_tampering() {
+        let key = key();
+        let content = content(100_000);
+        let mut object = seal(&key, &content).await;
+
+        object[HEADER_SIZE as usize + 10] ^= 1;
+
+        let_assert!(Err(err) = open(&key, &object, ..).await);
+        check!(err.kind() == std::io::ErrorKind::InvalidData);
+    }
+
+    #[tokio::test]
+    async fn it_detects_truncation() {
+        let key = key();
+        let content = content(200_000);
+        let object = seal(&key, &content).await;
+
+        // dropping the last chunk leaves a chunk that is not marked as the last one at the end
+        let truncated = &object[..(HEADER_SIZE + 2 * SEALED_CHUNK_SIZE) as usize];
+
+        let_assert!(Err(err) = open(&key, truncated, ..).await);
+        check!(err.kind() == std::io::ErrorKind::InvalidData);
+    }
+
+    #[tokio::test]
+    async fn it_rejects_content_sealed_with_another_key() {
+        let object = seal(&key(), b"hello world").await;
+
+        check!(open(&key(), &object, ..).await.is_err());
+    }
+
+    #[test]
+    fn it_ignores_plaintext_objects() {
+        check!(Sealed::parse(b"hello world, this is not encrypted", 34).is_none());
+    }
+
+    #[tokio::test]
+    async fn it_creates_one_data_key_per_account() {
+        let keys = InMemoryDataKeys::default();
+        let encryption = Encryption::new(Arc::new(keys.clone()), "master", &[]);
+
+        let object = seal(&encryption.data_key(OWNER_ID).await.unwrap(), b"hello").await;
+
+        // a new instance has an empty cache, so it must unwrap the stored key
+        let encryption = Encryption::new(Arc::new(keys), "master", &[]);
+        let key = encryption.data_key(OWNER_ID).await.unwrap();
+
+        check!(open(&key, &object, ..).await.unwrap() == b"hello");
+    }
+
+    #[tokio::test]
+    async fn it_rotates_master_keys() {
+        let keys = InMemoryDataKeys::default();
+
+        let encryption = Encryption::new(Arc::new(keys.clone()), "old", &[]);
+        let object = seal(&encryption.data_key(OWNER_ID).await.unwrap(), b"hello").await;
+
+        // the new master key can't unwrap data keys until told about the old one
+        let encryption = Encryption::new(Arc::new(keys.clone()), "new", &[]);
+        let_assert!(Err(DataKeyError::UnknownMasterKey(..)) = encryption.data_key(OWNER_ID).await);
+        let_assert!(Err(RotateKeysError::UnwrapFailed(_)) = encryption.rotate().await);
+
+        let encryption = Encryption::new(Arc::new(keys.clone()), "new", &["old".into()]);
+        check!(encryption.rotate().await.unwrap() == 1);
+        check!(encryption.rotate().await.unwrap() == 0);
+
+        // once rotated, the old master key is not needed anymore
+        let encryption = Encryption::new(Arc::new(keys), "new", &[]);
+        let key = encryption.data_key(OWNER_ID).await.unwrap();
+
+        check!(open(&key, &object, ..).await.unwrap() == b"hello");
+    }
+}
@@ -0,0 +1,98 @@
+use std::{collections::HashMap, sync::Arc};
+
+use async_trait::async_trait;
+use oxidrive_accounts::account::AccountId;
+use oxidrive_domain::make_error_wrapper;
+use tokio::sync::RwLock;
+
+mod pg;
+mod sqlite;
+
+pub use pg::*;
+pub use sqlite::*;
+
+make_error_wrapper!(DataKeyByAccountError);
+make_error_wrapper!(CreateDataKeyError);
+make_error_wrapper!(SaveDataKeyError);
+make_error_wrapper!(ListDataKeysError);
+
+/// The key encrypting the content of an account, itself encrypted with a master key
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WrappedKey {
+    pub account_id: AccountId,
+    /// Tells which master key the data key is encrypted with, without revealing anything about it
+    pub master_key_id: String,
+    /// The nonce the data key was encrypted with, followed by the encrypted data key
+    pub ciphertext: Vec<u8>,
+}
+
+/// Stores the data keys of the encrypted storage
+#[async_trait]
+pub trait DataKeys: Send + Sync + 'static {
+    async fn by_account(
+        &self,
+        account_id: AccountId,
+    ) -> Result<Option<WrappedKey>, DataKeyByAccountError>;
+
+    /// Stores the key unless the account already has one, returning the key the account ends up with
+    async fn create(&self, key: WrappedKey) -> Result<WrappedKey, CreateDataKeyError>;
+
+    /// Replaces the key of the account, e.g. once it has been wrapped with another master key
+    async fn save(&self, key: WrappedKey) -> Result<(), SaveDataKeyError>;
+
+    /// Loads up to `limit` keys wrapped with any master key other than the given one
+    async fn wrapped_with_other_than(
+        &self,
+        master_key_id: &str,
+        limit: usize,
+    ) -> Result<Vec<WrappedKey>, ListDataKeysError>;
+}
+
+#[derive(Clone, Default)]
+pub struct InMemoryDataKeys {
+    inner: Arc<RwLock<HashMap<AccountId, WrappedKey>>>,
+}
+
+#[async_trait]
+impl DataKeys for InMemoryDataKeys {
+    async fn by_account(
+        &self,
+        account_id: AccountId,
+    ) -> Result<Option<WrappedKey>, DataKeyByAccountError> {
+        let inner = self.inner.read().await;
+        Ok(inner.get(&account_id).cloned())
+    }
+
+    async fn create(&self, key: WrappedKey) -> Result<WrappedKey, CreateDataKeyError> {
+        let mut inner = self.inner.write().await;
+        Ok(inner.entry(key.account_id).or_insert(key).clone())
+    }
+
+    async fn save(&self, key: WrappedKey) -> Result<(), SaveDataKeyError> {
+        let mut inner = self.inner.write().await;
+        inner.insert(key.account_id, key);
+        Ok(())
+    }
+
+    async fn wrapped_with_other_than(
+        &self,
+        master_key_id: &str,
+        limit: usize,
+    ) -> Result<Vec<WrappedKey>, ListDataKeysError> {
+        let inner = self.inner.read().await;
+
+        let mut keys: Vec<WrappedKey> = inner
+            .values()
+            .filter(|key| key.master_key_id != master_key_id)
+            .cloned()
+            .collect();
+
+        keys.sort_by_key(|key| key.account_id);
+        keys.truncate(limit);
+
+        Ok(keys)
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,157 @@
+use async_trait::async_trait;
+use oxidrive_accounts::account::AccountId;
+
+use super::{
+    CreateDataKeyError, DataKeyByAccountError, DataKeys, ListDataKeysError, SaveDataKeyError,
+    WrappedKey,
+};
+
+pub struct SqliteDataKeys {
+    pool: sqlx::SqlitePool,
+}
+
+impl SqliteDataKeys {
+    pub fn new(pool: sqlx::SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl DataKeys for SqliteDataKeys {
+    async fn by_account(
+        &self,
+        account_id: AccountId,
+    ) -> Result<Option<WrappedKey>, DataKeyByAccountError> {
+        let key: Option<SqliteWrappedKey> = sqlx::query_as(
+            r#"
+select
+  account_id,
+  master_key_id,
+  ciphertext
+from data_keys
+where account_id = $1
+"#,
+        )
+        .bind(account_id.to_string())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(DataKeyByAccountError::wrap)?;
+
+        Ok(key.map(WrappedKey::from))
+    }
+
+    async fn create(&self, key: WrappedKey) -> Result<WrappedKey, CreateDataKeyError> {
+        let mut tx = self.pool.begin().await.map_err(CreateDataKeyError::wrap)?;
+
+        // another request may have created the key of the account in the meantime, in which case it wins
+        sqlx::query(
+            r#"
+insert into data_keys (
+  account_id,
+  master_key_id,
+  ciphertext
+) values (
+  $1,
+  $2,
+  $3
+) on conflict (account_id)
+do nothing
+"#,
+        )
+        .bind(key.account_id.to_string())
+        .bind(&key.master_key_id)
+        .bind(&key.ciphertext)
+        .execute(&mut *tx)
+        .await
+        .map_err(CreateDataKeyError::wrap)?;
+
+        let key: SqliteWrappedKey = sqlx::query_as(
+            r#"
+select
+  account_id,
+  master_key_id,
+  ciphertext
+from data_keys
+where account_id = $1
+"#,
+        )
+        .bind(key.account_id.to_string())
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(CreateDataKeyError::wrap)?;
+
+        tx.commit().await.map_err(CreateDataKeyError::wrap)?;
+
+        Ok(key.into())
+    }
+
+    async fn save(&self, key: WrappedKey) -> Result<(), SaveDataKeyError> {
+        sqlx::query(
+            r#"
+insert into data_keys (
+  account_id,
+  master_key_id,
+  ciphertext
+) values (
+  $1,
+  $2,
+  $3
+) on conflict (account_id)
+do update set
+  master_key_id = excluded.master_key_id,
+  ciphertext = excluded.ciphertext
+"#,
+        )
+        .bind(key.account_id.to_string())
+        .bind(&key.master_key_id)
+        .bind(&key.ciphertext)
+        .execute(&self.pool)
+        .await
+        .map_err(SaveDataKeyError::wrap)?;
+
+        Ok(())
+    }
+
+    async fn wrapped_with_other_than(
+        &self,
+        master_key_id: &str,
+        limit: usize,
+    ) -> Result<Vec<WrappedKey>, ListDataKeysError> {
+        let keys: Vec<SqliteWrappedKey> = sqlx::query_as(
+            r#"
+select
+  account_id,
+  master_key_id,
+  ciphertext
+from data_keys
+where master_key_id <> $1
+order by account_id
+limit $2
+"#,
+        )
+        .bind(master_key_id)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(ListDataKeysError::wrap)?;
+
+        Ok(keys.into_iter().map(WrappedKey::from).collect())
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct SqliteWrappedKey {
+    account_id: String,
+    master_key_id: String,
+    ciphertext: Vec<u8>,
+}
+
+impl From<SqliteWrappedKey> for WrappedKey {
+    fn from(key: SqliteWrappedKey) -> Self {
+        Self {
+            account_id: key.account_id.parse().unwrap(),
+            master_key_id: key.master_key_id,
+            ciphertext: key.ciphertext,
+        }
+    }
+}
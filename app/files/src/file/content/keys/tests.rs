@@ -0,0 +1,113 @@
+use assert2::{check, let_assert};
+use oxidrive_accounts::{account::AccountId, account_id};
+
+use super::{DataKeys, WrappedKey};
+
+const OWNER_ID: AccountId = account_id!("0194327d-becc-7ef3-809c-35dd09f62f45");
+const OTHER_OWNER_ID: AccountId = account_id!("01943350-aacf-7b8c-b45f-b0f5f220ab93");
+
+fn wrapped_key(account_id: AccountId, master_key_id: &str, ciphertext: &[u8]) -> WrappedKey {
+    WrappedKey {
+        account_id,
+        master_key_id: master_key_id.into(),
+        ciphertext: ciphertext.to_vec(),
+    }
+}
+
+async fn create_a_key_once<S: DataKeys>(keys: S) {
+    check!(keys.by_account(OWNER_ID).await.unwrap() == None);
+
+    let first = wrapped_key(OWNER_ID, "current", b"first");
+    check!(keys.create(first.clone()).await.unwrap() == first);
+
+    // the key created first wins
+    let second = wrapped_key(OWNER_ID, "current", b"second");
+    check!(keys.create(second).await.unwrap() == first);
+
+    let_assert!(Some(found) = keys.by_account(OWNER_ID).await.unwrap());
+    check!(found == first);
+}
+
+async fn rewrap_keys<S: DataKeys>(keys: S) {
+    keys.create(wrapped_key(OWNER_ID, "previous", b"owner"))
+        .await
+        .unwrap();
+    keys.create(wrapped_key(OTHER_OWNER_ID, "current", b"other"))
+        .await
+        .unwrap();
+
+    let stale = keys.wrapped_with_other_than("current", 10).await.unwrap();
+    check!(stale == vec![wrapped_key(OWNER_ID, "previous", b"owner")]);
+
+    let rewrapped = wrapped_key(OWNER_ID, "current", b"rewrapped");
+    keys.save(rewrapped.clone()).await.unwrap();
+
+    check!(keys.wrapped_with_other_than("current", 10).await.unwrap() == vec![]);
+
+    let_assert!(Some(found) = keys.by_account(OWNER_ID).await.unwrap());
+    check!(found == rewrapped);
+}
+
+mod inmemory {
+    use crate::file::InMemoryDataKeys;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn it_creates_a_key_once() {
+        create_a_key_once(InMemoryDataKeys::default()).await;
+    }
+
+    #[tokio::test]
+    async fn it_rewraps_keys() {
+        rewrap_keys(InMemoryDataKeys::default()).await;
+    }
+}
+
+mod pg {
+    use oxidrive_database::migrate::PG_MIGRATOR;
+
+    use crate::file::PgDataKeys;
+
+    use super::*;
+
+    #[sqlx::test(
+        migrator = "PG_MIGRATOR",
+        fixtures("../../../fixtures/postgres/accounts.sql")
+    )]
+    async fn it_creates_a_key_once(pool: sqlx::PgPool) {
+        create_a_key_once(PgDataKeys::new(pool)).await;
+    }
+
+    #[sqlx::test(
+        migrator = "PG_MIGRATOR",
+        fixtures("../../../fixtures/postgres/accounts.sql")
+    )]
+    async fn it_rewraps_keys(pool: sqlx::PgPool) {
+        rewrap_keys(PgDataKeys::new(pool)).await;
+    }
+}
+
+mod sqlite {
+    use oxidrive_database::migrate::SQLITE_MIGRATOR;
+
+    use crate::file::SqliteDataKeys;
+
+    use super::*;
+
+    #[sqlx::test(
+        migrator = "SQLITE_MIGRATOR",
+        fixtures("../../../fixtures/sqlite/accounts.sql")
+    )]
+    async fn it_creates_a_key_once(pool: sqlx::SqlitePool) {
+        create_a_key_once(SqliteDataKeys::new(pool)).await;
+    }
+
+    #[sqlx::test(
+        migrator = "SQLITE_MIGRATOR",
+        fixtures("../../../fixtures/sqlite/accounts.sql")
+    )]
+    async fn it_rewraps_keys(pool: sqlx::SqlitePool) {
+        rewrap_keys(SqliteDataKeys::new(pool)).await;
+    }
+}
@@ -2,22 +2,30 @@ use assert2::{check, let_assert};
 use bytes::BytesMut;
 use futures::{StreamExt, TryStreamExt};
 
-use crate::file;
+use crate::File;
+use crate::file::{self, version::FileVersion};
+use crate::upload::UploadId;
 
-use super::FileStorage;
+use super::{
+    CopyContentError, FileStorage, StoredContent, Transfer, TransferContentError, path_for,
+};
 
 async fn upload_and_download_a_file(storage: FileStorage) {
     let owner = oxidrive_accounts::account::fixtures::account();
-    let file = file::fixtures::file(owner);
+    let mut file = file::fixtures::file(owner);
 
     // It creates a new file
     let data = "hello world";
     let content = file::fixtures::content(data).boxed();
 
-    let size = storage.upload(&file, content).await.unwrap();
-    check!(size == data.len());
+    let uploaded = storage.upload(&file, content).await.unwrap();
+    check!(uploaded.size == data.len());
+    check!(uploaded.hash == blake3::hash(data.as_bytes()));
+    storage.promote(&file, &uploaded).await.unwrap();
+    file.set_hash(uploaded.hash);
+    file.encoding = uploaded.encoding;
 
-    let downloaded = storage.download(&file).await.unwrap().unwrap();
+    let downloaded = storage.download(&file, ..).await.unwrap().unwrap();
 
     let downloaded: BytesMut = downloaded.try_collect().await.unwrap();
     check!(downloaded.freeze() == data);
@@ -26,23 +34,527 @@ async fn upload_and_download_a_file(storage: FileStorage) {
     let data = "updated";
     let content = file::fixtures::content(data).boxed();
 
-    let size = storage.upload(&file, content).await.unwrap();
-    check!(size == data.len());
+    let previous = file.clone();
+    let uploaded = storage.upload(&file, content).await.unwrap();
+    check!(uploaded.size == data.len());
+    storage.promote(&file, &uploaded).await.unwrap();
+    file.set_hash(uploaded.hash);
+    file.encoding = uploaded.encoding;
+    storage.release(&previous).await.unwrap();
 
-    let downloaded = storage.download(&file).await.unwrap().unwrap();
+    let downloaded = storage.download(&file, ..).await.unwrap().unwrap();
 
     let downloaded: BytesMut = downloaded.try_collect().await.unwrap();
     check!(downloaded.freeze() == data);
 }
 
+async fn download_a_range(storage: FileStorage) {
+    let owner = oxidrive_accounts::account::fixtures::account();
+    let mut file = file::fixtures::file(owner);
+
+    let content = file::fixtures::content("hello world").boxed();
+    let uploaded = storage.upload(&file, content).await.unwrap();
+    storage.promote(&file, &uploaded).await.unwrap();
+    file.set_hash(uploaded.hash);
+    file.encoding = uploaded.encoding;
+
+    let downloaded = storage.download(&file, 6..11).await.unwrap().unwrap();
+    let downloaded: BytesMut = downloaded.try_collect().await.unwrap();
+    check!(downloaded.freeze() == "world");
+
+    let downloaded = storage.download(&file, ..5).await.unwrap().unwrap();
+    let downloaded: BytesMut = downloaded.try_collect().await.unwrap();
+    check!(downloaded.freeze() == "hello");
+}
+
 async fn download_a_file_that_does_not_exist(storage: FileStorage) {
     let owner = oxidrive_accounts::account::fixtures::account();
     let file = file::fixtures::file(owner);
 
-    let found = storage.download(&file).await.unwrap();
+    let found = storage.download(&file, ..).await.unwrap();
+    let_assert!(None = found);
+}
+
+async fn delete_a_file(storage: FileStorage) {
+    let owner = oxidrive_accounts::account::fixtures::account();
+    let mut file = file::fixtures::file(owner);
+
+    let content = file::fixtures::content("hello world").boxed();
+    let uploaded = storage.upload(&file, content).await.unwrap();
+    storage.promote(&file, &uploaded).await.unwrap();
+    file.set_hash(uploaded.hash);
+    file.encoding = uploaded.encoding;
+
+    storage.delete(&file).await.unwrap();
+
+    let found = storage.download(&file, ..).await.unwrap();
+    let_assert!(None = found);
+
+    // ensuring FileStorage::delete is idempotent
+    storage.delete(&file).await.unwrap();
+}
+
+async fn archive_and_restore_versions(storage: FileStorage) {
+    let owner = oxidrive_accounts::account::fixtures::account();
+    let mut file = file::fixtures::file(owner);
+
+    let uploaded = storage
+        .upload(&file, file::fixtures::content("first").boxed())
+        .await
+        .unwrap();
+    storage.promote(&file, &uploaded).await.unwrap();
+    file.set_size(uploaded.size);
+    file.set_hash(uploaded.hash);
+    file.encoding = uploaded.encoding;
+
+    storage.archive(&file, 1).await.unwrap();
+    let version = FileVersion::of(&file, 1);
+
+    let previous = file.clone();
+    let uploaded = storage
+        .upload(&file, file::fixtures::content("second").boxed())
+        .await
+        .unwrap();
+    storage.promote(&file, &uploaded).await.unwrap();
+    file.set_size(uploaded.size);
+    file.set_hash(uploaded.hash);
+    file.encoding = uploaded.encoding;
+    storage.release(&previous).await.unwrap();
+
+    let downloaded = storage
+        .download_version(&file, &version, ..)
+        .await
+        .unwrap()
+        .unwrap();
+    let downloaded: BytesMut = downloaded.try_collect().await.unwrap();
+    check!(downloaded.freeze() == "first");
+
+    let previous = file.clone();
+    storage.restore_version(&file, &version).await.unwrap();
+    file.restore_version(&version);
+    storage.release(&previous).await.unwrap();
+
+    let current = storage.download(&file, ..).await.unwrap().unwrap();
+    let current: BytesMut = current.try_collect().await.unwrap();
+    check!(current.freeze() == "first");
+
+    storage.delete_version(&file, &version).await.unwrap();
+    storage.delete(&file).await.unwrap();
+
+    let found = storage.download_version(&file, &version, ..).await.unwrap();
+    let_assert!(None = found);
+
+    let found = storage.download(&file, ..).await.unwrap();
+    let_assert!(None = found);
+}
+
+async fn list_stored_content(storage: FileStorage) {
+    let owner = oxidrive_accounts::account::fixtures::account();
+    let file = file::fixtures::file(owner);
+
+    let content = file::fixtures::content("hello world").boxed();
+    let uploaded = storage.upload(&file, content).await.unwrap();
+    storage.promote(&file, &uploaded).await.unwrap();
+
+    let stored: Vec<StoredContent> = storage.list().await.unwrap().try_collect().await.unwrap();
+    check!(stored.len() == 1);
+
+    let_assert!(Some(content) = stored.first());
+    check!(content.owner_id == file.owner_id);
+    check!(content.file_id == file.id);
+    check!(content.version.is_none());
+
+    storage.delete_stored(content).await.unwrap();
+
+    let found = storage.download(&file, ..).await.unwrap();
+    let_assert!(None = found);
+}
+
+async fn deduplicate_identical_content(storage: FileStorage) {
+    // identical content is shared across accounts too
+    let mut first = file::fixtures::file(oxidrive_accounts::account::fixtures::account());
+    let mut second = file::fixtures::file(oxidrive_accounts::account::fixtures::account());
+
+    let uploaded = storage
+        .upload(&first, file::fixtures::content("hello world").boxed())
+        .await
+        .unwrap();
+    storage.promote(&first, &uploaded).await.unwrap();
+    first.set_hash(uploaded.hash);
+    first.encoding = uploaded.encoding;
+
+    let uploaded = storage
+        .upload(&second, file::fixtures::content("hello world").boxed())
+        .await
+        .unwrap();
+    storage.promote(&second, &uploaded).await.unwrap();
+    second.set_hash(uploaded.hash);
+    second.encoding = uploaded.encoding;
+
+    // content stored under its hash is not listed as per-file content
+    let stored: Vec<StoredContent> = storage.list().await.unwrap().try_collect().await.unwrap();
+    check!(stored.is_empty());
+
+    // the content is shared until the last file referring to it is deleted
+    storage.delete(&first).await.unwrap();
+
+    let downloaded = storage.download(&second, ..).await.unwrap().unwrap();
+    let downloaded: BytesMut = downloaded.try_collect().await.unwrap();
+    check!(downloaded.freeze() == "hello world");
+
+    storage.delete(&second).await.unwrap();
+
+    let found = storage.download(&first, ..).await.unwrap();
     let_assert!(None = found);
 }
 
+async fn deduplicate_per_file_content(per_file: FileStorage, content_addressed: FileStorage) {
+    let owner = oxidrive_accounts::account::fixtures::account();
+    let mut file = file::fixtures::file(owner);
+
+    let uploaded = per_file
+        .upload(&file, file::fixtures::content("hello world").boxed())
+        .await
+        .unwrap();
+    per_file.promote(&file, &uploaded).await.unwrap();
+    file.set_hash(uploaded.hash);
+    file.encoding = uploaded.encoding;
+
+    // content stored per file keeps being readable after switching layout
+    let found = content_addressed.download(&file, ..).await.unwrap();
+    let_assert!(Some(_) = found);
+
+    let stored: Vec<StoredContent> = content_addressed
+        .list()
+        .await
+        .unwrap()
+        .try_collect()
+        .await
+        .unwrap();
+    let_assert!([content] = stored.as_slice());
+    check!(
+        content_addressed
+            .hash_stored(content, file.encoding)
+            .await
+            .unwrap()
+            == uploaded.hash
+    );
+
+    content_addressed.deduplicate(&file).await.unwrap();
+
+    let stored: Vec<StoredContent> = content_addressed
+        .list()
+        .await
+        .unwrap()
+        .try_collect()
+        .await
+        .unwrap();
+    check!(stored.is_empty());
+
+    let downloaded = content_addressed
+        .download(&file, ..)
+        .await
+        .unwrap()
+        .unwrap();
+    let downloaded: BytesMut = downloaded.try_collect().await.unwrap();
+    check!(downloaded.freeze() == "hello world");
+}
+
+async fn keep_current_content_until_promoted(storage: FileStorage) {
+    let owner = oxidrive_accounts::account::fixtures::account();
+    let mut file = file::fixtures::file(owner);
+
+    let content = file::fixtures::content("hello world").boxed();
+    let uploaded = storage.upload(&file, content).await.unwrap();
+    storage.promote(&file, &uploaded).await.unwrap();
+    file.set_hash(uploaded.hash);
+    file.encoding = uploaded.encoding;
+
+    // an interrupted upload leaves the current content untouched
+    let interrupted = futures::stream::iter([
+        Ok(bytes::Bytes::from("updated")),
+        Err(std::io::Error::other("connection reset")),
+    ]);
+    let failed = storage.upload(&file, interrupted).await;
+    let_assert!(Err(_) = failed);
+
+    // staged content is not visible until it is promoted
+    let content = file::fixtures::content("discarded").boxed();
+    let staged = storage.upload(&file, content).await.unwrap();
+    storage.discard(&staged).await.unwrap();
+
+    let downloaded = storage.download(&file, ..).await.unwrap().unwrap();
+    let downloaded: BytesMut = downloaded.try_collect().await.unwrap();
+    check!(downloaded.freeze() == "hello world");
+
+    let stored: Vec<StoredContent> = storage.list().await.unwrap().try_collect().await.unwrap();
+    check!(stored.iter().all(|content| content.file_id == file.id));
+}
+
+async fn stage_existing_content(storage: FileStorage) {
+    let owner = oxidrive_accounts::account::fixtures::account();
+    let mut source = file::fixtures::file(owner.clone());
+    let mut file = file::fixtures::file(owner);
+
+    let content = file::fixtures::content("hello world").boxed();
+    let uploaded = storage.upload(&source, content).await.unwrap();
+    storage.promote(&source, &uploaded).await.unwrap();
+    source.set_size(uploaded.size);
+    source.set_hash(uploaded.hash);
+    source.encoding = uploaded.encoding;
+
+    let staged = storage.stage_existing(&file, &source).await.unwrap();
+    check!(staged.size == source.size);
+    check!(staged.hash == uploaded.hash);
+    storage.promote(&file, &staged).await.unwrap();
+    file.set_size(staged.size);
+    file.set_hash(staged.hash);
+    file.encoding = staged.encoding;
+
+    // the content outlives the file it was taken from
+    storage.delete(&source).await.unwrap();
+
+    let downloaded = storage.download(&file, ..).await.unwrap().unwrap();
+    let downloaded: BytesMut = downloaded.try_collect().await.unwrap();
+    check!(downloaded.freeze() == "hello world");
+}
+
+async fn stage_content_stored_before_hashing(per_file: FileStorage, storage: FileStorage) {
+    let owner = oxidrive_accounts::account::fixtures::account();
+    let mut source = file::fixtures::file(owner.clone());
+    let mut file = file::fixtures::file(owner);
+
+    let content = file::fixtures::content("hello world").boxed();
+    let uploaded = per_file.upload(&source, content).await.unwrap();
+    per_file.promote(&source, &uploaded).await.unwrap();
+    source.set_size(uploaded.size);
+    source.encoding = uploaded.encoding;
+
+    let staged = storage.stage_existing(&file, &source).await.unwrap();
+    check!(staged.hash == uploaded.hash);
+    storage.promote(&file, &staged).await.unwrap();
+    file.set_size(staged.size);
+    file.set_hash(staged.hash);
+    file.encoding = staged.encoding;
+
+    let downloaded = storage.download(&file, ..).await.unwrap().unwrap();
+    let downloaded: BytesMut = downloaded.try_collect().await.unwrap();
+    check!(downloaded.freeze() == "hello world");
+
+    let missing = file::fixtures::file(oxidrive_accounts::account::fixtures::account());
+    let_assert!(Err(CopyContentError::NotFound) = storage.stage_existing(&file, &missing).await);
+}
+
+async fn hash_current_content(storage: FileStorage) {
+    let owner = oxidrive_accounts::account::fixtures::account();
+    let mut file = file::fixtures::file(owner);
+
+    let content = file::fixtures::content("hello world").boxed();
+    let uploaded = storage.upload(&file, content).await.unwrap();
+    storage.promote(&file, &uploaded).await.unwrap();
+    file.set_hash(uploaded.hash);
+    file.encoding = uploaded.encoding;
+
+    let_assert!(Some(hash) = storage.hash_current(&file).await.unwrap());
+    check!(hash == blake3::hash(b"hello world"));
+
+    storage.delete(&file).await.unwrap();
+
+    let_assert!(None = storage.hash_current(&file).await.unwrap());
+}
+
+async fn write_and_read_chunks(storage: FileStorage) {
+    let owner = oxidrive_accounts::account::fixtures::account();
+    let upload_id = UploadId::new();
+
+    let chunk = storage
+        .write_chunk(
+            owner.id,
+            upload_id,
+            0,
+            file::fixtures::content("hello ").boxed(),
+        )
+        .await
+        .unwrap();
+    check!(chunk.size == 6);
+
+    // content received before an interruption is kept
+    let interrupted = futures::stream::iter([
+        Ok(bytes::Bytes::from("world")),
+        Err(std::io::Error::other("connection reset")),
+        Ok(bytes::Bytes::from("never received")),
+    ]);
+    let chunk = storage
+        .write_chunk(owner.id, upload_id, 6, interrupted)
+        .await
+        .unwrap();
+    check!(chunk.size == 5);
+
+    let content = storage.read_chunks(owner.id, upload_id).await.unwrap();
+    let content: BytesMut = content.try_collect().await.unwrap();
+    check!(content.freeze() == "hello world");
+
+    // chunks are not mistaken for file content
+    let stored: Vec<StoredContent> = storage.list().await.unwrap().try_collect().await.unwrap();
+    check!(stored.is_empty());
+
+    storage.delete_chunks(upload_id).await.unwrap();
+
+    let content = storage.read_chunks(owner.id, upload_id).await.unwrap();
+    let content: BytesMut = content.try_collect().await.unwrap();
+    check!(content.is_empty());
+}
+
+async fn transfer_content(storage: FileStorage) {
+    let owner = oxidrive_accounts::account::fixtures::account();
+    let mut file = file::fixtures::file(owner.clone());
+    let target = storage.with_backend(FileStorage::memory());
+
+    let uploaded = storage
+        .upload(&file, file::fixtures::content("first").boxed())
+        .await
+        .unwrap();
+    storage.promote(&file, &uploaded).await.unwrap();
+    file.set_size(uploaded.size);
+    file.set_hash(uploaded.hash);
+    file.encoding = uploaded.encoding;
+
+    storage.archive(&file, 1).await.unwrap();
+    let version = FileVersion::of(&file, 1);
+
+    let previous = file.clone();
+    let uploaded = storage
+        .upload(&file, file::fixtures::content("second").boxed())
+        .await
+        .unwrap();
+    storage.promote(&file, &uploaded).await.unwrap();
+    file.set_size(uploaded.size);
+    file.set_hash(uploaded.hash);
+    file.encoding = uploaded.encoding;
+    storage.release(&previous).await.unwrap();
+
+    let_assert!(Transfer::Copied { .. } = storage.transfer(&target, &file).await.unwrap());
+    let_assert!(
+        Transfer::Copied { .. } = storage
+            .transfer_version(&target, &file, &version)
+            .await
+            .unwrap()
+    );
+
+    let downloaded = target.download(&file, ..).await.unwrap().unwrap();
+    let downloaded: BytesMut = downloaded.try_collect().await.unwrap();
+    check!(downloaded.freeze() == "second");
+
+    let downloaded = target
+        .download_version(&file, &version, ..)
+        .await
+        .unwrap()
+        .unwrap();
+    let downloaded: BytesMut = downloaded.try_collect().await.unwrap();
+    check!(downloaded.freeze() == "first");
+
+    // a migration run again skips what was already copied
+    check!(storage.transfer(&target, &file).await.unwrap() == Transfer::Skipped);
+
+    // content of the same size left behind by an interrupted copy is copied again
+    let path = storage
+        .resolve(file.hash, file.encoding, path_for(&file))
+        .await
+        .unwrap();
+    let length = target.service.stat(&path).await.unwrap().content_length();
+    target
+        .service
+        .write(&path, vec![0u8; length as usize])
+        .await
+        .unwrap();
+    let_assert!(Transfer::Copied { .. } = storage.transfer(&target, &file).await.unwrap());
+
+    let downloaded = target.download(&file, ..).await.unwrap().unwrap();
+    let downloaded: BytesMut = downloaded.try_collect().await.unwrap();
+    check!(downloaded.freeze() == "second");
+
+    let missing = file::fixtures::file(owner);
+    check!(storage.transfer(&target, &missing).await.unwrap() == Transfer::Missing);
+}
+
+async fn discard_corrupted_transfers(storage: FileStorage) {
+    let owner = oxidrive_accounts::account::fixtures::account();
+    let mut file = file::fixtures::file(owner);
+    let target = storage.with_backend(FileStorage::memory());
+
+    let uploaded = storage
+        .upload(&file, file::fixtures::content("hello world").boxed())
+        .await
+        .unwrap();
+    storage.promote(&file, &uploaded).await.unwrap();
+    file.set_hash(blake3::hash(b"something else"));
+
+    check!(storage.transfer(&target, &file).await.unwrap() == Transfer::Corrupted);
+
+    let found = target.download(&file, ..).await.unwrap();
+    let_assert!(None = found);
+}
+
+async fn move_content_between_tiers(storage: FileStorage) {
+    let owner = oxidrive_accounts::account::fixtures::account();
+    let mut file = file::fixtures::file(owner);
+    let storage = storage.with_tier("cold", FileStorage::memory());
+
+    let uploaded = storage
+        .upload(&file, file::fixtures::content("first").boxed())
+        .await
+        .unwrap();
+    storage.promote(&file, &uploaded).await.unwrap();
+    file.set_size(uploaded.size);
+    file.set_hash(uploaded.hash);
+    file.encoding = uploaded.encoding;
+
+    let_assert!(Transfer::Copied { .. } = storage.move_to_tier(&file, "cold").await.unwrap());
+    check!(storage.move_to_tier(&file, "unknown").await.is_err());
+
+    let previous = file.clone();
+    file.tier = Some("cold".into());
+    storage.evict(&previous).await.unwrap();
+
+    check!(storage.modified_at(&previous).await.unwrap() == None);
+    check!(storage.modified_at(&file).await.unwrap().is_some());
+
+    // content is read from the tier holding it
+    let downloaded = storage.download(&file, ..).await.unwrap().unwrap();
+    let downloaded: BytesMut = downloaded.try_collect().await.unwrap();
+    check!(downloaded.freeze() == "first");
+
+    // versions are archived to the default backend, as is new content
+    storage.archive(&file, 1).await.unwrap();
+    let version = FileVersion::of(&file, 1);
+
+    let previous = file.clone();
+    let uploaded = storage
+        .upload(&file, file::fixtures::content("second").boxed())
+        .await
+        .unwrap();
+    storage.promote(&file, &uploaded).await.unwrap();
+    file.set_size(uploaded.size);
+    file.set_hash(uploaded.hash);
+    file.encoding = uploaded.encoding;
+    file.tier = None;
+    storage.release(&previous).await.unwrap();
+
+    check!(storage.modified_at(&previous).await.unwrap() == None);
+
+    let downloaded = storage.download(&file, ..).await.unwrap().unwrap();
+    let downloaded: BytesMut = downloaded.try_collect().await.unwrap();
+    check!(downloaded.freeze() == "second");
+
+    let downloaded = storage
+        .download_version(&file, &version, ..)
+        .await
+        .unwrap()
+        .unwrap();
+    let downloaded: BytesMut = downloaded.try_collect().await.unwrap();
+    check!(downloaded.freeze() == "first");
+}
+
 mod inmemory {
     use super::*;
 
@@ -52,11 +564,503 @@ mod inmemory {
         upload_and_download_a_file(store).await;
     }
 
+    #[tokio::test]
+    async fn it_downloads_a_range() {
+        let store = FileStorage::memory();
+        download_a_range(store).await;
+    }
+
     #[tokio::test]
     async fn it_does_not_download_a_file_that_does_not_exist() {
         let store = FileStorage::memory();
         download_a_file_that_does_not_exist(store).await;
     }
+
+    #[tokio::test]
+    async fn it_deletes_a_file() {
+        let store = FileStorage::memory();
+        delete_a_file(store).await;
+    }
+
+    #[tokio::test]
+    async fn it_lists_stored_content() {
+        let store = FileStorage::memory();
+        list_stored_content(store).await;
+    }
+
+    #[tokio::test]
+    async fn it_archives_and_restores_versions() {
+        let store = FileStorage::memory();
+        archive_and_restore_versions(store).await;
+    }
+
+    #[tokio::test]
+    async fn it_keeps_current_content_until_promoted() {
+        let store = FileStorage::memory();
+        keep_current_content_until_promoted(store).await;
+    }
+
+    #[tokio::test]
+    async fn it_stages_existing_content() {
+        let store = FileStorage::memory();
+        stage_existing_content(store).await;
+    }
+
+    #[tokio::test]
+    async fn it_stages_content_stored_before_hashing() {
+        let store = FileStorage::memory();
+        stage_content_stored_before_hashing(store.clone(), store).await;
+    }
+
+    #[tokio::test]
+    async fn it_hashes_current_content() {
+        let store = FileStorage::memory();
+        hash_current_content(store).await;
+    }
+
+    #[tokio::test]
+    async fn it_writes_and_reads_chunks() {
+        let store = FileStorage::memory();
+        write_and_read_chunks(store).await;
+    }
+
+    #[tokio::test]
+    async fn it_transfers_content() {
+        let store = FileStorage::memory();
+        transfer_content(store).await;
+    }
+
+    #[tokio::test]
+    async fn it_discards_corrupted_transfers() {
+        let store = FileStorage::memory();
+        discard_corrupted_transfers(store).await;
+    }
+
+    #[tokio::test]
+    async fn it_moves_content_between_tiers() {
+        let store = FileStorage::memory();
+        move_content_between_tiers(store).await;
+    }
+}
+
+mod content_addressed {
+    use std::sync::Arc;
+
+    use rstest::{fixture, rstest};
+
+    use crate::file::InMemoryBlobRefs;
+
+    use super::*;
+
+    #[fixture]
+    fn storage() -> FileStorage {
+        FileStorage::memory().content_addressed(Arc::new(InMemoryBlobRefs::default()))
+    }
+
+    #[tokio::test]
+    #[rstest]
+    async fn it_uploads_and_downloads_a_file(storage: FileStorage) {
+        upload_and_download_a_file(storage).await;
+    }
+
+    #[tokio::test]
+    #[rstest]
+    async fn it_downloads_a_range(storage: FileStorage) {
+        download_a_range(storage).await;
+    }
+
+    #[tokio::test]
+    #[rstest]
+    async fn it_does_not_download_a_file_that_does_not_exist(storage: FileStorage) {
+        download_a_file_that_does_not_exist(storage).await;
+    }
+
+    #[tokio::test]
+    #[rstest]
+    async fn it_deletes_a_file(storage: FileStorage) {
+        delete_a_file(storage).await;
+    }
+
+    #[tokio::test]
+    #[rstest]
+    async fn it_archives_and_restores_versions(storage: FileStorage) {
+        archive_and_restore_versions(storage).await;
+    }
+
+    #[tokio::test]
+    #[rstest]
+    async fn it_keeps_current_content_until_promoted(storage: FileStorage) {
+        keep_current_content_until_promoted(storage).await;
+    }
+
+    #[tokio::test]
+    #[rstest]
+    async fn it_stages_existing_content(storage: FileStorage) {
+        stage_existing_content(storage).await;
+    }
+
+    #[tokio::test]
+    #[rstest]
+    async fn it_hashes_current_content(storage: FileStorage) {
+        hash_current_content(storage).await;
+    }
+
+    #[tokio::test]
+    #[rstest]
+    async fn it_deduplicates_identical_content(storage: FileStorage) {
+        deduplicate_identical_content(storage).await;
+    }
+
+    #[tokio::test]
+    async fn it_deduplicates_per_file_content() {
+        let per_file = FileStorage::memory();
+        let content_addressed = per_file
+            .clone()
+            .content_addressed(Arc::new(InMemoryBlobRefs::default()));
+
+        deduplicate_per_file_content(per_file, content_addressed).await;
+    }
+
+    #[tokio::test]
+    async fn it_stages_content_stored_before_hashing() {
+        let per_file = FileStorage::memory();
+        let content_addressed = per_file
+            .clone()
+            .content_addressed(Arc::new(InMemoryBlobRefs::default()));
+
+        stage_content_stored_before_hashing(per_file, content_addressed).await;
+    }
+
+    #[tokio::test]
+    #[rstest]
+    async fn it_transfers_content(storage: FileStorage) {
+        transfer_content(storage).await;
+    }
+
+    #[tokio::test]
+    #[rstest]
+    async fn it_does_not_move_content_between_tiers(storage: FileStorage) {
+        let owner = oxidrive_accounts::account::fixtures::account();
+        let storage = storage.with_tier("cold", FileStorage::memory());
+
+        let mut file = file::fixtures::file(owner.clone());
+        let mut other = file::fixtures::file(owner);
+        for file in [&mut file, &mut other] {
+            let uploaded = storage
+                .upload(file, file::fixtures::content("shared").boxed())
+                .await
+                .unwrap();
+            storage.promote(file, &uploaded).await.unwrap();
+            file.set_size(uploaded.size);
+            file.set_hash(uploaded.hash);
+            file.encoding = uploaded.encoding;
+        }
+
+        // content is found under its hash
+        check!(storage.modified_at(&file).await.unwrap().is_some());
+
+        let_assert!(
+            Err(TransferContentError::ContentAddressed) = storage.move_to_tier(&file, "cold").await
+        );
+
+        // evicting content shared with another file only lets go of the reference to it
+        storage.evict(&file).await.unwrap();
+        let downloaded = storage.download(&other, ..).await.unwrap().unwrap();
+        let downloaded: BytesMut = downloaded.try_collect().await.unwrap();
+        check!(downloaded.freeze() == "shared");
+    }
+}
+
+mod encrypted {
+    use std::sync::Arc;
+
+    use rstest::{fixture, rstest};
+
+    use crate::file::{Encryption, InMemoryDataKeys};
+
+    use super::*;
+
+    #[fixture]
+    fn storage() -> FileStorage {
+        FileStorage::memory().encrypted(Encryption::new(
+            Arc::new(InMemoryDataKeys::default()),
+            "master key",
+            &[],
+        ))
+    }
+
+    #[tokio::test]
+    #[rstest]
+    async fn it_uploads_and_downloads_a_file(storage: FileStorage) {
+        upload_and_download_a_file(storage).await;
+    }
+
+    #[tokio::test]
+    #[rstest]
+    async fn it_downloads_a_range(storage: FileStorage) {
+        download_a_range(storage).await;
+    }
+
+    #[tokio::test]
+    #[rstest]
+    async fn it_does_not_download_a_file_that_does_not_exist(storage: FileStorage) {
+        download_a_file_that_does_not_exist(storage).await;
+    }
+
+    #[tokio::test]
+    #[rstest]
+    async fn it_deletes_a_file(storage: FileStorage) {
+        delete_a_file(storage).await;
+    }
+
+    #[tokio::test]
+    #[rstest]
+    async fn it_archives_and_restores_versions(storage: FileStorage) {
+        archive_and_restore_versions(storage).await;
+    }
+
+    #[tokio::test]
+    #[rstest]
+    async fn it_keeps_current_content_until_promoted(storage: FileStorage) {
+        keep_current_content_until_promoted(storage).await;
+    }
+
+    #[tokio::test]
+    #[rstest]
+    async fn it_stages_existing_content(storage: FileStorage) {
+        stage_existing_content(storage).await;
+    }
+
+    #[tokio::test]
+    #[rstest]
+    async fn it_hashes_current_content(storage: FileStorage) {
+        hash_current_content(storage).await;
+    }
+
+    #[tokio::test]
+    #[rstest]
+    async fn it_writes_and_reads_chunks(storage: FileStorage) {
+        write_and_read_chunks(storage).await;
+    }
+
+    #[tokio::test]
+    #[rstest]
+    async fn it_encrypts_content_at_rest(storage: FileStorage) {
+        let owner = oxidrive_accounts::account::fixtures::account();
+        let file = file::fixtures::file(owner);
+
+        let content = file::fixtures::content("hello world").boxed();
+        let uploaded = storage.upload(&file, content).await.unwrap();
+        storage.promote(&file, &uploaded).await.unwrap();
+        check!(uploaded.encoding.encrypted);
+
+        let stored = storage.service.read(&path_for(&file)).await.unwrap();
+        let stored = stored.to_vec();
+        check!(!stored.windows(5).any(|window| window == b"hello"));
+    }
+
+    #[tokio::test]
+    #[rstest]
+    async fn it_reads_content_stored_before_encryption(storage: FileStorage) {
+        let owner = oxidrive_accounts::account::fixtures::account();
+        let file = file::fixtures::file(owner);
+
+        storage
+            .service
+            .write(&path_for(&file), bytes::Bytes::from("hello world"))
+            .await
+            .unwrap();
+
+        let downloaded = storage.download(&file, 6..).await.unwrap().unwrap();
+        let downloaded: BytesMut = downloaded.try_collect().await.unwrap();
+        check!(downloaded.freeze() == "world");
+    }
+
+    #[tokio::test]
+    #[rstest]
+    async fn it_reads_plaintext_that_looks_encrypted(storage: FileStorage) {
+        let owner = oxidrive_accounts::account::fixtures::account();
+        let file = file::fixtures::file(owner);
+
+        // only the metadata of the file tells whether its content is encrypted
+        let data = format!("oxidenc1{}", "a".repeat(64));
+        storage
+            .service
+            .write(&path_for(&file), bytes::Bytes::from(data.clone()))
+            .await
+            .unwrap();
+
+        let downloaded = storage.download(&file, ..).await.unwrap().unwrap();
+        let downloaded: BytesMut = downloaded.try_collect().await.unwrap();
+        check!(downloaded.freeze() == data);
+    }
+
+    #[tokio::test]
+    #[rstest]
+    async fn it_transfers_content(storage: FileStorage) {
+        transfer_content(storage).await;
+    }
+
+    #[tokio::test]
+    #[rstest]
+    async fn it_moves_content_between_tiers(storage: FileStorage) {
+        move_content_between_tiers(storage).await;
+    }
+}
+
+mod compressed {
+    use std::sync::Arc;
+
+    use rstest::{fixture, rstest};
+
+    use crate::file::{Compression, Encryption, InMemoryDataKeys};
+
+    use super::*;
+
+    #[fixture]
+    fn storage() -> FileStorage {
+        FileStorage::memory().compressed(Compression::new(&["*"], 3).unwrap())
+    }
+
+    #[tokio::test]
+    #[rstest]
+    async fn it_uploads_and_downloads_a_file(storage: FileStorage) {
+        upload_and_download_a_file(storage).await;
+    }
+
+    #[tokio::test]
+    #[rstest]
+    async fn it_downloads_a_range(storage: FileStorage) {
+        download_a_range(storage).await;
+    }
+
+    #[tokio::test]
+    #[rstest]
+    async fn it_archives_and_restores_versions(storage: FileStorage) {
+        archive_and_restore_versions(storage).await;
+    }
+
+    #[tokio::test]
+    #[rstest]
+    async fn it_keeps_current_content_until_promoted(storage: FileStorage) {
+        keep_current_content_until_promoted(storage).await;
+    }
+
+    #[tokio::test]
+    #[rstest]
+    async fn it_stages_existing_content(storage: FileStorage) {
+        stage_existing_content(storage).await;
+    }
+
+    #[tokio::test]
+    #[rstest]
+    async fn it_hashes_current_content(storage: FileStorage) {
+        hash_current_content(storage).await;
+    }
+
+    async fn compress_content(storage: FileStorage) {
+        let owner = oxidrive_accounts::account::fixtures::account();
+        let mut file = file::fixtures::file(owner);
+
+        let data = "hello world ".repeat(1000);
+        let content = file::fixtures::content(data.clone()).boxed();
+        let uploaded = storage.upload(&file, content).await.unwrap();
+        storage.promote(&file, &uploaded).await.unwrap();
+        file.encoding = uploaded.encoding;
+
+        check!(uploaded.size == data.len());
+        check!(uploaded.encoding.compressed);
+        check!(uploaded.stored_size < uploaded.size / 10);
+        check!(uploaded.hash == blake3::hash(data.as_bytes()));
+
+        let stored = storage.service.stat(&path_for(&file)).await.unwrap();
+        check!(stored.content_length() == uploaded.stored_size as u64);
+
+        let downloaded = storage.download(&file, 6000..6011).await.unwrap().unwrap();
+        let downloaded: BytesMut = downloaded.try_collect().await.unwrap();
+        check!(downloaded.freeze() == "hello world");
+    }
+
+    #[tokio::test]
+    #[rstest]
+    async fn it_compresses_content(storage: FileStorage) {
+        compress_content(storage).await;
+    }
+
+    #[tokio::test]
+    async fn it_compresses_content_before_encrypting_it() {
+        let storage = FileStorage::memory()
+            .compressed(Compression::new(&["*"], 3).unwrap())
+            .encrypted(Encryption::new(
+                Arc::new(InMemoryDataKeys::default()),
+                "master key",
+                &[],
+            ));
+
+        compress_content(storage).await;
+    }
+
+    #[tokio::test]
+    async fn it_only_compresses_the_configured_content_types() {
+        let storage = FileStorage::memory().compressed(Compression::new(&["text/*"], 3).unwrap());
+
+        let owner = oxidrive_accounts::account::fixtures::account();
+        let file = File::new(owner.id, "image.png", "image/png");
+
+        let data = "not really an image ".repeat(100);
+        let content = file::fixtures::content(data.clone()).boxed();
+        let uploaded = storage.upload(&file, content).await.unwrap();
+
+        check!(uploaded.stored_size == data.len());
+        check!(!uploaded.encoding.compressed);
+    }
+
+    #[tokio::test]
+    #[rstest]
+    async fn it_reads_uncompressed_content_that_looks_compressed(storage: FileStorage) {
+        let owner = oxidrive_accounts::account::fixtures::account();
+        let file = file::fixtures::file(owner);
+
+        // only the metadata of the file tells whether its content is compressed
+        let data = "oxidzst1 is not a zstd frame";
+        storage
+            .service
+            .write(&path_for(&file), bytes::Bytes::from(data))
+            .await
+            .unwrap();
+
+        let downloaded = storage.download(&file, 9..).await.unwrap().unwrap();
+        let downloaded: BytesMut = downloaded.try_collect().await.unwrap();
+        check!(downloaded.freeze() == "is not a zstd frame");
+    }
+
+    #[tokio::test]
+    async fn it_reads_compressed_content_once_compression_is_disabled() {
+        let storage = FileStorage::memory().compressed(Compression::new(&["*"], 3).unwrap());
+
+        let owner = oxidrive_accounts::account::fixtures::account();
+        let mut file = file::fixtures::file(owner);
+
+        let content = file::fixtures::content("hello world").boxed();
+        let uploaded = storage.upload(&file, content).await.unwrap();
+        storage.promote(&file, &uploaded).await.unwrap();
+        file.encoding = uploaded.encoding;
+
+        let storage = FileStorage {
+            compression: None,
+            ..storage
+        };
+
+        let downloaded = storage.download(&file, ..).await.unwrap().unwrap();
+        let downloaded: BytesMut = downloaded.try_collect().await.unwrap();
+        check!(downloaded.freeze() == "hello world");
+    }
+
+    #[tokio::test]
+    #[rstest]
+    async fn it_transfers_content(storage: FileStorage) {
+        transfer_content(storage).await;
+    }
 }
 
 mod fs {
@@ -80,11 +1084,59 @@ mod fs {
         upload_and_download_a_file(storage).await;
     }
 
+    #[tokio::test]
+    #[rstest]
+    async fn it_downloads_a_range(storage: FileStorage) {
+        download_a_range(storage).await;
+    }
+
     #[tokio::test]
     #[rstest]
     async fn it_does_not_download_a_file_that_does_not_exist(storage: FileStorage) {
         download_a_file_that_does_not_exist(storage).await;
     }
+
+    #[tokio::test]
+    #[rstest]
+    async fn it_deletes_a_file(storage: FileStorage) {
+        delete_a_file(storage).await;
+    }
+
+    #[tokio::test]
+    #[rstest]
+    async fn it_lists_stored_content(storage: FileStorage) {
+        list_stored_content(storage).await;
+    }
+
+    #[tokio::test]
+    #[rstest]
+    async fn it_archives_and_restores_versions(storage: FileStorage) {
+        archive_and_restore_versions(storage).await;
+    }
+
+    #[tokio::test]
+    #[rstest]
+    async fn it_keeps_current_content_until_promoted(storage: FileStorage) {
+        keep_current_content_until_promoted(storage).await;
+    }
+
+    #[tokio::test]
+    #[rstest]
+    async fn it_stages_existing_content(storage: FileStorage) {
+        stage_existing_content(storage).await;
+    }
+
+    #[tokio::test]
+    #[rstest]
+    async fn it_hashes_current_content(storage: FileStorage) {
+        hash_current_content(storage).await;
+    }
+
+    #[tokio::test]
+    #[rstest]
+    async fn it_writes_and_reads_chunks(storage: FileStorage) {
+        write_and_read_chunks(storage).await;
+    }
 }
 
 mod s3 {
@@ -119,9 +1171,57 @@ mod s3 {
         upload_and_download_a_file(storage).await;
     }
 
+    #[tokio::test]
+    #[rstest]
+    async fn it_downloads_a_range(storage: FileStorage) {
+        download_a_range(storage).await;
+    }
+
     #[tokio::test]
     #[rstest]
     async fn it_does_not_download_a_file_that_does_not_exist(storage: FileStorage) {
         download_a_file_that_does_not_exist(storage).await;
     }
+
+    #[tokio::test]
+    #[rstest]
+    async fn it_deletes_a_file(storage: FileStorage) {
+        delete_a_file(storage).await;
+    }
+
+    #[tokio::test]
+    #[rstest]
+    async fn it_lists_stored_content(storage: FileStorage) {
+        list_stored_content(storage).await;
+    }
+
+    #[tokio::test]
+    #[rstest]
+    async fn it_archives_and_restores_versions(storage: FileStorage) {
+        archive_and_restore_versions(storage).await;
+    }
+
+    #[tokio::test]
+    #[rstest]
+    async fn it_keeps_current_content_until_promoted(storage: FileStorage) {
+        keep_current_content_until_promoted(storage).await;
+    }
+
+    #[tokio::test]
+    #[rstest]
+    async fn it_stages_existing_content(storage: FileStorage) {
+        stage_existing_content(storage).await;
+    }
+
+    #[tokio::test]
+    #[rstest]
+    async fn it_hashes_current_content(storage: FileStorage) {
+        hash_current_content(storage).await;
+    }
+
+    #[tokio::test]
+    #[rstest]
+    async fn it_writes_and_reads_chunks(storage: FileStorage) {
+        write_and_read_chunks(storage).await;
+    }
 }
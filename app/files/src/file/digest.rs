@@ -0,0 +1,186 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use sha2::Digest as _;
+
+/// A checksum of some file content, used to verify it was received unaltered.
+/// Uploads can declare the digest they expect, and are rejected if the content does not match it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Digest {
+    Blake3(blake3::Hash),
+    Sha256([u8; 32]),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DigestAlgorithm {
+    Blake3,
+    Sha256,
+}
+
+impl Digest {
+    pub fn algorithm(&self) -> DigestAlgorithm {
+        match self {
+            Self::Blake3(_) => DigestAlgorithm::Blake3,
+            Self::Sha256(_) => DigestAlgorithm::Sha256,
+        }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            Self::Blake3(hash) => hash.as_bytes(),
+            Self::Sha256(hash) => hash,
+        }
+    }
+
+    /// Builds a digest from its raw bytes, failing if their length does not fit the algorithm
+    pub fn from_bytes(algorithm: DigestAlgorithm, bytes: &[u8]) -> Result<Self, InvalidDigest> {
+        let bytes: [u8; 32] = bytes.try_into().map_err(|_| InvalidDigest)?;
+
+        Ok(match algorithm {
+            DigestAlgorithm::Blake3 => Self::Blake3(bytes.into()),
+            DigestAlgorithm::Sha256 => Self::Sha256(bytes),
+        })
+    }
+}
+
+impl DigestAlgorithm {
+    /// The name of the algorithm, as registered for the `Repr-Digest` and `Content-Digest` HTTP headers
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Blake3 => "blake3",
+            Self::Sha256 => "sha-256",
+        }
+    }
+
+    pub fn hasher(&self) -> DigestHasher {
+        match self {
+            Self::Blake3 => DigestHasher::Blake3(Box::default()),
+            Self::Sha256 => DigestHasher::Sha256(Default::default()),
+        }
+    }
+}
+
+impl FromStr for DigestAlgorithm {
+    type Err = InvalidDigest;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "blake3" => Ok(Self::Blake3),
+            "sha-256" => Ok(Self::Sha256),
+            _ => Err(InvalidDigest),
+        }
+    }
+}
+
+impl Display for DigestAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.name().fmt(f)
+    }
+}
+
+/// Formats the digest as `<algorithm>:<hex>`, which is also how it is parsed back
+impl Display for Digest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:", self.algorithm())?;
+        for byte in self.as_bytes() {
+            write!(f, "{byte:02x}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Digest {
+    type Err = InvalidDigest;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (algorithm, hex) = s.split_once(':').ok_or(InvalidDigest)?;
+        let algorithm = algorithm.parse()?;
+
+        if !hex.is_ascii() || hex.len() % 2 != 0 {
+            return Err(InvalidDigest);
+        }
+
+        let bytes = (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| InvalidDigest))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Self::from_bytes(algorithm, &bytes)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("invalid digest")]
+pub struct InvalidDigest;
+
+/// Computes the digest of some content as it is streamed
+pub enum DigestHasher {
+    Blake3(Box<blake3::Hasher>),
+    Sha256(sha2::Sha256),
+}
+
+impl DigestHasher {
+    pub fn update(&mut self, data: &[u8]) {
+        match self {
+            Self::Blake3(hasher) => {
+                hasher.update(data);
+            }
+            Self::Sha256(hasher) => hasher.update(data),
+        }
+    }
+
+    pub fn finalize(self) -> Digest {
+        match self {
+            Self::Blake3(hasher) => Digest::Blake3(hasher.finalize()),
+            Self::Sha256(hasher) => Digest::Sha256(hasher.finalize().into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert2::{check, let_assert};
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case(DigestAlgorithm::Blake3)]
+    #[case(DigestAlgorithm::Sha256)]
+    fn it_formats_and_parses_digests(#[case] algorithm: DigestAlgorithm) {
+        let mut hasher = algorithm.hasher();
+        hasher.update(b"hello ");
+        hasher.update(b"world");
+        let digest = hasher.finalize();
+
+        check!(digest.algorithm() == algorithm);
+
+        let_assert!(Ok(parsed) = digest.to_string().parse::<Digest>());
+        check!(parsed == digest);
+    }
+
+    #[test]
+    fn it_computes_well_known_digests() {
+        let mut hasher = DigestAlgorithm::Sha256.hasher();
+        hasher.update(b"hello world");
+
+        check!(
+            hasher.finalize().to_string()
+                == "sha-256:b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9"
+        );
+
+        let mut hasher = DigestAlgorithm::Blake3.hasher();
+        hasher.update(b"hello world");
+
+        check!(hasher.finalize() == Digest::Blake3(blake3::hash(b"hello world")));
+    }
+
+    #[rstest]
+    #[case("sha-256")]
+    #[case("md5:5eb63bbbe01eeed093cb22bb8f5acdc3")]
+    #[case("sha-256:b94d27b9")]
+    #[case("blake3:not hex")]
+    fn it_rejects_invalid_digests(#[case] digest: &str) {
+        let_assert!(Err(_) = digest.parse::<Digest>());
+    }
+}
@@ -0,0 +1,166 @@
+use std::{future::Future, sync::Arc, time::Duration};
+
+use futures::StreamExt;
+use oxidrive_pubsub::Publisher;
+use oxidrive_workers::{
+    Dispatch, Process, Worker,
+    queue::{Enqueue, JobQueue},
+    scheduler::Scheduler,
+};
+
+pub use collect_orphaned_content::*;
+pub use index_all_content::*;
+pub use index_content::*;
+pub use purge_trash::*;
+
+use super::{FileEvent, FileMetadata};
+
+mod collect_orphaned_content;
+mod index_all_content;
+mod index_content;
+mod purge_trash;
+
+static COLLECT_ORPHANED_CONTENT_EVERY: Duration = Duration::from_secs(24 * 60 * 60);
+static PURGE_TRASH_EVERY: Duration = Duration::from_secs(60 * 60);
+static INDEX_ALL_CONTENT_EVERY: Duration = Duration::from_secs(24 * 60 * 60);
+
+pub(crate) struct JobsModule;
+
+impl app::Module for JobsModule {
+    fn mount(self: Box<Self>, c: &mut app::di::Context) {
+        c.bind(CollectOrphanedContentWorker::new);
+        c.bind(
+            |queue: Arc<dyn JobQueue>,
+             enqueue: Arc<dyn Enqueue>,
+             process: CollectOrphanedContentWorker| {
+                Worker::new(queue, enqueue, process)
+            },
+        );
+
+        c.bind(PurgeTrashWorker::new);
+        c.bind(
+            |queue: Arc<dyn JobQueue>, enqueue: Arc<dyn Enqueue>, process: PurgeTrashWorker| {
+                Worker::new(queue, enqueue, process)
+            },
+        );
+
+        c.bind(IndexContentWorker::new);
+        c.bind(
+            |queue: Arc<dyn JobQueue>, enqueue: Arc<dyn Enqueue>, process: IndexContentWorker| {
+                Worker::new(queue, enqueue, process)
+            },
+        );
+
+        c.bind(IndexAllContentWorker::new);
+        c.bind(
+            |queue: Arc<dyn JobQueue>,
+             enqueue: Arc<dyn Enqueue>,
+             process: IndexAllContentWorker| { Worker::new(queue, enqueue, process) },
+        );
+    }
+}
+
+#[app::async_trait]
+impl app::Hooks for JobsModule {
+    async fn after_start(
+        &mut self,
+        ctx: app::context::Context,
+        c: &app::di::Container,
+    ) -> app::eyre::Result<()> {
+        let worker = c.get::<Worker<CollectOrphanedContentWorker>>();
+        let dispatch = worker.dispatcher();
+
+        worker.clone().start(ctx.clone());
+
+        Scheduler::new(
+            COLLECT_ORPHANED_CONTENT_EVERY,
+            dispatch,
+            CollectOrphanedContent::default,
+        )
+        .start(ctx.clone());
+
+        let worker = c.get::<Worker<PurgeTrashWorker>>();
+        let dispatch = worker.dispatcher();
+
+        worker.clone().start(ctx.clone());
+
+        Scheduler::new(PURGE_TRASH_EVERY, dispatch, PurgeTrash::default).start(ctx.clone());
+
+        let metadata = c.get::<Arc<dyn FileMetadata>>();
+        start_event_listener::<IndexContentWorker, FileEvent, _, _>(
+            ctx.clone(),
+            c,
+            move |dispatcher, event| {
+                let metadata = metadata.clone();
+                async move {
+                    match event {
+                        FileEvent::Changed(file) | FileEvent::Deleted(file) => {
+                            // renaming, moving or tagging a file changes it without changing its content
+                            if !file.is_trashed()
+                                && metadata
+                                    .indexed_hash(file.id)
+                                    .await
+                                    .is_ok_and(|hash| hash == Some(file.hash))
+                            {
+                                return;
+                            }
+
+                            if let Err(err) =
+                                dispatcher.dispatch(IndexContent { file_id: file.id }).await
+                            {
+                                tracing::error!(
+                                    error = %err,
+                                    account_id = %file.owner_id,
+                                    file_id = %file.id,
+                                    "failed to queue IndexContent job",
+                                );
+                            }
+                        }
+                    }
+                }
+            },
+        );
+
+        let worker = c.get::<Worker<IndexAllContentWorker>>();
+        let dispatch = worker.dispatcher();
+
+        worker.clone().start(ctx.clone());
+
+        Scheduler::new(INDEX_ALL_CONTENT_EVERY, dispatch, IndexAllContent::default).start(ctx);
+
+        Ok(())
+    }
+}
+
+/// Starts the worker, and dispatches jobs to it with `handler` for each event published
+pub(crate) fn start_event_listener<W, E, F, Fut>(
+    ctx: app::context::Context,
+    c: &app::di::Container,
+    mut handler: F,
+) where
+    W: Process,
+    W::Job: Send,
+    E: Clone + Send + 'static,
+    F: FnMut(Dispatch<W::Job>, E) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send,
+{
+    let worker = c.get::<Worker<W>>();
+    let publisher = c.get::<Publisher<E>>();
+    let dispatcher = worker.dispatcher();
+    let mut subscriber = publisher.subscribe();
+
+    worker.clone().start(ctx.clone());
+
+    let run = async move {
+        while let Some(event) = subscriber.next().await {
+            handler(dispatcher.clone(), event).await
+        }
+    };
+
+    tokio::spawn(async move {
+        tokio::select! {
+            _ = run => {},
+            _ = ctx.cancelled() => {},
+        };
+    });
+}
@@ -0,0 +1,188 @@
+use std::{
+    pin::pin,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use futures::TryStreamExt;
+use oxidrive_workers::{Job, Process};
+use serde::{Deserialize, Serialize};
+
+use crate::file::{ByIdError, DeleteContentError, FileMetadata, FileStorage, ListContentError};
+
+/// Content written more recently than this may belong to an upload that is still in progress,
+/// and whose metadata has not been saved yet
+static DEFAULT_GRACE_PERIOD: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Clone)]
+pub struct CollectOrphanedContentWorker {
+    metadata: Arc<dyn FileMetadata>,
+    storage: FileStorage,
+    grace_period: Duration,
+}
+
+impl CollectOrphanedContentWorker {
+    pub fn new(metadata: Arc<dyn FileMetadata>, storage: FileStorage) -> Self {
+        Self {
+            metadata,
+            storage,
+            grace_period: DEFAULT_GRACE_PERIOD,
+        }
+    }
+
+    pub fn with_grace_period(mut self, grace_period: Duration) -> Self {
+        self.grace_period = grace_period;
+        self
+    }
+}
+
+impl Process for CollectOrphanedContentWorker {
+    type Job = CollectOrphanedContent;
+
+    type Error = CollectOrphanedContentError;
+
+    async fn process(&self, _: Self::Job) -> Result<(), Self::Error> {
+        let threshold = SystemTime::now() - self.grace_period;
+
+        let mut contents = pin!(self.storage.list().await?);
+        let mut collected = 0;
+
+        while let Some(content) = contents.try_next().await? {
+            if content.last_modified.is_none_or(|t| t > threshold) {
+                continue;
+            }
+
+            if let Some(file) = self.metadata.by_id(content.file_id).await? {
+                if file.owner_id == content.owner_id {
+                    continue;
+                }
+            }
+
+            tracing::debug!(
+                account_id = %content.owner_id,
+                file_id = %content.file_id,
+                "deleting orphaned file content",
+            );
+
+            self.storage.delete_stored(&content).await?;
+            collected += 1;
+        }
+
+        tracing::info!(collected, "orphaned file content collected");
+
+        let deleted = self.storage.delete_staged_before(threshold).await?;
+
+        tracing::info!(deleted, "abandoned staged content deleted");
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct CollectOrphanedContent;
+
+impl Job for CollectOrphanedContent {}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CollectOrphanedContentError {
+    #[error("failed to list stored content: {0}")]
+    ListFailed(#[from] ListContentError),
+
+    #[error("failed to load file: {0}")]
+    LoadFailed(#[from] ByIdError),
+
+    #[error("failed to delete orphaned content: {0}")]
+    DeleteFailed(#[from] DeleteContentError),
+}
+
+#[cfg(test)]
+mod tests {
+    use assert2::let_assert;
+    use futures::StreamExt;
+    use rstest::rstest;
+
+    use crate::{
+        File,
+        file::{
+            InMemoryFileMetadata,
+            fixtures::{content, file},
+            fs,
+        },
+    };
+
+    use super::*;
+
+    #[rstest]
+    #[tokio::test]
+    async fn it_deletes_content_without_metadata(
+        #[from(file)] kept: File,
+        #[from(file)] orphaned: File,
+    ) {
+        let root_dir = tempfile::tempdir().unwrap();
+        let storage = FileStorage::file_system(fs::Config {
+            root_folder_path: root_dir.path().into(),
+        });
+
+        for (file, data) in [(&kept, "kept"), (&orphaned, "orphaned")] {
+            let staged = storage.upload(file, content(data).boxed()).await.unwrap();
+            storage.promote(file, &staged).await.unwrap();
+        }
+
+        let metadata = Arc::new(InMemoryFileMetadata::from([kept.clone()]));
+
+        let worker = CollectOrphanedContentWorker::new(metadata, storage.clone())
+            .with_grace_period(Duration::ZERO);
+
+        worker.process(CollectOrphanedContent).await.unwrap();
+
+        let_assert!(Some(_) = storage.download(&kept, ..).await.unwrap());
+        let_assert!(None = storage.download(&orphaned, ..).await.unwrap());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn it_keeps_recently_written_content(#[from(file)] orphaned: File) {
+        let root_dir = tempfile::tempdir().unwrap();
+        let storage = FileStorage::file_system(fs::Config {
+            root_folder_path: root_dir.path().into(),
+        });
+
+        let staged = storage
+            .upload(&orphaned, content("orphaned").boxed())
+            .await
+            .unwrap();
+        storage.promote(&orphaned, &staged).await.unwrap();
+
+        let metadata = Arc::new(InMemoryFileMetadata::default());
+
+        let worker = CollectOrphanedContentWorker::new(metadata, storage.clone());
+
+        worker.process(CollectOrphanedContent).await.unwrap();
+
+        let_assert!(Some(_) = storage.download(&orphaned, ..).await.unwrap());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn it_deletes_abandoned_staged_content(file: File) {
+        let root_dir = tempfile::tempdir().unwrap();
+        let storage = FileStorage::file_system(fs::Config {
+            root_folder_path: root_dir.path().into(),
+        });
+
+        let staged = storage
+            .upload(&file, content("abandoned").boxed())
+            .await
+            .unwrap();
+
+        let metadata = Arc::new(InMemoryFileMetadata::default());
+
+        let worker = CollectOrphanedContentWorker::new(metadata, storage.clone())
+            .with_grace_period(Duration::ZERO);
+
+        worker.process(CollectOrphanedContent).await.unwrap();
+
+        let_assert!(Err(_) = storage.promote(&file, &staged).await);
+        let_assert!(None = storage.download(&file, ..).await.unwrap());
+    }
+}
@@ -0,0 +1,88 @@
+use std::sync::Arc;
+
+use oxidrive_workers::{Dispatch, DispatchError, Job, Process, Worker};
+use serde::{Deserialize, Serialize};
+
+use crate::file::{
+    AllFilesError, FileMetadata, IndexedHashError,
+    text::{MAX_INDEXED_SIZE, is_indexable},
+};
+
+use super::{IndexContent, IndexContentWorker};
+
+const BATCH_SIZE: usize = 100;
+
+/// Queues the indexing of every file whose content has not been indexed yet, like files uploaded before
+/// content was indexed, or whose change was missed
+#[derive(Clone)]
+pub struct IndexAllContentWorker {
+    metadata: Arc<dyn FileMetadata>,
+    index: Dispatch<IndexContent>,
+}
+
+impl IndexAllContentWorker {
+    pub fn new(metadata: Arc<dyn FileMetadata>, worker: Worker<IndexContentWorker>) -> Self {
+        Self {
+            metadata,
+            index: worker.dispatcher(),
+        }
+    }
+}
+
+impl Process for IndexAllContentWorker {
+    type Job = IndexAllContent;
+
+    type Error = IndexAllContentError;
+
+    async fn process(&self, _: Self::Job) -> Result<(), Self::Error> {
+        let mut after = None;
+        let mut queued = 0;
+
+        loop {
+            let files = self.metadata.all_after(after, BATCH_SIZE).await?;
+            let Some(last) = files.last() else {
+                break;
+            };
+            after = Some(last.id);
+
+            for file in files {
+                if file.is_trashed()
+                    || file.size > MAX_INDEXED_SIZE
+                    || !is_indexable(&file.content_type)
+                {
+                    continue;
+                }
+
+                if self.metadata.indexed_hash(file.id).await? == Some(file.hash) {
+                    continue;
+                }
+
+                self.index
+                    .dispatch(IndexContent { file_id: file.id })
+                    .await?;
+                queued += 1;
+            }
+        }
+
+        tracing::info!(queued, "files queued for content indexing");
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct IndexAllContent;
+
+impl Job for IndexAllContent {}
+
+#[derive(Debug, thiserror::Error)]
+pub enum IndexAllContentError {
+    #[error("failed to load files to index: {0}")]
+    LoadFailed(#[from] AllFilesError),
+
+    #[error("failed to load the hash of the indexed content: {0}")]
+    IndexedHashFailed(#[from] IndexedHashError),
+
+    #[error(transparent)]
+    DispatchFailed(#[from] DispatchError),
+}
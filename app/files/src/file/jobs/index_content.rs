@@ -0,0 +1,227 @@
+use std::{pin::pin, sync::Arc};
+
+use futures::TryStreamExt;
+use oxidrive_workers::{Job, Process};
+use serde::{Deserialize, Serialize};
+
+use crate::file::{
+    ByIdError, DownloadFileError, FileId, FileMetadata, FileStorage, IndexTextError,
+    IndexedHashError, RemoveTextError,
+    text::{MAX_INDEXED_SIZE, extract_text, is_indexable},
+};
+
+/// Keeps the text of a file up to date in the full-text index, or removes it once the file is gone
+#[derive(Clone)]
+pub struct IndexContentWorker {
+    metadata: Arc<dyn FileMetadata>,
+    storage: FileStorage,
+}
+
+impl IndexContentWorker {
+    pub fn new(metadata: Arc<dyn FileMetadata>, storage: FileStorage) -> Self {
+        Self { metadata, storage }
+    }
+}
+
+impl Process for IndexContentWorker {
+    type Job = IndexContent;
+
+    type Error = IndexContentError;
+
+    async fn process(&self, job: Self::Job) -> Result<(), Self::Error> {
+        let file = match self.metadata.by_id(job.file_id).await? {
+            Some(file)
+                if !file.is_trashed()
+                    && file.size <= MAX_INDEXED_SIZE
+                    && is_indexable(&file.content_type) =>
+            {
+                file
+            }
+            _ => {
+                self.metadata.remove_text(job.file_id).await?;
+                return Ok(());
+            }
+        };
+
+        // renaming, moving or tagging a file changes it without changing its content
+        if self.metadata.indexed_hash(file.id).await? == Some(file.hash) {
+            tracing::trace!(file_id = %file.id, "file content already indexed, skipping");
+            return Ok(());
+        }
+
+        let Some(content) = self.storage.download(&file, ..).await? else {
+            self.metadata.remove_text(file.id).await?;
+            return Ok(());
+        };
+
+        let mut content = pin!(content);
+        let mut bytes = Vec::with_capacity(file.size);
+        while let Some(chunk) = content
+            .try_next()
+            .await
+            .map_err(|err| IndexContentError::ReadFailed(err.to_string()))?
+        {
+            bytes.extend_from_slice(&chunk);
+        }
+
+        let content_type = file.content_type.clone();
+        let text = tokio::task::spawn_blocking(move || extract_text(&content_type, &bytes))
+            .await
+            .map_err(|err| IndexContentError::ReadFailed(err.to_string()))?;
+
+        // content without text is indexed too, so that it is not downloaded again until it changes
+        let text = text.unwrap_or_default();
+
+        tracing::debug!(file_id = %file.id, len = text.len(), "indexing file content");
+        self.metadata.index_text(file.id, file.hash, &text).await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct IndexContent {
+    pub file_id: FileId,
+}
+
+impl Job for IndexContent {}
+
+#[derive(Debug, thiserror::Error)]
+pub enum IndexContentError {
+    #[error("failed to load file: {0}")]
+    LoadFailed(#[from] ByIdError),
+
+    #[error("failed to download file content: {0}")]
+    DownloadFailed(#[from] DownloadFileError),
+
+    #[error("failed to read file content: {0}")]
+    ReadFailed(String),
+
+    #[error("failed to load the hash of the indexed content: {0}")]
+    IndexedHashFailed(#[from] IndexedHashError),
+
+    #[error("failed to index file content: {0}")]
+    IndexFailed(#[from] IndexTextError),
+
+    #[error("failed to remove file content from the index: {0}")]
+    RemoveFailed(#[from] RemoveTextError),
+}
+
+#[cfg(test)]
+mod tests {
+    use assert2::check;
+    use futures::StreamExt;
+    use oxidrive_paginate::Paginate;
+    use oxidrive_search::parse_query;
+    use rstest::rstest;
+
+    use crate::{
+        File,
+        file::{
+            InMemoryFileMetadata, Sort,
+            fixtures::{content, file},
+            fs,
+        },
+    };
+
+    use super::*;
+
+    #[rstest]
+    #[tokio::test]
+    async fn it_indexes_and_removes_file_content(file: File) {
+        let root_dir = tempfile::tempdir().unwrap();
+        let storage = FileStorage::file_system(fs::Config {
+            root_folder_path: root_dir.path().into(),
+        });
+
+        let mut file = file;
+        file.content_type = "text/plain".into();
+        file.set_size("the quarterly report".len());
+
+        let staged = storage
+            .upload(&file, content("the quarterly report").boxed())
+            .await
+            .unwrap();
+        storage.promote(&file, &staged).await.unwrap();
+
+        let metadata = Arc::new(InMemoryFileMetadata::from([file.clone()]));
+        let worker = IndexContentWorker::new(metadata.clone(), storage);
+
+        let filter = parse_query("\"quarterly report\"").unwrap();
+
+        worker
+            .process(IndexContent { file_id: file.id })
+            .await
+            .unwrap();
+
+        let found = metadata
+            .search(
+                file.owner_id,
+                filter.clone(),
+                Sort::default(),
+                Paginate::default(),
+            )
+            .await
+            .unwrap();
+        check!(found.items.iter().map(|f| f.id).collect::<Vec<_>>() == vec![file.id]);
+
+        metadata.delete(file.id).await.unwrap();
+        worker
+            .process(IndexContent { file_id: file.id })
+            .await
+            .unwrap();
+
+        let found = metadata
+            .search(file.owner_id, filter, Sort::default(), Paginate::default())
+            .await
+            .unwrap();
+        check!(found.items.is_empty());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn it_skips_content_already_indexed(file: File) {
+        let root_dir = tempfile::tempdir().unwrap();
+        let storage = FileStorage::file_system(fs::Config {
+            root_folder_path: root_dir.path().into(),
+        });
+
+        let mut file = file;
+        file.content_type = "text/plain".into();
+        file.set_size("the budget".len());
+        file.set_hash(blake3::hash(b"the budget"));
+
+        let staged = storage
+            .upload(&file, content("the budget").boxed())
+            .await
+            .unwrap();
+        storage.promote(&file, &staged).await.unwrap();
+
+        let metadata = Arc::new(InMemoryFileMetadata::from([file.clone()]));
+        let worker = IndexContentWorker::new(metadata.clone(), storage.clone());
+
+        worker
+            .process(IndexContent { file_id: file.id })
+            .await
+            .unwrap();
+        check!(metadata.indexed_hash(file.id).await.unwrap() == Some(file.hash));
+
+        // the content is not downloaded again, so that deleting it goes unnoticed
+        storage.delete(&file).await.unwrap();
+        worker
+            .process(IndexContent { file_id: file.id })
+            .await
+            .unwrap();
+
+        let found = metadata
+            .search(
+                file.owner_id,
+                parse_query("content:budget").unwrap(),
+                Sort::default(),
+                Paginate::default(),
+            )
+            .await
+            .unwrap();
+        check!(found.items.len() == 1);
+    }
+}
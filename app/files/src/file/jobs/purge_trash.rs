@@ -0,0 +1,41 @@
+use oxidrive_workers::{Job, Process};
+use serde::{Deserialize, Serialize};
+use time::{Duration, OffsetDateTime};
+
+use crate::{Config, Files, PurgeTrashError};
+
+#[derive(Clone)]
+pub struct PurgeTrashWorker {
+    files: Files,
+    retention: Duration,
+}
+
+impl PurgeTrashWorker {
+    pub fn new(files: Files, cfg: Config) -> Self {
+        Self {
+            files,
+            retention: cfg.trash.retention(),
+        }
+    }
+}
+
+impl Process for PurgeTrashWorker {
+    type Job = PurgeTrash;
+
+    type Error = PurgeTrashError;
+
+    async fn process(&self, _: Self::Job) -> Result<(), Self::Error> {
+        let before = OffsetDateTime::now_utc() - self.retention;
+
+        let purged = self.files.purge_trashed_before(before).await?;
+
+        tracing::info!(purged, "expired files purged from the trash");
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct PurgeTrash;
+
+impl Job for PurgeTrash {}
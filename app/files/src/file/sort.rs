@@ -0,0 +1,190 @@
+use std::cmp::Ordering;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use super::{File, FileId};
+
+/// How files found by a search are ordered, written like `size` or `updated:desc`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Sort {
+    pub by: SortBy,
+    pub descending: bool,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SortBy {
+    /// Ignoring case
+    #[default]
+    Name,
+    Size,
+    ContentType,
+    Created,
+    Updated,
+    /// How well the content of files matches the phrases searched for, like `"quarterly report"`
+    Relevance,
+}
+
+impl Sort {
+    pub const fn asc(by: SortBy) -> Self {
+        Self {
+            by,
+            descending: false,
+        }
+    }
+
+    pub const fn desc(by: SortBy) -> Self {
+        Self {
+            by,
+            descending: true,
+        }
+    }
+
+    /// Compares files the way stores order them, ties being broken by id
+    pub(crate) fn compare(
+        &self,
+        a: &File,
+        b: &File,
+        relevance: impl Fn(&File) -> usize,
+    ) -> Ordering {
+        let ordering = match self.by {
+            SortBy::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+            SortBy::Size => a.size.cmp(&b.size),
+            SortBy::ContentType => a.content_type.cmp(&b.content_type),
+            SortBy::Created => a.created_at.cmp(&b.created_at),
+            SortBy::Updated => a.updated_at.cmp(&b.updated_at),
+            SortBy::Relevance => relevance(a).cmp(&relevance(b)),
+        }
+        .then_with(|| a.id.to_string().cmp(&b.id.to_string()));
+
+        if self.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+
+    /// The value a file is sorted by, as stores put it in cursors
+    pub(crate) fn key(&self, file: &File, relevance: impl Fn(&File) -> usize) -> String {
+        match self.by {
+            SortBy::Name => file.name.to_lowercase(),
+            SortBy::Size => file.size.to_string(),
+            SortBy::ContentType => file.content_type.clone(),
+            SortBy::Created => file.created_at.unix_timestamp_nanos().to_string(),
+            SortBy::Updated => file.updated_at.unix_timestamp_nanos().to_string(),
+            SortBy::Relevance => relevance(file).to_string(),
+        }
+    }
+
+    /// Compares a file to the position a cursor points to, given by the [key](Sort::key) and id of the file
+    /// it was taken from. Returns [None] if the key can't be one files are sorted by
+    pub(crate) fn compare_to_key(
+        &self,
+        file: &File,
+        key: &str,
+        id: FileId,
+        relevance: impl Fn(&File) -> usize,
+    ) -> Option<Ordering> {
+        let ordering = match self.by {
+            SortBy::Name => file.name.to_lowercase().as_str().cmp(key),
+            SortBy::Size => file.size.cmp(&key.parse().ok()?),
+            SortBy::ContentType => file.content_type.as_str().cmp(key),
+            SortBy::Created => file
+                .created_at
+                .unix_timestamp_nanos()
+                .cmp(&key.parse().ok()?),
+            SortBy::Updated => file
+                .updated_at
+                .unix_timestamp_nanos()
+                .cmp(&key.parse().ok()?),
+            SortBy::Relevance => relevance(file).cmp(&key.parse().ok()?),
+        }
+        .then_with(|| file.id.to_string().cmp(&id.to_string()));
+
+        if self.descending {
+            Some(ordering.reverse())
+        } else {
+            Some(ordering)
+        }
+    }
+}
+
+impl FromStr for Sort {
+    type Err = InvalidSortError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // the most relevant files come first unless asked otherwise
+        let (by, order) = match s.split_once(':') {
+            Some((by, order)) => (by, order),
+            None if s == "relevance" => (s, "desc"),
+            None => (s, "asc"),
+        };
+
+        let by = match by {
+            "name" => SortBy::Name,
+            "size" => SortBy::Size,
+            "content_type" => SortBy::ContentType,
+            "created" => SortBy::Created,
+            "updated" => SortBy::Updated,
+            "relevance" => SortBy::Relevance,
+            _ => return Err(InvalidSortError(s.into())),
+        };
+
+        match order {
+            "asc" => Ok(Self::asc(by)),
+            "desc" => Ok(Self::desc(by)),
+            _ => Err(InvalidSortError(s.into())),
+        }
+    }
+}
+
+impl Display for Sort {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let by = match self.by {
+            SortBy::Name => "name",
+            SortBy::Size => "size",
+            SortBy::ContentType => "content_type",
+            SortBy::Created => "created",
+            SortBy::Updated => "updated",
+            SortBy::Relevance => "relevance",
+        };
+        let order = if self.descending { "desc" } else { "asc" };
+
+        write!(f, "{by}:{order}")
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "invalid sort '{0}', expected one of name, size, content_type, created, updated or relevance, optionally followed by :asc or :desc"
+)]
+pub struct InvalidSortError(String);
+
+#[cfg(test)]
+mod tests {
+    use assert2::{check, let_assert};
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case("name", Sort::asc(SortBy::Name))]
+    #[case("size:desc", Sort::desc(SortBy::Size))]
+    #[case("content_type:asc", Sort::asc(SortBy::ContentType))]
+    #[case("created", Sort::asc(SortBy::Created))]
+    #[case("updated:desc", Sort::desc(SortBy::Updated))]
+    #[case("relevance", Sort::desc(SortBy::Relevance))]
+    #[case("relevance:asc", Sort::asc(SortBy::Relevance))]
+    fn it_parses_sorts(#[case] s: &str, #[case] expected: Sort) {
+        let_assert!(Ok(sort) = s.parse::<Sort>());
+        check!(sort == expected);
+        check!(sort.to_string().parse::<Sort>().unwrap() == sort);
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("owner")]
+    #[case("size:up")]
+    fn it_rejects_invalid_sorts(#[case] s: &str) {
+        check!(s.parse::<Sort>().is_err());
+    }
+}
@@ -1,17 +1,19 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{cmp::Ordering, collections::HashMap, sync::Arc};
 
 use async_trait::async_trait;
 use globset::Glob;
 use oxidrive_accounts::account::AccountId;
+use oxidrive_database::paginate::{InvalidCursor, SortCursor};
 use oxidrive_domain::make_error_wrapper;
 use oxidrive_paginate::{Paginate, Slice};
 use oxidrive_search::{Filter, Values};
+use time::OffsetDateTime;
 use tokio::sync::RwLock;
 use uuid::Uuid;
 
-use crate::Tag;
+use crate::{Tag, folder::FolderPath};
 
-use super::{File, FileId, Tags};
+use super::{File, FileId, Sort, Tags};
 
 mod pg;
 mod sqlite;
@@ -19,12 +21,21 @@ mod sqlite;
 pub use pg::*;
 pub use sqlite::*;
 
+make_error_wrapper!(AllFilesError);
+make_error_wrapper!(AllInFolderError);
 make_error_wrapper!(AllOwnedByError);
 make_error_wrapper!(AllOwnedByInError);
 make_error_wrapper!(ByIdError);
-make_error_wrapper!(ByNameError);
+make_error_wrapper!(ByPathError);
+make_error_wrapper!(ByHashError);
+make_error_wrapper!(IndexTextError);
+make_error_wrapper!(IndexedHashError);
+make_error_wrapper!(RecordAccessError);
+make_error_wrapper!(RemoveTextError);
 make_error_wrapper!(SaveFileError);
 make_error_wrapper!(SearchError);
+make_error_wrapper!(TrashedByError);
+make_error_wrapper!(TrashedBeforeError);
 make_error_wrapper!(DeleteFileError);
 
 #[mockall::automock]
@@ -35,7 +46,7 @@ pub trait FileMetadata: Send + Sync + 'static {
         owner_id: AccountId,
         paginate: Paginate,
     ) -> Result<Slice<File>, AllOwnedByError> {
-        self.search(owner_id, Filter::All, paginate)
+        self.search(owner_id, Filter::All, Sort::default(), paginate)
             .await
             .map_err(AllOwnedByError::wrap)
     }
@@ -49,27 +60,92 @@ pub trait FileMetadata: Send + Sync + 'static {
 
     async fn by_id(&self, id: FileId) -> Result<Option<File>, ByIdError>;
 
-    async fn by_owner_and_name(
+    async fn by_owner_and_path(
         &self,
         owner_id: AccountId,
+        folder: &FolderPath,
         file_name: &str,
-    ) -> Result<Option<File>, ByNameError>;
+    ) -> Result<Option<File>, ByPathError>;
+
+    /// Finds any file owned by the account whose content has the given hash
+    async fn by_owner_and_hash(
+        &self,
+        owner_id: AccountId,
+        hash: blake3::Hash,
+    ) -> Result<Option<File>, ByHashError>;
 
     async fn save(&self, file: File) -> Result<File, SaveFileError>;
 
+    /// Records that the content of the file was downloaded, without touching anything else about it
+    async fn record_access(&self, id: FileId, at: OffsetDateTime) -> Result<(), RecordAccessError>;
+
+    /// Replaces the text extracted from the content of the file, which phrases in search queries are looked for in,
+    /// remembering the hash of the content it was extracted from
+    async fn index_text(
+        &self,
+        id: FileId,
+        hash: Option<blake3::Hash>,
+        text: &str,
+    ) -> Result<(), IndexTextError>;
+
+    /// The hash of the content the indexed text of the file was extracted from.
+    /// `None` if the file is not indexed, `Some(None)` if it was indexed from content whose hash was unknown
+    async fn indexed_hash(
+        &self,
+        id: FileId,
+    ) -> Result<Option<Option<blake3::Hash>>, IndexedHashError>;
+
+    /// Forgets the text extracted from the content of the file, so that it is no longer found by phrases
+    async fn remove_text(&self, id: FileId) -> Result<(), RemoveTextError>;
+
     async fn search(
         &self,
         owner_id: AccountId,
         filter: Filter,
+        sort: Sort,
         paginate: Paginate,
     ) -> Result<Slice<File>, SearchError>;
 
+    /// Lists the files owned by the account that have been moved to the trash.
+    /// Trashed files are excluded from every other query, except [FileMetadata::by_id]
+    async fn trashed_by(
+        &self,
+        owner_id: AccountId,
+        paginate: Paginate,
+    ) -> Result<Slice<File>, TrashedByError>;
+
+    /// Loads up to `limit` files, across all accounts, that were moved to the trash before the given instant
+    async fn trashed_before(
+        &self,
+        before: OffsetDateTime,
+        limit: usize,
+    ) -> Result<Vec<File>, TrashedBeforeError>;
+
+    /// Loads up to `limit` files, across all accounts and including trashed ones, ordered by id
+    /// and starting right after the given one
+    async fn all_after(
+        &self,
+        after: Option<FileId>,
+        limit: usize,
+    ) -> Result<Vec<File>, AllFilesError>;
+
+    /// Loads up to `limit` files owned by the account in the folder or any folder nested in it,
+    /// including trashed ones, ordered by id and starting right after the given one
+    async fn all_in_folder(
+        &self,
+        owner_id: AccountId,
+        folder: &FolderPath,
+        after: Option<FileId>,
+        limit: usize,
+    ) -> Result<Vec<File>, AllInFolderError>;
+
     async fn delete(&self, id: FileId) -> Result<(), DeleteFileError>;
 }
 
 #[derive(Clone, Default)]
 pub struct InMemoryFileMetadata {
     inner: Arc<RwLock<HashMap<FileId, File>>>,
+    texts: Arc<RwLock<HashMap<FileId, (Option<blake3::Hash>, String)>>>,
 }
 
 impl<const N: usize> From<[File; N]> for InMemoryFileMetadata {
@@ -77,6 +153,7 @@ impl<const N: usize> From<[File; N]> for InMemoryFileMetadata {
         let files = HashMap::from_iter(files.into_iter().map(|f| (f.id, f)));
         Self {
             inner: Arc::new(RwLock::new(files)),
+            texts: Default::default(),
         }
     }
 }
@@ -93,6 +170,7 @@ impl FileMetadata for InMemoryFileMetadata {
 
         let files = inner
             .values()
+            .filter(|f| !f.is_trashed())
             .filter(|f| f.owner_id == owner_id && ids.contains(&f.id));
 
         Ok(paginate(files, params))
@@ -103,15 +181,33 @@ impl FileMetadata for InMemoryFileMetadata {
         Ok(inner.get(&id).cloned())
     }
 
-    async fn by_owner_and_name(
+    async fn by_owner_and_path(
         &self,
         owner_id: AccountId,
+        folder: &FolderPath,
         file_name: &str,
-    ) -> Result<Option<File>, ByNameError> {
+    ) -> Result<Option<File>, ByPathError> {
+        let inner = self.inner.read().await;
+        Ok(inner
+            .values()
+            .find(|f| {
+                !f.is_trashed()
+                    && f.owner_id == owner_id
+                    && &f.folder == folder
+                    && f.name == file_name
+            })
+            .cloned())
+    }
+
+    async fn by_owner_and_hash(
+        &self,
+        owner_id: AccountId,
+        hash: blake3::Hash,
+    ) -> Result<Option<File>, ByHashError> {
         let inner = self.inner.read().await;
         Ok(inner
             .values()
-            .find(|f| f.owner_id == owner_id && f.name == file_name)
+            .find(|f| !f.is_trashed() && f.owner_id == owner_id && f.hash == Some(hash))
             .cloned())
     }
 
@@ -121,58 +217,255 @@ impl FileMetadata for InMemoryFileMetadata {
         Ok(file)
     }
 
+    async fn record_access(&self, id: FileId, at: OffsetDateTime) -> Result<(), RecordAccessError> {
+        let mut inner = self.inner.write().await;
+        if let Some(file) = inner.get_mut(&id) {
+            file.access(at);
+        }
+        Ok(())
+    }
+
+    async fn index_text(
+        &self,
+        id: FileId,
+        hash: Option<blake3::Hash>,
+        text: &str,
+    ) -> Result<(), IndexTextError> {
+        let mut texts = self.texts.write().await;
+        texts.insert(id, (hash, text.into()));
+        Ok(())
+    }
+
+    async fn indexed_hash(
+        &self,
+        id: FileId,
+    ) -> Result<Option<Option<blake3::Hash>>, IndexedHashError> {
+        let texts = self.texts.read().await;
+        Ok(texts.get(&id).map(|(hash, _)| *hash))
+    }
+
+    async fn remove_text(&self, id: FileId) -> Result<(), RemoveTextError> {
+        let mut texts = self.texts.write().await;
+        texts.remove(&id);
+        Ok(())
+    }
+
     async fn search(
         &self,
         owner_id: AccountId,
         filter: Filter,
+        sort: Sort,
         params: Paginate,
     ) -> Result<Slice<File>, SearchError> {
         let inner = self.inner.read().await;
-        let mut filter = traverse(|_| true, filter);
+        let texts = self.texts.read().await;
+        let text = |file: &File| texts.get(&file.id).map(|(_, text)| text.as_str());
+
+        let phrases = filter
+            .content_phrases()
+            .into_iter()
+            .map(words)
+            .collect::<Vec<_>>();
+        let relevance = |file: &File| {
+            text(file).map_or(0, |text| {
+                phrases.iter().map(|phrase| occurrences(text, phrase)).sum()
+            })
+        };
+
+        let filter = content_matcher(filter);
+
+        let mut files = inner
+            .values()
+            .filter(|f| !f.is_trashed() && f.owner_id == owner_id)
+            .filter(|file| filter(&file.tags, text(file)))
+            .collect::<Vec<_>>();
+        files.sort_by(|a, b| sort.compare(a, b, relevance));
+
+        paginate_sorted(
+            files,
+            |file, cursor| sort.compare_to_key(file, &cursor.value, cursor.id.into(), relevance),
+            |file| sort.key(file, relevance),
+            params,
+        )
+        .map_err(SearchError::wrap)
+    }
+
+    async fn trashed_by(
+        &self,
+        owner_id: AccountId,
+        params: Paginate,
+    ) -> Result<Slice<File>, TrashedByError> {
+        let inner = self.inner.read().await;
 
         let files = inner
             .values()
-            .filter(|f| f.owner_id == owner_id)
-            .filter(|file| filter(&file.tags));
+            .filter(|f| f.is_trashed() && f.owner_id == owner_id);
 
         Ok(paginate(files, params))
     }
 
+    async fn trashed_before(
+        &self,
+        before: OffsetDateTime,
+        limit: usize,
+    ) -> Result<Vec<File>, TrashedBeforeError> {
+        let inner = self.inner.read().await;
+
+        let mut files = inner
+            .values()
+            .filter(|f| f.deleted_at.is_some_and(|deleted_at| deleted_at < before))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        files.sort_by_key(|f| f.deleted_at);
+        files.truncate(limit);
+
+        Ok(files)
+    }
+
+    async fn all_after(
+        &self,
+        after: Option<FileId>,
+        limit: usize,
+    ) -> Result<Vec<File>, AllFilesError> {
+        let inner = self.inner.read().await;
+
+        let mut files = inner
+            .values()
+            .filter(|f| after.is_none_or(|after| f.id > after))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        files.sort_by_key(|f| f.id);
+        files.truncate(limit);
+
+        Ok(files)
+    }
+
+    async fn all_in_folder(
+        &self,
+        owner_id: AccountId,
+        folder: &FolderPath,
+        after: Option<FileId>,
+        limit: usize,
+    ) -> Result<Vec<File>, AllInFolderError> {
+        let inner = self.inner.read().await;
+
+        let mut files = inner
+            .values()
+            .filter(|f| f.owner_id == owner_id && folder.contains(&f.folder))
+            .filter(|f| after.is_none_or(|after| f.id > after))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        files.sort_by_key(|f| f.id);
+        files.truncate(limit);
+
+        Ok(files)
+    }
+
     async fn delete(&self, id: FileId) -> Result<(), DeleteFileError> {
         let mut inner = self.inner.write().await;
         inner.remove(&id);
+        self.texts.write().await.remove(&id);
         Ok(())
     }
 }
 
-type FilterFn = Box<dyn FnMut(&Tags) -> bool>;
+pub(crate) type FilterFn = Box<dyn Fn(&Tags) -> bool + Send + Sync>;
 
-fn traverse<F>(mut current: F, filter: Filter) -> FilterFn
+/// Evaluates the filter against the tags of a single file, the same way [InMemoryFileMetadata] searches.
+/// Content is not looked at, so phrases never match
+pub(crate) fn matcher(filter: Filter) -> FilterFn {
+    let matches = content_matcher(filter);
+    Box::new(move |tags| matches(tags, None))
+}
+
+type ContentFilterFn = Box<dyn Fn(&Tags, Option<&str>) -> bool + Send + Sync>;
+
+/// Evaluates the filter against the tags of a single file and the text indexed from its content
+fn content_matcher(filter: Filter) -> ContentFilterFn {
+    traverse(|_, _| true, filter)
+}
+
+fn traverse<F>(current: F, filter: Filter) -> ContentFilterFn
 where
-    F: FnMut(&Tags) -> bool + Clone + 'static,
+    F: Fn(&Tags, Option<&str>) -> bool + Clone + Send + Sync + 'static,
 {
     match filter {
         Filter::All => Box::new(current),
-        Filter::Tag { key, values } => Box::new(move |tags| {
-            current(tags) && tags.values().any(|tag| tag_matches(tag, &key, &values))
+        Filter::Tag { key, values } => Box::new(move |tags, text| {
+            current(tags, text) && tags.values().any(|tag| tag_matches(tag, &key, &values))
         }),
+        Filter::Compare { key, cmp, operand } => {
+            let (cmp, comparable) = operand.comparable(cmp);
+            Box::new(move |tags, text| {
+                current(tags, text)
+                    && tag_value(tags, &key)
+                        .is_some_and(|value| comparable.is_satisfied_by(cmp, value))
+            })
+        }
+        Filter::Range { key, from, to } => {
+            let comparisons = oxidrive_search::range_comparisons(from.as_ref(), to.as_ref());
+            Box::new(move |tags, text| {
+                current(tags, text)
+                    && tag_value(tags, &key).is_some_and(|value| {
+                        comparisons
+                            .iter()
+                            .all(|(cmp, comparable)| comparable.is_satisfied_by(*cmp, value))
+                    })
+            })
+        }
         Filter::Op { lhs, op, rhs } => {
-            let mut lhs = traverse(current.clone(), *lhs);
-            let mut rhs = traverse(current, *rhs);
-            Box::new(move |tags| match op {
-                oxidrive_search::Op::And => lhs(tags) && rhs(tags),
-                oxidrive_search::Op::Or => lhs(tags) || rhs(tags),
+            let lhs = traverse(current.clone(), *lhs);
+            let rhs = traverse(current, *rhs);
+            Box::new(move |tags, text| match op {
+                oxidrive_search::Op::And => lhs(tags, text) && rhs(tags, text),
+                oxidrive_search::Op::Or => lhs(tags, text) || rhs(tags, text),
             })
         }
         Filter::Mod { modifier, inner } => {
-            let mut inner = traverse(current, *inner);
-            Box::new(move |tags| match modifier {
-                oxidrive_search::Mod::Not => !inner(tags),
+            let inner = traverse(|_, _| true, *inner);
+            Box::new(move |tags, text| {
+                current(tags, text)
+                    && match modifier {
+                        oxidrive_search::Mod::Not => !inner(tags, text),
+                    }
+            })
+        }
+        Filter::Content(phrase) => {
+            let phrase = words(&phrase);
+            Box::new(move |tags, text| {
+                current(tags, text) && text.is_some_and(|text| occurrences(text, &phrase) > 0)
             })
         }
     }
 }
 
+/// Splits text into lowercase words, roughly like the full-text search of the databases does
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// How many times the words of the phrase appear in this order in the text
+fn occurrences(text: &str, phrase: &[String]) -> usize {
+    if phrase.is_empty() {
+        return 0;
+    }
+
+    words(text)
+        .windows(phrase.len())
+        .filter(|window| window == &phrase)
+        .count()
+}
+
+fn tag_value<'a>(tags: &'a Tags, key: &str) -> Option<&'a str> {
+    tags.get(key).and_then(|tag| tag.value.as_deref())
+}
+
 fn tag_matches(tag: &Tag, key: &String, values: &Values) -> bool {
     let key_matches = &tag.key == key;
 
@@ -245,5 +538,57 @@ where
     }
 }
 
+/// Pages through files already ordered by `compare`, resuming from the position of the file the cursor points to
+/// Pages through sorted files from the position the cursor points to, found from the value it carries
+/// rather than from the file it was taken from, which may be gone by now
+fn paginate_sorted(
+    files: Vec<&File>,
+    compare: impl Fn(&File, &SortCursor) -> Option<Ordering>,
+    key: impl Fn(&File) -> String,
+    params: Paginate,
+) -> Result<Slice<File>, InvalidCursor> {
+    let cursor = SortCursor::parse(&params)?;
+    let to_cursor = |file: &File| SortCursor::new(file.id.as_uuid(), key(file)).to_string();
+
+    let files = files
+        .into_iter()
+        .map(|file| match &cursor {
+            Some(cursor) => compare(file, cursor)
+                .map(|position| (file, Some(position)))
+                .ok_or_else(|| InvalidCursor(cursor.to_string())),
+            None => Ok((file, None)),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let slice = match params {
+        Paginate::Forward { first, .. } => {
+            let files: Vec<File> = files
+                .into_iter()
+                .filter(|(_, position)| position.is_none_or(Ordering::is_gt))
+                .take(first)
+                .map(|(file, _)| file.clone())
+                .collect();
+
+            let next = files.last().map(to_cursor);
+            Slice::new(files, next, None)
+        }
+        Paginate::Backward { last, .. } => {
+            let mut files: Vec<File> = files
+                .into_iter()
+                .rev()
+                .filter(|(_, position)| position.is_none_or(Ordering::is_lt))
+                .take(last)
+                .map(|(file, _)| file.clone())
+                .collect();
+            files.reverse();
+
+            let previous = files.first().map(to_cursor);
+            Slice::new(files, None, previous)
+        }
+    };
+
+    Ok(slice)
+}
+
 #[cfg(test)]
 pub(crate) mod tests;
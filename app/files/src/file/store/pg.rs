@@ -1,19 +1,23 @@
 use async_trait::async_trait;
 use oxidrive_accounts::account::AccountId;
-use oxidrive_database::paginate;
+use oxidrive_database::paginate::{self, Expression, SortCursor};
 use oxidrive_paginate::{Paginate, Slice};
-use oxidrive_search::Filter;
+use oxidrive_search::{Cmp, Comparable, Filter};
 use sqlx::{QueryBuilder, postgres::types::PgHstore};
+use time::OffsetDateTime;
 use uuid::Uuid;
 
 use crate::{
     Tag,
-    file::{File, FileId},
+    file::{Encoding, File, FileId, Sort, SortBy, time_tag},
+    folder::FolderPath,
+    tag::reserved::ACCESSED,
 };
 
 use super::{
-    AllOwnedByInError, ByIdError, ByNameError, DeleteFileError, FileMetadata, SaveFileError,
-    SearchError,
+    AllFilesError, AllInFolderError, AllOwnedByInError, ByHashError, ByIdError, ByPathError,
+    DeleteFileError, FileMetadata, IndexTextError, IndexedHashError, RecordAccessError,
+    RemoveTextError, SaveFileError, SearchError, TrashedBeforeError, TrashedByError,
 };
 
 pub struct PgFileMetadata {
@@ -40,12 +44,22 @@ select
   id,
   owner_id,
   name,
+  folder,
   content_type,
   size,
+  stored_size,
+  compressed,
+  encrypted,
+  tier,
   tags,
-  hash
+  hash,
+  created_at,
+  updated_at,
+  accessed_at,
+  deleted_at
 from files
-where owner_id =
+where deleted_at is null
+  and owner_id =
 "#,
         );
 
@@ -74,10 +88,19 @@ select
   id,
   owner_id,
   name,
+  folder,
   content_type,
   size,
+  stored_size,
+  compressed,
+  encrypted,
+  tier,
   tags,
-  hash
+  hash,
+  created_at,
+  updated_at,
+  accessed_at,
+  deleted_at
 from files
 where id = $1
 "#,
@@ -90,31 +113,84 @@ where id = $1
         Ok(file.map(File::from))
     }
 
-    async fn by_owner_and_name(
+    async fn by_owner_and_path(
         &self,
         owner_id: AccountId,
+        folder: &FolderPath,
         file_name: &str,
-    ) -> Result<Option<File>, ByNameError> {
+    ) -> Result<Option<File>, ByPathError> {
         let file: Option<PgFile> = sqlx::query_as(
             r#"
 select
   id,
   owner_id,
   name,
+  folder,
   content_type,
   size,
+  stored_size,
+  compressed,
+  encrypted,
+  tier,
   tags,
-  hash
+  hash,
+  created_at,
+  updated_at,
+  accessed_at,
+  deleted_at
 from files
 where owner_id = $1
-  and name = $2
+  and folder = $2
+  and name = $3
+  and deleted_at is null
 "#,
         )
         .bind(owner_id.as_uuid())
+        .bind(folder.as_str())
         .bind(file_name)
         .fetch_optional(&self.pool)
         .await
-        .map_err(ByNameError::wrap)?;
+        .map_err(ByPathError::wrap)?;
+
+        Ok(file.map(File::from))
+    }
+
+    async fn by_owner_and_hash(
+        &self,
+        owner_id: AccountId,
+        hash: blake3::Hash,
+    ) -> Result<Option<File>, ByHashError> {
+        let file: Option<PgFile> = sqlx::query_as(
+            r#"
+select
+  id,
+  owner_id,
+  name,
+  folder,
+  content_type,
+  size,
+  stored_size,
+  compressed,
+  encrypted,
+  tier,
+  tags,
+  hash,
+  created_at,
+  updated_at,
+  accessed_at,
+  deleted_at
+from files
+where owner_id = $1
+  and hash = $2
+  and deleted_at is null
+limit 1
+"#,
+        )
+        .bind(owner_id.as_uuid())
+        .bind(hash.as_bytes().as_slice())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(ByHashError::wrap)?;
 
         Ok(file.map(File::from))
     }
@@ -126,10 +202,19 @@ insert into files (
   id,
   owner_id,
   name,
+  folder,
   content_type,
   size,
+  stored_size,
+  compressed,
+  encrypted,
+  tier,
   tags,
-  hash
+  hash,
+  created_at,
+  updated_at,
+  accessed_at,
+  deleted_at
 ) values (
   $1,
   $2,
@@ -137,21 +222,43 @@ insert into files (
   $4,
   $5,
   $6,
-  $7
+  $7,
+  $8,
+  $9,
+  $10,
+  $11,
+  $12,
+  $13,
+  $14,
+  $15,
+  $16
 ) on conflict (id)
 do update set
   name = excluded.name,
+  folder = excluded.folder,
   content_type = excluded.content_type,
   size = excluded.size,
+  stored_size = excluded.stored_size,
+  compressed = excluded.compressed,
+  encrypted = excluded.encrypted,
+  tier = excluded.tier,
   tags = excluded.tags,
-  hash = excluded.hash
+  hash = excluded.hash,
+  updated_at = excluded.updated_at,
+  accessed_at = excluded.accessed_at,
+  deleted_at = excluded.deleted_at
 "#,
         )
         .bind(file.id.as_uuid())
         .bind(file.owner_id.as_uuid())
         .bind(&file.name)
+        .bind(file.folder.as_str())
         .bind(&file.content_type)
         .bind(file.size as i64)
+        .bind(file.stored_size as i64)
+        .bind(file.encoding.compressed)
+        .bind(file.encoding.encrypted)
+        .bind(&file.tier)
         .bind(PgHstore(
             file.tags
                 .clone()
@@ -160,6 +267,10 @@ do update set
                 .collect(),
         ))
         .bind(file.hash.as_ref().map(blake3::Hash::as_bytes))
+        .bind(file.created_at)
+        .bind(file.updated_at)
+        .bind(file.accessed_at)
+        .bind(file.deleted_at)
         .execute(&self.pool)
         .await
         .map_err(SaveFileError::wrap)?;
@@ -167,43 +278,309 @@ do update set
         Ok(file)
     }
 
+    async fn record_access(&self, id: FileId, at: OffsetDateTime) -> Result<(), RecordAccessError> {
+        let tag = time_tag(ACCESSED, at);
+
+        sqlx::query(
+            r#"
+update files
+set accessed_at = $2,
+  tags = tags || hstore($3::text, $4::text)
+where id = $1
+"#,
+        )
+        .bind(id.as_uuid())
+        .bind(at)
+        .bind(tag.key)
+        .bind(tag.value)
+        .execute(&self.pool)
+        .await
+        .map_err(RecordAccessError::wrap)?;
+
+        Ok(())
+    }
+
+    async fn index_text(
+        &self,
+        id: FileId,
+        hash: Option<blake3::Hash>,
+        text: &str,
+    ) -> Result<(), IndexTextError> {
+        sqlx::query(
+            r#"
+insert into file_texts (file_id, hash, content)
+values ($1, $2, to_tsvector('simple', $3))
+on conflict (file_id) do update
+set hash = excluded.hash,
+    content = excluded.content
+"#,
+        )
+        .bind(id.as_uuid())
+        .bind(hash.as_ref().map(blake3::Hash::as_bytes))
+        .bind(text)
+        .execute(&self.pool)
+        .await
+        .map_err(IndexTextError::wrap)?;
+
+        Ok(())
+    }
+
+    async fn indexed_hash(
+        &self,
+        id: FileId,
+    ) -> Result<Option<Option<blake3::Hash>>, IndexedHashError> {
+        let hash: Option<Option<Vec<u8>>> =
+            sqlx::query_scalar("select hash from file_texts where file_id = $1")
+                .bind(id.as_uuid())
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(IndexedHashError::wrap)?;
+
+        Ok(hash.map(|hash| {
+            hash.and_then(|bytes| bytes.try_into().ok())
+                .map(blake3::Hash::from_bytes)
+        }))
+    }
+
+    async fn remove_text(&self, id: FileId) -> Result<(), RemoveTextError> {
+        sqlx::query("delete from file_texts where file_id = $1")
+            .bind(id.as_uuid())
+            .execute(&self.pool)
+            .await
+            .map_err(RemoveTextError::wrap)?;
+
+        Ok(())
+    }
+
     async fn search(
         &self,
         owner_id: AccountId,
         filter: Filter,
+        sort: Sort,
         paginate: Paginate,
     ) -> Result<Slice<File>, SearchError> {
+        let by = sort_expression(sort.by, &filter.content_phrases());
+        let order = paginate::OrderBy {
+            by: &by,
+            kind: sort_kind(sort.by),
+            descending: sort.descending,
+        };
+
         let mut qb = QueryBuilder::new(
             r#"
 select
   id,
   owner_id,
   name,
+  folder,
   content_type,
   size,
+  stored_size,
+  compressed,
+  encrypted,
+  tier,
   tags,
-  hash
-from files
-where owner_id =
-"#,
+  hash,
+  created_at,
+  updated_at,
+  accessed_at,
+  deleted_at,
+  "#,
         );
 
+        paginate::postgres::push_sort_key(&mut qb, order);
+        qb.push("\nfrom files\nwhere deleted_at is null\n  and owner_id = ");
+
         qb.push_bind(owner_id.as_uuid());
 
         push_search_query(&mut qb, filter);
 
+        paginate::postgres::push_sorted_query(&mut qb, &paginate, order)
+            .map_err(SearchError::wrap)?;
+
+        let files: Vec<SortedPgFile> = qb
+            .build_query_as()
+            .fetch_all(&self.pool)
+            .await
+            .map_err(SearchError::wrap)?;
+
+        let slice = paginate::to_sorted_slice(
+            files,
+            |f| SortCursor::new(f.file.id, &f.sort_key).to_string(),
+            &paginate,
+        )
+        .map(|f| File::from(f.file));
+        Ok(slice)
+    }
+
+    async fn trashed_by(
+        &self,
+        owner_id: AccountId,
+        paginate: Paginate,
+    ) -> Result<Slice<File>, TrashedByError> {
+        let mut qb = QueryBuilder::new(
+            r#"
+select
+  id,
+  owner_id,
+  name,
+  folder,
+  content_type,
+  size,
+  stored_size,
+  compressed,
+  encrypted,
+  tier,
+  tags,
+  hash,
+  created_at,
+  updated_at,
+  accessed_at,
+  deleted_at
+from files
+where deleted_at is not null
+  and owner_id =
+"#,
+        );
+
+        qb.push_bind(owner_id.as_uuid());
+
         paginate::postgres::push_query(&mut qb, &paginate, "lower(name)");
 
         let files: Vec<PgFile> = qb
             .build_query_as()
             .fetch_all(&self.pool)
             .await
-            .map_err(SearchError::wrap)?;
+            .map_err(TrashedByError::wrap)?;
 
         let slice = paginate::to_slice(files, |f| f.id.to_string(), &paginate).map(File::from);
         Ok(slice)
     }
 
+    async fn trashed_before(
+        &self,
+        before: OffsetDateTime,
+        limit: usize,
+    ) -> Result<Vec<File>, TrashedBeforeError> {
+        let files: Vec<PgFile> = sqlx::query_as(
+            r#"
+select
+  id,
+  owner_id,
+  name,
+  folder,
+  content_type,
+  size,
+  stored_size,
+  compressed,
+  encrypted,
+  tier,
+  tags,
+  hash,
+  created_at,
+  updated_at,
+  accessed_at,
+  deleted_at
+from files
+where deleted_at < $1
+order by deleted_at
+limit $2
+"#,
+        )
+        .bind(before)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(TrashedBeforeError::wrap)?;
+
+        Ok(files.into_iter().map(File::from).collect())
+    }
+
+    async fn all_after(
+        &self,
+        after: Option<FileId>,
+        limit: usize,
+    ) -> Result<Vec<File>, AllFilesError> {
+        let files: Vec<PgFile> = sqlx::query_as(
+            r#"
+select
+  id,
+  owner_id,
+  name,
+  folder,
+  content_type,
+  size,
+  stored_size,
+  compressed,
+  encrypted,
+  tier,
+  tags,
+  hash,
+  created_at,
+  updated_at,
+  accessed_at,
+  deleted_at
+from files
+where $1::uuid is null
+  or id > $1
+order by id
+limit $2
+"#,
+        )
+        .bind(after.map(|id| id.as_uuid()))
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(AllFilesError::wrap)?;
+
+        Ok(files.into_iter().map(File::from).collect())
+    }
+
+    async fn all_in_folder(
+        &self,
+        owner_id: AccountId,
+        folder: &FolderPath,
+        after: Option<FileId>,
+        limit: usize,
+    ) -> Result<Vec<File>, AllInFolderError> {
+        let files: Vec<PgFile> = sqlx::query_as(
+            r#"
+select
+  id,
+  owner_id,
+  name,
+  folder,
+  content_type,
+  size,
+  stored_size,
+  compressed,
+  encrypted,
+  tier,
+  tags,
+  hash,
+  created_at,
+  updated_at,
+  accessed_at,
+  deleted_at
+from files
+where owner_id = $1
+  and ($2 = '/' or folder = $2 or substr(folder, 1, length($2) + 1) = $2 || '/')
+  and ($3::uuid is null or id > $3)
+order by id
+limit $4
+"#,
+        )
+        .bind(owner_id.as_uuid())
+        .bind(folder.as_str())
+        .bind(after.map(|id| id.as_uuid()))
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(AllInFolderError::wrap)?;
+
+        Ok(files.into_iter().map(File::from).collect())
+    }
+
     async fn delete(&self, id: FileId) -> Result<(), DeleteFileError> {
         sqlx::query("delete from files where id = $1")
             .bind(id.as_uuid())
@@ -214,6 +591,44 @@ where owner_id =
     }
 }
 
+/// The expression files are sorted by. Ranking them by relevance needs the phrases searched for,
+/// which are bound as parameters wherever the expression is used
+fn sort_expression(by: SortBy, phrases: &[&str]) -> Expression {
+    match by {
+        SortBy::Name => Expression::new("lower(name)"),
+        SortBy::Size => Expression::new("size"),
+        SortBy::ContentType => Expression::new("content_type"),
+        SortBy::Created => Expression::new("created_at"),
+        SortBy::Updated => Expression::new("updated_at"),
+        SortBy::Relevance if phrases.is_empty() => Expression::new("0"),
+        SortBy::Relevance => {
+            let mut by = Expression::new("coalesce((select ts_rank(t.content, ");
+            for (i, phrase) in phrases.iter().enumerate() {
+                if i > 0 {
+                    by.push(" || ");
+                }
+                by.push("phraseto_tsquery('simple', ")
+                    .push_bind(*phrase)
+                    .push(")");
+            }
+            by.push(") from file_texts t where t.file_id = files.id), 0)");
+            by
+        }
+    }
+}
+
+/// The SQL type of the expression files are sorted by
+fn sort_kind(by: SortBy) -> &'static str {
+    match by {
+        SortBy::Name => "text",
+        SortBy::Size => "bigint",
+        SortBy::ContentType => "text",
+        SortBy::Created => "timestamptz",
+        SortBy::Updated => "timestamptz",
+        SortBy::Relevance => "real",
+    }
+}
+
 fn push_search_query(qb: &mut QueryBuilder<'_, sqlx::Postgres>, filter: Filter) {
     qb.push(" and (");
     traverse_query(qb, filter);
@@ -235,10 +650,33 @@ fn traverse_query(qb: &mut QueryBuilder<'_, sqlx::Postgres>, filter: Filter) {
 
             if values.has_matches() {
                 let value = value.replace('*', "%");
-                qb.push(format!("tags->'{key}' LIKE ")).push_bind(value);
+                qb.push("tags -> ")
+                    .push_bind(key)
+                    .push(" LIKE ")
+                    .push_bind(value);
             } else {
-                qb.push(format!("tags->'{key}' = ")).push_bind(value);
+                qb.push("tags -> ")
+                    .push_bind(key)
+                    .push(" = ")
+                    .push_bind(value);
+            }
+        }
+        Filter::Compare { key, cmp, operand } => {
+            let (cmp, comparable) = operand.comparable(cmp);
+            push_comparison(qb, &key, cmp, comparable);
+        }
+        Filter::Range { key, from, to } => {
+            qb.push("(");
+
+            let comparisons = oxidrive_search::range_comparisons(from.as_ref(), to.as_ref());
+            for (i, (cmp, comparable)) in comparisons.into_iter().enumerate() {
+                if i > 0 {
+                    qb.push(" and ");
+                }
+                push_comparison(qb, &key, cmp, comparable);
             }
+
+            qb.push(")");
         }
         Filter::Op { lhs, op, rhs } => {
             qb.push("(");
@@ -254,25 +692,74 @@ fn traverse_query(qb: &mut QueryBuilder<'_, sqlx::Postgres>, filter: Filter) {
             traverse_query(qb, *rhs);
             qb.push(") ");
         }
+        Filter::Content(phrase) => {
+            qb.push("exists (select 1 from file_texts t where t.file_id = files.id and t.content @@ phraseto_tsquery('simple', ")
+                .push_bind(phrase)
+                .push("))");
+        }
         Filter::Mod { modifier, inner } => {
             match modifier {
-                oxidrive_search::Mod::Not => qb.push(" not "),
+                // tags missing from a file make the inner expression null rather than false
+                oxidrive_search::Mod::Not => qb.push(" not coalesce(("),
             };
 
             traverse_query(qb, *inner);
+            qb.push("), false)");
+        }
+    }
+}
+
+fn push_comparison(
+    qb: &mut QueryBuilder<'_, sqlx::Postgres>,
+    key: &str,
+    cmp: Cmp,
+    comparable: Comparable,
+) {
+    match comparable {
+        Comparable::Number(number) => {
+            qb.push("(case when (tags -> ")
+                .push_bind(key.to_string())
+                .push(") ~ '^[0-9]+$' then (tags -> ")
+                .push_bind(key.to_string())
+                .push(format!(")::numeric end) {cmp} "))
+                .push_bind(number.to_string())
+                .push("::numeric");
+        }
+        Comparable::Text(text) => {
+            // byte order, so that timestamps sort chronologically whatever the collation of the database
+            qb.push("(tags -> ")
+                .push_bind(key.to_string())
+                .push(format!(") collate \"C\" {cmp} "))
+                .push_bind(text);
         }
     }
 }
 
+#[derive(sqlx::FromRow)]
+struct SortedPgFile {
+    #[sqlx(flatten)]
+    file: PgFile,
+    sort_key: String,
+}
+
 #[derive(sqlx::FromRow)]
 struct PgFile {
     id: Uuid,
     owner_id: Uuid,
     name: String,
+    folder: String,
     content_type: String,
     size: i64,
+    stored_size: i64,
+    compressed: bool,
+    encrypted: bool,
+    tier: Option<String>,
     tags: PgHstore,
     hash: Option<Vec<u8>>,
+    created_at: OffsetDateTime,
+    updated_at: OffsetDateTime,
+    accessed_at: Option<OffsetDateTime>,
+    deleted_at: Option<OffsetDateTime>,
 }
 
 impl From<PgFile> for File {
@@ -281,8 +768,15 @@ impl From<PgFile> for File {
             id: file.id.into(),
             owner_id: file.owner_id.into(),
             name: file.name,
+            folder: file.folder.parse().unwrap(),
             content_type: file.content_type,
             size: file.size.try_into().unwrap(),
+            stored_size: file.stored_size.try_into().unwrap(),
+            encoding: Encoding {
+                compressed: file.compressed,
+                encrypted: file.encrypted,
+            },
+            tier: file.tier,
             tags: file
                 .tags
                 .0
@@ -293,6 +787,10 @@ impl From<PgFile> for File {
             hash: file
                 .hash
                 .map(|bytes| blake3::Hash::from_bytes(bytes.try_into().unwrap())),
+            created_at: file.created_at,
+            updated_at: file.updated_at,
+            accessed_at: file.accessed_at,
+            deleted_at: file.deleted_at,
         }
     }
 }
@@ -2,19 +2,23 @@ use std::collections::BTreeMap;
 
 use async_trait::async_trait;
 use oxidrive_accounts::account::AccountId;
-use oxidrive_database::paginate;
+use oxidrive_database::paginate::{self, Expression, SortCursor};
 use oxidrive_paginate::{Paginate, Slice};
-use oxidrive_search::Filter;
+use oxidrive_search::{Cmp, Comparable, Filter};
 use sqlx::{QueryBuilder, types::Json};
+use time::OffsetDateTime;
 
 use crate::{
     Tag,
-    file::{File, FileId, Tags},
+    file::{Encoding, File, FileId, Sort, SortBy, Tags, time_tag},
+    folder::FolderPath,
+    tag::reserved::ACCESSED,
 };
 
 use super::{
-    AllOwnedByInError, ByIdError, ByNameError, DeleteFileError, FileMetadata, SaveFileError,
-    SearchError,
+    AllFilesError, AllInFolderError, AllOwnedByInError, ByHashError, ByIdError, ByPathError,
+    DeleteFileError, FileMetadata, IndexTextError, IndexedHashError, RecordAccessError,
+    RemoveTextError, SaveFileError, SearchError, TrashedBeforeError, TrashedByError,
 };
 
 pub struct SqliteFileMetadata {
@@ -41,12 +45,22 @@ select distinct
   id,
   owner_id,
   name,
+  folder,
   content_type,
   size,
+  stored_size,
+  compressed,
+  encrypted,
+  tier,
   tags,
-  hash
+  hash,
+  created_at,
+  updated_at,
+  accessed_at,
+  deleted_at
 from files
-where owner_id ="#,
+where deleted_at is null
+  and owner_id ="#,
         );
 
         qb.push_bind(owner_id.to_string());
@@ -79,10 +93,19 @@ select
   id,
   owner_id,
   name,
+  folder,
   content_type,
   size,
+  stored_size,
+  compressed,
+  encrypted,
+  tier,
   tags,
-  hash
+  hash,
+  created_at,
+  updated_at,
+  accessed_at,
+  deleted_at
 from files
 where id = $1
 "#,
@@ -95,31 +118,84 @@ where id = $1
         Ok(file.map(File::from))
     }
 
-    async fn by_owner_and_name(
+    async fn by_owner_and_path(
         &self,
         owner_id: AccountId,
+        folder: &FolderPath,
         file_name: &str,
-    ) -> Result<Option<File>, ByNameError> {
+    ) -> Result<Option<File>, ByPathError> {
         let file: Option<SqliteFile> = sqlx::query_as(
             r#"
 select
   id,
   owner_id,
   name,
+  folder,
   content_type,
   size,
+  stored_size,
+  compressed,
+  encrypted,
+  tier,
   tags,
-  hash
+  hash,
+  created_at,
+  updated_at,
+  accessed_at,
+  deleted_at
 from files
 where owner_id = $1
-  and name = $2
+  and folder = $2
+  and name = $3
+  and deleted_at is null
 "#,
         )
         .bind(owner_id.to_string())
+        .bind(folder.as_str())
         .bind(file_name)
         .fetch_optional(&self.pool)
         .await
-        .map_err(ByNameError::wrap)?;
+        .map_err(ByPathError::wrap)?;
+
+        Ok(file.map(File::from))
+    }
+
+    async fn by_owner_and_hash(
+        &self,
+        owner_id: AccountId,
+        hash: blake3::Hash,
+    ) -> Result<Option<File>, ByHashError> {
+        let file: Option<SqliteFile> = sqlx::query_as(
+            r#"
+select
+  id,
+  owner_id,
+  name,
+  folder,
+  content_type,
+  size,
+  stored_size,
+  compressed,
+  encrypted,
+  tier,
+  tags,
+  hash,
+  created_at,
+  updated_at,
+  accessed_at,
+  deleted_at
+from files
+where owner_id = $1
+  and hash = $2
+  and deleted_at is null
+limit 1
+"#,
+        )
+        .bind(owner_id.to_string())
+        .bind(hash.as_bytes().as_slice())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(ByHashError::wrap)?;
 
         Ok(file.map(File::from))
     }
@@ -134,10 +210,19 @@ insert into files (
   id,
   owner_id,
   name,
+  folder,
   content_type,
   size,
+  stored_size,
+  compressed,
+  encrypted,
+  tier,
   tags,
-  hash
+  hash,
+  created_at,
+  updated_at,
+  accessed_at,
+  deleted_at
 ) values (
   $1,
   $2,
@@ -145,23 +230,49 @@ insert into files (
   $4,
   $5,
   $6,
-  $7
+  $7,
+  $8,
+  $9,
+  $10,
+  $11,
+  $12,
+  $13,
+  $14,
+  $15,
+  $16
 ) on conflict (id)
 do update set
   name = excluded.name,
+  folder = excluded.folder,
   content_type = excluded.content_type,
   size = excluded.size,
+  stored_size = excluded.stored_size,
+  compressed = excluded.compressed,
+  encrypted = excluded.encrypted,
+  tier = excluded.tier,
   tags = excluded.tags,
-  hash = excluded.hash
+  hash = excluded.hash,
+  updated_at = excluded.updated_at,
+  accessed_at = excluded.accessed_at,
+  deleted_at = excluded.deleted_at
 "#,
         )
         .bind(id)
         .bind(owner_id)
         .bind(&file.name)
+        .bind(file.folder.as_str())
         .bind(&file.content_type)
         .bind(file.size as i64)
+        .bind(file.stored_size as i64)
+        .bind(file.encoding.compressed)
+        .bind(file.encoding.encrypted)
+        .bind(&file.tier)
         .bind(to_sqlite_tags(file.tags.clone()))
         .bind(file.hash.as_ref().map(|hash| hash.as_bytes().as_slice()))
+        .bind(file.created_at)
+        .bind(file.updated_at)
+        .bind(file.accessed_at)
+        .bind(file.deleted_at)
         .execute(&self.pool)
         .await
         .map_err(SaveFileError::wrap)?;
@@ -169,43 +280,318 @@ do update set
         Ok(file)
     }
 
+    async fn record_access(&self, id: FileId, at: OffsetDateTime) -> Result<(), RecordAccessError> {
+        let tag = time_tag(ACCESSED, at);
+
+        sqlx::query(
+            r#"
+update files
+set accessed_at = $2,
+  tags = json_set(tags, '$.' || $3, $4)
+where id = $1
+"#,
+        )
+        .bind(id.to_string())
+        .bind(at)
+        .bind(tag.key)
+        .bind(tag.value)
+        .execute(&self.pool)
+        .await
+        .map_err(RecordAccessError::wrap)?;
+
+        Ok(())
+    }
+
+    async fn index_text(
+        &self,
+        id: FileId,
+        hash: Option<blake3::Hash>,
+        text: &str,
+    ) -> Result<(), IndexTextError> {
+        // FTS5 tables have no unique constraints to upsert on
+        let mut tx = self.pool.begin().await.map_err(IndexTextError::wrap)?;
+
+        sqlx::query("delete from file_texts where file_id = $1")
+            .bind(id.to_string())
+            .execute(&mut *tx)
+            .await
+            .map_err(IndexTextError::wrap)?;
+
+        sqlx::query("insert into file_texts (file_id, hash, content) values ($1, $2, $3)")
+            .bind(id.to_string())
+            .bind(hash.as_ref().map(|hash| hash.as_bytes().as_slice()))
+            .bind(text)
+            .execute(&mut *tx)
+            .await
+            .map_err(IndexTextError::wrap)?;
+
+        tx.commit().await.map_err(IndexTextError::wrap)?;
+
+        Ok(())
+    }
+
+    async fn indexed_hash(
+        &self,
+        id: FileId,
+    ) -> Result<Option<Option<blake3::Hash>>, IndexedHashError> {
+        let hash: Option<Option<Vec<u8>>> =
+            sqlx::query_scalar("select hash from file_texts where file_id = $1")
+                .bind(id.to_string())
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(IndexedHashError::wrap)?;
+
+        Ok(hash.map(|hash| {
+            hash.and_then(|bytes| bytes.try_into().ok())
+                .map(blake3::Hash::from_bytes)
+        }))
+    }
+
+    async fn remove_text(&self, id: FileId) -> Result<(), RemoveTextError> {
+        sqlx::query("delete from file_texts where file_id = $1")
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await
+            .map_err(RemoveTextError::wrap)?;
+
+        Ok(())
+    }
+
     async fn search(
         &self,
         owner_id: AccountId,
         filter: Filter,
+        sort: Sort,
         paginate: Paginate,
     ) -> Result<Slice<File>, SearchError> {
+        let by = sort_expression(sort.by, &filter.content_phrases());
+        let order = paginate::OrderBy {
+            by: &by,
+            kind: sort_kind(sort.by),
+            descending: sort.descending,
+        };
+
         let mut qb = QueryBuilder::new(
             r#"
 select distinct
   id,
   owner_id,
   name,
+  folder,
   content_type,
   size,
+  stored_size,
+  compressed,
+  encrypted,
+  tier,
   tags,
-  hash
-from files
-where owner_id ="#,
+  hash,
+  created_at,
+  updated_at,
+  accessed_at,
+  deleted_at,
+  "#,
         );
 
+        paginate::sqlite::push_sort_key(&mut qb, order);
+        qb.push("\nfrom files\nwhere deleted_at is null\n  and owner_id = ");
+
         qb.push_bind(owner_id.to_string());
 
         push_search_query(&mut qb, filter);
 
+        paginate::sqlite::push_sorted_query(&mut qb, &paginate, order)
+            .map_err(SearchError::wrap)?;
+
+        let files: Vec<SortedSqliteFile> = qb
+            .build_query_as()
+            .fetch_all(&self.pool)
+            .await
+            .map_err(SearchError::wrap)?;
+
+        let slice = paginate::to_sorted_slice(
+            files,
+            |f| SortCursor::new(f.file.id.parse().unwrap(), &f.sort_key).to_string(),
+            &paginate,
+        )
+        .map(|f| File::from(f.file));
+        Ok(slice)
+    }
+
+    async fn trashed_by(
+        &self,
+        owner_id: AccountId,
+        paginate: Paginate,
+    ) -> Result<Slice<File>, TrashedByError> {
+        let mut qb = QueryBuilder::new(
+            r#"
+select distinct
+  id,
+  owner_id,
+  name,
+  folder,
+  content_type,
+  size,
+  stored_size,
+  compressed,
+  encrypted,
+  tier,
+  tags,
+  hash,
+  created_at,
+  updated_at,
+  accessed_at,
+  deleted_at
+from files
+where deleted_at is not null
+  and owner_id ="#,
+        );
+
+        qb.push_bind(owner_id.to_string());
+
         paginate::sqlite::push_query(&mut qb, &paginate, "lower(name)");
 
         let files: Vec<SqliteFile> = qb
             .build_query_as()
             .fetch_all(&self.pool)
             .await
-            .map_err(SearchError::wrap)?;
+            .map_err(TrashedByError::wrap)?;
 
         let slice = paginate::to_slice(files, |f| f.id.to_string(), &paginate).map(File::from);
         Ok(slice)
     }
 
+    async fn trashed_before(
+        &self,
+        before: OffsetDateTime,
+        limit: usize,
+    ) -> Result<Vec<File>, TrashedBeforeError> {
+        let files: Vec<SqliteFile> = sqlx::query_as(
+            r#"
+select
+  id,
+  owner_id,
+  name,
+  folder,
+  content_type,
+  size,
+  stored_size,
+  compressed,
+  encrypted,
+  tier,
+  tags,
+  hash,
+  created_at,
+  updated_at,
+  accessed_at,
+  deleted_at
+from files
+where deleted_at < ?
+order by deleted_at
+limit ?
+"#,
+        )
+        .bind(before)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(TrashedBeforeError::wrap)?;
+
+        Ok(files.into_iter().map(File::from).collect())
+    }
+
+    async fn all_after(
+        &self,
+        after: Option<FileId>,
+        limit: usize,
+    ) -> Result<Vec<File>, AllFilesError> {
+        let files: Vec<SqliteFile> = sqlx::query_as(
+            r#"
+select
+  id,
+  owner_id,
+  name,
+  folder,
+  content_type,
+  size,
+  stored_size,
+  compressed,
+  encrypted,
+  tier,
+  tags,
+  hash,
+  created_at,
+  updated_at,
+  accessed_at,
+  deleted_at
+from files
+where ?1 is null
+  or id > ?1
+order by id
+limit ?2
+"#,
+        )
+        .bind(after.map(|id| id.to_string()))
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(AllFilesError::wrap)?;
+
+        Ok(files.into_iter().map(File::from).collect())
+    }
+
+    async fn all_in_folder(
+        &self,
+        owner_id: AccountId,
+        folder: &FolderPath,
+        after: Option<FileId>,
+        limit: usize,
+    ) -> Result<Vec<File>, AllInFolderError> {
+        let files: Vec<SqliteFile> = sqlx::query_as(
+            r#"
+select
+  id,
+  owner_id,
+  name,
+  folder,
+  content_type,
+  size,
+  stored_size,
+  compressed,
+  encrypted,
+  tier,
+  tags,
+  hash,
+  created_at,
+  updated_at,
+  accessed_at,
+  deleted_at
+from files
+where owner_id = $1
+  and ($2 = '/' or folder = $2 or substr(folder, 1, length($2) + 1) = $2 || '/')
+  and ($3 is null or id > $3)
+order by id
+limit $4
+"#,
+        )
+        .bind(owner_id.to_string())
+        .bind(folder.as_str())
+        .bind(after.map(|id| id.to_string()))
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(AllInFolderError::wrap)?;
+
+        Ok(files.into_iter().map(File::from).collect())
+    }
+
     async fn delete(&self, id: FileId) -> Result<(), DeleteFileError> {
+        sqlx::query("delete from file_texts where file_id = ?")
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await
+            .map_err(DeleteFileError::wrap)?;
+
         sqlx::query("delete from files where id = ?")
             .bind(id.to_string())
             .execute(&self.pool)
@@ -215,6 +601,50 @@ where owner_id ="#,
     }
 }
 
+/// The expression files are sorted by. Ranking them by relevance needs the phrases searched for,
+/// which are bound as parameters wherever the expression is used
+fn sort_expression(by: SortBy, phrases: &[&str]) -> Expression {
+    match by {
+        SortBy::Name => Expression::new("lower(name)"),
+        SortBy::Size => Expression::new("size"),
+        SortBy::ContentType => Expression::new("content_type"),
+        SortBy::Created => Expression::new("created_at"),
+        SortBy::Updated => Expression::new("updated_at"),
+        SortBy::Relevance if phrases.is_empty() => Expression::new("0"),
+        SortBy::Relevance => {
+            let query = phrases
+                .iter()
+                .map(|phrase| fts_phrase(phrase))
+                .collect::<Vec<_>>()
+                .join(" OR ");
+
+            // bm25 scores are lower for better matches
+            let mut by = Expression::new(
+                "coalesce((select -bm25(file_texts) from file_texts where file_texts match ",
+            );
+            by.push_bind(query).push(" and file_id = files.id), 0)");
+            by
+        }
+    }
+}
+
+/// The SQL type of the expression files are sorted by
+fn sort_kind(by: SortBy) -> &'static str {
+    match by {
+        SortBy::Name => "text",
+        SortBy::Size => "integer",
+        SortBy::ContentType => "text",
+        SortBy::Created => "text",
+        SortBy::Updated => "text",
+        SortBy::Relevance => "real",
+    }
+}
+
+/// Quotes the phrase for FTS5, so that its words are matched in this order
+fn fts_phrase(phrase: &str) -> String {
+    format!(r#""{}""#, phrase.replace('"', r#""""#))
+}
+
 fn push_search_query(qb: &mut QueryBuilder<'_, sqlx::Sqlite>, filter: Filter) {
     qb.push(" and (");
     traverse_query(qb, filter);
@@ -228,7 +658,7 @@ fn traverse_query(qb: &mut QueryBuilder<'_, sqlx::Sqlite>, filter: Filter) {
         }
         Filter::Tag { key, values } => {
             if values.is_empty() {
-                qb.push(format!("tags->>'{key}' is not null"));
+                qb.push("tags ->> ").push_bind(key).push(" is not null");
                 return;
             }
 
@@ -236,11 +666,34 @@ fn traverse_query(qb: &mut QueryBuilder<'_, sqlx::Sqlite>, filter: Filter) {
 
             if values.has_matches() {
                 let value = value.replace('*', "%");
-                qb.push(format!("tags->>'{key}' LIKE ")).push_bind(value);
+                qb.push("tags ->> ")
+                    .push_bind(key)
+                    .push(" LIKE ")
+                    .push_bind(value);
             } else {
-                qb.push(format!("tags->>'{key}' = ")).push_bind(value);
+                qb.push("tags ->> ")
+                    .push_bind(key)
+                    .push(" = ")
+                    .push_bind(value);
             }
         }
+        Filter::Compare { key, cmp, operand } => {
+            let (cmp, comparable) = operand.comparable(cmp);
+            push_comparison(qb, &key, cmp, comparable);
+        }
+        Filter::Range { key, from, to } => {
+            qb.push("(");
+
+            let comparisons = oxidrive_search::range_comparisons(from.as_ref(), to.as_ref());
+            for (i, (cmp, comparable)) in comparisons.into_iter().enumerate() {
+                if i > 0 {
+                    qb.push(" and ");
+                }
+                push_comparison(qb, &key, cmp, comparable);
+            }
+
+            qb.push(")");
+        }
         Filter::Op { lhs, op, rhs } => {
             qb.push("(");
             traverse_query(qb, *lhs);
@@ -255,25 +708,74 @@ fn traverse_query(qb: &mut QueryBuilder<'_, sqlx::Sqlite>, filter: Filter) {
             traverse_query(qb, *rhs);
             qb.push(") ");
         }
+        Filter::Content(phrase) => {
+            qb.push("files.id in (select file_id from file_texts where file_texts match ")
+                .push_bind(fts_phrase(&phrase))
+                .push(")");
+        }
         Filter::Mod { modifier, inner } => {
             match modifier {
-                oxidrive_search::Mod::Not => qb.push(" not "),
+                // tags missing from a file make the inner expression null rather than false
+                oxidrive_search::Mod::Not => qb.push(" not coalesce(("),
             };
 
             traverse_query(qb, *inner);
+            qb.push("), false)");
+        }
+    }
+}
+
+fn push_comparison(
+    qb: &mut QueryBuilder<'_, sqlx::Sqlite>,
+    key: &str,
+    cmp: Cmp,
+    comparable: Comparable,
+) {
+    match comparable {
+        Comparable::Number(number) => {
+            qb.push("(case when tags ->> ")
+                .push_bind(key.to_string())
+                .push(" != '' and tags ->> ")
+                .push_bind(key.to_string())
+                .push(" not glob '*[^0-9]*' then cast(tags ->> ")
+                .push_bind(key.to_string())
+                .push(format!(" as integer) end) {cmp} "))
+                .push_bind(i64::try_from(number).unwrap_or(i64::MAX));
+        }
+        Comparable::Text(text) => {
+            qb.push("tags ->> ")
+                .push_bind(key.to_string())
+                .push(format!(" {cmp} "))
+                .push_bind(text);
         }
     }
 }
 
+#[derive(sqlx::FromRow)]
+struct SortedSqliteFile {
+    #[sqlx(flatten)]
+    file: SqliteFile,
+    sort_key: String,
+}
+
 #[derive(sqlx::FromRow)]
 struct SqliteFile {
     id: String,
     owner_id: String,
     name: String,
+    folder: String,
     content_type: String,
     size: i64,
+    stored_size: i64,
+    compressed: bool,
+    encrypted: bool,
+    tier: Option<String>,
     tags: SqliteTags,
     hash: Option<Vec<u8>>,
+    created_at: OffsetDateTime,
+    updated_at: OffsetDateTime,
+    accessed_at: Option<OffsetDateTime>,
+    deleted_at: Option<OffsetDateTime>,
 }
 
 impl From<SqliteFile> for File {
@@ -282,8 +784,15 @@ impl From<SqliteFile> for File {
             id: file.id.parse().unwrap(),
             owner_id: file.owner_id.parse().unwrap(),
             name: file.name,
+            folder: file.folder.parse().unwrap(),
             content_type: file.content_type,
             size: file.size.try_into().unwrap(),
+            stored_size: file.stored_size.try_into().unwrap(),
+            encoding: Encoding {
+                compressed: file.compressed,
+                encrypted: file.encrypted,
+            },
+            tier: file.tier,
             tags: file
                 .tags
                 .0
@@ -298,6 +807,10 @@ impl From<SqliteFile> for File {
             hash: file
                 .hash
                 .map(|bytes| blake3::Hash::from_bytes(bytes.try_into().unwrap())),
+            created_at: file.created_at,
+            updated_at: file.updated_at,
+            accessed_at: file.accessed_at,
+            deleted_at: file.deleted_at,
         }
     }
 }
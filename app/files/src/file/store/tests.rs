@@ -4,11 +4,14 @@ use oxidrive_accounts::{
     account_id,
 };
 use oxidrive_paginate::Paginate;
+use oxidrive_search::Filter;
+use time::OffsetDateTime;
 
 use crate::{
     File, FileId,
-    file::{self, macros::file_id},
-    tag,
+    file::{self, Encoding, Sort, SortBy, macros::file_id},
+    folder::FolderPath,
+    tag::{self, reserved::ACCESSED},
 };
 
 use super::FileMetadata;
@@ -18,9 +21,17 @@ macro_rules! check_file {
         check!($expected.id == $actual.id);
         check!($expected.owner_id == $actual.owner_id);
         check!($expected.name == $actual.name);
+        check!($expected.folder == $actual.folder);
         check!($expected.size == $actual.size);
+        check!($expected.stored_size == $actual.stored_size);
+        check!($expected.encoding == $actual.encoding);
+        check!($expected.tier == $actual.tier);
         check!($expected.tags == $actual.tags);
         check!($expected.hash == $actual.hash);
+        check!($expected.created_at == $actual.created_at);
+        check!($expected.updated_at == $actual.updated_at);
+        check!($expected.accessed_at == $actual.accessed_at);
+        check!($expected.is_trashed() == $actual.is_trashed());
     };
 }
 
@@ -32,10 +43,18 @@ pub fn file_1() -> File {
         id: FILE_ID_1,
         owner_id: OWNER_ID,
         name: "hello.txt".into(),
+        folder: FolderPath::root(),
         content_type: "text/plain".into(),
         size: 0,
+        stored_size: 0,
+        encoding: Encoding::default(),
+        tier: None,
         tags: Default::default(),
         hash: Some(blake3::hash(b"hello world")),
+        created_at: at(1736499600),
+        updated_at: at(1736499600),
+        accessed_at: None,
+        deleted_at: None,
     };
 
     file.tags = File::default_tags(&file);
@@ -48,10 +67,21 @@ pub fn file_2() -> File {
         id: FILE_ID_2,
         owner_id: OWNER_ID,
         name: "world.txt".into(),
+        folder: FolderPath::parse("/docs").unwrap(),
         content_type: "text/plain".into(),
         size: 0,
+        stored_size: 0,
+        encoding: Encoding {
+            compressed: true,
+            encrypted: true,
+        },
+        tier: Some("cold".into()),
         tags: Default::default(),
         hash: Some(blake3::hash(b"hello world")),
+        created_at: at(1736499600),
+        updated_at: at(1736706600),
+        accessed_at: None,
+        deleted_at: None,
     };
 
     file.tags = File::default_tags(&file);
@@ -59,6 +89,10 @@ pub fn file_2() -> File {
     file
 }
 
+fn at(timestamp: i64) -> OffsetDateTime {
+    OffsetDateTime::from_unix_timestamp(timestamp).unwrap()
+}
+
 const OWNER_ID: AccountId = account_id!("0194327d-becc-7ef3-809c-35dd09f62f45");
 
 fn owner() -> Account {
@@ -109,20 +143,50 @@ async fn store_and_load_file_by_id<S: FileMetadata>(store: S) {
     check_file!(file, loaded);
 }
 
-async fn store_and_load_file_by_name<S: FileMetadata>(store: S) {
+async fn store_and_load_file_by_path<S: FileMetadata>(store: S) {
     let owner = owner();
 
-    let file = file::fixtures::file(owner.clone());
+    let file = file::fixtures::file(owner.clone()).in_folder(FolderPath::parse("/docs").unwrap());
 
     let stored = store.save(file.clone()).await.unwrap();
     check_file!(file, stored);
 
     let loaded = store
-        .by_owner_and_name(owner.id, &file.name)
+        .by_owner_and_path(owner.id, &file.folder, &file.name)
         .await
         .unwrap()
         .unwrap();
     check_file!(file, loaded);
+
+    // the same name can be used in other folders
+    let_assert!(
+        None = store
+            .by_owner_and_path(owner.id, &FolderPath::root(), &file.name)
+            .await
+            .unwrap()
+    );
+}
+
+async fn store_and_load_file_by_hash<S: FileMetadata>(store: S) {
+    let owner = owner();
+
+    let mut file = file::fixtures::file(owner.clone());
+    let hash = blake3::hash(b"hello world");
+    file.set_hash(hash);
+
+    store.save(file.clone()).await.unwrap();
+
+    let_assert!(Some(loaded) = store.by_owner_and_hash(owner.id, hash).await.unwrap());
+    check_file!(file, loaded);
+
+    let other = blake3::hash(b"other");
+    let_assert!(None = store.by_owner_and_hash(owner.id, other).await.unwrap());
+
+    // trashed files can't be used as a source of content
+    file.trash();
+    store.save(file).await.unwrap();
+
+    let_assert!(None = store.by_owner_and_hash(owner.id, hash).await.unwrap());
 }
 
 const SEARCH_FILES_CASES: &[(&str, &[FileId])] = &[
@@ -135,16 +199,188 @@ const SEARCH_FILES_CASES: &[(&str, &[FileId])] = &[
     ("-file1", &[FILE_ID_2]),
     ("-file2", &[FILE_ID_1]),
     ("name:*.txt", &[FILE_ID_1, FILE_ID_2]),
+    ("dir:/", &[FILE_ID_1]),
+    ("dir:/docs", &[FILE_ID_2]),
+    ("path:/docs/world.txt", &[FILE_ID_2]),
+    ("path:/docs/*", &[FILE_ID_2]),
+    ("created:2025-01-10*", &[FILE_ID_1, FILE_ID_2]),
+    ("updated:2025-01-12*", &[FILE_ID_2]),
+    ("updated:2025-01-10T09:00:00Z", &[FILE_ID_1]),
+    ("updated>2025-01-10", &[FILE_ID_2]),
+    ("updated<=2025-01-10", &[FILE_ID_1]),
+    ("updated>=2025-01-12T18:30:00Z", &[FILE_ID_2]),
+    ("created<2025-01-10", &[]),
+    ("updated:2025-01-11..2025-01-31", &[FILE_ID_2]),
+    ("updated:..2025-01-10", &[FILE_ID_1]),
+    ("created:2025-01-10.. -dir:/", &[FILE_ID_2]),
+    ("size>=0", &[FILE_ID_1, FILE_ID_2]),
+    ("size>1KB", &[]),
+    ("size:..1KiB", &[FILE_ID_1, FILE_ID_2]),
+    ("file1>0", &[]),
+    ("-(file1 OR file2)", &[]),
+    ("-(file1 AND dir:/docs)", &[FILE_ID_1, FILE_ID_2]),
+    ("-(dir:/docs OR updated>2025-01-11)", &[FILE_ID_1]),
+    ("name:*.txt AND -(-(file2))", &[FILE_ID_2]),
+    ("-(file1) OR -(file2)", &[FILE_ID_1, FILE_ID_2]),
+    ("-(file1) name:*.txt", &[FILE_ID_2]),
+    // keys are bound as parameters, so quotes in them cannot break out of the query
+    ("it's", &[]),
+    ("it's:x", &[]),
+    ("it's:x*", &[]),
+    ("it's>1", &[]),
+    ("it's<x", &[]),
+    ("it's:1..2", &[]),
+    ("-it's:x", &[FILE_ID_1, FILE_ID_2]),
 ];
 
 async fn search_files<S: FileMetadata>(store: S) {
     let owner = owner();
 
     for (query, expected_ids) in SEARCH_FILES_CASES {
+        let parsed = oxidrive_search::parse_query(query).unwrap();
+        // collections store their filter as text, so it must mean the same once printed
+        let reparsed = oxidrive_search::parse_query(parsed.to_string()).unwrap();
+
+        for filter in [parsed, reparsed] {
+            let files = store
+                .search(owner.id, filter, Sort::default(), Paginate::default())
+                .await
+                .unwrap()
+                .items;
+
+            let mut ids = files.into_iter().map(|f| f.id).collect::<Vec<_>>();
+            ids.sort();
+
+            check!(*expected_ids == ids.as_slice(), "query failed: {query}");
+        }
+    }
+}
+
+const SORT_FILES_CASES: &[(Sort, &[FileId])] = &[
+    (Sort::asc(SortBy::Name), &[FILE_ID_1, FILE_ID_2]),
+    (Sort::desc(SortBy::Name), &[FILE_ID_2, FILE_ID_1]),
+    (Sort::asc(SortBy::Updated), &[FILE_ID_1, FILE_ID_2]),
+    (Sort::desc(SortBy::Updated), &[FILE_ID_2, FILE_ID_1]),
+    // same size and creation time, so the id decides
+    (Sort::asc(SortBy::Size), &[FILE_ID_1, FILE_ID_2]),
+    (Sort::desc(SortBy::Created), &[FILE_ID_2, FILE_ID_1]),
+    (Sort::asc(SortBy::ContentType), &[FILE_ID_1, FILE_ID_2]),
+];
+
+async fn sort_files<S: FileMetadata>(store: S) {
+    let owner = owner();
+
+    for (sort, expected_ids) in SORT_FILES_CASES {
+        let files = store
+            .search(owner.id, Filter::All, *sort, Paginate::default())
+            .await
+            .unwrap();
+        let ids = files.iter().map(|f| f.id).collect::<Vec<_>>();
+        check!(*expected_ids == ids.as_slice(), "sort failed: {sort}");
+
+        // one file per page, going forward then back again
+        let first = store
+            .search(owner.id, Filter::All, *sort, Paginate::first(1))
+            .await
+            .unwrap();
+        let_assert!(Some(next) = first.next.clone());
+        let second = store
+            .search(owner.id, Filter::All, *sort, Paginate::after(next))
+            .await
+            .unwrap();
+        let_assert!(Some(last) = second.next.clone());
+        let end = store
+            .search(owner.id, Filter::All, *sort, Paginate::after(last.clone()))
+            .await
+            .unwrap();
+        let back = store
+            .search(owner.id, Filter::All, *sort, Paginate::backward(last, 1))
+            .await
+            .unwrap();
+
+        let pages =
+            [&first, &second, &back].map(|page| page.iter().map(|f| f.id).collect::<Vec<_>>());
+        check!(
+            pages
+                == [
+                    vec![expected_ids[0]],
+                    vec![expected_ids[1]],
+                    vec![expected_ids[0]]
+                ],
+            "pagination failed: {sort}"
+        );
+        check!(end.is_empty());
+    }
+}
+
+async fn resume_after_deleted_cursor<S: FileMetadata>(store: S) {
+    let owner = owner();
+    let sort = Sort::asc(SortBy::Size);
+
+    let first = store
+        .search(owner.id, Filter::All, sort, Paginate::first(1))
+        .await
+        .unwrap();
+    check!(first.iter().map(|f| f.id).collect::<Vec<_>>() == [FILE_ID_1]);
+    let_assert!(Some(next) = first.next.clone());
+
+    // the cursor carries the position of the file, which is still known once it is gone
+    store.delete(FILE_ID_1).await.unwrap();
+
+    let second = store
+        .search(owner.id, Filter::All, sort, Paginate::after(next))
+        .await
+        .unwrap();
+    check!(second.iter().map(|f| f.id).collect::<Vec<_>>() == [FILE_ID_2]);
+}
+
+async fn reject_invalid_cursor<S: FileMetadata>(store: S) {
+    let owner = owner();
+
+    for cursor in ["not a cursor", "not-a-uuid:file.txt", "0"] {
+        let result = store
+            .search(
+                owner.id,
+                Filter::All,
+                Sort::default(),
+                Paginate::after(cursor),
+            )
+            .await;
+        check!(result.is_err(), "cursor accepted: {cursor}");
+    }
+}
+
+const SEARCH_CONTENT_CASES: &[(&str, &[FileId])] = &[
+    ("\"quarterly report\"", &[FILE_ID_1, FILE_ID_2]),
+    ("content:budget", &[FILE_ID_1]),
+    ("content:BUDGET", &[FILE_ID_1]),
+    ("\"report quarterly\"", &[]),
+    ("\"quarterly report\" -content:budget", &[FILE_ID_2]),
+    ("file2 AND \"quarterly report\"", &[FILE_ID_2]),
+    ("file2 AND content:budget", &[]),
+];
+
+async fn search_content<S: FileMetadata>(store: S) {
+    let owner = owner();
+
+    store
+        .index_text(
+            FILE_ID_1,
+            None,
+            "The quarterly report shows the budget. The budget is tight.",
+        )
+        .await
+        .unwrap();
+    store
+        .index_text(FILE_ID_2, None, "Notes on the quarterly report")
+        .await
+        .unwrap();
+
+    for (query, expected_ids) in SEARCH_CONTENT_CASES {
         let filter = oxidrive_search::parse_query(query).unwrap();
 
         let files = store
-            .search(owner.id, filter, Paginate::default())
+            .search(owner.id, filter, Sort::default(), Paginate::default())
             .await
             .unwrap()
             .items;
@@ -154,6 +390,194 @@ async fn search_files<S: FileMetadata>(store: S) {
 
         check!(*expected_ids == ids.as_slice(), "query failed: {query}");
     }
+
+    let filter = oxidrive_search::parse_query("\"quarterly report\" OR content:budget").unwrap();
+    let files = store
+        .search(
+            owner.id,
+            filter,
+            Sort::desc(SortBy::Relevance),
+            Paginate::default(),
+        )
+        .await
+        .unwrap();
+    let ids = files.iter().map(|f| f.id).collect::<Vec<_>>();
+    check!(ids == [FILE_ID_1, FILE_ID_2]);
+
+    // phrases are bound as parameters, whatever characters they contain
+    store
+        .index_text(FILE_ID_2, None, r"Notes on the report it's in C:\reports")
+        .await
+        .unwrap();
+    let filter = oxidrive_search::parse_query(r#""it's in C:\reports""#).unwrap();
+    let sort = Sort::desc(SortBy::Relevance);
+    let first = store
+        .search(owner.id, filter.clone(), sort, Paginate::first(1))
+        .await
+        .unwrap();
+    check!(first.iter().map(|f| f.id).collect::<Vec<_>>() == [FILE_ID_2]);
+    let_assert!(Some(next) = first.next.clone());
+    let rest = store
+        .search(owner.id, filter, sort, Paginate::after(next))
+        .await
+        .unwrap();
+    check!(rest.is_empty());
+
+    let hash = blake3::hash(b"The budget, again");
+
+    check!(store.indexed_hash(FILE_ID_1).await.unwrap() == Some(None));
+    store.remove_text(FILE_ID_1).await.unwrap();
+    check!(store.indexed_hash(FILE_ID_1).await.unwrap().is_none());
+
+    store
+        .index_text(FILE_ID_2, Some(hash), "The budget, again")
+        .await
+        .unwrap();
+    check!(store.indexed_hash(FILE_ID_2).await.unwrap() == Some(Some(hash)));
+
+    let filter = oxidrive_search::parse_query("content:budget OR \"quarterly report\"").unwrap();
+    let files = store
+        .search(owner.id, filter, Sort::default(), Paginate::default())
+        .await
+        .unwrap();
+    let ids = files.iter().map(|f| f.id).collect::<Vec<_>>();
+    check!(ids == [FILE_ID_2]);
+}
+
+async fn record_file_access<S: FileMetadata>(store: S) {
+    let accessed_at = at(1736766000);
+
+    store.record_access(FILE_ID_1, accessed_at).await.unwrap();
+
+    let file = store.by_id(FILE_ID_1).await.unwrap().unwrap();
+    check!(file.accessed_at() == Some(accessed_at));
+    check!(file.tags.get(ACCESSED) == Some(&tag!("accessed:2025-01-13T11:00:00Z")));
+
+    // nothing else changes
+    let mut expected = file_1();
+    expected.access(accessed_at);
+    check_file!(expected, file);
+}
+
+async fn trash_file<S: FileMetadata>(store: S) {
+    let owner = owner();
+
+    let mut file = file_1();
+    file.trash();
+    store.save(file.clone()).await.unwrap();
+
+    let_assert!(Some(trashed) = store.by_id(FILE_ID_1).await.unwrap());
+    check_file!(file, trashed);
+
+    let_assert!(
+        None = store
+            .by_owner_and_path(owner.id, &file.folder, &file.name)
+            .await
+            .unwrap()
+    );
+
+    let files = store
+        .all_owned_by(owner.id, Paginate::default())
+        .await
+        .unwrap();
+    let ids = files.into_iter().map(|f| f.id).collect::<Vec<_>>();
+    check!(ids == [FILE_ID_2]);
+
+    let files = store
+        .all_owned_by_in(owner.id, &[FILE_ID_1], Paginate::default())
+        .await
+        .unwrap();
+    check!(files.is_empty());
+
+    let trashed = store
+        .trashed_by(owner.id, Paginate::default())
+        .await
+        .unwrap();
+    let ids = trashed.into_iter().map(|f| f.id).collect::<Vec<_>>();
+    check!(ids == [FILE_ID_1]);
+
+    let expired = store
+        .trashed_before(time::OffsetDateTime::now_utc(), 10)
+        .await
+        .unwrap();
+    let ids = expired.into_iter().map(|f| f.id).collect::<Vec<_>>();
+    check!(ids == [FILE_ID_1]);
+
+    let expired = store
+        .trashed_before(
+            time::OffsetDateTime::now_utc() - time::Duration::days(1),
+            10,
+        )
+        .await
+        .unwrap();
+    check!(expired.is_empty());
+
+    // a trashed file does not prevent reusing its name
+    let mut replacement = file_1();
+    replacement.id = FileId::new();
+    store.save(replacement.clone()).await.unwrap();
+
+    let_assert!(
+        Some(loaded) = store
+            .by_owner_and_path(owner.id, &file.folder, &file.name)
+            .await
+            .unwrap()
+    );
+    check_file!(replacement, loaded);
+}
+
+async fn list_all_files<S: FileMetadata>(store: S) {
+    // trashed files are included too
+    let mut file = file_1();
+    file.trash();
+    store.save(file).await.unwrap();
+
+    let files = store.all_after(None, 1).await.unwrap();
+    let ids = files.into_iter().map(|f| f.id).collect::<Vec<_>>();
+    check!(ids == [FILE_ID_1]);
+
+    let files = store.all_after(Some(FILE_ID_1), 10).await.unwrap();
+    let ids = files.into_iter().map(|f| f.id).collect::<Vec<_>>();
+    check!(ids == [FILE_ID_2]);
+
+    let files = store.all_after(Some(FILE_ID_2), 10).await.unwrap();
+    check!(files.is_empty());
+}
+
+async fn list_files_in_folder<S: FileMetadata>(store: S) {
+    let owner = owner();
+
+    // trashed files are included too
+    let mut file = file_2();
+    file.trash();
+    store.save(file).await.unwrap();
+
+    let files = store
+        .all_in_folder(owner.id, &FolderPath::root(), None, 10)
+        .await
+        .unwrap();
+    let ids = files.into_iter().map(|f| f.id).collect::<Vec<_>>();
+    check!(ids == [FILE_ID_1, FILE_ID_2]);
+
+    let files = store
+        .all_in_folder(owner.id, &FolderPath::root(), Some(FILE_ID_1), 10)
+        .await
+        .unwrap();
+    let ids = files.into_iter().map(|f| f.id).collect::<Vec<_>>();
+    check!(ids == [FILE_ID_2]);
+
+    let files = store
+        .all_in_folder(owner.id, &FolderPath::parse("/docs").unwrap(), None, 10)
+        .await
+        .unwrap();
+    let ids = files.into_iter().map(|f| f.id).collect::<Vec<_>>();
+    check!(ids == [FILE_ID_2]);
+
+    let files = store
+        .all_in_folder(owner.id, &FolderPath::parse("/doc").unwrap(), None, 10)
+        .await
+        .unwrap();
+    check!(files.is_empty());
 }
 
 async fn delete_file<S: FileMetadata>(store: S) {
@@ -186,9 +610,15 @@ mod inmemory {
     }
 
     #[tokio::test]
-    async fn it_stores_and_loads_file_by_name() {
+    async fn it_stores_and_loads_file_by_path() {
+        let store = InMemoryFileMetadata::default();
+        store_and_load_file_by_path(store).await;
+    }
+
+    #[tokio::test]
+    async fn it_stores_and_loads_file_by_hash() {
         let store = InMemoryFileMetadata::default();
-        store_and_load_file_by_name(store).await;
+        store_and_load_file_by_hash(store).await;
     }
 
     #[tokio::test]
@@ -197,6 +627,54 @@ mod inmemory {
         search_files(store).await;
     }
 
+    #[tokio::test]
+    async fn it_sorts_files() {
+        let store = InMemoryFileMetadata::from([file_1(), file_2()]);
+        sort_files(store).await;
+    }
+
+    #[tokio::test]
+    async fn it_rejects_an_invalid_cursor() {
+        let store = InMemoryFileMetadata::from([file_1(), file_2()]);
+        reject_invalid_cursor(store).await;
+    }
+
+    #[tokio::test]
+    async fn it_resumes_after_a_deleted_cursor() {
+        let store = InMemoryFileMetadata::from([file_1(), file_2()]);
+        resume_after_deleted_cursor(store).await;
+    }
+
+    #[tokio::test]
+    async fn it_searches_file_content() {
+        let store = InMemoryFileMetadata::from([file_1(), file_2()]);
+        search_content(store).await;
+    }
+
+    #[tokio::test]
+    async fn it_records_file_access() {
+        let store = InMemoryFileMetadata::from([file_1(), file_2()]);
+        record_file_access(store).await;
+    }
+
+    #[tokio::test]
+    async fn it_trashes_a_file() {
+        let store = InMemoryFileMetadata::from([file_1(), file_2()]);
+        trash_file(store).await;
+    }
+
+    #[tokio::test]
+    async fn it_lists_all_files_including_trashed() {
+        let store = InMemoryFileMetadata::from([file_1(), file_2()]);
+        list_all_files(store).await;
+    }
+
+    #[tokio::test]
+    async fn it_lists_files_in_a_folder() {
+        let store = InMemoryFileMetadata::from([file_1(), file_2()]);
+        list_files_in_folder(store).await;
+    }
+
     #[tokio::test]
     async fn it_deletes_a_file() {
         let store = InMemoryFileMetadata::from([file_1()]);
@@ -236,9 +714,18 @@ mod pg {
         migrator = "PG_MIGRATOR",
         fixtures("../../fixtures/postgres/accounts.sql")
     )]
-    async fn it_stores_and_loads_file_by_name(pool: sqlx::PgPool) {
+    async fn it_stores_and_loads_file_by_path(pool: sqlx::PgPool) {
         let store = PgFileMetadata::new(pool);
-        store_and_load_file_by_name(store).await;
+        store_and_load_file_by_path(store).await;
+    }
+
+    #[sqlx::test(
+        migrator = "PG_MIGRATOR",
+        fixtures("../../fixtures/postgres/accounts.sql")
+    )]
+    async fn it_stores_and_loads_file_by_hash(pool: sqlx::PgPool) {
+        let store = PgFileMetadata::new(pool);
+        store_and_load_file_by_hash(store).await;
     }
 
     #[sqlx::test(
@@ -253,6 +740,102 @@ mod pg {
         search_files(store).await;
     }
 
+    #[sqlx::test(
+        migrator = "PG_MIGRATOR",
+        fixtures(
+            "../../fixtures/postgres/accounts.sql",
+            "../../fixtures/postgres/files.sql"
+        )
+    )]
+    async fn it_sorts_files(pool: sqlx::PgPool) {
+        let store = PgFileMetadata::new(pool);
+        sort_files(store).await;
+    }
+
+    #[sqlx::test(
+        migrator = "PG_MIGRATOR",
+        fixtures(
+            "../../fixtures/postgres/accounts.sql",
+            "../../fixtures/postgres/files.sql"
+        )
+    )]
+    async fn it_rejects_an_invalid_cursor(pool: sqlx::PgPool) {
+        let store = PgFileMetadata::new(pool);
+        reject_invalid_cursor(store).await;
+    }
+
+    #[sqlx::test(
+        migrator = "PG_MIGRATOR",
+        fixtures(
+            "../../fixtures/postgres/accounts.sql",
+            "../../fixtures/postgres/files.sql"
+        )
+    )]
+    async fn it_resumes_after_a_deleted_cursor(pool: sqlx::PgPool) {
+        let store = PgFileMetadata::new(pool);
+        resume_after_deleted_cursor(store).await;
+    }
+
+    #[sqlx::test(
+        migrator = "PG_MIGRATOR",
+        fixtures(
+            "../../fixtures/postgres/accounts.sql",
+            "../../fixtures/postgres/files.sql"
+        )
+    )]
+    async fn it_searches_file_content(pool: sqlx::PgPool) {
+        let store = PgFileMetadata::new(pool);
+        search_content(store).await;
+    }
+
+    #[sqlx::test(
+        migrator = "PG_MIGRATOR",
+        fixtures(
+            "../../fixtures/postgres/accounts.sql",
+            "../../fixtures/postgres/files.sql"
+        )
+    )]
+    async fn it_records_file_access(pool: sqlx::PgPool) {
+        let store = PgFileMetadata::new(pool);
+        record_file_access(store).await;
+    }
+
+    #[sqlx::test(
+        migrator = "PG_MIGRATOR",
+        fixtures(
+            "../../fixtures/postgres/accounts.sql",
+            "../../fixtures/postgres/files.sql"
+        )
+    )]
+    async fn it_trashes_a_file(pool: sqlx::PgPool) {
+        let store = PgFileMetadata::new(pool);
+        trash_file(store).await;
+    }
+
+    #[sqlx::test(
+        migrator = "PG_MIGRATOR",
+        fixtures(
+            "../../fixtures/postgres/accounts.sql",
+            "../../fixtures/postgres/files.sql"
+        )
+    )]
+    async fn it_lists_all_files_including_trashed(pool: sqlx::PgPool) {
+        let store = PgFileMetadata::new(pool);
+        list_all_files(store).await;
+    }
+
+    #[sqlx::test(
+        migrator = "PG_MIGRATOR",
+        fixtures(
+            "../../fixtures/postgres/accounts.sql",
+            "../../fixtures/postgres/files.sql"
+        )
+    )]
+    async fn it_lists_files_in_a_folder(pool: sqlx::PgPool) {
+        let store = PgFileMetadata::new(pool);
+        list_files_in_folder(store).await;
+    }
+
     #[sqlx::test(
         migrator = "PG_MIGRATOR",
         fixtures(
@@ -298,9 +881,18 @@ mod sqlite {
         migrator = "SQLITE_MIGRATOR",
         fixtures("../../fixtures/sqlite/accounts.sql",)
     )]
-    async fn it_stores_and_loads_file_by_name(pool: sqlx::SqlitePool) {
+    async fn it_stores_and_loads_file_by_path(pool: sqlx::SqlitePool) {
         let store = SqliteFileMetadata::new(pool);
-        store_and_load_file_by_name(store).await;
+        store_and_load_file_by_path(store).await;
+    }
+
+    #[sqlx::test(
+        migrator = "SQLITE_MIGRATOR",
+        fixtures("../../fixtures/sqlite/accounts.sql")
+    )]
+    async fn it_stores_and_loads_file_by_hash(pool: sqlx::SqlitePool) {
+        let store = SqliteFileMetadata::new(pool);
+        store_and_load_file_by_hash(store).await;
     }
 
     #[sqlx::test(
@@ -315,6 +907,102 @@ mod sqlite {
         search_files(store).await;
     }
 
+    #[sqlx::test(
+        migrator = "SQLITE_MIGRATOR",
+        fixtures(
+            "../../fixtures/sqlite/accounts.sql",
+            "../../fixtures/sqlite/files.sql"
+        )
+    )]
+    async fn it_sorts_files(pool: sqlx::SqlitePool) {
+        let store = SqliteFileMetadata::new(pool);
+        sort_files(store).await;
+    }
+
+    #[sqlx::test(
+        migrator = "SQLITE_MIGRATOR",
+        fixtures(
+            "../../fixtures/sqlite/accounts.sql",
+            "../../fixtures/sqlite/files.sql"
+        )
+    )]
+    async fn it_rejects_an_invalid_cursor(pool: sqlx::SqlitePool) {
+        let store = SqliteFileMetadata::new(pool);
+        reject_invalid_cursor(store).await;
+    }
+
+    #[sqlx::test(
+        migrator = "SQLITE_MIGRATOR",
+        fixtures(
+            "../../fixtures/sqlite/accounts.sql",
+            "../../fixtures/sqlite/files.sql"
+        )
+    )]
+    async fn it_resumes_after_a_deleted_cursor(pool: sqlx::SqlitePool) {
+        let store = SqliteFileMetadata::new(pool);
+        resume_after_deleted_cursor(store).await;
+    }
+
+    #[sqlx::test(
+        migrator = "SQLITE_MIGRATOR",
+        fixtures(
+            "../../fixtures/sqlite/accounts.sql",
+            "../../fixtures/sqlite/files.sql"
+        )
+    )]
+    async fn it_searches_file_content(pool: sqlx::SqlitePool) {
+        let store = SqliteFileMetadata::new(pool);
+        search_content(store).await;
+    }
+
+    #[sqlx::test(
+        migrator = "SQLITE_MIGRATOR",
+        fixtures(
+            "../../fixtures/sqlite/accounts.sql",
+            "../../fixtures/sqlite/files.sql"
+        )
+    )]
+    async fn it_records_file_access(pool: sqlx::SqlitePool) {
+        let store = SqliteFileMetadata::new(pool);
+        record_file_access(store).await;
+    }
+
+    #[sqlx::test(
+        migrator = "SQLITE_MIGRATOR",
+        fixtures(
+            "../../fixtures/sqlite/accounts.sql",
+            "../../fixtures/sqlite/files.sql"
+        )
+    )]
+    async fn it_trashes_a_file(pool: sqlx::SqlitePool) {
+        let store = SqliteFileMetadata::new(pool);
+        trash_file(store).await;
+    }
+
+    #[sqlx::test(
+        migrator = "SQLITE_MIGRATOR",
+        fixtures(
+            "../../fixtures/sqlite/accounts.sql",
+            "../../fixtures/sqlite/files.sql"
+        )
+    )]
+    async fn it_lists_all_files_including_trashed(pool: sqlx::SqlitePool) {
+        let store = SqliteFileMetadata::new(pool);
+        list_all_files(store).await;
+    }
+
+    #[sqlx::test(
+        migrator = "SQLITE_MIGRATOR",
+        fixtures(
+            "../../fixtures/sqlite/accounts.sql",
+            "../../fixtures/sqlite/files.sql"
+        )
+    )]
+    async fn it_lists_files_in_a_folder(pool: sqlx::SqlitePool) {
+        let store = SqliteFileMetadata::new(pool);
+        list_files_in_folder(store).await;
+    }
+
     #[sqlx::test(
         migrator = "SQLITE_MIGRATOR",
         fixtures(
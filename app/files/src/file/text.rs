@@ -0,0 +1,147 @@
+/// Files larger than this are not indexed, extracting their text would take too long
+pub(crate) const MAX_INDEXED_SIZE: usize = 32 * 1024 * 1024;
+
+/// Only this much of the text of a file is indexed
+const MAX_TEXT_LEN: usize = 512 * 1024;
+
+/// Whether text can be extracted from content of this type
+pub(crate) fn is_indexable(content_type: &str) -> bool {
+    let essence = essence(content_type);
+    matches!(
+        essence.as_str(),
+        "text/html" | "application/xhtml+xml" | "application/pdf" | "application/json"
+    ) || essence.starts_with("text/")
+}
+
+/// Extracts the searchable text of a file, if its content type is one that can be indexed
+pub(crate) fn extract_text(content_type: &str, content: &[u8]) -> Option<String> {
+    let text = match essence(content_type).as_str() {
+        "text/html" | "application/xhtml+xml" => strip_html(&String::from_utf8_lossy(content)),
+        "application/pdf" => match pdf_extract::extract_text_from_mem(content) {
+            Ok(text) => text,
+            Err(err) => {
+                tracing::debug!(error = %err, "failed to extract text from pdf");
+                return None;
+            }
+        },
+        "application/json" => String::from_utf8_lossy(content).into_owned(),
+        essence if essence.starts_with("text/") => String::from_utf8_lossy(content).into_owned(),
+        _ => return None,
+    };
+
+    let text = truncate(text.trim(), MAX_TEXT_LEN);
+    if text.is_empty() {
+        return None;
+    }
+
+    Some(text.into())
+}
+
+/// The content type without its parameters, like `text/plain` for `text/plain; charset=utf-8`
+fn essence(content_type: &str) -> String {
+    content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase()
+}
+
+fn truncate(text: &str, len: usize) -> &str {
+    if text.len() <= len {
+        return text;
+    }
+
+    let mut end = len;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+/// Keeps the text of an HTML document, dropping its markup, scripts and styles
+fn strip_html(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some(start) = rest.find('<') {
+        text.push_str(&decode_entities(&rest[..start]));
+        rest = &rest[start..];
+
+        let Some(end) = rest.find('>') else {
+            rest = "";
+            break;
+        };
+
+        let tag = rest[1..end].trim_start().to_ascii_lowercase();
+        rest = &rest[end + 1..];
+
+        for skipped in ["script", "style"] {
+            if tag.starts_with(skipped) {
+                let close = format!("</{skipped}");
+                rest = match rest.to_ascii_lowercase().find(&close) {
+                    Some(i) => &rest[i..],
+                    None => "",
+                };
+            }
+        }
+
+        text.push(' ');
+    }
+    text.push_str(&decode_entities(rest));
+
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use assert2::check;
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case("text/plain", "quarterly report", Some("quarterly report"))]
+    #[case("text/markdown; charset=utf-8", "# Budget\n", Some("# Budget"))]
+    #[case("application/json", r#"{"a": 1}"#, Some(r#"{"a": 1}"#))]
+    #[case(
+        "text/html",
+        "<html><head><style>p { color: red; }</style><script>let a = 1 < 2;</script></head><body><p>Tom &amp; Jerry</p><p>report</p></body></html>",
+        Some("Tom & Jerry report")
+    )]
+    #[case("text/plain", "   ", None)]
+    #[case("image/png", "not really a png", None)]
+    fn it_extracts_text(
+        #[case] content_type: &str,
+        #[case] content: &str,
+        #[case] expected: Option<&str>,
+    ) {
+        check!(extract_text(content_type, content.as_bytes()).as_deref() == expected);
+    }
+
+    #[rstest]
+    #[case("text/plain", true)]
+    #[case("text/markdown; charset=utf-8", true)]
+    #[case("application/pdf", true)]
+    #[case("Application/JSON", true)]
+    #[case("image/png", false)]
+    #[case("application/octet-stream", false)]
+    fn it_tells_indexable_content_types(#[case] content_type: &str, #[case] expected: bool) {
+        check!(is_indexable(content_type) == expected);
+    }
+
+    #[test]
+    fn it_truncates_on_a_char_boundary() {
+        check!(truncate("aé", 2) == "a");
+        check!(truncate("abc", 5) == "abc");
+    }
+}
@@ -0,0 +1,106 @@
+use std::fmt::Display;
+
+use time::OffsetDateTime;
+
+use crate::tag;
+use crate::tag::Tag;
+
+use super::{Encoding, File, FileId};
+
+pub use store::*;
+
+mod store;
+
+/// A previous content of a [File], archived when it was overwritten by a newer upload
+#[derive(Clone, Debug)]
+pub struct FileVersion {
+    pub file_id: FileId,
+    pub number: u32,
+    pub content_type: String,
+    pub size: usize,
+    pub stored_size: usize,
+    pub encoding: Encoding,
+    pub(super) hash: Option<blake3::Hash>,
+    /// When the content was archived, i.e. when it stopped being the current one
+    pub created_at: OffsetDateTime,
+}
+
+impl FileVersion {
+    /// Archives the current content of the file as the given version
+    pub(crate) fn of(file: &File, number: u32) -> Self {
+        Self {
+            file_id: file.id,
+            number,
+            content_type: file.content_type.clone(),
+            size: file.size,
+            stored_size: file.stored_size,
+            encoding: file.encoding,
+            hash: file.hash,
+            created_at: OffsetDateTime::now_utc(),
+        }
+    }
+
+    pub fn hash(&self) -> Option<impl Display> {
+        self.hash
+    }
+
+    pub(crate) fn set_hash(&mut self, hash: blake3::Hash) {
+        self.hash = Some(hash);
+    }
+}
+
+impl File {
+    /// Returns the file as it was at the given version
+    pub fn at_version(&self, version: &FileVersion) -> File {
+        let mut file = self.clone();
+        file.restore_version(version);
+        file
+    }
+
+    pub(crate) fn restore_version(&mut self, version: &FileVersion) {
+        self.content_type = version.content_type.clone();
+        self.add_tag(Tag::full(
+            tag::reserved::CONTENT_TYPE,
+            self.content_type.clone(),
+        ));
+        self.set_size(version.size);
+        self.stored_size = version.stored_size;
+        self.encoding = version.encoding;
+        // versions are kept in the default backend, where their content is restored
+        self.tier = None;
+        self.hash = version.hash;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert2::check;
+    use rstest::rstest;
+
+    use crate::file::fixtures::file;
+    use crate::tag::reserved::*;
+
+    use super::*;
+
+    #[rstest]
+    fn it_restores_a_previous_version(mut file: File) {
+        file.set_size(5);
+        file.set_hash(blake3::hash(b"first"));
+
+        let version = FileVersion::of(&file, 1);
+
+        file.content_type = "application/json".into();
+        file.set_size(2);
+        file.set_hash(blake3::hash(b"{}"));
+
+        file.restore_version(&version);
+
+        check!(file.content_type == version.content_type);
+        check!(file.size == 5);
+        check!(file.hash == Some(blake3::hash(b"first")));
+        check!(file.tags.get(SIZE) == Some(&Tag::full(SIZE, "5")));
+        check!(
+            file.tags.get(CONTENT_TYPE) == Some(&Tag::full(CONTENT_TYPE, &version.content_type))
+        );
+    }
+}
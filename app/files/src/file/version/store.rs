@@ -0,0 +1,117 @@
+use std::{collections::HashMap, sync::Arc};
+
+use async_trait::async_trait;
+use oxidrive_domain::make_error_wrapper;
+use tokio::sync::RwLock;
+
+use crate::{File, FileId};
+
+use super::FileVersion;
+
+mod pg;
+mod sqlite;
+
+pub use pg::*;
+pub use sqlite::*;
+
+make_error_wrapper!(AllVersionsError);
+make_error_wrapper!(VersionByNumberError);
+make_error_wrapper!(CreateVersionError);
+make_error_wrapper!(SaveVersionError);
+make_error_wrapper!(DeleteVersionError);
+
+/// How many times creating a version is attempted when another version of the same file takes its number
+const CREATE_ATTEMPTS: usize = 5;
+
+#[async_trait]
+pub trait FileVersions: Send + Sync + 'static {
+    /// Lists all the archived versions of the file, from the most recent one
+    async fn all_for(&self, file_id: FileId) -> Result<Vec<FileVersion>, AllVersionsError>;
+
+    async fn by_number(
+        &self,
+        file_id: FileId,
+        number: u32,
+    ) -> Result<Option<FileVersion>, VersionByNumberError>;
+
+    /// Archives the current content of the file as its next version, numbered after the latest one.
+    /// Versions created concurrently for the same file never get the same number
+    async fn create(&self, file: &File) -> Result<FileVersion, CreateVersionError>;
+
+    /// Updates a version already created with [FileVersions::create]
+    async fn save(&self, version: FileVersion) -> Result<FileVersion, SaveVersionError>;
+
+    async fn delete(&self, file_id: FileId, number: u32) -> Result<(), DeleteVersionError>;
+}
+
+#[derive(Clone, Default)]
+pub struct InMemoryFileVersions {
+    inner: Arc<RwLock<HashMap<(FileId, u32), FileVersion>>>,
+}
+
+impl<const N: usize> From<[FileVersion; N]> for InMemoryFileVersions {
+    fn from(versions: [FileVersion; N]) -> Self {
+        let versions = HashMap::from_iter(versions.into_iter().map(|v| ((v.file_id, v.number), v)));
+        Self {
+            inner: Arc::new(RwLock::new(versions)),
+        }
+    }
+}
+
+#[async_trait]
+impl FileVersions for InMemoryFileVersions {
+    async fn all_for(&self, file_id: FileId) -> Result<Vec<FileVersion>, AllVersionsError> {
+        let inner = self.inner.read().await;
+
+        let mut versions: Vec<FileVersion> = inner
+            .values()
+            .filter(|v| v.file_id == file_id)
+            .cloned()
+            .collect();
+
+        versions.sort_by(|a, b| b.number.cmp(&a.number));
+
+        Ok(versions)
+    }
+
+    async fn by_number(
+        &self,
+        file_id: FileId,
+        number: u32,
+    ) -> Result<Option<FileVersion>, VersionByNumberError> {
+        let inner = self.inner.read().await;
+        Ok(inner.get(&(file_id, number)).cloned())
+    }
+
+    async fn create(&self, file: &File) -> Result<FileVersion, CreateVersionError> {
+        let mut inner = self.inner.write().await;
+
+        let number = inner
+            .keys()
+            .filter(|(file_id, _)| *file_id == file.id)
+            .map(|(_, number)| number + 1)
+            .max()
+            .unwrap_or(1);
+
+        let version = FileVersion::of(file, number);
+        inner.insert((version.file_id, version.number), version.clone());
+        Ok(version)
+    }
+
+    async fn save(&self, version: FileVersion) -> Result<FileVersion, SaveVersionError> {
+        let mut inner = self.inner.write().await;
+        if let Some(existing) = inner.get_mut(&(version.file_id, version.number)) {
+            *existing = version.clone();
+        }
+        Ok(version)
+    }
+
+    async fn delete(&self, file_id: FileId, number: u32) -> Result<(), DeleteVersionError> {
+        let mut inner = self.inner.write().await;
+        inner.remove(&(file_id, number));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests;
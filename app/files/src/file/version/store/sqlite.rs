@@ -0,0 +1,218 @@
+use async_trait::async_trait;
+use time::OffsetDateTime;
+
+use crate::{
+    File, FileId,
+    file::{Encoding, version::FileVersion},
+};
+
+use super::{
+    AllVersionsError, CREATE_ATTEMPTS, CreateVersionError, DeleteVersionError, FileVersions,
+    SaveVersionError, VersionByNumberError,
+};
+
+pub struct SqliteFileVersions {
+    pool: sqlx::SqlitePool,
+}
+
+impl SqliteFileVersions {
+    pub fn new(pool: sqlx::SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl FileVersions for SqliteFileVersions {
+    async fn all_for(&self, file_id: FileId) -> Result<Vec<FileVersion>, AllVersionsError> {
+        let versions: Vec<SqliteFileVersion> = sqlx::query_as(
+            r#"
+select
+  file_id,
+  number,
+  content_type,
+  size,
+  stored_size,
+  compressed,
+  encrypted,
+  hash,
+  created_at
+from file_versions
+where file_id = $1
+order by number desc
+"#,
+        )
+        .bind(file_id.to_string())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(AllVersionsError::wrap)?;
+
+        Ok(versions.into_iter().map(FileVersion::from).collect())
+    }
+
+    async fn by_number(
+        &self,
+        file_id: FileId,
+        number: u32,
+    ) -> Result<Option<FileVersion>, VersionByNumberError> {
+        let version: Option<SqliteFileVersion> = sqlx::query_as(
+            r#"
+select
+  file_id,
+  number,
+  content_type,
+  size,
+  stored_size,
+  compressed,
+  encrypted,
+  hash,
+  created_at
+from file_versions
+where file_id = $1
+  and number = $2
+"#,
+        )
+        .bind(file_id.to_string())
+        .bind(number as i32)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(VersionByNumberError::wrap)?;
+
+        Ok(version.map(FileVersion::from))
+    }
+
+    async fn create(&self, file: &File) -> Result<FileVersion, CreateVersionError> {
+        let mut version = FileVersion::of(file, 0);
+        let mut attempts = 0;
+
+        loop {
+            // the next number is picked by the insert itself, so concurrent archives of the same file
+            // can only collide on it, in which case the loser tries again
+            let created: Result<i32, sqlx::Error> = sqlx::query_scalar(
+                r#"
+insert into file_versions (
+  file_id,
+  number,
+  content_type,
+  size,
+  stored_size,
+  compressed,
+  encrypted,
+  hash,
+  created_at
+)
+select
+  $1,
+  coalesce(max(number), 0) + 1,
+  $2,
+  $3,
+  $4,
+  $5,
+  $6,
+  $7,
+  $8
+from file_versions
+where file_id = $1
+returning number
+"#,
+            )
+            .bind(version.file_id.to_string())
+            .bind(&version.content_type)
+            .bind(version.size as i64)
+            .bind(version.stored_size as i64)
+            .bind(version.encoding.compressed)
+            .bind(version.encoding.encrypted)
+            .bind(version.hash.as_ref().map(|hash| hash.as_bytes().as_slice()))
+            .bind(version.created_at)
+            .fetch_one(&self.pool)
+            .await;
+
+            match created {
+                Ok(number) => {
+                    version.number = number.try_into().unwrap();
+                    return Ok(version);
+                }
+                Err(sqlx::Error::Database(err))
+                    if err.is_unique_violation() && attempts < CREATE_ATTEMPTS =>
+                {
+                    attempts += 1;
+                }
+                Err(err) => return Err(CreateVersionError::wrap(err)),
+            }
+        }
+    }
+
+    async fn save(&self, version: FileVersion) -> Result<FileVersion, SaveVersionError> {
+        sqlx::query(
+            r#"
+update file_versions
+set
+  content_type = $3,
+  size = $4,
+  stored_size = $5,
+  compressed = $6,
+  encrypted = $7,
+  hash = $8,
+  created_at = $9
+where file_id = $1
+  and number = $2
+"#,
+        )
+        .bind(version.file_id.to_string())
+        .bind(version.number as i32)
+        .bind(&version.content_type)
+        .bind(version.size as i64)
+        .bind(version.stored_size as i64)
+        .bind(version.encoding.compressed)
+        .bind(version.encoding.encrypted)
+        .bind(version.hash.as_ref().map(|hash| hash.as_bytes().as_slice()))
+        .bind(version.created_at)
+        .execute(&self.pool)
+        .await
+        .map_err(SaveVersionError::wrap)?;
+
+        Ok(version)
+    }
+
+    async fn delete(&self, file_id: FileId, number: u32) -> Result<(), DeleteVersionError> {
+        sqlx::query("delete from file_versions where file_id = $1 and number = $2")
+            .bind(file_id.to_string())
+            .bind(number as i32)
+            .execute(&self.pool)
+            .await
+            .map_err(DeleteVersionError::wrap)?;
+        Ok(())
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct SqliteFileVersion {
+    file_id: String,
+    number: i32,
+    content_type: String,
+    size: i64,
+    stored_size: i64,
+    compressed: bool,
+    encrypted: bool,
+    hash: Option<Vec<u8>>,
+    created_at: OffsetDateTime,
+}
+
+impl From<SqliteFileVersion> for FileVersion {
+    fn from(version: SqliteFileVersion) -> Self {
+        Self {
+            file_id: version.file_id.parse().unwrap(),
+            number: version.number.try_into().unwrap(),
+            content_type: version.content_type,
+            size: version.size.try_into().unwrap(),
+            stored_size: version.stored_size.try_into().unwrap(),
+            encoding: Encoding {
+                compressed: version.compressed,
+                encrypted: version.encrypted,
+            },
+            hash: version
+                .hash
+                .map(|bytes| blake3::Hash::from_bytes(bytes.try_into().unwrap())),
+            created_at: version.created_at,
+        }
+    }
+}
@@ -0,0 +1,232 @@
+use assert2::{check, let_assert};
+use oxidrive_accounts::account::fixtures::account;
+use time::Duration;
+
+use crate::{
+    File, FileId,
+    file::{Encoding, macros::file_id},
+};
+
+use super::FileVersions;
+
+const FILE_ID: FileId = file_id!("019433e9-ffbb-7c8b-af6c-d4cb061fb919");
+
+fn file(content: &[u8]) -> File {
+    let mut file = File::new(account().id, "hello.txt", "text/plain");
+    file.id = FILE_ID;
+    file.set_size(content.len());
+    file.stored_size = content.len() / 2;
+    file.encoding = Encoding {
+        compressed: true,
+        encrypted: false,
+    };
+    file.set_hash(blake3::hash(content));
+    file
+}
+
+async fn store_and_load_versions<S: FileVersions>(store: S) {
+    let first = store.create(&file(b"first")).await.unwrap();
+    let second = store.create(&file(b"second")).await.unwrap();
+    check!(first.number == 1);
+    check!(second.number == 2);
+
+    let versions = store.all_for(FILE_ID).await.unwrap();
+    check!(versions.len() == 2);
+    check!(versions[0].number == second.number);
+    check!(versions[1].number == first.number);
+
+    let_assert!(Some(found) = store.by_number(FILE_ID, 1).await.unwrap());
+    check!(found.number == first.number);
+    check!(found.size == first.size);
+    check!(found.stored_size == first.stored_size);
+    check!(found.encoding == first.encoding);
+    check!(found.content_type == first.content_type);
+    check!(found.hash == first.hash);
+    // databases don't keep timestamps down to the nanosecond
+    check!((found.created_at - first.created_at).abs() < Duration::milliseconds(1));
+
+    let_assert!(None = store.by_number(FILE_ID, 3).await.unwrap());
+}
+
+async fn number_versions_created_concurrently<S: FileVersions>(store: S) {
+    let file = file(b"content");
+
+    let versions = futures::future::try_join_all((0..5).map(|_| store.create(&file)))
+        .await
+        .unwrap();
+
+    let mut numbers: Vec<u32> = versions.iter().map(|v| v.number).collect();
+    numbers.sort();
+    check!(numbers == vec![1, 2, 3, 4, 5]);
+}
+
+async fn update_version<S: FileVersions>(store: S) {
+    let mut version = store.create(&file(b"first")).await.unwrap();
+
+    version.hash = Some(blake3::hash(b"rehashed"));
+    store.save(version.clone()).await.unwrap();
+
+    let_assert!(Some(found) = store.by_number(FILE_ID, version.number).await.unwrap());
+    check!(found.hash == version.hash);
+
+    // saving only updates versions that were created
+    let mut missing = version.clone();
+    missing.number = 2;
+    store.save(missing).await.unwrap();
+    let_assert!(None = store.by_number(FILE_ID, 2).await.unwrap());
+}
+
+async fn delete_version<S: FileVersions>(store: S) {
+    store.create(&file(b"first")).await.unwrap();
+    store.create(&file(b"second")).await.unwrap();
+
+    store.delete(FILE_ID, 1).await.unwrap();
+
+    let_assert!(None = store.by_number(FILE_ID, 1).await.unwrap());
+
+    let versions = store.all_for(FILE_ID).await.unwrap();
+    check!(versions.len() == 1);
+    check!(versions[0].number == 2);
+}
+
+mod inmemory {
+    use crate::file::version::InMemoryFileVersions;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn it_stores_and_loads_versions() {
+        let store = InMemoryFileVersions::default();
+        store_and_load_versions(store).await;
+    }
+
+    #[tokio::test]
+    async fn it_numbers_versions_created_concurrently() {
+        let store = InMemoryFileVersions::default();
+        number_versions_created_concurrently(store).await;
+    }
+
+    #[tokio::test]
+    async fn it_updates_a_version() {
+        let store = InMemoryFileVersions::default();
+        update_version(store).await;
+    }
+
+    #[tokio::test]
+    async fn it_deletes_a_version() {
+        let store = InMemoryFileVersions::default();
+        delete_version(store).await;
+    }
+}
+
+mod pg {
+    use oxidrive_database::migrate::PG_MIGRATOR;
+
+    use crate::file::version::PgFileVersions;
+
+    use super::*;
+
+    #[sqlx::test(
+        migrator = "PG_MIGRATOR",
+        fixtures(
+            "../../../fixtures/postgres/accounts.sql",
+            "../../../fixtures/postgres/files.sql"
+        )
+    )]
+    async fn it_stores_and_loads_versions(pool: sqlx::PgPool) {
+        let store = PgFileVersions::new(pool);
+        store_and_load_versions(store).await;
+    }
+
+    #[sqlx::test(
+        migrator = "PG_MIGRATOR",
+        fixtures(
+            "../../../fixtures/postgres/accounts.sql",
+            "../../../fixtures/postgres/files.sql"
+        )
+    )]
+    async fn it_numbers_versions_created_concurrently(pool: sqlx::PgPool) {
+        let store = PgFileVersions::new(pool);
+        number_versions_created_concurrently(store).await;
+    }
+
+    #[sqlx::test(
+        migrator = "PG_MIGRATOR",
+        fixtures(
+            "../../../fixtures/postgres/accounts.sql",
+            "../../../fixtures/postgres/files.sql"
+        )
+    )]
+    async fn it_updates_a_version(pool: sqlx::PgPool) {
+        let store = PgFileVersions::new(pool);
+        update_version(store).await;
+    }
+
+    #[sqlx::test(
+        migrator = "PG_MIGRATOR",
+        fixtures(
+            "../../../fixtures/postgres/accounts.sql",
+            "../../../fixtures/postgres/files.sql"
+        )
+    )]
+    async fn it_deletes_a_version(pool: sqlx::PgPool) {
+        let store = PgFileVersions::new(pool);
+        delete_version(store).await;
+    }
+}
+
+mod sqlite {
+    use oxidrive_database::migrate::SQLITE_MIGRATOR;
+
+    use crate::file::version::SqliteFileVersions;
+
+    use super::*;
+
+    #[sqlx::test(
+        migrator = "SQLITE_MIGRATOR",
+        fixtures(
+            "../../../fixtures/sqlite/accounts.sql",
+            "../../../fixtures/sqlite/files.sql"
+        )
+    )]
+    async fn it_stores_and_loads_versions(pool: sqlx::SqlitePool) {
+        let store = SqliteFileVersions::new(pool);
+        store_and_load_versions(store).await;
+    }
+
+    #[sqlx::test(
+        migrator = "SQLITE_MIGRATOR",
+        fixtures(
+            "../../../fixtures/sqlite/accounts.sql",
+            "../../../fixtures/sqlite/files.sql"
+        )
+    )]
+    async fn it_numbers_versions_created_concurrently(pool: sqlx::SqlitePool) {
+        let store = SqliteFileVersions::new(pool);
+        number_versions_created_concurrently(store).await;
+    }
+
+    #[sqlx::test(
+        migrator = "SQLITE_MIGRATOR",
+        fixtures(
+            "../../../fixtures/sqlite/accounts.sql",
+            "../../../fixtures/sqlite/files.sql"
+        )
+    )]
+    async fn it_updates_a_version(pool: sqlx::SqlitePool) {
+        let store = SqliteFileVersions::new(pool);
+        update_version(store).await;
+    }
+
+    #[sqlx::test(
+        migrator = "SQLITE_MIGRATOR",
+        fixtures(
+            "../../../fixtures/sqlite/accounts.sql",
+            "../../../fixtures/sqlite/files.sql"
+        )
+    )]
+    async fn it_deletes_a_version(pool: sqlx::SqlitePool) {
+        let store = SqliteFileVersions::new(pool);
+        delete_version(store).await;
+    }
+}
@@ -1,20 +1,26 @@
-insert into files (id, owner_id, name, content_type, size, tags, hash) values
+insert into files (id, owner_id, name, folder, content_type, size, tags, hash, created_at, updated_at) values
     (
         '019433e9-ffbb-7c8b-af6c-d4cb061fb919',
         '0194327d-becc-7ef3-809c-35dd09f62f45',
         'hello.txt',
+        '/',
         'text/plain',
         0,
-        'name => hello.txt, content_type => text/plain, file1 => null, size => 0, ext => txt'::hstore,
-        decode('d74981efa70a0c880b8d8c1985d075dbcbf679b99a5f9914e5aaf96b831a9e24', 'hex')
+        'name => hello.txt, content_type => text/plain, file1 => null, size => 0, ext => txt, dir => /, path => /hello.txt, created => 2025-01-10T09:00:00Z, updated => 2025-01-10T09:00:00Z'::hstore,
+        decode('d74981efa70a0c880b8d8c1985d075dbcbf679b99a5f9914e5aaf96b831a9e24', 'hex'),
+        '2025-01-10T09:00:00Z',
+        '2025-01-10T09:00:00Z'
     ),
     (
         '019433ea-5976-7982-bedb-760ad14d4c1a',
         '0194327d-becc-7ef3-809c-35dd09f62f45',
         'world.txt',
+        '/docs',
         'text/plain',
         0,
-        'name => world.txt, content_type => text/plain, file2 => null, size => 0, ext => txt'::hstore,
-        decode('d74981efa70a0c880b8d8c1985d075dbcbf679b99a5f9914e5aaf96b831a9e24', 'hex')
+        'name => world.txt, content_type => text/plain, file2 => null, size => 0, ext => txt, dir => /docs, path => /docs/world.txt, created => 2025-01-10T09:00:00Z, updated => 2025-01-12T18:30:00Z'::hstore,
+        decode('d74981efa70a0c880b8d8c1985d075dbcbf679b99a5f9914e5aaf96b831a9e24', 'hex'),
+        '2025-01-10T09:00:00Z',
+        '2025-01-12T18:30:00Z'
     )
 ;
@@ -0,0 +1,258 @@
+use std::{fmt::Display, str::FromStr, sync::Arc};
+
+use oxidrive_database::Database;
+
+pub use store::*;
+
+mod store;
+
+const SEPARATOR: char = '/';
+
+/// The absolute path of a virtual folder, like `/photos/2025`. The root folder is `/`
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FolderPath(String);
+
+impl FolderPath {
+    pub fn root() -> Self {
+        Self(SEPARATOR.to_string())
+    }
+
+    /// Parses a folder path, ignoring leading, trailing and repeated separators
+    pub fn parse(path: impl AsRef<str>) -> Result<Self, InvalidPathError> {
+        let path = path.as_ref();
+        let segments = segments(path)?;
+
+        if segments.is_empty() {
+            return Ok(Self::root());
+        }
+
+        Ok(Self(
+            segments
+                .into_iter()
+                .fold(String::new(), |path, segment| path + "/" + segment),
+        ))
+    }
+
+    /// Splits the path of a file into the folder holding it and the file name
+    pub fn split_file_path(path: impl AsRef<str>) -> Result<(Self, String), InvalidPathError> {
+        let path = path.as_ref();
+        let mut segments = segments(path)?;
+
+        let Some(name) = segments.pop() else {
+            return Err(InvalidPathError(path.into()));
+        };
+
+        let folder = Self::parse(segments.join("/"))?;
+        Ok((folder, name.into()))
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.0.len() == 1
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The last segment of the path, empty for the root folder
+    pub fn name(&self) -> &str {
+        self.0.rsplit(SEPARATOR).next().unwrap_or_default()
+    }
+
+    /// The folder holding this one, `None` for the root folder
+    pub fn parent(&self) -> Option<Self> {
+        if self.is_root() {
+            return None;
+        }
+
+        match self.0.rsplit_once(SEPARATOR) {
+            Some(("", _)) | None => Some(Self::root()),
+            Some((parent, _)) => Some(Self(parent.into())),
+        }
+    }
+
+    /// Every folder this one is nested in, from the root down, excluding itself
+    pub fn ancestors(&self) -> Vec<Self> {
+        let mut ancestors = Vec::new();
+        let mut current = self.parent();
+
+        while let Some(folder) = current {
+            current = folder.parent();
+            ancestors.push(folder);
+        }
+
+        ancestors.reverse();
+        ancestors
+    }
+
+    /// The path of a folder or file named `name` inside this folder
+    pub fn join(&self, name: &str) -> String {
+        if self.is_root() {
+            format!("/{name}")
+        } else {
+            format!("{}/{name}", self.0)
+        }
+    }
+
+    /// Whether the other folder is this one or nested in it at any depth
+    pub fn contains(&self, other: &FolderPath) -> bool {
+        self.is_root()
+            || other == self
+            || other
+                .0
+                .strip_prefix(&self.0)
+                .is_some_and(|rest| rest.starts_with(SEPARATOR))
+    }
+
+    /// Moves a path nested in `from` to the same position in `to`, `None` if it isn't nested in `from`
+    pub fn rebase(&self, from: &FolderPath, to: &FolderPath) -> Option<Self> {
+        if !from.contains(self) {
+            return None;
+        }
+
+        let rest = if from.is_root() {
+            &self.0
+        } else {
+            &self.0[from.0.len()..]
+        };
+
+        Self::parse(format!("{}{rest}", to.0)).ok()
+    }
+}
+
+fn segments(path: &str) -> Result<Vec<&str>, InvalidPathError> {
+    let segments: Vec<&str> = path
+        .split(SEPARATOR)
+        .filter(|segment| !segment.is_empty())
+        .collect();
+
+    if segments
+        .iter()
+        .any(|segment| matches!(*segment, "." | "..") || segment.contains('\\'))
+    {
+        return Err(InvalidPathError(path.into()));
+    }
+
+    Ok(segments)
+}
+
+impl Default for FolderPath {
+    fn default() -> Self {
+        Self::root()
+    }
+}
+
+impl Display for FolderPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromStr for FolderPath {
+    type Err = InvalidPathError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl From<FolderPath> for String {
+    fn from(path: FolderPath) -> Self {
+        path.0
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("'{0}' is not a valid path")]
+pub struct InvalidPathError(String);
+
+pub(crate) fn store(database: Database) -> Arc<dyn Folders> {
+    match database {
+        Database::Sqlite(pool) => Arc::new(SqliteFolders::new(pool)),
+        Database::Pg(pool) => Arc::new(PgFolders::new(pool)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert2::{check, let_assert};
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case("/", "/")]
+    #[case("", "/")]
+    #[case("photos", "/photos")]
+    #[case("/photos/2025/", "/photos/2025")]
+    #[case("//photos//2025", "/photos/2025")]
+    fn it_parses_a_valid_path(#[case] path: &str, #[case] expected: &str) {
+        let path = FolderPath::parse(path).unwrap();
+        check!(path.as_str() == expected);
+    }
+
+    #[rstest]
+    #[case("/photos/../secrets")]
+    #[case("./photos")]
+    #[case("/photos\\2025")]
+    fn it_rejects_an_invalid_path(#[case] path: &str) {
+        let_assert!(Err(_) = FolderPath::parse(path));
+    }
+
+    #[test]
+    fn it_splits_a_file_path() {
+        let (folder, name) = FolderPath::split_file_path("/photos/2025/beach.jpg").unwrap();
+        check!(folder.as_str() == "/photos/2025");
+        check!(name == "beach.jpg");
+
+        let (folder, name) = FolderPath::split_file_path("notes.txt").unwrap();
+        check!(folder.is_root());
+        check!(name == "notes.txt");
+
+        let_assert!(Err(_) = FolderPath::split_file_path("/"));
+    }
+
+    #[test]
+    fn it_navigates_the_hierarchy() {
+        let path = FolderPath::parse("/photos/2025/summer").unwrap();
+
+        check!(path.name() == "summer");
+        check!(path.parent() == Some(FolderPath::parse("/photos/2025").unwrap()));
+        check!(
+            path.ancestors()
+                == vec![
+                    FolderPath::root(),
+                    FolderPath::parse("/photos").unwrap(),
+                    FolderPath::parse("/photos/2025").unwrap(),
+                ]
+        );
+        check!(path.join("beach.jpg") == "/photos/2025/summer/beach.jpg");
+
+        check!(FolderPath::root().parent() == None);
+        check!(FolderPath::root().join("notes.txt") == "/notes.txt");
+    }
+
+    #[test]
+    fn it_checks_whether_a_folder_contains_another() {
+        let photos = FolderPath::parse("/photos").unwrap();
+
+        check!(photos.contains(&photos));
+        check!(photos.contains(&FolderPath::parse("/photos/2025").unwrap()));
+        check!(!photos.contains(&FolderPath::parse("/photos-old").unwrap()));
+        check!(!photos.contains(&FolderPath::root()));
+        check!(FolderPath::root().contains(&photos));
+    }
+
+    #[test]
+    fn it_rebases_a_nested_path() {
+        let from = FolderPath::parse("/photos").unwrap();
+        let to = FolderPath::parse("/archive/pictures").unwrap();
+
+        let path = FolderPath::parse("/photos/2025").unwrap();
+        check!(
+            path.rebase(&from, &to) == Some(FolderPath::parse("/archive/pictures/2025").unwrap())
+        );
+        check!(from.rebase(&from, &to) == Some(to.clone()));
+        check!(FolderPath::parse("/music").unwrap().rebase(&from, &to) == None);
+    }
+}
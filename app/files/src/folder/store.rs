@@ -0,0 +1,152 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::Arc,
+};
+
+use async_trait::async_trait;
+use oxidrive_accounts::account::AccountId;
+use oxidrive_domain::make_error_wrapper;
+use tokio::sync::RwLock;
+
+use super::FolderPath;
+
+pub use pg::*;
+pub use sqlite::*;
+
+mod pg;
+mod sqlite;
+
+make_error_wrapper!(FolderExistsError);
+make_error_wrapper!(ChildFoldersError);
+make_error_wrapper!(CreateFolderError);
+make_error_wrapper!(RenameFolderError);
+make_error_wrapper!(DeleteFolderError);
+
+#[async_trait]
+pub trait Folders: Send + Sync + 'static {
+    /// Whether the account has the folder. The root folder always exists
+    async fn exists(
+        &self,
+        owner_id: AccountId,
+        path: &FolderPath,
+    ) -> Result<bool, FolderExistsError>;
+
+    /// Lists the folders directly nested in the given one, ordered by path
+    async fn children(
+        &self,
+        owner_id: AccountId,
+        parent: &FolderPath,
+    ) -> Result<Vec<FolderPath>, ChildFoldersError>;
+
+    /// Creates the folder along with any missing ancestor. Existing folders are left as they are
+    async fn create(&self, owner_id: AccountId, path: &FolderPath)
+    -> Result<(), CreateFolderError>;
+
+    /// Moves the folder and everything nested in it to a new path, which must not exist yet
+    async fn rename(
+        &self,
+        owner_id: AccountId,
+        from: &FolderPath,
+        to: &FolderPath,
+    ) -> Result<(), RenameFolderError>;
+
+    /// Deletes the folder and everything nested in it
+    async fn delete(&self, owner_id: AccountId, path: &FolderPath)
+    -> Result<(), DeleteFolderError>;
+}
+
+#[derive(Clone, Default)]
+pub struct InMemoryFolders {
+    inner: Arc<RwLock<HashMap<AccountId, BTreeSet<FolderPath>>>>,
+}
+
+#[async_trait]
+impl Folders for InMemoryFolders {
+    async fn exists(
+        &self,
+        owner_id: AccountId,
+        path: &FolderPath,
+    ) -> Result<bool, FolderExistsError> {
+        if path.is_root() {
+            return Ok(true);
+        }
+
+        let inner = self.inner.read().await;
+        Ok(inner
+            .get(&owner_id)
+            .is_some_and(|folders| folders.contains(path)))
+    }
+
+    async fn children(
+        &self,
+        owner_id: AccountId,
+        parent: &FolderPath,
+    ) -> Result<Vec<FolderPath>, ChildFoldersError> {
+        let inner = self.inner.read().await;
+        Ok(inner
+            .get(&owner_id)
+            .into_iter()
+            .flatten()
+            .filter(|folder| folder.parent().as_ref() == Some(parent))
+            .cloned()
+            .collect())
+    }
+
+    async fn create(
+        &self,
+        owner_id: AccountId,
+        path: &FolderPath,
+    ) -> Result<(), CreateFolderError> {
+        let mut inner = self.inner.write().await;
+        let folders = inner.entry(owner_id).or_default();
+
+        folders.extend(
+            path.ancestors()
+                .into_iter()
+                .chain([path.clone()])
+                .filter(|folder| !folder.is_root()),
+        );
+
+        Ok(())
+    }
+
+    async fn rename(
+        &self,
+        owner_id: AccountId,
+        from: &FolderPath,
+        to: &FolderPath,
+    ) -> Result<(), RenameFolderError> {
+        let mut inner = self.inner.write().await;
+        let folders = inner.entry(owner_id).or_default();
+
+        let moved: Vec<FolderPath> = folders
+            .iter()
+            .filter(|folder| from.contains(folder))
+            .cloned()
+            .collect();
+
+        for folder in moved {
+            folders.remove(&folder);
+            if let Some(folder) = folder.rebase(from, to) {
+                folders.insert(folder);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn delete(
+        &self,
+        owner_id: AccountId,
+        path: &FolderPath,
+    ) -> Result<(), DeleteFolderError> {
+        let mut inner = self.inner.write().await;
+        if let Some(folders) = inner.get_mut(&owner_id) {
+            folders.retain(|folder| !path.contains(folder));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,161 @@
+use async_trait::async_trait;
+use oxidrive_accounts::account::AccountId;
+use sqlx::QueryBuilder;
+
+use crate::folder::FolderPath;
+
+use super::{
+    ChildFoldersError, CreateFolderError, DeleteFolderError, FolderExistsError, Folders,
+    RenameFolderError,
+};
+
+pub struct SqliteFolders {
+    pool: sqlx::SqlitePool,
+}
+
+impl SqliteFolders {
+    pub fn new(pool: sqlx::SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl Folders for SqliteFolders {
+    async fn exists(
+        &self,
+        owner_id: AccountId,
+        path: &FolderPath,
+    ) -> Result<bool, FolderExistsError> {
+        if path.is_root() {
+            return Ok(true);
+        }
+
+        let exists: bool = sqlx::query_scalar(
+            r#"
+select exists (
+  select 1
+  from folders
+  where owner_id = $1
+    and path = $2
+)
+"#,
+        )
+        .bind(owner_id.to_string())
+        .bind(path.as_str())
+        .fetch_one(&self.pool)
+        .await
+        .map_err(FolderExistsError::wrap)?;
+
+        Ok(exists)
+    }
+
+    async fn children(
+        &self,
+        owner_id: AccountId,
+        parent: &FolderPath,
+    ) -> Result<Vec<FolderPath>, ChildFoldersError> {
+        let paths: Vec<String> = sqlx::query_scalar(
+            r#"
+select path
+from folders
+where owner_id = $1
+  and parent = $2
+order by path
+"#,
+        )
+        .bind(owner_id.to_string())
+        .bind(parent.as_str())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(ChildFoldersError::wrap)?;
+
+        paths
+            .into_iter()
+            .map(FolderPath::parse)
+            .collect::<Result<_, _>>()
+            .map_err(ChildFoldersError::wrap)
+    }
+
+    async fn create(
+        &self,
+        owner_id: AccountId,
+        path: &FolderPath,
+    ) -> Result<(), CreateFolderError> {
+        if path.is_root() {
+            return Ok(());
+        }
+
+        let folders = path
+            .ancestors()
+            .into_iter()
+            .chain([path.clone()])
+            .filter(|folder| !folder.is_root());
+
+        let mut qb = QueryBuilder::new("insert into folders (owner_id, path, parent) ");
+
+        qb.push_values(folders, |mut row, folder| {
+            row.push_bind(owner_id.to_string())
+                .push_bind(folder.to_string())
+                .push_bind(folder.parent().unwrap_or_default().to_string());
+        });
+
+        qb.push(" on conflict (owner_id, path) do nothing");
+
+        qb.build()
+            .execute(&self.pool)
+            .await
+            .map_err(CreateFolderError::wrap)?;
+
+        Ok(())
+    }
+
+    async fn rename(
+        &self,
+        owner_id: AccountId,
+        from: &FolderPath,
+        to: &FolderPath,
+    ) -> Result<(), RenameFolderError> {
+        sqlx::query(
+            r#"
+update folders
+set path = $3 || substr(path, length($2) + 1),
+    parent = case
+      when path = $2 then $4
+      else $3 || substr(parent, length($2) + 1)
+    end
+where owner_id = $1
+  and (path = $2 or substr(path, 1, length($2) + 1) = $2 || '/')
+"#,
+        )
+        .bind(owner_id.to_string())
+        .bind(from.as_str())
+        .bind(to.as_str())
+        .bind(to.parent().unwrap_or_default().to_string())
+        .execute(&self.pool)
+        .await
+        .map_err(RenameFolderError::wrap)?;
+
+        Ok(())
+    }
+
+    async fn delete(
+        &self,
+        owner_id: AccountId,
+        path: &FolderPath,
+    ) -> Result<(), DeleteFolderError> {
+        sqlx::query(
+            r#"
+delete from folders
+where owner_id = $1
+  and ($2 = '/' or path = $2 or substr(path, 1, length($2) + 1) = $2 || '/')
+"#,
+        )
+        .bind(owner_id.to_string())
+        .bind(path.as_str())
+        .execute(&self.pool)
+        .await
+        .map_err(DeleteFolderError::wrap)?;
+
+        Ok(())
+    }
+}
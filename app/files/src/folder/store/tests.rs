@@ -0,0 +1,147 @@
+use assert2::check;
+use oxidrive_accounts::{account::AccountId, account_id};
+
+use crate::folder::{FolderPath, Folders};
+
+const OWNER_ID: AccountId = account_id!("0194327d-becc-7ef3-809c-35dd09f62f45");
+
+fn path(path: &str) -> FolderPath {
+    FolderPath::parse(path).unwrap()
+}
+
+async fn create_folders<S: Folders>(store: S) {
+    check!(store.exists(OWNER_ID, &FolderPath::root()).await.unwrap());
+    check!(!store.exists(OWNER_ID, &path("/photos")).await.unwrap());
+
+    store.create(OWNER_ID, &path("/photos/2025")).await.unwrap();
+    store.create(OWNER_ID, &path("/photos/2024")).await.unwrap();
+    store.create(OWNER_ID, &path("/photos/2024")).await.unwrap();
+
+    check!(store.exists(OWNER_ID, &path("/photos")).await.unwrap());
+    check!(store.exists(OWNER_ID, &path("/photos/2025")).await.unwrap());
+
+    let children = store.children(OWNER_ID, &FolderPath::root()).await.unwrap();
+    check!(children == vec![path("/photos")]);
+
+    let children = store.children(OWNER_ID, &path("/photos")).await.unwrap();
+    check!(children == vec![path("/photos/2024"), path("/photos/2025")]);
+}
+
+async fn rename_folders<S: Folders>(store: S) {
+    store.create(OWNER_ID, &path("/photos/2025")).await.unwrap();
+    store.create(OWNER_ID, &path("/photos-old")).await.unwrap();
+    store.create(OWNER_ID, &path("/archive")).await.unwrap();
+
+    store
+        .rename(OWNER_ID, &path("/photos"), &path("/archive/pictures"))
+        .await
+        .unwrap();
+
+    check!(!store.exists(OWNER_ID, &path("/photos")).await.unwrap());
+    check!(store.exists(OWNER_ID, &path("/photos-old")).await.unwrap());
+
+    let children = store.children(OWNER_ID, &path("/archive")).await.unwrap();
+    check!(children == vec![path("/archive/pictures")]);
+
+    let children = store
+        .children(OWNER_ID, &path("/archive/pictures"))
+        .await
+        .unwrap();
+    check!(children == vec![path("/archive/pictures/2025")]);
+}
+
+async fn delete_folders<S: Folders>(store: S) {
+    store.create(OWNER_ID, &path("/photos/2025")).await.unwrap();
+    store.create(OWNER_ID, &path("/photos-old")).await.unwrap();
+
+    store.delete(OWNER_ID, &path("/photos")).await.unwrap();
+
+    check!(!store.exists(OWNER_ID, &path("/photos")).await.unwrap());
+    check!(!store.exists(OWNER_ID, &path("/photos/2025")).await.unwrap());
+    check!(store.exists(OWNER_ID, &path("/photos-old")).await.unwrap());
+}
+
+mod inmemory {
+    use crate::folder::InMemoryFolders;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn it_creates_folders() {
+        create_folders(InMemoryFolders::default()).await;
+    }
+
+    #[tokio::test]
+    async fn it_renames_folders() {
+        rename_folders(InMemoryFolders::default()).await;
+    }
+
+    #[tokio::test]
+    async fn it_deletes_folders() {
+        delete_folders(InMemoryFolders::default()).await;
+    }
+}
+
+mod pg {
+    use oxidrive_database::migrate::PG_MIGRATOR;
+
+    use crate::folder::PgFolders;
+
+    use super::*;
+
+    #[sqlx::test(
+        migrator = "PG_MIGRATOR",
+        fixtures("../../fixtures/postgres/accounts.sql")
+    )]
+    async fn it_creates_folders(pool: sqlx::PgPool) {
+        create_folders(PgFolders::new(pool)).await;
+    }
+
+    #[sqlx::test(
+        migrator = "PG_MIGRATOR",
+        fixtures("../../fixtures/postgres/accounts.sql")
+    )]
+    async fn it_renames_folders(pool: sqlx::PgPool) {
+        rename_folders(PgFolders::new(pool)).await;
+    }
+
+    #[sqlx::test(
+        migrator = "PG_MIGRATOR",
+        fixtures("../../fixtures/postgres/accounts.sql")
+    )]
+    async fn it_deletes_folders(pool: sqlx::PgPool) {
+        delete_folders(PgFolders::new(pool)).await;
+    }
+}
+
+mod sqlite {
+    use oxidrive_database::migrate::SQLITE_MIGRATOR;
+
+    use crate::folder::SqliteFolders;
+
+    use super::*;
+
+    #[sqlx::test(
+        migrator = "SQLITE_MIGRATOR",
+        fixtures("../../fixtures/sqlite/accounts.sql")
+    )]
+    async fn it_creates_folders(pool: sqlx::SqlitePool) {
+        create_folders(SqliteFolders::new(pool)).await;
+    }
+
+    #[sqlx::test(
+        migrator = "SQLITE_MIGRATOR",
+        fixtures("../../fixtures/sqlite/accounts.sql")
+    )]
+    async fn it_renames_folders(pool: sqlx::SqlitePool) {
+        rename_folders(SqliteFolders::new(pool)).await;
+    }
+
+    #[sqlx::test(
+        migrator = "SQLITE_MIGRATOR",
+        fixtures("../../fixtures/sqlite/accounts.sql")
+    )]
+    async fn it_deletes_folders(pool: sqlx::SqlitePool) {
+        delete_folders(SqliteFolders::new(pool)).await;
+    }
+}
@@ -0,0 +1,143 @@
+use std::sync::Arc;
+
+use jobs::JobsModule;
+use oxidrive_accounts::account::AccountId;
+use oxidrive_database::Database;
+use oxidrive_domain::make_uuid_type;
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use crate::FileId;
+
+pub use service::*;
+pub use store::*;
+
+pub mod jobs;
+mod service;
+mod store;
+
+make_uuid_type!(IntegrityCheckId, integrity_check_id);
+
+/// A pass over the content of every stored file, comparing it against the hash recorded when it was uploaded
+#[derive(Clone, Debug)]
+pub struct IntegrityCheck {
+    pub id: IntegrityCheckId,
+    pub started_at: OffsetDateTime,
+    /// When all files have been checked. Unfinished checks were interrupted, or are still running
+    pub finished_at: Option<OffsetDateTime>,
+    /// How many files have been checked
+    pub checked: usize,
+    /// How many files had no hash and have been given one, computed from their current content
+    pub backfilled: usize,
+    pub issues: Vec<IntegrityIssue>,
+}
+
+impl IntegrityCheck {
+    pub fn start() -> Self {
+        Self {
+            id: IntegrityCheckId::new(),
+            started_at: OffsetDateTime::now_utc(),
+            finished_at: None,
+            checked: 0,
+            backfilled: 0,
+            issues: Vec::new(),
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished_at.is_some()
+    }
+
+    pub(crate) fn finish(&mut self) {
+        self.finished_at = Some(OffsetDateTime::now_utc());
+    }
+}
+
+/// A file whose content could not be verified
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IntegrityIssue {
+    pub file_id: FileId,
+    pub owner_id: AccountId,
+    #[serde(flatten)]
+    pub kind: IntegrityIssueKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum IntegrityIssueKind {
+    /// The content of the file can't be found in the storage backend
+    Missing,
+    /// The content of the file does not match its hash, e.g. because it was corrupted
+    Mismatched { expected: String, actual: String },
+    /// The file has no hash to compare its content against
+    Unhashed,
+}
+
+#[derive(Copy, Clone)]
+pub struct IntegrityModule;
+
+impl app::Module for IntegrityModule {
+    fn mount(self: Box<Self>, c: &mut app::di::Context) {
+        c.bind(store);
+        c.bind(Integrity::new);
+        c.mount(JobsModule);
+    }
+}
+
+fn store(database: Database) -> Arc<dyn IntegrityCheckStore> {
+    match database {
+        Database::Sqlite(pool) => Arc::new(SqliteIntegrityCheckStore::new(pool)),
+        Database::Pg(pool) => Arc::new(PgIntegrityCheckStore::new(pool)),
+    }
+}
+
+#[app::async_trait]
+impl app::Hooks for IntegrityModule {
+    async fn after_start(
+        &mut self,
+        ctx: app::context::Context,
+        c: &app::di::Container,
+    ) -> app::eyre::Result<()> {
+        JobsModule.after_start(ctx, c).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert2::check;
+    use oxidrive_accounts::account_id;
+    use serde_json::json;
+
+    use crate::file::macros::file_id;
+
+    use super::*;
+
+    #[test]
+    fn it_serializes_issues() {
+        let file_id = file_id!("0194327d-becc-7ef3-809c-35dd09f62f46");
+        let owner_id = account_id!("0194327d-becc-7ef3-809c-35dd09f62f45");
+
+        let issue = IntegrityIssue {
+            file_id,
+            owner_id,
+            kind: IntegrityIssueKind::Mismatched {
+                expected: "abc".into(),
+                actual: "def".into(),
+            },
+        };
+
+        let value = serde_json::to_value(&issue).unwrap();
+        check!(
+            value
+                == json!({
+                    "file_id": "0194327d-becc-7ef3-809c-35dd09f62f46",
+                    "owner_id": "0194327d-becc-7ef3-809c-35dd09f62f45",
+                    "kind": "mismatched",
+                    "expected": "abc",
+                    "actual": "def",
+                })
+        );
+        check!(serde_json::from_value::<IntegrityIssue>(value).unwrap() == issue);
+    }
+}
@@ -0,0 +1,52 @@
+use std::{sync::Arc, time::Duration};
+
+use oxidrive_workers::{
+    Worker,
+    queue::{Enqueue, JobQueue},
+    scheduler::Scheduler,
+};
+
+use crate::Config;
+
+pub use verify_integrity::*;
+
+mod verify_integrity;
+
+pub(crate) struct JobsModule;
+
+impl app::Module for JobsModule {
+    fn mount(self: Box<Self>, c: &mut app::di::Context) {
+        c.bind(VerifyIntegrityWorker::new);
+        c.bind(
+            |queue: Arc<dyn JobQueue>,
+             enqueue: Arc<dyn Enqueue>,
+             process: VerifyIntegrityWorker| { Worker::new(queue, enqueue, process) },
+        );
+    }
+}
+
+#[app::async_trait]
+impl app::Hooks for JobsModule {
+    async fn after_start(
+        &mut self,
+        ctx: app::context::Context,
+        c: &app::di::Container,
+    ) -> app::eyre::Result<()> {
+        let worker = c.get::<Worker<VerifyIntegrityWorker>>();
+        let dispatch = worker.dispatcher();
+
+        worker.clone().start(ctx.clone());
+
+        let cfg = &c.get::<Config>().integrity;
+        if cfg.interval_days == 0 {
+            return Ok(());
+        }
+
+        let every = Duration::from_secs(u64::from(cfg.interval_days) * 24 * 60 * 60);
+        let backfill = cfg.backfill;
+
+        Scheduler::new(every, dispatch, move || VerifyIntegrity { backfill }).start(ctx);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,35 @@
+use oxidrive_workers::{Job, Process};
+use serde::{Deserialize, Serialize};
+
+use crate::integrity::{Integrity, VerifyIntegrityError};
+
+#[derive(Clone)]
+pub struct VerifyIntegrityWorker {
+    integrity: Integrity,
+}
+
+impl VerifyIntegrityWorker {
+    pub fn new(integrity: Integrity) -> Self {
+        Self { integrity }
+    }
+}
+
+impl Process for VerifyIntegrityWorker {
+    type Job = VerifyIntegrity;
+
+    type Error = VerifyIntegrityError;
+
+    async fn process(&self, job: Self::Job) -> Result<(), Self::Error> {
+        self.integrity.verify(job.backfill).await?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct VerifyIntegrity {
+    /// Whether files without a hash should be given one, instead of being reported
+    #[serde(default)]
+    pub backfill: bool,
+}
+
+impl Job for VerifyIntegrity {}
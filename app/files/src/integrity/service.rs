@@ -0,0 +1,156 @@
+use std::sync::Arc;
+
+use crate::{
+    File,
+    file::{
+        AllFilesError, ByIdError, Digest, FileMetadata, FileStorage, HashContentError,
+        SaveFileError,
+    },
+};
+
+use super::{
+    IntegrityCheck, IntegrityCheckByIdError, IntegrityCheckId, IntegrityCheckStore, IntegrityIssue,
+    IntegrityIssueKind, RecentIntegrityChecksError, SaveIntegrityCheckError,
+};
+
+const VERIFY_BATCH_SIZE: usize = 100;
+
+#[derive(Clone)]
+pub struct Integrity {
+    checks: Arc<dyn IntegrityCheckStore>,
+    metadata: Arc<dyn FileMetadata>,
+    storage: FileStorage,
+}
+
+impl Integrity {
+    pub fn new(
+        checks: Arc<dyn IntegrityCheckStore>,
+        metadata: Arc<dyn FileMetadata>,
+        storage: FileStorage,
+    ) -> Self {
+        Self {
+            checks,
+            metadata,
+            storage,
+        }
+    }
+
+    pub async fn by_id(
+        &self,
+        id: IntegrityCheckId,
+    ) -> Result<Option<IntegrityCheck>, IntegrityCheckByIdError> {
+        self.checks.by_id(id).await
+    }
+
+    /// Loads up to `limit` checks, the most recently started first
+    pub async fn recent(
+        &self,
+        limit: usize,
+    ) -> Result<Vec<IntegrityCheck>, RecentIntegrityChecksError> {
+        self.checks.recent(limit).await
+    }
+
+    /// Reads the current content of every file, across all accounts, comparing it against the hash recorded on the file.
+    /// Files without a hash are reported, unless `backfill` is set, in which case they are given the hash of their current content
+    pub async fn verify(&self, backfill: bool) -> Result<IntegrityCheck, VerifyIntegrityError> {
+        let mut check = self.checks.save(IntegrityCheck::start()).await?;
+
+        tracing::info!(check_id = %check.id, backfill, "integrity check started");
+
+        let mut after = None;
+
+        loop {
+            let files = self.metadata.all_after(after, VERIFY_BATCH_SIZE).await?;
+            let Some(last) = files.last() else {
+                break;
+            };
+            after = Some(last.id);
+
+            for file in files {
+                check.checked += 1;
+
+                match self.verify_file(file, backfill).await? {
+                    Verified::Ok => {}
+                    Verified::Backfilled => check.backfilled += 1,
+                    Verified::Issue(issue) => {
+                        tracing::warn!(
+                            account_id = %issue.owner_id,
+                            file_id = %issue.file_id,
+                            issue = ?issue.kind,
+                            "file failed integrity check",
+                        );
+                        check.issues.push(issue);
+                    }
+                }
+            }
+        }
+
+        check.finish();
+        let check = self.checks.save(check).await?;
+
+        tracing::info!(
+            check_id = %check.id,
+            checked = check.checked,
+            backfilled = check.backfilled,
+            issues = check.issues.len(),
+            "integrity check finished",
+        );
+
+        Ok(check)
+    }
+
+    async fn verify_file(
+        &self,
+        mut file: File,
+        backfill: bool,
+    ) -> Result<Verified, VerifyIntegrityError> {
+        let actual = self.storage.hash_current(&file).await?.map(Digest::Blake3);
+
+        let kind = match (file.digest(), actual) {
+            (_, None) => IntegrityIssueKind::Missing,
+            (Some(expected), Some(actual)) if expected == actual => return Ok(Verified::Ok),
+            (Some(expected), Some(actual)) => IntegrityIssueKind::Mismatched {
+                expected: expected.to_string(),
+                actual: actual.to_string(),
+            },
+            (None, Some(Digest::Blake3(hash))) if backfill => {
+                file.set_hash(hash);
+                self.metadata.save(file).await?;
+                return Ok(Verified::Backfilled);
+            }
+            (None, Some(_)) => IntegrityIssueKind::Unhashed,
+        };
+
+        // the file may have been overwritten or deleted while its content was being read
+        let current = self.metadata.by_id(file.id).await?;
+        if current.is_none_or(|current| current.digest() != file.digest()) {
+            return Ok(Verified::Ok);
+        }
+
+        Ok(Verified::Issue(IntegrityIssue {
+            file_id: file.id,
+            owner_id: file.owner_id,
+            kind,
+        }))
+    }
+}
+
+enum Verified {
+    Ok,
+    Backfilled,
+    Issue(IntegrityIssue),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum VerifyIntegrityError {
+    #[error("failed to save integrity check")]
+    SaveCheckFailed(#[from] SaveIntegrityCheckError),
+    #[error("failed to load files")]
+    LoadFilesFailed(#[from] AllFilesError),
+    #[error("failed to load file")]
+    LoadFileFailed(#[from] ByIdError),
+    #[error("failed to hash file content")]
+    HashFailed(#[from] HashContentError),
+    #[error("failed to save file hash")]
+    BackfillFailed(#[from] SaveFileError),
+}
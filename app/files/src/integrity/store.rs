@@ -0,0 +1,70 @@
+use std::{collections::HashMap, sync::Arc};
+
+use async_trait::async_trait;
+use oxidrive_domain::make_error_wrapper;
+use tokio::sync::RwLock;
+
+use super::{IntegrityCheck, IntegrityCheckId};
+
+pub use pg::*;
+pub use sqlite::*;
+
+mod pg;
+mod sqlite;
+
+make_error_wrapper!(IntegrityCheckByIdError);
+make_error_wrapper!(RecentIntegrityChecksError);
+make_error_wrapper!(SaveIntegrityCheckError);
+
+#[async_trait]
+pub trait IntegrityCheckStore: Send + Sync + 'static {
+    async fn by_id(
+        &self,
+        id: IntegrityCheckId,
+    ) -> Result<Option<IntegrityCheck>, IntegrityCheckByIdError>;
+
+    /// Loads up to `limit` checks, the most recently started first
+    async fn recent(&self, limit: usize)
+    -> Result<Vec<IntegrityCheck>, RecentIntegrityChecksError>;
+
+    async fn save(&self, check: IntegrityCheck) -> Result<IntegrityCheck, SaveIntegrityCheckError>;
+}
+
+#[derive(Clone, Default)]
+pub struct InMemoryIntegrityCheckStore {
+    inner: Arc<RwLock<HashMap<IntegrityCheckId, IntegrityCheck>>>,
+}
+
+#[async_trait]
+impl IntegrityCheckStore for InMemoryIntegrityCheckStore {
+    async fn by_id(
+        &self,
+        id: IntegrityCheckId,
+    ) -> Result<Option<IntegrityCheck>, IntegrityCheckByIdError> {
+        let inner = self.inner.read().await;
+        Ok(inner.get(&id).cloned())
+    }
+
+    async fn recent(
+        &self,
+        limit: usize,
+    ) -> Result<Vec<IntegrityCheck>, RecentIntegrityChecksError> {
+        let inner = self.inner.read().await;
+
+        let mut checks = inner.values().cloned().collect::<Vec<_>>();
+
+        checks.sort_by_key(|check| std::cmp::Reverse(check.started_at));
+        checks.truncate(limit);
+
+        Ok(checks)
+    }
+
+    async fn save(&self, check: IntegrityCheck) -> Result<IntegrityCheck, SaveIntegrityCheckError> {
+        let mut inner = self.inner.write().await;
+        inner.insert(check.id, check.clone());
+        Ok(check)
+    }
+}
+
+#[cfg(test)]
+mod tests;
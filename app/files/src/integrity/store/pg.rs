@@ -0,0 +1,136 @@
+use async_trait::async_trait;
+use sqlx::types::Json;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::integrity::{IntegrityCheck, IntegrityCheckId, IntegrityIssue};
+
+use super::{
+    IntegrityCheckByIdError, IntegrityCheckStore, RecentIntegrityChecksError,
+    SaveIntegrityCheckError,
+};
+
+pub struct PgIntegrityCheckStore {
+    pool: sqlx::PgPool,
+}
+
+impl PgIntegrityCheckStore {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl IntegrityCheckStore for PgIntegrityCheckStore {
+    async fn by_id(
+        &self,
+        id: IntegrityCheckId,
+    ) -> Result<Option<IntegrityCheck>, IntegrityCheckByIdError> {
+        let check: Option<PgIntegrityCheck> = sqlx::query_as(
+            r#"
+select
+  id,
+  started_at,
+  finished_at,
+  checked,
+  backfilled,
+  issues
+from integrity_checks
+where id = $1
+"#,
+        )
+        .bind(id.as_uuid())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(IntegrityCheckByIdError::wrap)?;
+
+        Ok(check.map(IntegrityCheck::from))
+    }
+
+    async fn recent(
+        &self,
+        limit: usize,
+    ) -> Result<Vec<IntegrityCheck>, RecentIntegrityChecksError> {
+        let checks: Vec<PgIntegrityCheck> = sqlx::query_as(
+            r#"
+select
+  id,
+  started_at,
+  finished_at,
+  checked,
+  backfilled,
+  issues
+from integrity_checks
+order by started_at desc
+limit $1
+"#,
+        )
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(RecentIntegrityChecksError::wrap)?;
+
+        Ok(checks.into_iter().map(IntegrityCheck::from).collect())
+    }
+
+    async fn save(&self, check: IntegrityCheck) -> Result<IntegrityCheck, SaveIntegrityCheckError> {
+        sqlx::query(
+            r#"
+insert into integrity_checks (
+  id,
+  started_at,
+  finished_at,
+  checked,
+  backfilled,
+  issues
+) values (
+  $1,
+  $2,
+  $3,
+  $4,
+  $5,
+  $6
+) on conflict (id)
+do update set
+  finished_at = excluded.finished_at,
+  checked = excluded.checked,
+  backfilled = excluded.backfilled,
+  issues = excluded.issues
+"#,
+        )
+        .bind(check.id.as_uuid())
+        .bind(check.started_at)
+        .bind(check.finished_at)
+        .bind(check.checked as i64)
+        .bind(check.backfilled as i64)
+        .bind(Json(&check.issues))
+        .execute(&self.pool)
+        .await
+        .map_err(SaveIntegrityCheckError::wrap)?;
+
+        Ok(check)
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct PgIntegrityCheck {
+    id: Uuid,
+    started_at: OffsetDateTime,
+    finished_at: Option<OffsetDateTime>,
+    checked: i64,
+    backfilled: i64,
+    issues: Json<Vec<IntegrityIssue>>,
+}
+
+impl From<PgIntegrityCheck> for IntegrityCheck {
+    fn from(check: PgIntegrityCheck) -> Self {
+        Self {
+            id: check.id.into(),
+            started_at: check.started_at,
+            finished_at: check.finished_at,
+            checked: check.checked.try_into().unwrap(),
+            backfilled: check.backfilled.try_into().unwrap(),
+            issues: check.issues.0,
+        }
+    }
+}
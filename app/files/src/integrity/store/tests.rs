@@ -0,0 +1,119 @@
+use assert2::{check, let_assert};
+use oxidrive_accounts::{account::AccountId, account_id};
+use time::{Duration, OffsetDateTime};
+
+use crate::{
+    FileId,
+    file::macros::file_id,
+    integrity::{IntegrityCheck, IntegrityCheckStore, IntegrityIssue, IntegrityIssueKind},
+};
+
+const OWNER_ID: AccountId = account_id!("0194327d-becc-7ef3-809c-35dd09f62f45");
+const FILE_ID: FileId = file_id!("019433e9-ffbb-7c8b-af6c-d4cb061fb919");
+
+fn integrity_check(started_ago: Duration) -> IntegrityCheck {
+    let mut check = IntegrityCheck::start();
+    check.started_at = OffsetDateTime::now_utc().replace_nanosecond(0).unwrap() - started_ago;
+    check
+}
+
+async fn store_and_load_check<S: IntegrityCheckStore>(store: S) {
+    let mut check = integrity_check(Duration::ZERO);
+
+    store.save(check.clone()).await.unwrap();
+
+    let_assert!(Some(found) = store.by_id(check.id).await.unwrap());
+    check!(found.id == check.id);
+    check!(found.started_at == check.started_at);
+    check!(!found.is_finished());
+    check!(found.issues.is_empty());
+
+    check.checked = 2;
+    check.backfilled = 1;
+    check.issues = vec![IntegrityIssue {
+        file_id: FILE_ID,
+        owner_id: OWNER_ID,
+        kind: IntegrityIssueKind::Missing,
+    }];
+    check.finished_at = Some(check.started_at + Duration::minutes(1));
+
+    store.save(check.clone()).await.unwrap();
+
+    let_assert!(Some(found) = store.by_id(check.id).await.unwrap());
+    check!(found.finished_at == check.finished_at);
+    check!(found.checked == 2);
+    check!(found.backfilled == 1);
+    check!(found.issues == check.issues);
+}
+
+async fn list_recent_checks<S: IntegrityCheckStore>(store: S) {
+    let oldest = integrity_check(Duration::days(2));
+    let older = integrity_check(Duration::days(1));
+    let latest = integrity_check(Duration::ZERO);
+
+    store.save(older.clone()).await.unwrap();
+    store.save(latest.clone()).await.unwrap();
+    store.save(oldest.clone()).await.unwrap();
+
+    let checks = store.recent(10).await.unwrap();
+    check!(checks.len() == 3);
+    check!(checks[0].id == latest.id);
+    check!(checks[1].id == older.id);
+    check!(checks[2].id == oldest.id);
+
+    let checks = store.recent(1).await.unwrap();
+    check!(checks.len() == 1);
+    check!(checks[0].id == latest.id);
+}
+
+mod inmemory {
+    use crate::integrity::InMemoryIntegrityCheckStore;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn it_stores_and_loads_a_check() {
+        store_and_load_check(InMemoryIntegrityCheckStore::default()).await;
+    }
+
+    #[tokio::test]
+    async fn it_lists_recent_checks() {
+        list_recent_checks(InMemoryIntegrityCheckStore::default()).await;
+    }
+}
+
+mod pg {
+    use oxidrive_database::migrate::PG_MIGRATOR;
+
+    use crate::integrity::PgIntegrityCheckStore;
+
+    use super::*;
+
+    #[sqlx::test(migrator = "PG_MIGRATOR")]
+    async fn it_stores_and_loads_a_check(pool: sqlx::PgPool) {
+        store_and_load_check(PgIntegrityCheckStore::new(pool)).await;
+    }
+
+    #[sqlx::test(migrator = "PG_MIGRATOR")]
+    async fn it_lists_recent_checks(pool: sqlx::PgPool) {
+        list_recent_checks(PgIntegrityCheckStore::new(pool)).await;
+    }
+}
+
+mod sqlite {
+    use oxidrive_database::migrate::SQLITE_MIGRATOR;
+
+    use crate::integrity::SqliteIntegrityCheckStore;
+
+    use super::*;
+
+    #[sqlx::test(migrator = "SQLITE_MIGRATOR")]
+    async fn it_stores_and_loads_a_check(pool: sqlx::SqlitePool) {
+        store_and_load_check(SqliteIntegrityCheckStore::new(pool)).await;
+    }
+
+    #[sqlx::test(migrator = "SQLITE_MIGRATOR")]
+    async fn it_lists_recent_checks(pool: sqlx::SqlitePool) {
+        list_recent_checks(SqliteIntegrityCheckStore::new(pool)).await;
+    }
+}
@@ -1,10 +1,19 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
+use bytesize::ByteSize;
 use collection::CollectionsModule;
-use file::{FileEvent, FileMetadata, FileStorage, PgFileMetadata, SqliteFileMetadata};
+use file::{
+    BlobRefs, Compression, DataKeys, Encryption, FileEvent, FileMetadata, FileStorage, PgBlobRefs,
+    PgDataKeys, PgFileMetadata, SqliteBlobRefs, SqliteDataKeys, SqliteFileMetadata,
+    jobs::JobsModule,
+    version::{FileVersions, PgFileVersions, SqliteFileVersions},
+};
+use integrity::IntegrityModule;
+use lifecycle::LifecycleModule;
 use oxidrive_database::Database;
 use oxidrive_pubsub::Publisher;
 use serde::Deserialize;
+use upload::UploadsModule;
 
 pub use file::{File, FileId};
 pub use service::*;
@@ -14,28 +23,274 @@ pub mod auth;
 pub mod collection;
 mod content_type;
 pub mod file;
+pub mod folder;
+pub mod integrity;
+pub mod lifecycle;
+pub mod quota;
 mod service;
 pub mod tag;
+pub mod upload;
 
 #[derive(Clone)]
 pub struct FilesModule;
 
+#[derive(Clone, Debug, Deserialize)]
+pub struct Config {
+    #[serde(flatten)]
+    pub provider: StorageConfig,
+
+    #[serde(default)]
+    pub layout: StorageLayout,
+
+    #[serde(default)]
+    pub trash: TrashConfig,
+
+    #[serde(default)]
+    pub versions: VersionsConfig,
+
+    #[serde(default)]
+    pub uploads: UploadsConfig,
+
+    #[serde(default)]
+    pub integrity: IntegrityConfig,
+
+    #[serde(default)]
+    pub quotas: QuotasConfig,
+
+    /// Encrypts content at rest when set. Not supported with the content addressed layout,
+    /// whose blobs are shared across accounts
+    #[serde(default)]
+    pub encryption: Option<EncryptionConfig>,
+
+    #[serde(default)]
+    pub compression: CompressionConfig,
+
+    /// Additional storage backends, by name, that lifecycle rules can move content to.
+    /// Not supported with the content addressed layout
+    #[serde(default)]
+    pub tiers: HashMap<String, StorageConfig>,
+
+    #[serde(default)]
+    pub lifecycle: LifecycleConfig,
+}
+
 #[derive(Clone, Debug, Deserialize)]
 #[serde(tag = "provider")]
-pub enum Config {
+pub enum StorageConfig {
     #[serde(alias = "fs")]
     FileSystem(file::fs::Config),
     #[serde(alias = "s3")]
     S3(file::s3::Config),
 }
 
+impl From<StorageConfig> for FileStorage {
+    fn from(cfg: StorageConfig) -> Self {
+        match cfg {
+            StorageConfig::FileSystem(cfg) => FileStorage::file_system(cfg),
+            StorageConfig::S3(cfg) => FileStorage::s3(cfg),
+        }
+    }
+}
+
+/// How content is laid out in the storage backend
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StorageLayout {
+    /// Each file owns its content, even when identical to another file's
+    #[default]
+    PerFile,
+    /// Content is stored once under its hash and shared across files, versions and accounts
+    ContentAddressed,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct TrashConfig {
+    /// How many days files are kept in the trash before being permanently deleted
+    #[serde(default = "default_trash_retention_days")]
+    pub retention_days: u32,
+}
+
+impl TrashConfig {
+    pub fn retention(&self) -> time::Duration {
+        time::Duration::days(self.retention_days.into())
+    }
+}
+
+impl Default for TrashConfig {
+    fn default() -> Self {
+        Self {
+            retention_days: default_trash_retention_days(),
+        }
+    }
+}
+
+fn default_trash_retention_days() -> u32 {
+    30
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct VersionsConfig {
+    /// How many previous versions are kept for each file. Set to 0 to disable versioning
+    #[serde(default = "default_versions_retention")]
+    pub retention: usize,
+}
+
+impl Default for VersionsConfig {
+    fn default() -> Self {
+        Self {
+            retention: default_versions_retention(),
+        }
+    }
+}
+
+fn default_versions_retention() -> usize {
+    10
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct UploadsConfig {
+    /// How many hours a resumable upload is kept after receiving its last chunk, before being abandoned
+    #[serde(default = "default_uploads_expiration_hours")]
+    pub expiration_hours: u32,
+}
+
+impl UploadsConfig {
+    pub fn expiration(&self) -> time::Duration {
+        time::Duration::hours(self.expiration_hours.into())
+    }
+}
+
+impl Default for UploadsConfig {
+    fn default() -> Self {
+        Self {
+            expiration_hours: default_uploads_expiration_hours(),
+        }
+    }
+}
+
+fn default_uploads_expiration_hours() -> u32 {
+    24
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct IntegrityConfig {
+    /// How many days pass between integrity checks of the stored content. Set to 0 to disable scheduled checks
+    #[serde(default = "default_integrity_interval_days")]
+    pub interval_days: u32,
+
+    /// Whether scheduled checks give files without a hash the one of their current content, instead of reporting them
+    #[serde(default)]
+    pub backfill: bool,
+}
+
+impl Default for IntegrityConfig {
+    fn default() -> Self {
+        Self {
+            interval_days: default_integrity_interval_days(),
+            backfill: false,
+        }
+    }
+}
+
+fn default_integrity_interval_days() -> u32 {
+    7
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct QuotasConfig {
+    /// How much content each account can store, including previous versions and trashed files.
+    /// Unlimited if not set. It can be overridden for single accounts with `oxidrive storage quota`
+    #[serde(default)]
+    pub default: Option<ByteSize>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct EncryptionConfig {
+    /// The secret every data key is wrapped with. Losing it makes all encrypted content unreadable
+    pub master_key: String,
+
+    /// Master keys that were replaced by `master_key`. They are only needed until `oxidrive storage rotate-keys`
+    /// has wrapped every data key with the current one
+    #[serde(default)]
+    pub previous_master_keys: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct CompressionConfig {
+    /// Content types compressed with zstd before being stored, e.g. `text/*` or `application/json`.
+    /// Nothing is compressed if empty
+    #[serde(default)]
+    pub content_types: Vec<String>,
+
+    /// The zstd compression level, from 1 (fastest) to 22 (smallest)
+    #[serde(default = "default_compression_level")]
+    pub level: i32,
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self {
+            content_types: Vec::new(),
+            level: default_compression_level(),
+        }
+    }
+}
+
+fn default_compression_level() -> i32 {
+    3
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct LifecycleConfig {
+    /// How many hours pass between evaluations of the lifecycle rules. Set to 0 to disable scheduled evaluations
+    #[serde(default = "default_lifecycle_interval_hours")]
+    pub interval_hours: u32,
+
+    /// The first rule matching a file decides which tier its content is moved to
+    #[serde(default)]
+    pub rules: Vec<LifecycleRuleConfig>,
+}
+
+impl Default for LifecycleConfig {
+    fn default() -> Self {
+        Self {
+            interval_hours: default_lifecycle_interval_hours(),
+            rules: Vec::new(),
+        }
+    }
+}
+
+fn default_lifecycle_interval_hours() -> u32 {
+    24
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct LifecycleRuleConfig {
+    /// Which files the rule applies to, as a search query, e.g. `ext:mkv`
+    pub query: String,
+
+    /// How many days the content of a file must have been left untouched before being moved
+    #[serde(default)]
+    pub untouched_days: u32,
+
+    /// The name of the tier content is moved to, one of `tiers`
+    pub tier: String,
+}
+
 impl app::Module for FilesModule {
     fn mount(self: Box<Self>, c: &mut app::di::Context) {
         c.bind(Publisher::<FileEvent>::new);
         c.bind(metadata);
+        c.bind(versions);
         c.bind(contents);
+        c.bind(folder::store);
+        c.bind(quota::store);
+        c.mount(JobsModule);
         c.mount(CollectionsModule);
         c.bind(Files::new);
+        c.mount(UploadsModule);
+        c.mount(IntegrityModule);
+        c.mount(LifecycleModule);
     }
 }
 
@@ -46,10 +301,64 @@ fn metadata(database: Database) -> Arc<dyn FileMetadata> {
     }
 }
 
-fn contents(cfg: Config) -> FileStorage {
-    match cfg {
-        Config::FileSystem(cfg) => FileStorage::file_system(cfg),
-        Config::S3(cfg) => FileStorage::s3(cfg),
+fn versions(database: Database) -> Arc<dyn FileVersions> {
+    match database {
+        Database::Sqlite(pool) => Arc::new(SqliteFileVersions::new(pool)),
+        Database::Pg(pool) => Arc::new(PgFileVersions::new(pool)),
+    }
+}
+
+fn contents(cfg: Config, database: Database) -> FileStorage {
+    let mut storage = FileStorage::from(cfg.provider);
+
+    if !cfg.tiers.is_empty() {
+        // blobs are shared by several files, which may not all be moved by the same rules
+        if let StorageLayout::ContentAddressed = cfg.layout {
+            panic!("storage tiers are not supported with the content addressed layout");
+        }
+
+        for (name, tier) in cfg.tiers {
+            storage = storage.with_tier(name, tier.into());
+        }
+    }
+
+    if let Some(encryption) = &cfg.encryption {
+        // data keys belong to a single account, while blobs can be shared by several
+        if let StorageLayout::ContentAddressed = cfg.layout {
+            panic!("storage encryption is not supported with the content addressed layout");
+        }
+
+        storage = storage.encrypted(Encryption::new(
+            data_keys(database.clone()),
+            &encryption.master_key,
+            &encryption.previous_master_keys,
+        ));
+    }
+
+    if !cfg.compression.content_types.is_empty() {
+        let compression = Compression::new(&cfg.compression.content_types, cfg.compression.level)
+            .expect("invalid storage compression configuration");
+
+        storage = storage.compressed(compression);
+    }
+
+    match cfg.layout {
+        StorageLayout::PerFile => storage,
+        StorageLayout::ContentAddressed => storage.content_addressed(blob_refs(database)),
+    }
+}
+
+fn blob_refs(database: Database) -> Arc<dyn BlobRefs> {
+    match database {
+        Database::Sqlite(pool) => Arc::new(SqliteBlobRefs::new(pool)),
+        Database::Pg(pool) => Arc::new(PgBlobRefs::new(pool)),
+    }
+}
+
+fn data_keys(database: Database) -> Arc<dyn DataKeys> {
+    match database {
+        Database::Sqlite(pool) => Arc::new(SqliteDataKeys::new(pool)),
+        Database::Pg(pool) => Arc::new(PgDataKeys::new(pool)),
     }
 }
 
@@ -60,6 +369,7 @@ impl app::Hooks for FilesModule {
         ctx: app::context::Context,
         c: &app::di::Container,
     ) -> app::eyre::Result<()> {
+        JobsModule.before_start(ctx.clone(), c).await?;
         CollectionsModule.before_start(ctx, c).await?;
         Ok(())
     }
@@ -69,7 +379,11 @@ impl app::Hooks for FilesModule {
         ctx: app::context::Context,
         c: &app::di::Container,
     ) -> app::eyre::Result<()> {
-        CollectionsModule.after_start(ctx, c).await?;
+        JobsModule.after_start(ctx.clone(), c).await?;
+        CollectionsModule.after_start(ctx.clone(), c).await?;
+        UploadsModule.after_start(ctx.clone(), c).await?;
+        IntegrityModule.after_start(ctx.clone(), c).await?;
+        LifecycleModule.after_start(ctx, c).await?;
         Ok(())
     }
 
@@ -78,6 +392,7 @@ impl app::Hooks for FilesModule {
         ctx: app::context::Context,
         c: &app::di::Container,
     ) -> app::eyre::Result<()> {
+        JobsModule.on_shutdown(ctx.clone(), c).await?;
         CollectionsModule.on_shutdown(ctx, c).await?;
         Ok(())
     }
@@ -0,0 +1,172 @@
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use jobs::JobsModule;
+use oxidrive_search::{Filter, QueryParseError};
+
+use crate::{
+    Config, File,
+    file::{
+        FileMetadata, FileStorage,
+        store::{FilterFn, matcher},
+    },
+};
+
+pub use service::*;
+
+pub mod jobs;
+mod service;
+
+/// Moves the content of files matching a search query to another storage tier,
+/// once it has been left untouched for long enough
+pub struct LifecycleRule {
+    pub query: Filter,
+    matches: FilterFn,
+    pub untouched_for: Duration,
+    pub tier: String,
+}
+
+impl LifecycleRule {
+    pub fn new(
+        query: &str,
+        untouched_for: Duration,
+        tier: impl Into<String>,
+    ) -> Result<Self, QueryParseError> {
+        let query = oxidrive_search::parse_query(query)?;
+
+        Ok(Self {
+            matches: matcher(query.clone()),
+            query,
+            untouched_for,
+            tier: tier.into(),
+        })
+    }
+
+    /// Whether the file matches the query of the rule, however long ago its content was written
+    pub fn matches(&self, file: &File) -> bool {
+        (self.matches)(&file.tags)
+    }
+
+    /// Whether the content of the file, last written at `modified_at`, must be moved to the tier of the rule
+    pub fn applies_to(&self, file: &File, modified_at: SystemTime, now: SystemTime) -> bool {
+        self.matches(file) && modified_at + self.untouched_for <= now
+    }
+}
+
+#[derive(Copy, Clone)]
+pub struct LifecycleModule;
+
+impl app::Module for LifecycleModule {
+    fn mount(self: Box<Self>, c: &mut app::di::Context) {
+        c.bind(lifecycle);
+        c.mount(JobsModule);
+    }
+}
+
+fn lifecycle(metadata: Arc<dyn FileMetadata>, storage: FileStorage, cfg: Config) -> Lifecycle {
+    assert!(
+        cfg.lifecycle.rules.is_empty() || !storage.is_content_addressed(),
+        "lifecycle rules can't be used with the content addressed storage layout"
+    );
+
+    let rules = cfg
+        .lifecycle
+        .rules
+        .iter()
+        .map(|rule| {
+            assert!(
+                storage.has_tier(&rule.tier),
+                "lifecycle rule moving content to unknown storage tier '{}'",
+                rule.tier
+            );
+
+            let untouched_for = Duration::from_secs(u64::from(rule.untouched_days) * 24 * 60 * 60);
+
+            LifecycleRule::new(&rule.query, untouched_for, &rule.tier)
+                .expect("invalid lifecycle rule query")
+        })
+        .collect();
+
+    Lifecycle::new(metadata, storage, rules)
+}
+
+#[app::async_trait]
+impl app::Hooks for LifecycleModule {
+    async fn after_start(
+        &mut self,
+        ctx: app::context::Context,
+        c: &app::di::Container,
+    ) -> app::eyre::Result<()> {
+        JobsModule.after_start(ctx, c).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert2::{check, let_assert};
+    use futures::StreamExt;
+    use oxidrive_accounts::account::fixtures::account;
+
+    use crate::file::{InMemoryBlobRefs, InMemoryFileMetadata, fixtures::content};
+
+    use super::*;
+
+    const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+    fn video() -> File {
+        File::new(account().id, "movie.mkv", "video/x-matroska")
+    }
+
+    #[test]
+    fn it_matches_files_by_query() {
+        let rule = LifecycleRule::new("ext:mkv OR ext:mp4", 90 * DAY, "cold").unwrap();
+
+        check!(rule.matches(&video()));
+        check!(!rule.matches(&File::new(account().id, "notes.txt", "text/plain")));
+    }
+
+    #[test]
+    fn it_applies_to_content_left_untouched() {
+        let rule = LifecycleRule::new("ext:mkv", 90 * DAY, "cold").unwrap();
+        let now = SystemTime::now();
+
+        check!(rule.applies_to(&video(), now - 91 * DAY, now));
+        check!(rule.applies_to(&video(), now - 90 * DAY, now));
+        check!(!rule.applies_to(&video(), now - 89 * DAY, now));
+    }
+
+    #[test]
+    fn it_rejects_invalid_queries() {
+        check!(LifecycleRule::new("ext:mkv AND (", DAY, "cold").is_err());
+    }
+
+    #[tokio::test]
+    async fn it_does_not_apply_with_the_content_addressed_layout() {
+        let storage = FileStorage::memory()
+            .content_addressed(Arc::new(InMemoryBlobRefs::default()))
+            .with_tier("cold", FileStorage::memory());
+
+        let mut file = video();
+        let uploaded = storage
+            .upload(&file, content("a movie").boxed())
+            .await
+            .unwrap();
+        storage.promote(&file, &uploaded).await.unwrap();
+        file.set_size(uploaded.size);
+        file.set_hash(uploaded.hash);
+        file.encoding = uploaded.encoding;
+
+        let metadata = Arc::new(InMemoryFileMetadata::from([file.clone()]));
+        let rule = LifecycleRule::new("ext:mkv", Duration::ZERO, "cold").unwrap();
+        let lifecycle = Lifecycle::new(metadata.clone(), storage.clone(), vec![rule]);
+
+        let_assert!(Err(ApplyLifecycleError::ContentAddressed) = lifecycle.apply().await);
+
+        let current = metadata.by_id(file.id).await.unwrap().unwrap();
+        check!(current.tier == None);
+        check!(storage.download(&current, ..).await.unwrap().is_some());
+    }
+}
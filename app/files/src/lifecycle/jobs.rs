@@ -0,0 +1,55 @@
+use std::{sync::Arc, time::Duration};
+
+use oxidrive_workers::{
+    Worker,
+    queue::{Enqueue, JobQueue},
+    scheduler::Scheduler,
+};
+
+use crate::Config;
+
+use super::Lifecycle;
+
+pub use apply_lifecycle_rules::*;
+
+mod apply_lifecycle_rules;
+
+pub(crate) struct JobsModule;
+
+impl app::Module for JobsModule {
+    fn mount(self: Box<Self>, c: &mut app::di::Context) {
+        c.bind(ApplyLifecycleRulesWorker::new);
+        c.bind(
+            |queue: Arc<dyn JobQueue>,
+             enqueue: Arc<dyn Enqueue>,
+             process: ApplyLifecycleRulesWorker| {
+                Worker::new(queue, enqueue, process)
+            },
+        );
+    }
+}
+
+#[app::async_trait]
+impl app::Hooks for JobsModule {
+    async fn after_start(
+        &mut self,
+        ctx: app::context::Context,
+        c: &app::di::Container,
+    ) -> app::eyre::Result<()> {
+        let worker = c.get::<Worker<ApplyLifecycleRulesWorker>>();
+        let dispatch = worker.dispatcher();
+
+        worker.clone().start(ctx.clone());
+
+        let cfg = &c.get::<Config>().lifecycle;
+        if cfg.interval_hours == 0 || !c.get::<Lifecycle>().has_rules() {
+            return Ok(());
+        }
+
+        let every = Duration::from_secs(u64::from(cfg.interval_hours) * 60 * 60);
+
+        Scheduler::new(every, dispatch, ApplyLifecycleRules::default).start(ctx);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,31 @@
+use oxidrive_workers::{Job, Process};
+use serde::{Deserialize, Serialize};
+
+use crate::lifecycle::{ApplyLifecycleError, Lifecycle};
+
+#[derive(Clone)]
+pub struct ApplyLifecycleRulesWorker {
+    lifecycle: Lifecycle,
+}
+
+impl ApplyLifecycleRulesWorker {
+    pub fn new(lifecycle: Lifecycle) -> Self {
+        Self { lifecycle }
+    }
+}
+
+impl Process for ApplyLifecycleRulesWorker {
+    type Job = ApplyLifecycleRules;
+
+    type Error = ApplyLifecycleError;
+
+    async fn process(&self, _: Self::Job) -> Result<(), Self::Error> {
+        self.lifecycle.apply().await?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct ApplyLifecycleRules;
+
+impl Job for ApplyLifecycleRules {}
@@ -0,0 +1,196 @@
+use std::{sync::Arc, time::SystemTime};
+
+use crate::{
+    File,
+    file::{
+        AllFilesError, ByIdError, DeleteContentError, FileMetadata, FileStorage, SaveFileError,
+        StatContentError, Transfer, TransferContentError,
+    },
+};
+
+use super::LifecycleRule;
+
+const APPLY_BATCH_SIZE: usize = 100;
+
+#[derive(Clone)]
+pub struct Lifecycle {
+    metadata: Arc<dyn FileMetadata>,
+    storage: FileStorage,
+    rules: Arc<[LifecycleRule]>,
+}
+
+/// The outcome of a pass of the lifecycle rules over every file
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LifecycleRun {
+    /// How many files matched the query of a rule
+    pub matched: usize,
+    /// How many files had their content moved to another tier
+    pub moved: usize,
+    /// How much stored content was moved
+    pub bytes: u64,
+}
+
+impl Lifecycle {
+    pub fn new(
+        metadata: Arc<dyn FileMetadata>,
+        storage: FileStorage,
+        rules: Vec<LifecycleRule>,
+    ) -> Self {
+        Self {
+            metadata,
+            storage,
+            rules: rules.into(),
+        }
+    }
+
+    pub fn has_rules(&self) -> bool {
+        !self.rules.is_empty()
+    }
+
+    /// Moves the content of every file to the tier of the first rule applying to it.
+    /// Only content still held by the default backend is considered, since it counts as written again
+    /// once moved to another tier. Files overwritten afterwards are written to the default backend again
+    pub async fn apply(&self) -> Result<LifecycleRun, ApplyLifecycleError> {
+        let mut run = LifecycleRun::default();
+
+        if self.rules.is_empty() {
+            return Ok(run);
+        }
+
+        // content shared under its hash can't be moved for a single file
+        if self.storage.is_content_addressed() {
+            return Err(ApplyLifecycleError::ContentAddressed);
+        }
+
+        tracing::info!(
+            rules = self.rules.len(),
+            "lifecycle rules evaluation started"
+        );
+
+        let now = SystemTime::now();
+        let mut after = None;
+
+        loop {
+            let files = self.metadata.all_after(after, APPLY_BATCH_SIZE).await?;
+            let Some(last) = files.last() else {
+                break;
+            };
+            after = Some(last.id);
+
+            for file in files {
+                if file.is_trashed() || file.tier.is_some() {
+                    continue;
+                }
+
+                // the modification time is only looked up for files that could be moved at all
+                if !self.rules.iter().any(|rule| rule.matches(&file)) {
+                    continue;
+                }
+
+                run.matched += 1;
+
+                let Some(modified_at) = self.storage.modified_at(&file).await? else {
+                    continue;
+                };
+
+                let Some(rule) = self
+                    .rules
+                    .iter()
+                    .find(|rule| rule.applies_to(&file, modified_at, now))
+                else {
+                    continue;
+                };
+
+                if let Some(size) = self.move_to_tier(file, &rule.tier).await? {
+                    run.moved += 1;
+                    run.bytes += size;
+                }
+            }
+        }
+
+        tracing::info!(
+            matched = run.matched,
+            moved = run.moved,
+            bytes = run.bytes,
+            "lifecycle rules evaluation finished",
+        );
+
+        Ok(run)
+    }
+
+    /// Moves the content of the file to the tier, returning how much stored content was moved
+    async fn move_to_tier(
+        &self,
+        file: File,
+        tier: &str,
+    ) -> Result<Option<u64>, ApplyLifecycleError> {
+        let size = match self.storage.move_to_tier(&file, tier).await? {
+            Transfer::Copied { size } => size,
+            Transfer::Skipped => file.stored_size as u64,
+            Transfer::Missing => return Ok(None),
+            Transfer::Corrupted => {
+                tracing::warn!(
+                    account_id = %file.owner_id,
+                    file_id = %file.id,
+                    tier,
+                    "file content does not match its hash once moved, leaving it where it was",
+                );
+                return Ok(None);
+            }
+        };
+
+        let mut moved = file.clone();
+        moved.tier = Some(tier.into());
+
+        // the file may have been overwritten or deleted while its content was being moved
+        let current = self
+            .metadata
+            .by_id(file.id)
+            .await?
+            .filter(|current| current.tier == file.tier && current.digest() == file.digest());
+
+        let Some(mut current) = current else {
+            self.storage.evict(&moved).await?;
+            return Ok(None);
+        };
+
+        current.tier = moved.tier;
+        self.metadata.save(current).await?;
+
+        if let Err(err) = self.storage.evict(&file).await {
+            tracing::warn!(
+                error = %err,
+                error.details = ?err,
+                file_id = %file.id,
+                "failed to delete file content moved to another tier",
+            );
+        }
+
+        tracing::debug!(
+            account_id = %file.owner_id,
+            file_id = %file.id,
+            tier,
+            "file content moved to another tier",
+        );
+
+        Ok(Some(size))
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ApplyLifecycleError {
+    #[error("failed to load files")]
+    LoadFilesFailed(#[from] AllFilesError),
+    #[error("failed to load file")]
+    LoadFileFailed(#[from] ByIdError),
+    #[error("failed to look up file content")]
+    StatFailed(#[from] StatContentError),
+    #[error("failed to move file content")]
+    MoveFailed(#[from] TransferContentError),
+    #[error("failed to save file tier")]
+    SaveFailed(#[from] SaveFileError),
+    #[error("failed to delete file content")]
+    DeleteFailed(#[from] DeleteContentError),
+    #[error("lifecycle rules can't be used with the content addressed storage layout")]
+    ContentAddressed,
+}
@@ -0,0 +1,76 @@
+use std::sync::Arc;
+
+use oxidrive_database::Database;
+
+pub use store::*;
+
+mod store;
+
+/// How much content an account stores, including previous versions and trashed files, and how much it is allowed to
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Usage {
+    /// How many bytes the account stores
+    pub used: u64,
+    /// The most bytes the account can store. `None` means unlimited
+    pub quota: Option<u64>,
+}
+
+impl Usage {
+    /// How many more bytes the account can store, or `None` if it is unlimited
+    pub fn remaining(&self) -> Option<u64> {
+        self.quota.map(|quota| quota.saturating_sub(self.used))
+    }
+
+    /// Whether storing `size` more bytes would exceed the quota
+    pub fn exceeded_by(&self, size: u64) -> bool {
+        self.remaining().is_some_and(|remaining| size > remaining)
+    }
+}
+
+pub(crate) fn store(database: Database) -> Arc<dyn UsageStore> {
+    match database {
+        Database::Sqlite(pool) => Arc::new(SqliteUsageStore::new(pool)),
+        Database::Pg(pool) => Arc::new(PgUsageStore::new(pool)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert2::check;
+
+    use super::*;
+
+    #[test]
+    fn it_is_unlimited_without_a_quota() {
+        let usage = Usage {
+            used: 1024,
+            quota: None,
+        };
+
+        check!(usage.remaining() == None);
+        check!(!usage.exceeded_by(u64::MAX));
+    }
+
+    #[test]
+    fn it_is_exceeded_past_the_remaining_space() {
+        let usage = Usage {
+            used: 1024,
+            quota: Some(2048),
+        };
+
+        check!(usage.remaining() == Some(1024));
+        check!(!usage.exceeded_by(1024));
+        check!(usage.exceeded_by(1025));
+    }
+
+    #[test]
+    fn it_has_no_remaining_space_once_over_quota() {
+        let usage = Usage {
+            used: 4096,
+            quota: Some(2048),
+        };
+
+        check!(usage.remaining() == Some(0));
+        check!(usage.exceeded_by(1));
+    }
+}
@@ -0,0 +1,67 @@
+use std::{collections::HashMap, sync::Arc};
+
+use async_trait::async_trait;
+use oxidrive_accounts::account::AccountId;
+use oxidrive_domain::make_error_wrapper;
+use tokio::sync::RwLock;
+
+use super::Usage;
+
+pub use pg::*;
+pub use sqlite::*;
+
+mod pg;
+mod sqlite;
+
+make_error_wrapper!(UsageByAccountError);
+make_error_wrapper!(AddUsageError);
+make_error_wrapper!(SetQuotaError);
+
+#[async_trait]
+pub trait UsageStore: Send + Sync + 'static {
+    /// Loads the usage of the account, with the quota set specifically for it if any
+    async fn by_account(&self, account_id: AccountId) -> Result<Usage, UsageByAccountError>;
+
+    /// Adds to how many bytes the account uses. Negative amounts free space up
+    async fn add(&self, account_id: AccountId, bytes: i64) -> Result<(), AddUsageError>;
+
+    /// Sets the quota of the account, overriding the default one. `None` goes back to the default
+    async fn set_quota(
+        &self,
+        account_id: AccountId,
+        quota: Option<u64>,
+    ) -> Result<(), SetQuotaError>;
+}
+
+#[derive(Clone, Default)]
+pub struct InMemoryUsageStore {
+    inner: Arc<RwLock<HashMap<AccountId, Usage>>>,
+}
+
+#[async_trait]
+impl UsageStore for InMemoryUsageStore {
+    async fn by_account(&self, account_id: AccountId) -> Result<Usage, UsageByAccountError> {
+        let inner = self.inner.read().await;
+        Ok(inner.get(&account_id).copied().unwrap_or_default())
+    }
+
+    async fn add(&self, account_id: AccountId, bytes: i64) -> Result<(), AddUsageError> {
+        let mut inner = self.inner.write().await;
+        let usage = inner.entry(account_id).or_default();
+        usage.used = usage.used.saturating_add_signed(bytes);
+        Ok(())
+    }
+
+    async fn set_quota(
+        &self,
+        account_id: AccountId,
+        quota: Option<u64>,
+    ) -> Result<(), SetQuotaError> {
+        let mut inner = self.inner.write().await;
+        inner.entry(account_id).or_default().quota = quota;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests;
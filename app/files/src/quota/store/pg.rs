@@ -0,0 +1,92 @@
+use async_trait::async_trait;
+use oxidrive_accounts::account::AccountId;
+
+use crate::quota::Usage;
+
+use super::{AddUsageError, SetQuotaError, UsageByAccountError, UsageStore};
+
+pub struct PgUsageStore {
+    pool: sqlx::PgPool,
+}
+
+impl PgUsageStore {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl UsageStore for PgUsageStore {
+    async fn by_account(&self, account_id: AccountId) -> Result<Usage, UsageByAccountError> {
+        let usage: Option<PgUsage> = sqlx::query_as(
+            r#"
+select
+  used,
+  quota
+from account_usage
+where account_id = $1
+"#,
+        )
+        .bind(account_id.as_uuid())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(UsageByAccountError::wrap)?;
+
+        Ok(usage.map(Usage::from).unwrap_or_default())
+    }
+
+    async fn add(&self, account_id: AccountId, bytes: i64) -> Result<(), AddUsageError> {
+        sqlx::query(
+            r#"
+insert into account_usage (account_id, used)
+values ($1, greatest($2, 0))
+on conflict (account_id)
+do update set used = greatest(account_usage.used + $2, 0)
+"#,
+        )
+        .bind(account_id.as_uuid())
+        .bind(bytes)
+        .execute(&self.pool)
+        .await
+        .map_err(AddUsageError::wrap)?;
+
+        Ok(())
+    }
+
+    async fn set_quota(
+        &self,
+        account_id: AccountId,
+        quota: Option<u64>,
+    ) -> Result<(), SetQuotaError> {
+        sqlx::query(
+            r#"
+insert into account_usage (account_id, quota)
+values ($1, $2)
+on conflict (account_id)
+do update set quota = excluded.quota
+"#,
+        )
+        .bind(account_id.as_uuid())
+        .bind(quota.map(|quota| quota as i64))
+        .execute(&self.pool)
+        .await
+        .map_err(SetQuotaError::wrap)?;
+
+        Ok(())
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct PgUsage {
+    used: i64,
+    quota: Option<i64>,
+}
+
+impl From<PgUsage> for Usage {
+    fn from(usage: PgUsage) -> Self {
+        Self {
+            used: usage.used.try_into().unwrap(),
+            quota: usage.quota.map(|quota| quota.try_into().unwrap()),
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use assert2::check;
+use oxidrive_accounts::{account::AccountId, account_id};
+
+use crate::quota::{Usage, UsageStore};
+
+const OWNER_ID: AccountId = account_id!("0194327d-becc-7ef3-809c-35dd09f62f45");
+
+async fn track_usage<S: UsageStore>(store: S) {
+    check!(store.by_account(OWNER_ID).await.unwrap() == Usage::default());
+
+    store.add(OWNER_ID, 1024).await.unwrap();
+    store.add(OWNER_ID, 512).await.unwrap();
+    store.add(OWNER_ID, -256).await.unwrap();
+
+    let usage = store.by_account(OWNER_ID).await.unwrap();
+    check!(usage.used == 1280);
+    check!(usage.quota == None);
+
+    store.add(OWNER_ID, -4096).await.unwrap();
+
+    let usage = store.by_account(OWNER_ID).await.unwrap();
+    check!(usage.used == 0);
+}
+
+async fn set_account_quota<S: UsageStore>(store: S) {
+    store.set_quota(OWNER_ID, Some(2048)).await.unwrap();
+    store.add(OWNER_ID, 1024).await.unwrap();
+
+    let usage = store.by_account(OWNER_ID).await.unwrap();
+    check!(usage.used == 1024);
+    check!(usage.quota == Some(2048));
+
+    store.set_quota(OWNER_ID, None).await.unwrap();
+
+    let usage = store.by_account(OWNER_ID).await.unwrap();
+    check!(usage.used == 1024);
+    check!(usage.quota == None);
+}
+
+mod inmemory {
+    use crate::quota::InMemoryUsageStore;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn it_tracks_usage() {
+        track_usage(InMemoryUsageStore::default()).await;
+    }
+
+    #[tokio::test]
+    async fn it_sets_an_account_quota() {
+        set_account_quota(InMemoryUsageStore::default()).await;
+    }
+}
+
+mod pg {
+    use oxidrive_database::migrate::PG_MIGRATOR;
+
+    use crate::quota::PgUsageStore;
+
+    use super::*;
+
+    #[sqlx::test(
+        migrator = "PG_MIGRATOR",
+        fixtures("../../fixtures/postgres/accounts.sql")
+    )]
+    async fn it_tracks_usage(pool: sqlx::PgPool) {
+        track_usage(PgUsageStore::new(pool)).await;
+    }
+
+    #[sqlx::test(
+        migrator = "PG_MIGRATOR",
+        fixtures("../../fixtures/postgres/accounts.sql")
+    )]
+    async fn it_sets_an_account_quota(pool: sqlx::PgPool) {
+        set_account_quota(PgUsageStore::new(pool)).await;
+    }
+}
+
+mod sqlite {
+    use oxidrive_database::migrate::SQLITE_MIGRATOR;
+
+    use crate::quota::SqliteUsageStore;
+
+    use super::*;
+
+    #[sqlx::test(
+        migrator = "SQLITE_MIGRATOR",
+        fixtures("../../fixtures/sqlite/accounts.sql")
+    )]
+    async fn it_tracks_usage(pool: sqlx::SqlitePool) {
+        track_usage(SqliteUsageStore::new(pool)).await;
+    }
+
+    #[sqlx::test(
+        migrator = "SQLITE_MIGRATOR",
+        fixtures("../../fixtures/sqlite/accounts.sql")
+    )]
+    async fn it_sets_an_account_quota(pool: sqlx::SqlitePool) {
+        set_account_quota(SqliteUsageStore::new(pool)).await;
+    }
+}
@@ -1,36 +1,72 @@
-use std::sync::Arc;
+use std::{ops::RangeBounds, sync::Arc};
 
 use crate::{
-    File, content_type,
+    Config, File, Tag, content_type,
     file::{
-        self, ByNameError, DeleteFileError, DownloadFileError, FileEvent, FileMetadata,
-        FileStorage, SaveFileError, UpdateFile, UploadFileError,
+        self, AllFilesError, AllInFolderError, ByHashError, ByIdError, ByPathError,
+        CopyContentError, DeleteFileError, Digest, DownloadFileError, FileEvent, FileMetadata,
+        FileStorage, HashContentError, InvalidSortError, ListContentError, SaveFileError, Sort,
+        SortBy, StagedContent, StoredContent, TrashedBeforeError, TrashedByError, UpdateFile,
+        UploadFileError,
+        version::{
+            AllVersionsError, CreateVersionError, DeleteVersionError, FileVersion, FileVersions,
+            SaveVersionError, VersionByNumberError,
+        },
     },
+    folder::{
+        ChildFoldersError, CreateFolderError, DeleteFolderError, FolderExistsError, FolderPath,
+        Folders, InvalidPathError, RenameFolderError,
+    },
+    quota::{SetQuotaError, Usage, UsageByAccountError, UsageStore},
+    tag::reserved::DIR,
 };
 use bytes::Bytes;
-use futures::{Stream, TryStreamExt};
+use futures::{Stream, StreamExt, TryStreamExt};
 use oxidrive_accounts::account::AccountId;
+use oxidrive_database::paginate::{InvalidCursor, SortCursor};
 use oxidrive_paginate::{Paginate, Slice};
 use oxidrive_pubsub::Publisher;
-use oxidrive_search::QueryParseError;
+use oxidrive_search::{Filter, QueryParseError, Value};
+use time::OffsetDateTime;
+
+const PURGE_BATCH_SIZE: usize = 100;
+const FOLDER_BATCH_SIZE: usize = 100;
+/// The query directive picking how search results are ordered
+const SORT: &str = "sort";
+/// Downloads are only recorded once in a while, ranged requests of a single download would record each of them
+const ACCESS_RECORD_INTERVAL: time::Duration = time::Duration::minutes(1);
 
 #[derive(Clone)]
 pub struct Files {
     metadata: Arc<dyn FileMetadata>,
+    versions: Arc<dyn FileVersions>,
+    folders: Arc<dyn Folders>,
     storage: FileStorage,
+    usage: Arc<dyn UsageStore>,
     publisher: Publisher<FileEvent>,
+    versions_retention: usize,
+    default_quota: Option<u64>,
 }
 
 impl Files {
     pub fn new(
         files: Arc<dyn FileMetadata>,
+        versions: Arc<dyn FileVersions>,
+        folders: Arc<dyn Folders>,
         storage: FileStorage,
+        usage: Arc<dyn UsageStore>,
         publisher: Publisher<FileEvent>,
+        cfg: Config,
     ) -> Self {
         Self {
             metadata: files,
+            versions,
+            folders,
             storage,
+            usage,
             publisher,
+            versions_retention: cfg.versions.retention,
+            default_quota: cfg.quotas.default.map(|quota| quota.as_u64()),
         }
     }
 
@@ -38,14 +74,42 @@ impl Files {
         self.metadata.as_ref()
     }
 
+    /// Reads the content of the file, or only the requested range of bytes
     pub async fn download(
         &self,
         file: &File,
+        range: impl RangeBounds<u64>,
     ) -> Result<
         Option<impl Stream<Item = Result<Bytes, impl std::error::Error + 'static>> + 'static>,
         DownloadError,
     > {
-        Ok(self.storage.download(file).await?)
+        let content = self.storage.download(file, range).await?;
+
+        if content.is_some() {
+            self.record_access(file).await;
+        }
+
+        Ok(content)
+    }
+
+    /// Remembers when the file was last downloaded. It is only informative, so failing here is not fatal
+    async fn record_access(&self, file: &File) {
+        let now = file::now();
+        if file
+            .accessed_at()
+            .is_some_and(|at| now - at < ACCESS_RECORD_INTERVAL)
+        {
+            return;
+        }
+
+        if let Err(err) = self.metadata.record_access(file.id, now).await {
+            tracing::warn!(
+                error = %err,
+                error.details = ?err,
+                file_id = %file.id,
+                "failed to record file access",
+            );
+        }
     }
 
     pub async fn upload<C, E>(&self, meta: UploadMetadata, content: C) -> Result<File, UploadError>
@@ -53,61 +117,951 @@ impl Files {
         C: Stream<Item = Result<Bytes, E>> + Unpin + Send,
         E: std::error::Error + Send + Sync + 'static,
     {
-        let (content, content_type) =
-            content_type::detect_from_stream(&meta.file_name, content).await;
+        let (folder, file_name) = FolderPath::split_file_path(&meta.file_name)?;
 
-        let mut file = match self
-            .metadata
-            .by_owner_and_name(meta.owner_id, &meta.file_name)
-            .await?
-        {
-            Some(mut file) => {
+        if let Some(precondition) = &meta.precondition {
+            let current = self
+                .metadata
+                .by_owner_and_path(meta.owner_id, &folder, &file_name)
+                .await?;
+
+            if !precondition.holds(current.as_ref()) {
+                return Err(UploadError::PreconditionFailed);
+            }
+        }
+
+        let (file_name, previous) = self
+            .destination(meta.owner_id, &folder, &file_name, meta.on_conflict)
+            .await?;
+
+        let (content, content_type) = content_type::detect_from_stream(&file_name, content).await;
+
+        let mut file = match &previous {
+            Some(previous) => {
+                let mut file = previous.clone();
                 file.content_type = content_type;
                 file
             }
-            None => File::new(meta.owner_id, meta.file_name, content_type),
+            None => {
+                self.folders.create(meta.owner_id, &folder).await?;
+                File::new(meta.owner_id, file_name, content_type).in_folder(folder)
+            }
         };
+        file.add_tags(meta.tags.into_iter().filter(Tag::is_public));
+
+        let usage = self
+            .usage(meta.owner_id)
+            .await
+            .map_err(QuotaError::UsageFailed)?;
+        let mut allowance = self.allowance(&usage, previous.as_ref());
+        let mut exceeded = false;
 
-        let mut hasher = blake3::Hasher::new();
-        let content = content.inspect_ok(|bytes| {
-            hasher.update(bytes);
+        let mut hasher = meta.digest.map(|digest| digest.algorithm().hasher());
+        let content = content.map(|chunk| match chunk {
+            Ok(bytes) => {
+                if let Some(allowance) = &mut allowance {
+                    let Some(left) = allowance.checked_sub(bytes.len() as u64) else {
+                        exceeded = true;
+                        return Err(std::io::Error::other("content exceeds the storage quota"));
+                    };
+                    *allowance = left;
+                }
+                if let Some(hasher) = &mut hasher {
+                    hasher.update(&bytes);
+                }
+                Ok(bytes)
+            }
+            Err(err) => Err(std::io::Error::other(err)),
         });
 
-        let size = self.storage.upload(&file, content).await?;
+        let staged = self.storage.upload(&file, content).await;
+        if exceeded {
+            return Err(QuotaError::Exceeded.into());
+        }
+        let staged = staged?;
 
-        file.set_size(size);
-        file.set_hash(hasher.finalize());
+        if let (Some(expected), Some(hasher)) = (meta.digest, hasher) {
+            let actual = hasher.finalize();
+            if actual != expected {
+                self.discard(&staged).await;
+                return Err(UploadError::DigestMismatch { expected, actual });
+            }
+        }
+
+        self.replace(file, previous, staged).await
+    }
+
+    /// Creates or overwrites a file with content the account already stored for another file,
+    /// so that it does not need to be uploaded again.
+    /// Returns `None` if none of the files of the account has content with the given hash
+    pub async fn upload_existing(
+        &self,
+        owner_id: AccountId,
+        file_name: String,
+        hash: blake3::Hash,
+    ) -> Result<Option<File>, UploadError> {
+        let (folder, file_name) = FolderPath::split_file_path(&file_name)?;
+
+        let Some(source) = self.metadata.by_owner_and_hash(owner_id, hash).await? else {
+            return Ok(None);
+        };
+
+        let previous = self
+            .metadata
+            .by_owner_and_path(owner_id, &folder, &file_name)
+            .await?;
+
+        let file = match &previous {
+            // the file already has this content, so there is nothing to replace
+            Some(previous) if previous.digest() == source.digest() => {
+                return Ok(Some(previous.clone()));
+            }
+            Some(previous) => {
+                let mut file = previous.clone();
+                file.content_type = source.content_type.clone();
+                file
+            }
+            None => {
+                self.folders.create(owner_id, &folder).await?;
+                File::new(owner_id, file_name, source.content_type.clone()).in_folder(folder)
+            }
+        };
+
+        self.check_quota(owner_id, previous.as_ref(), source.size as u64)
+            .await?;
+
+        let staged = self
+            .storage
+            .stage_existing(&file, &source)
+            .await
+            .map_err(UploadError::StageFailed)?;
+
+        self.replace(file, previous, staged).await.map(Some)
+    }
+
+    /// Makes the staged content the current content of the file, once it is saved in its metadata.
+    /// The previous content is archived as a version of the file, and only released when everything succeeded
+    async fn replace(
+        &self,
+        mut file: File,
+        previous: Option<File>,
+        staged: StagedContent,
+    ) -> Result<File, UploadError> {
+        // the previous content is only archived once the new one is known to be good
+        if let Some(previous) = &previous {
+            if let Err(err) = self.archive(previous).await {
+                self.discard(&staged).await;
+                return Err(err.into());
+            }
+        }
+
+        file.set_size(staged.size);
+        file.touch();
+        file.stored_size = staged.stored_size;
+        file.encoding = staged.encoding;
+        file.tier = None;
+        file.set_hash(staged.hash);
+
+        let file = match self.metadata.save(file).await {
+            Ok(file) => file,
+            Err(err) => {
+                self.discard(&staged).await;
+                return Err(err.into());
+            }
+        };
+
+        if let Err(err) = self.storage.promote(&file, &staged).await {
+            self.rollback(&file, previous.as_ref()).await;
+            self.discard(&staged).await;
+            return Err(UploadError::PromoteFailed(err));
+        }
+
+        let freed = previous.as_ref().map_or(0, |previous| previous.size);
+        self.account(file.owner_id, file.size as i64 - freed as i64)
+            .await;
+
+        if let Some(previous) = previous {
+            self.release(&previous).await;
+        }
+
+        self.prune_versions(&file).await?;
+
+        self.publisher.publish(FileEvent::Changed(file.clone()));
+
+        Ok(file)
+    }
+
+    /// Lists the previous versions of the file, from the most recent one
+    pub async fn versions(&self, file: &File) -> Result<Vec<FileVersion>, AllVersionsError> {
+        self.versions.all_for(file.id).await
+    }
+
+    pub async fn version(
+        &self,
+        file: &File,
+        number: u32,
+    ) -> Result<Option<FileVersion>, VersionByNumberError> {
+        self.versions.by_number(file.id, number).await
+    }
+
+    pub async fn download_version(
+        &self,
+        file: &File,
+        version: &FileVersion,
+        range: impl RangeBounds<u64>,
+    ) -> Result<
+        Option<impl Stream<Item = Result<Bytes, impl std::error::Error + 'static>> + 'static>,
+        DownloadError,
+    > {
+        Ok(self.storage.download_version(file, version, range).await?)
+    }
+
+    /// Makes a previous version the current content of the file.
+    /// The content being replaced is archived as a new version, so restoring can be undone
+    pub async fn restore_version(
+        &self,
+        mut file: File,
+        version: &FileVersion,
+    ) -> Result<File, RestoreVersionError> {
+        self.archive(&file).await?;
+
+        let previous = file.clone();
+
+        self.storage.restore_version(&file, version).await?;
+        file.restore_version(version);
+        file.touch();
 
         let file = self.metadata.save(file).await?;
 
+        self.account(file.owner_id, file.size as i64 - previous.size as i64)
+            .await;
+
+        self.release(&previous).await;
+
+        self.prune_versions(&file).await?;
+
         self.publisher.publish(FileEvent::Changed(file.clone()));
 
         Ok(file)
     }
 
+    /// Archives the current content of the file as a new version, before it gets overwritten
+    async fn archive(&self, file: &File) -> Result<(), VersioningError> {
+        if self.versions_retention == 0 {
+            return Ok(());
+        }
+
+        // the number of the version is only known once it is created
+        let version = self.versions.create(file).await?;
+
+        if let Err(err) = self.storage.archive(file, version.number).await {
+            if let Err(err) = self.versions.delete(file.id, version.number).await {
+                tracing::warn!(
+                    error = %err,
+                    file_id = %file.id,
+                    version = version.number,
+                    "failed to delete version without content",
+                );
+            }
+            return Err(err.into());
+        }
+
+        self.account(file.owner_id, file.size as i64).await;
+
+        Ok(())
+    }
+
+    /// Deletes the oldest versions of the file past the configured retention
+    async fn prune_versions(&self, file: &File) -> Result<(), VersioningError> {
+        let versions = self.versions.all_for(file.id).await?;
+
+        for version in versions.into_iter().skip(self.versions_retention) {
+            self.versions.delete(file.id, version.number).await?;
+
+            self.account(file.owner_id, -(version.size as i64)).await;
+
+            if let Err(err) = self.storage.delete_version(file, &version).await {
+                tracing::warn!(
+                    error = %err,
+                    error.details = ?err,
+                    file_id = %file.id,
+                    version = version.number,
+                    "failed to delete file version content",
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Puts the metadata of the file back to what it was before a failed upload,
+    /// so that it keeps describing the content still in place
+    async fn rollback(&self, file: &File, previous: Option<&File>) {
+        let result = match previous {
+            Some(previous) => self
+                .metadata
+                .save(previous.clone())
+                .await
+                .map(|_| ())
+                .map_err(|err| err.to_string()),
+            None => self
+                .metadata
+                .delete(file.id)
+                .await
+                .map_err(|err| err.to_string()),
+        };
+
+        if let Err(err) = result {
+            tracing::error!(
+                error = err,
+                file_id = %file.id,
+                "failed to roll back file metadata after a failed upload",
+            );
+        }
+    }
+
+    /// Loads how much content the account stores, and how much it is allowed to
+    pub async fn usage(&self, owner_id: AccountId) -> Result<Usage, UsageByAccountError> {
+        let mut usage = self.usage.by_account(owner_id).await?;
+        usage.quota = usage.quota.or(self.default_quota);
+        Ok(usage)
+    }
+
+    /// Sets how much content the account can store. `None` goes back to the configured default
+    pub async fn set_quota(
+        &self,
+        owner_id: AccountId,
+        quota: Option<u64>,
+    ) -> Result<(), SetQuotaError> {
+        self.usage.set_quota(owner_id, quota).await
+    }
+
+    /// Checks that the account has enough space left to store a file of the given size under the given path
+    pub async fn check_quota_for(
+        &self,
+        owner_id: AccountId,
+        file_name: &str,
+        size: u64,
+    ) -> Result<(), QuotaError> {
+        let (folder, file_name) = FolderPath::split_file_path(file_name)?;
+        let previous = self
+            .metadata
+            .by_owner_and_path(owner_id, &folder, &file_name)
+            .await?;
+        self.check_quota(owner_id, previous.as_ref(), size).await
+    }
+
+    async fn check_quota(
+        &self,
+        owner_id: AccountId,
+        previous: Option<&File>,
+        size: u64,
+    ) -> Result<(), QuotaError> {
+        let usage = self.usage(owner_id).await?;
+
+        if let Some(quota) = usage.quota {
+            if size > quota {
+                return Err(QuotaError::TooLarge { quota });
+            }
+        }
+
+        match self.allowance(&usage, previous) {
+            Some(allowance) if size > allowance => Err(QuotaError::Exceeded),
+            _ => Ok(()),
+        }
+    }
+
+    /// How many bytes the account can still store when replacing the given file, or `None` if it is unlimited.
+    /// Without versioning, the content being replaced is freed and counts as available
+    fn allowance(&self, usage: &Usage, previous: Option<&File>) -> Option<u64> {
+        let freed = match previous {
+            Some(previous) if self.versions_retention == 0 => previous.size as u64,
+            _ => 0,
+        };
+
+        usage.remaining().map(|remaining| remaining + freed)
+    }
+
+    /// Keeps track of how much content the account stores. The content is already stored or deleted
+    /// at this point, so failing here is not fatal
+    async fn account(&self, owner_id: AccountId, bytes: i64) {
+        if let Err(err) = self.usage.add(owner_id, bytes).await {
+            tracing::error!(
+                error = %err,
+                error.details = ?err,
+                account_id = %owner_id,
+                bytes,
+                "failed to update account usage",
+            );
+        }
+    }
+
+    async fn discard(&self, staged: &StagedContent) {
+        if let Err(err) = self.storage.discard(staged).await {
+            tracing::warn!(
+                error = %err,
+                error.details = ?err,
+                "failed to discard staged content",
+            );
+        }
+    }
+
+    /// Lets go of the content a file referred to before being overwritten.
+    /// The new content is already saved at this point, so failing here is not fatal
+    async fn release(&self, previous: &File) {
+        if let Err(err) = self.storage.release(previous).await {
+            tracing::warn!(
+                error = %err,
+                error.details = ?err,
+                file_id = %previous.id,
+                "failed to release previous file content",
+            );
+        }
+    }
+
     pub async fn update(&self, mut file: File, data: UpdateFile) -> Result<File, UpdateError> {
+        if let Some(name) = data.name.as_deref().filter(|name| *name != file.name) {
+            self.destination(file.owner_id, &file.folder, name, ConflictPolicy::Fail)
+                .await?;
+        }
+
         file.update(data);
         let file = self.metadata.save(file).await?;
         self.publisher.publish(FileEvent::Changed(file.clone()));
         Ok(file)
     }
 
+    /// Copies the file to another path, without its content leaving the storage backend.
+    /// The copy gets its own id and the tags of the original, but none of its previous versions
+    pub async fn copy(
+        &self,
+        file: &File,
+        path: &str,
+        policy: ConflictPolicy,
+    ) -> Result<File, CopyError> {
+        let (folder, name) = FolderPath::split_file_path(path)?;
+
+        let mut source = file.clone();
+        if source.hash().is_none() {
+            let Some(hash) = self.storage.hash_current(&source).await? else {
+                return Err(CopyError::ContentMissing);
+            };
+            source.set_hash(hash);
+            source = self.metadata.save(source).await?;
+        }
+
+        let (name, previous) = self
+            .destination(source.owner_id, &folder, &name, policy)
+            .await?;
+
+        // overwriting a file with itself leaves it as it is
+        if previous
+            .as_ref()
+            .is_some_and(|previous| previous.id == source.id)
+        {
+            return Ok(source);
+        }
+
+        let mut copy = match &previous {
+            Some(previous) => {
+                let mut copy = previous.clone();
+                copy.content_type = source.content_type.clone();
+                copy
+            }
+            None => {
+                self.folders.create(source.owner_id, &folder).await?;
+                File::new(source.owner_id, name, source.content_type.clone()).in_folder(folder)
+            }
+        };
+        copy.set_tags(source.tags.values().cloned());
+
+        self.check_quota(source.owner_id, previous.as_ref(), source.size as u64)
+            .await?;
+
+        let staged = self
+            .storage
+            .stage_existing(&copy, &source)
+            .await
+            .map_err(CopyError::StageFailed)?;
+
+        Ok(self.replace(copy, previous, staged).await?)
+    }
+
+    /// Moves or renames the file. It keeps its id, content and previous versions.
+    /// A file overwritten by the move is sent to the trash
+    pub async fn move_file(
+        &self,
+        mut file: File,
+        path: &str,
+        policy: ConflictPolicy,
+    ) -> Result<File, MoveError> {
+        let (folder, name) = FolderPath::split_file_path(path)?;
+
+        if file.folder == folder && file.name == name {
+            return Ok(file);
+        }
+
+        let (name, previous) = self
+            .destination(file.owner_id, &folder, &name, policy)
+            .await?;
+
+        if let Some(previous) = previous {
+            self.delete(&previous).await?;
+        }
+
+        self.folders.create(file.owner_id, &folder).await?;
+
+        file.set_folder(folder);
+        file.update(UpdateFile {
+            name: Some(name),
+            ..Default::default()
+        });
+
+        let file = self.metadata.save(file).await?;
+        self.publisher.publish(FileEvent::Changed(file.clone()));
+        Ok(file)
+    }
+
+    /// Decides which name a file gets in the folder according to the policy,
+    /// along with the file it replaces if it overwrites one
+    async fn destination(
+        &self,
+        owner_id: AccountId,
+        folder: &FolderPath,
+        name: &str,
+        policy: ConflictPolicy,
+    ) -> Result<(String, Option<File>), ConflictError> {
+        let Some(existing) = self
+            .metadata
+            .by_owner_and_path(owner_id, folder, name)
+            .await?
+        else {
+            return Ok((name.into(), None));
+        };
+
+        match policy {
+            ConflictPolicy::Fail => Err(ConflictError::Taken(existing.path())),
+            ConflictPolicy::Overwrite => Ok((name.into(), Some(existing))),
+            ConflictPolicy::Rename => {
+                let mut number = 1;
+
+                loop {
+                    let candidate = file::numbered_name(name, number);
+
+                    if self
+                        .metadata
+                        .by_owner_and_path(owner_id, folder, &candidate)
+                        .await?
+                        .is_none()
+                    {
+                        return Ok((candidate, None));
+                    }
+
+                    number += 1;
+                }
+            }
+        }
+    }
+
+    /// Searches the files of the account with an OxiQL query.
+    /// A `sort:` directive in the query, like `sort:size:desc`, takes precedence over `sort`.
+    /// Without either, files matching phrases of their content come first
     pub async fn search(
         &self,
         owner_id: AccountId,
         query: impl AsRef<str>,
+        sort: Option<Sort>,
         paginate: Paginate,
     ) -> Result<Slice<File>, SearchError> {
-        let filter = oxidrive_search::parse_query(query)?;
-        let files = self.metadata.search(owner_id, filter, paginate).await?;
+        let (filter, directive) = oxidrive_search::parse_query(query)?.take_directive(SORT);
+
+        let sort = match (directive, sort) {
+            (Some(values), _) => values.to_string().parse()?,
+            (None, Some(sort)) => sort,
+            (None, None) if !filter.content_phrases().is_empty() => Sort::desc(SortBy::Relevance),
+            (None, None) => Sort::default(),
+        };
+
+        SortCursor::parse(&paginate)?;
+
+        let files = self
+            .metadata
+            .search(owner_id, filter, sort, paginate)
+            .await?;
         Ok(files)
     }
 
-    pub async fn delete(&self, file: &File) -> Result<(), DeleteFileError> {
+    /// Moves the file to the trash, from where it can be restored until it gets purged
+    pub async fn delete(&self, file: &File) -> Result<(), DeleteError> {
+        let mut file = file.clone();
+        file.trash();
+
+        let file = self.metadata.save(file).await?;
+        self.publisher.publish(FileEvent::Deleted(file));
+        Ok(())
+    }
+
+    pub async fn trashed(
+        &self,
+        owner_id: AccountId,
+        paginate: Paginate,
+    ) -> Result<Slice<File>, TrashedByError> {
+        self.metadata.trashed_by(owner_id, paginate).await
+    }
+
+    /// Takes the file out of the trash, recreating the folder it was in if it was deleted in the meantime
+    pub async fn restore(&self, mut file: File) -> Result<File, RestoreError> {
+        if self
+            .metadata
+            .by_owner_and_path(file.owner_id, &file.folder, &file.name)
+            .await?
+            .is_some()
+        {
+            return Err(RestoreError::NameTaken(file.path()));
+        }
+
+        self.folders.create(file.owner_id, &file.folder).await?;
+
+        file.restore();
+
+        let file = self.metadata.save(file).await?;
+        self.publisher.publish(FileEvent::Changed(file.clone()));
+        Ok(file)
+    }
+
+    /// Creates an empty folder, along with any missing folder it is nested in
+    pub async fn create_folder(
+        &self,
+        owner_id: AccountId,
+        path: &FolderPath,
+    ) -> Result<(), FolderError> {
+        if self.folders.exists(owner_id, path).await? {
+            return Err(FolderError::AlreadyExists(path.clone()));
+        }
+
+        self.folders.create(owner_id, path).await?;
+        Ok(())
+    }
+
+    /// Lists the folders and the files directly inside the given folder
+    pub async fn list_folder(
+        &self,
+        owner_id: AccountId,
+        path: &FolderPath,
+        paginate: Paginate,
+    ) -> Result<FolderListing, FolderError> {
+        if !self.folders.exists(owner_id, path).await? {
+            return Err(FolderError::NotFound(path.clone()));
+        }
+
+        let folders = self.folders.children(owner_id, path).await?;
+
+        let filter = Filter::Tag {
+            key: DIR.into(),
+            values: [Value::Text(path.to_string())].into_iter().collect(),
+        };
+        let files = self
+            .metadata
+            .search(owner_id, filter, Sort::default(), paginate)
+            .await?;
+
+        Ok(FolderListing { folders, files })
+    }
+
+    /// Moves or renames a folder, along with everything nested in it.
+    /// Files are moved first and the folders last, so an interrupted move can simply be run again
+    pub async fn move_folder(
+        &self,
+        owner_id: AccountId,
+        from: &FolderPath,
+        to: &FolderPath,
+    ) -> Result<(), FolderError> {
+        if from.is_root() || to.is_root() {
+            return Err(FolderError::Root);
+        }
+
+        if from.contains(to) {
+            return Err(FolderError::IntoItself(from.clone()));
+        }
+
+        if !self.folders.exists(owner_id, from).await? {
+            return Err(FolderError::NotFound(from.clone()));
+        }
+
+        if self.folders.exists(owner_id, to).await? {
+            return Err(FolderError::AlreadyExists(to.clone()));
+        }
+
+        if let Some(parent) = to.parent() {
+            self.folders.create(owner_id, &parent).await?;
+        }
+
+        let mut after = None;
+
+        loop {
+            let files = self
+                .metadata
+                .all_in_folder(owner_id, from, after, FOLDER_BATCH_SIZE)
+                .await?;
+            let Some(last) = files.last() else {
+                break;
+            };
+            after = Some(last.id);
+
+            for mut file in files {
+                let Some(folder) = file.folder.rebase(from, to) else {
+                    continue;
+                };
+                file.set_folder(folder);
+                file.touch();
+
+                let file = self.metadata.save(file).await?;
+                if !file.is_trashed() {
+                    self.publisher.publish(FileEvent::Changed(file));
+                }
+            }
+        }
+
+        self.folders.rename(owner_id, from, to).await?;
+        Ok(())
+    }
+
+    /// Deletes a folder along with everything nested in it. Its files are moved to the trash,
+    /// and restoring any of them recreates the folder it was in
+    pub async fn delete_folder(
+        &self,
+        owner_id: AccountId,
+        path: &FolderPath,
+    ) -> Result<usize, FolderError> {
+        if path.is_root() {
+            return Err(FolderError::Root);
+        }
+
+        if !self.folders.exists(owner_id, path).await? {
+            return Err(FolderError::NotFound(path.clone()));
+        }
+
+        let mut trashed = 0;
+        let mut after = None;
+
+        loop {
+            let files = self
+                .metadata
+                .all_in_folder(owner_id, path, after, FOLDER_BATCH_SIZE)
+                .await?;
+            let Some(last) = files.last() else {
+                break;
+            };
+            after = Some(last.id);
+
+            for file in files.iter().filter(|file| !file.is_trashed()) {
+                self.delete(file).await?;
+                trashed += 1;
+            }
+        }
+
+        self.folders.delete(owner_id, path).await?;
+        Ok(trashed)
+    }
+
+    /// Permanently deletes the file and its content
+    pub async fn purge(&self, file: &File) -> Result<(), PurgeError> {
+        let versions = self.versions.all_for(file.id).await?;
+
         self.metadata.delete(file.id).await?;
-        self.publisher.publish(FileEvent::Deleted(file.clone()));
+
+        let freed = file.size + versions.iter().map(|version| version.size).sum::<usize>();
+        self.account(file.owner_id, -(freed as i64)).await;
+
+        // the metadata is gone already, so the content is unreachable anyway.
+        // If this fails, the orphaned content will be collected later by CollectOrphanedContent
+        for version in versions {
+            if let Err(err) = self.storage.delete_version(file, &version).await {
+                tracing::warn!(
+                    error = %err,
+                    error.details = ?err,
+                    file_id = %file.id,
+                    version = version.number,
+                    "failed to delete file version content",
+                );
+            }
+        }
+
+        if let Err(err) = self.storage.delete(file).await {
+            tracing::warn!(
+                error = %err,
+                error.details = ?err,
+                file_id = %file.id,
+                "failed to delete file content",
+            );
+        }
+
         Ok(())
     }
+
+    /// Moves all the content still stored per file under its hash, returning how many objects were moved.
+    /// This converts an existing store to the content addressed layout, which must already be enabled
+    pub async fn deduplicate(&self) -> Result<usize, DeduplicateError> {
+        if !self.storage.is_content_addressed() {
+            return Err(DeduplicateError::NotContentAddressed);
+        }
+
+        let stored: Vec<StoredContent> = self.storage.list().await?.try_collect().await?;
+        let mut moved = 0;
+
+        for content in stored {
+            // orphaned content is left to CollectOrphanedContent
+            let Some(mut file) = self
+                .metadata
+                .by_id(content.file_id)
+                .await?
+                .filter(|file| file.owner_id == content.owner_id)
+            else {
+                continue;
+            };
+
+            match content.version {
+                None => {
+                    if file.hash().is_none() {
+                        file.set_hash(self.storage.hash_stored(&content, file.encoding).await?);
+                        file = self.metadata.save(file).await?;
+                    }
+
+                    self.storage.deduplicate(&file).await?;
+                }
+                Some(number) => {
+                    let Some(mut version) = self.versions.by_number(file.id, number).await? else {
+                        continue;
+                    };
+
+                    if version.hash().is_none() {
+                        version
+                            .set_hash(self.storage.hash_stored(&content, version.encoding).await?);
+                        version = self.versions.save(version).await?;
+                    }
+
+                    self.storage.deduplicate_version(&file, &version).await?;
+                }
+            }
+
+            tracing::debug!(
+                account_id = %content.owner_id,
+                file_id = %content.file_id,
+                version = content.version,
+                "file content deduplicated",
+            );
+
+            moved += 1;
+        }
+
+        Ok(moved)
+    }
+
+    /// Copies the content of every file, and of its versions, from one storage backend to another,
+    /// verifying each object against its hash once copied.
+    /// Content already found in the target is skipped, so an interrupted migration can simply be run again
+    pub async fn migrate_storage(
+        &self,
+        source: &FileStorage,
+        target: &FileStorage,
+    ) -> Result<MigrateStorageReport, MigrateStorageError> {
+        let mut report = MigrateStorageReport::default();
+        let mut after = None;
+
+        tracing::info!(
+            from = %source.display_name(),
+            to = %target.display_name(),
+            "storage migration started",
+        );
+
+        loop {
+            let files = self.metadata.all_after(after, MIGRATE_BATCH_SIZE).await?;
+            let Some(last) = files.last() else {
+                break;
+            };
+            after = Some(last.id);
+
+            for file in files {
+                report.files += 1;
+
+                // content moved to another tier is not held by the backend being migrated
+                if file.tier.is_none() {
+                    let transfer = source.transfer(target, &file).await?;
+                    report.record(&file, None, transfer);
+                }
+
+                for version in self.versions.all_for(file.id).await? {
+                    let transfer = source.transfer_version(target, &file, &version).await?;
+                    report.record(&file, Some(version.number), transfer);
+                }
+            }
+
+            tracing::info!(
+                files = report.files,
+                copied = report.copied,
+                skipped = report.skipped,
+                bytes = report.bytes,
+                "storage migration in progress",
+            );
+        }
+
+        tracing::info!(
+            files = report.files,
+            copied = report.copied,
+            skipped = report.skipped,
+            missing = report.missing,
+            corrupted = report.corrupted,
+            bytes = report.bytes,
+            "storage migration finished",
+        );
+
+        Ok(report)
+    }
+
+    /// Permanently deletes all the files in the account's trash, returning how many were purged
+    pub async fn empty_trash(&self, owner_id: AccountId) -> Result<usize, EmptyTrashError> {
+        let mut purged = 0;
+
+        loop {
+            let files = self
+                .metadata
+                .trashed_by(owner_id, Paginate::first(PURGE_BATCH_SIZE))
+                .await?;
+
+            if files.is_empty() {
+                return Ok(purged);
+            }
+
+            for file in files {
+                self.purge(&file).await?;
+                purged += 1;
+            }
+        }
+    }
+
+    /// Permanently deletes all the files, across all accounts, moved to the trash before the given instant
+    pub async fn purge_trashed_before(
+        &self,
+        before: OffsetDateTime,
+    ) -> Result<usize, PurgeTrashError> {
+        let mut purged = 0;
+
+        loop {
+            let files = self
+                .metadata
+                .trashed_before(before, PURGE_BATCH_SIZE)
+                .await?;
+
+            if files.is_empty() {
+                return Ok(purged);
+            }
+
+            for file in files {
+                self.purge(&file).await?;
+                purged += 1;
+            }
+        }
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -119,14 +1073,103 @@ pub enum DownloadError {
 pub struct UploadMetadata {
     pub file_name: String,
     pub owner_id: AccountId,
+    /// The digest the client expects the content to have, if any.
+    /// The upload is rejected if the received content does not match it
+    pub digest: Option<Digest>,
+    /// Tags given to the file, in addition to the ones it already has if it overwrites another
+    pub tags: Vec<Tag>,
+    /// What happens when a file already exists at the same path
+    pub on_conflict: ConflictPolicy,
+    /// A condition the file currently at the same path must satisfy for the upload to go ahead
+    pub precondition: Option<Precondition>,
+}
+
+/// A condition on the file currently at the path of an upload, compared through its ETag, the hash of its content
+#[derive(Clone, Debug)]
+pub enum Precondition {
+    /// A file must exist with one of the given ETags, or with any if there are none
+    Match(Vec<String>),
+    /// No file must exist
+    NoneMatch,
+}
+
+impl Precondition {
+    fn holds(&self, current: Option<&File>) -> bool {
+        match (self, current) {
+            (Self::Match(_), None) => false,
+            (Self::Match(etags), Some(file)) => {
+                etags.is_empty()
+                    || file
+                        .hash()
+                        .is_some_and(|hash| etags.contains(&hash.to_string()))
+            }
+            (Self::NoneMatch, current) => current.is_none(),
+        }
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
 pub enum UploadError {
-    #[error("failed to load file by name")]
-    LoadFailed(#[from] ByNameError),
+    #[error(transparent)]
+    InvalidPath(#[from] InvalidPathError),
+    #[error("failed to load file by path")]
+    LoadFailed(#[from] ByPathError),
+    #[error("failed to create the folder of the file")]
+    CreateFolderFailed(#[from] CreateFolderError),
+    #[error("failed to load file by hash")]
+    LoadByHashFailed(#[from] ByHashError),
     #[error("failed to upload file content")]
     UploadFailed(#[from] UploadFileError),
+    #[error("expected content with digest {expected}, but got {actual}")]
+    DigestMismatch { expected: Digest, actual: Digest },
+    #[error("the file at the upload path does not satisfy the precondition")]
+    PreconditionFailed,
+    #[error(transparent)]
+    Conflict(#[from] ConflictError),
+    #[error("failed to save file metadata")]
+    SaveMetadataFailed(#[from] SaveFileError),
+    #[error("failed to stage existing file content")]
+    StageFailed(CopyContentError),
+    #[error("failed to replace the current file content")]
+    PromoteFailed(CopyContentError),
+    #[error(transparent)]
+    VersioningFailed(#[from] VersioningError),
+    #[error(transparent)]
+    QuotaFailed(#[from] QuotaError),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum QuotaError {
+    #[error("the file is larger than the storage quota of {quota} bytes")]
+    TooLarge { quota: u64 },
+    #[error("there is not enough space left in the storage quota")]
+    Exceeded,
+    #[error("failed to load account usage")]
+    UsageFailed(#[from] UsageByAccountError),
+    #[error(transparent)]
+    InvalidPath(#[from] InvalidPathError),
+    #[error("failed to load file by path")]
+    LoadFailed(#[from] ByPathError),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum VersioningError {
+    #[error("failed to load file versions")]
+    LoadFailed(#[from] AllVersionsError),
+    #[error("failed to archive file content")]
+    ArchiveFailed(#[from] CopyContentError),
+    #[error("failed to create file version")]
+    CreateFailed(#[from] CreateVersionError),
+    #[error("failed to delete file version")]
+    DeleteFailed(#[from] DeleteVersionError),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RestoreVersionError {
+    #[error(transparent)]
+    VersioningFailed(#[from] VersioningError),
+    #[error("failed to restore file content")]
+    RestoreFailed(#[from] CopyContentError),
     #[error("failed to save file metadata")]
     SaveMetadataFailed(#[from] SaveFileError),
 }
@@ -136,11 +1179,225 @@ pub enum SearchError {
     #[error(transparent)]
     QueryParse(#[from] QueryParseError),
     #[error(transparent)]
+    InvalidSort(#[from] InvalidSortError),
+    #[error(transparent)]
+    InvalidCursor(#[from] InvalidCursor),
+    #[error(transparent)]
     SearchFailed(#[from] file::SearchError),
 }
 
 #[derive(Debug, thiserror::Error)]
 pub enum UpdateError {
+    #[error(transparent)]
+    Conflict(#[from] ConflictError),
     #[error("failed to save file")]
     SaveFileFailed(#[from] SaveFileError),
 }
+
+/// What happens when a file is uploaded, copied or moved where another one already exists
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    /// Nothing is changed and the conflict is reported
+    #[default]
+    Fail,
+    /// The existing file is replaced
+    Overwrite,
+    /// The file gets a free name, numbered like `report (1).pdf`
+    Rename,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConflictError {
+    #[error("a file already exists at '{0}'")]
+    Taken(String),
+    #[error("failed to check for existing files")]
+    LoadFailed(#[from] ByPathError),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CopyError {
+    #[error(transparent)]
+    InvalidPath(#[from] InvalidPathError),
+    #[error(transparent)]
+    Conflict(#[from] ConflictError),
+    #[error("the content of the file is missing from the storage")]
+    ContentMissing,
+    #[error("failed to hash file content")]
+    HashFailed(#[from] HashContentError),
+    #[error("failed to save file metadata")]
+    SaveFailed(#[from] SaveFileError),
+    #[error("failed to create the destination folder")]
+    CreateFolderFailed(#[from] CreateFolderError),
+    #[error(transparent)]
+    QuotaFailed(#[from] QuotaError),
+    #[error("failed to copy file content")]
+    StageFailed(CopyContentError),
+    #[error("failed to store the copy")]
+    StoreFailed(#[from] UploadError),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum MoveError {
+    #[error(transparent)]
+    InvalidPath(#[from] InvalidPathError),
+    #[error(transparent)]
+    Conflict(#[from] ConflictError),
+    #[error("failed to move the overwritten file to the trash")]
+    TrashFailed(#[from] DeleteError),
+    #[error("failed to create the destination folder")]
+    CreateFolderFailed(#[from] CreateFolderError),
+    #[error("failed to save file metadata")]
+    SaveFailed(#[from] SaveFileError),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DeleteError {
+    #[error("failed to move file to the trash")]
+    TrashFailed(#[from] SaveFileError),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RestoreError {
+    #[error("a file already exists at '{0}'")]
+    NameTaken(String),
+    #[error("failed to check for existing files")]
+    LoadFailed(#[from] ByPathError),
+    #[error("failed to recreate the folder of the file")]
+    CreateFolderFailed(#[from] CreateFolderError),
+    #[error("failed to restore file")]
+    SaveFailed(#[from] SaveFileError),
+}
+
+/// The content of a folder
+#[derive(Debug)]
+pub struct FolderListing {
+    /// The folders directly nested in it, ordered by path
+    pub folders: Vec<FolderPath>,
+    /// The files directly inside it
+    pub files: Slice<File>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum FolderError {
+    #[error("folder '{0}' does not exist")]
+    NotFound(FolderPath),
+    #[error("folder '{0}' already exists")]
+    AlreadyExists(FolderPath),
+    #[error("folder '{0}' cannot be moved inside itself")]
+    IntoItself(FolderPath),
+    #[error("the root folder cannot be moved or deleted")]
+    Root,
+    #[error("failed to check whether the folder exists")]
+    ExistsFailed(#[from] FolderExistsError),
+    #[error("failed to list child folders")]
+    ChildrenFailed(#[from] ChildFoldersError),
+    #[error("failed to create folder")]
+    CreateFailed(#[from] CreateFolderError),
+    #[error("failed to rename folder")]
+    RenameFailed(#[from] RenameFolderError),
+    #[error("failed to delete folder")]
+    DeleteFailed(#[from] DeleteFolderError),
+    #[error("failed to list files in the folder")]
+    LoadFilesFailed(#[from] file::SearchError),
+    #[error("failed to load files in the folder")]
+    LoadAllFilesFailed(#[from] AllInFolderError),
+    #[error("failed to save file metadata")]
+    SaveFailed(#[from] SaveFileError),
+    #[error("failed to move file to the trash")]
+    TrashFailed(#[from] DeleteError),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PurgeError {
+    #[error("failed to load file versions")]
+    LoadVersionsFailed(#[from] AllVersionsError),
+    #[error("failed to delete file")]
+    DeleteFailed(#[from] DeleteFileError),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum EmptyTrashError {
+    #[error("failed to load trashed files")]
+    LoadFailed(#[from] TrashedByError),
+    #[error("failed to delete trashed file")]
+    DeleteFailed(#[from] PurgeError),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PurgeTrashError {
+    #[error("failed to load trashed files")]
+    LoadFailed(#[from] TrashedBeforeError),
+    #[error("failed to delete trashed file")]
+    DeleteFailed(#[from] PurgeError),
+}
+
+/// What was done to each object while migrating content between storage backends
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MigrateStorageReport {
+    pub files: usize,
+    pub copied: usize,
+    pub skipped: usize,
+    pub missing: usize,
+    pub corrupted: usize,
+    pub bytes: u64,
+}
+
+impl MigrateStorageReport {
+    fn record(&mut self, file: &File, version: Option<u32>, transfer: Transfer) {
+        match transfer {
+            Transfer::Copied { size } => {
+                self.copied += 1;
+                self.bytes += size;
+            }
+            Transfer::Skipped => self.skipped += 1,
+            Transfer::Missing => {
+                tracing::warn!(
+                    account_id = %file.owner_id,
+                    file_id = %file.id,
+                    version,
+                    "file content not found in the source storage",
+                );
+                self.missing += 1;
+            }
+            Transfer::Corrupted => {
+                tracing::warn!(
+                    account_id = %file.owner_id,
+                    file_id = %file.id,
+                    version,
+                    "copied file content does not match its hash",
+                );
+                self.corrupted += 1;
+            }
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum MigrateStorageError {
+    #[error("failed to load files")]
+    LoadFilesFailed(#[from] AllFilesError),
+    #[error("failed to load file versions")]
+    LoadVersionsFailed(#[from] AllVersionsError),
+    #[error("failed to copy file content")]
+    TransferFailed(#[from] TransferContentError),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DeduplicateError {
+    #[error("the content addressed storage layout is not enabled")]
+    NotContentAddressed,
+    #[error("failed to list stored content")]
+    ListFailed(#[from] ListContentError),
+    #[error("failed to load file")]
+    LoadFailed(#[from] ByIdError),
+    #[error("failed to load file version")]
+    LoadVersionFailed(#[from] VersionByNumberError),
+    #[error("failed to hash stored content")]
+    HashFailed(#[from] HashContentError),
+    #[error("failed to save file metadata")]
+    SaveFailed(#[from] SaveFileError),
+    #[error("failed to save file version")]
+    SaveVersionFailed(#[from] SaveVersionError),
+    #[error("failed to move content under its hash")]
+    MoveFailed(#[from] CopyContentError),
+}
@@ -1,12 +1,29 @@
 use std::fmt::Display;
 
 pub mod reserved {
-    pub const ALL: &[&str] = &[NAME, CONTENT_TYPE, SIZE];
+    pub const ALL: &[&str] = &[
+        NAME,
+        CONTENT_TYPE,
+        SIZE,
+        DIR,
+        PATH,
+        CREATED,
+        UPDATED,
+        ACCESSED,
+        CONTENT,
+    ];
 
     pub const NAME: &str = "name";
     pub const CONTENT_TYPE: &str = "content_type";
     pub const SIZE: &str = "size";
     pub const FILE_EXT: &str = "ext";
+    pub const DIR: &str = "dir";
+    pub const PATH: &str = "path";
+    pub const CREATED: &str = "created";
+    pub const UPDATED: &str = "updated";
+    pub const ACCESSED: &str = "accessed";
+    /// Searches the content of files rather than a tag, see [oxidrive_search::CONTENT]
+    pub const CONTENT: &str = oxidrive_search::CONTENT;
 }
 
 const RESERVED_KEYWORDS: &[&str] = &["AND", "OR"];
@@ -0,0 +1,149 @@
+use std::sync::Arc;
+
+use jobs::JobsModule;
+use oxidrive_accounts::account::AccountId;
+use oxidrive_database::Database;
+use oxidrive_domain::make_uuid_type;
+use time::OffsetDateTime;
+
+use crate::file::Digest;
+
+pub use service::*;
+pub use store::*;
+
+pub mod jobs;
+mod service;
+mod store;
+
+make_uuid_type!(UploadId, upload_id);
+
+/// A file being uploaded in multiple chunks, that can be resumed after an interruption.
+/// Chunks are kept in the storage backend until all of the declared length has been received,
+/// then the file is created or overwritten as with a regular upload
+#[derive(Clone, Debug)]
+pub struct Upload {
+    pub id: UploadId,
+    pub owner_id: AccountId,
+    pub file_name: String,
+    /// The total size of the file, declared when the upload is created
+    pub length: usize,
+    /// How many bytes have been received so far
+    pub offset: usize,
+    /// When the upload is abandoned if no more chunks are received
+    pub expires_at: OffsetDateTime,
+    /// The digest the complete content is expected to have, if the client declared one
+    pub digest: Option<Digest>,
+}
+
+impl Upload {
+    pub fn new(
+        owner_id: AccountId,
+        file_name: impl Into<String>,
+        length: usize,
+        expires_at: OffsetDateTime,
+    ) -> Self {
+        Self {
+            id: UploadId::new(),
+            owner_id,
+            file_name: file_name.into(),
+            length,
+            offset: 0,
+            expires_at,
+            digest: None,
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.offset >= self.length
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= OffsetDateTime::now_utc()
+    }
+
+    /// How many bytes are still expected
+    pub fn remaining(&self) -> usize {
+        self.length.saturating_sub(self.offset)
+    }
+}
+
+#[cfg(any(test, feature = "fixtures"))]
+pub mod fixtures {
+    use fake::Fake;
+    use oxidrive_accounts::account::{Account, fixtures::account};
+    use rstest::fixture;
+    use time::Duration;
+
+    use super::*;
+
+    #[fixture]
+    pub fn upload(account: Account) -> Upload {
+        let file_name = fake::faker::filesystem::en::FileName().fake::<String>();
+        Upload::new(
+            account.id,
+            file_name,
+            (1..4096).fake(),
+            OffsetDateTime::now_utc() + Duration::hours(1),
+        )
+    }
+}
+
+#[derive(Copy, Clone)]
+pub struct UploadsModule;
+
+impl app::Module for UploadsModule {
+    fn mount(self: Box<Self>, c: &mut app::di::Context) {
+        c.bind(store);
+        c.mount(JobsModule);
+        c.bind(Uploads::new);
+    }
+}
+
+fn store(database: Database) -> Arc<dyn UploadStore> {
+    match database {
+        Database::Sqlite(pool) => Arc::new(SqliteUploadStore::new(pool)),
+        Database::Pg(pool) => Arc::new(PgUploadStore::new(pool)),
+    }
+}
+
+#[app::async_trait]
+impl app::Hooks for UploadsModule {
+    async fn after_start(
+        &mut self,
+        ctx: app::context::Context,
+        c: &app::di::Container,
+    ) -> app::eyre::Result<()> {
+        JobsModule.after_start(ctx, c).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert2::check;
+    use rstest::rstest;
+    use time::Duration;
+
+    use super::fixtures::upload;
+    use super::*;
+
+    #[rstest]
+    fn it_is_complete_once_all_the_content_is_received(mut upload: Upload) {
+        check!(!upload.is_complete());
+        check!(upload.remaining() == upload.length);
+
+        upload.offset = upload.length;
+
+        check!(upload.is_complete());
+        check!(upload.remaining() == 0);
+    }
+
+    #[rstest]
+    fn it_expires(mut upload: Upload) {
+        check!(!upload.is_expired());
+
+        upload.expires_at = OffsetDateTime::now_utc() - Duration::seconds(1);
+
+        check!(upload.is_expired());
+    }
+}
@@ -0,0 +1,44 @@
+use std::{sync::Arc, time::Duration};
+
+use oxidrive_workers::{
+    Worker,
+    queue::{Enqueue, JobQueue},
+    scheduler::Scheduler,
+};
+
+pub use expire_uploads::*;
+
+mod expire_uploads;
+
+static EXPIRE_UPLOADS_EVERY: Duration = Duration::from_secs(60 * 60);
+
+pub(crate) struct JobsModule;
+
+impl app::Module for JobsModule {
+    fn mount(self: Box<Self>, c: &mut app::di::Context) {
+        c.bind(ExpireUploadsWorker::new);
+        c.bind(
+            |queue: Arc<dyn JobQueue>, enqueue: Arc<dyn Enqueue>, process: ExpireUploadsWorker| {
+                Worker::new(queue, enqueue, process)
+            },
+        );
+    }
+}
+
+#[app::async_trait]
+impl app::Hooks for JobsModule {
+    async fn after_start(
+        &mut self,
+        ctx: app::context::Context,
+        c: &app::di::Container,
+    ) -> app::eyre::Result<()> {
+        let worker = c.get::<Worker<ExpireUploadsWorker>>();
+        let dispatch = worker.dispatcher();
+
+        worker.clone().start(ctx.clone());
+
+        Scheduler::new(EXPIRE_UPLOADS_EVERY, dispatch, ExpireUploads::default).start(ctx);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,38 @@
+use oxidrive_workers::{Job, Process};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use crate::upload::{ExpireUploadsError, Uploads};
+
+#[derive(Clone)]
+pub struct ExpireUploadsWorker {
+    uploads: Uploads,
+}
+
+impl ExpireUploadsWorker {
+    pub fn new(uploads: Uploads) -> Self {
+        Self { uploads }
+    }
+}
+
+impl Process for ExpireUploadsWorker {
+    type Job = ExpireUploads;
+
+    type Error = ExpireUploadsError;
+
+    async fn process(&self, _: Self::Job) -> Result<(), Self::Error> {
+        let expired = self
+            .uploads
+            .expire_before(OffsetDateTime::now_utc())
+            .await?;
+
+        tracing::info!(expired, "abandoned uploads expired");
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct ExpireUploads;
+
+impl Job for ExpireUploads {}
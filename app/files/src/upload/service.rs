@@ -0,0 +1,290 @@
+use std::sync::Arc;
+
+use bytes::Bytes;
+use futures::{Stream, StreamExt};
+use oxidrive_accounts::account::AccountId;
+use time::{Duration, OffsetDateTime};
+
+use crate::{
+    Config, ConflictPolicy, File, Files, QuotaError, UploadError, UploadMetadata,
+    file::{Digest, DownloadFileError, FileStorage, UploadFileError, UploadedChunk},
+};
+
+use super::{
+    AdvanceUploadError, DeleteUploadError, ExpiredUploadsError, SaveUploadError, Upload,
+    UploadByIdError, UploadId, UploadStore,
+};
+
+const EXPIRE_BATCH_SIZE: usize = 100;
+
+#[derive(Clone)]
+pub struct Uploads {
+    uploads: Arc<dyn UploadStore>,
+    files: Files,
+    storage: FileStorage,
+    expiration: Duration,
+}
+
+impl Uploads {
+    pub fn new(
+        uploads: Arc<dyn UploadStore>,
+        files: Files,
+        storage: FileStorage,
+        cfg: Config,
+    ) -> Self {
+        Self {
+            uploads,
+            files,
+            storage,
+            expiration: cfg.uploads.expiration(),
+        }
+    }
+
+    /// Starts a new upload of the given length, optionally declaring the digest of its complete content.
+    /// Uploads that would not fit in the account's storage quota are rejected before any content is sent.
+    /// Empty files have no content to wait for, so they are created right away
+    pub async fn create(
+        &self,
+        owner_id: AccountId,
+        file_name: impl Into<String>,
+        length: usize,
+        digest: Option<Digest>,
+    ) -> Result<Upload, CreateUploadError> {
+        let file_name = file_name.into();
+
+        self.files
+            .check_quota_for(owner_id, &file_name, length as u64)
+            .await?;
+
+        let mut upload = Upload::new(
+            owner_id,
+            file_name,
+            length,
+            OffsetDateTime::now_utc() + self.expiration,
+        );
+        upload.digest = digest;
+
+        let upload = self.uploads.save(upload).await?;
+
+        if upload.is_complete() {
+            self.complete(&upload).await?;
+        }
+
+        Ok(upload)
+    }
+
+    /// Loads an upload that has not expired yet
+    pub async fn by_id(&self, id: UploadId) -> Result<Option<Upload>, UploadByIdError> {
+        Ok(self
+            .uploads
+            .by_id(id)
+            .await?
+            .filter(|upload| !upload.is_expired()))
+    }
+
+    /// Appends content to the upload, starting at the given offset which must be the one reached so far.
+    /// Once all the content has been received, the file is created, or overwritten as with [Files::upload]
+    pub async fn append<C, E>(
+        &self,
+        mut upload: Upload,
+        offset: usize,
+        content: C,
+    ) -> Result<Upload, AppendError>
+    where
+        C: Stream<Item = Result<Bytes, E>> + Unpin + Send,
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        if offset != upload.offset {
+            return Err(AppendError::OffsetMismatch);
+        }
+
+        if !upload.is_complete() {
+            let mut remaining = upload.remaining();
+            let mut exceeded = false;
+
+            let content = content.map(|chunk| match chunk {
+                Ok(bytes) if bytes.len() > remaining => {
+                    exceeded = true;
+                    Err(std::io::Error::other("content exceeds the upload length"))
+                }
+                Ok(bytes) => {
+                    remaining -= bytes.len();
+                    Ok(bytes)
+                }
+                Err(err) => Err(std::io::Error::other(err)),
+            });
+
+            let chunk = self
+                .storage
+                .write_chunk(upload.owner_id, upload.id, offset, content)
+                .await?;
+
+            if exceeded {
+                self.delete_chunk(&upload, &chunk).await;
+                return Err(AppendError::ExceedsLength(upload.length));
+            }
+
+            if chunk.size == 0 {
+                self.delete_chunk(&upload, &chunk).await;
+                return Ok(upload);
+            }
+
+            let expires_at = OffsetDateTime::now_utc() + self.expiration;
+
+            if !self
+                .uploads
+                .advance(upload.id, offset, offset + chunk.size, expires_at)
+                .await?
+            {
+                // another chunk was appended at the same offset in the meantime
+                self.delete_chunk(&upload, &chunk).await;
+                return Err(AppendError::OffsetMismatch);
+            }
+
+            upload.offset += chunk.size;
+            upload.expires_at = expires_at;
+        }
+
+        // an upload that failed to complete is completed again when its last chunk is resent
+        if upload.is_complete() {
+            self.complete(&upload).await?;
+        }
+
+        Ok(upload)
+    }
+
+    /// Abandons the upload, discarding all the content received so far
+    pub async fn terminate(&self, upload: &Upload) -> Result<(), TerminateUploadError> {
+        self.uploads.delete(upload.id).await?;
+        self.delete_chunks(upload).await;
+        Ok(())
+    }
+
+    /// Terminates all the uploads, across all accounts, that expired before the given instant
+    pub async fn expire_before(&self, before: OffsetDateTime) -> Result<usize, ExpireUploadsError> {
+        let mut expired = 0;
+
+        loop {
+            let uploads = self
+                .uploads
+                .expired_before(before, EXPIRE_BATCH_SIZE)
+                .await?;
+
+            if uploads.is_empty() {
+                return Ok(expired);
+            }
+
+            for upload in uploads {
+                self.terminate(&upload).await?;
+                expired += 1;
+            }
+        }
+    }
+
+    /// Hands the content received so far to [Files::upload], then forgets about the upload
+    async fn complete(&self, upload: &Upload) -> Result<File, CompleteUploadError> {
+        let content = self.storage.read_chunks(upload.owner_id, upload.id).await?;
+
+        let file = match self
+            .files
+            .upload(
+                UploadMetadata {
+                    file_name: upload.file_name.clone(),
+                    owner_id: upload.owner_id,
+                    digest: upload.digest,
+                    tags: Vec::new(),
+                    on_conflict: ConflictPolicy::Overwrite,
+                    precondition: None,
+                },
+                content,
+            )
+            .await
+        {
+            Ok(file) => file,
+            Err(err @ UploadError::DigestMismatch { .. }) => {
+                // the content received can never match, so there is no point in keeping it around
+                self.terminate(upload).await?;
+                return Err(err.into());
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        self.uploads.delete(upload.id).await?;
+        self.delete_chunks(upload).await;
+
+        Ok(file)
+    }
+
+    async fn delete_chunk(&self, upload: &Upload, chunk: &UploadedChunk) {
+        if let Err(err) = self.storage.delete_chunk(chunk).await {
+            tracing::warn!(
+                error = %err,
+                error.details = ?err,
+                upload_id = %upload.id,
+                "failed to delete upload chunk",
+            );
+        }
+    }
+
+    /// The upload is gone at this point, so leftover chunks are only wasted space
+    async fn delete_chunks(&self, upload: &Upload) {
+        if let Err(err) = self.storage.delete_chunks(upload.id).await {
+            tracing::warn!(
+                error = %err,
+                error.details = ?err,
+                upload_id = %upload.id,
+                "failed to delete upload chunks",
+            );
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CreateUploadError {
+    #[error("failed to save upload")]
+    SaveFailed(#[from] SaveUploadError),
+    #[error(transparent)]
+    QuotaFailed(#[from] QuotaError),
+    #[error(transparent)]
+    CompleteFailed(#[from] CompleteUploadError),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum AppendError {
+    #[error("the offset does not match the content received so far")]
+    OffsetMismatch,
+    #[error("the content exceeds the upload length of {0} bytes")]
+    ExceedsLength(usize),
+    #[error("failed to write upload chunk")]
+    WriteFailed(#[from] UploadFileError),
+    #[error("failed to save upload offset")]
+    AdvanceFailed(#[from] AdvanceUploadError),
+    #[error(transparent)]
+    CompleteFailed(#[from] CompleteUploadError),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CompleteUploadError {
+    #[error("failed to read upload chunks")]
+    ReadFailed(#[from] DownloadFileError),
+    #[error("failed to upload file")]
+    UploadFailed(#[from] UploadError),
+    #[error("failed to delete upload")]
+    DeleteFailed(#[from] DeleteUploadError),
+    #[error("failed to terminate upload")]
+    TerminateFailed(#[from] TerminateUploadError),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum TerminateUploadError {
+    #[error("failed to delete upload")]
+    DeleteFailed(#[from] DeleteUploadError),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ExpireUploadsError {
+    #[error("failed to load expired uploads")]
+    LoadFailed(#[from] ExpiredUploadsError),
+    #[error("failed to terminate expired upload")]
+    TerminateFailed(#[from] TerminateUploadError),
+}
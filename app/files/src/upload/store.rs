@@ -0,0 +1,121 @@
+use std::{collections::HashMap, sync::Arc};
+
+use async_trait::async_trait;
+use oxidrive_domain::make_error_wrapper;
+use time::OffsetDateTime;
+use tokio::sync::RwLock;
+
+use super::{Upload, UploadId};
+
+pub use pg::*;
+pub use sqlite::*;
+
+mod pg;
+mod sqlite;
+
+make_error_wrapper!(UploadByIdError);
+make_error_wrapper!(SaveUploadError);
+make_error_wrapper!(AdvanceUploadError);
+make_error_wrapper!(DeleteUploadError);
+make_error_wrapper!(ExpiredUploadsError);
+
+#[async_trait]
+pub trait UploadStore: Send + Sync + 'static {
+    async fn by_id(&self, id: UploadId) -> Result<Option<Upload>, UploadByIdError>;
+
+    async fn save(&self, upload: Upload) -> Result<Upload, SaveUploadError>;
+
+    /// Moves the offset of the upload forward, only if it is still at `from`.
+    /// Returns false if another chunk was received in the meantime
+    async fn advance(
+        &self,
+        id: UploadId,
+        from: usize,
+        to: usize,
+        expires_at: OffsetDateTime,
+    ) -> Result<bool, AdvanceUploadError>;
+
+    async fn delete(&self, id: UploadId) -> Result<(), DeleteUploadError>;
+
+    /// Loads up to `limit` uploads, across all accounts, that expired before the given instant
+    async fn expired_before(
+        &self,
+        before: OffsetDateTime,
+        limit: usize,
+    ) -> Result<Vec<Upload>, ExpiredUploadsError>;
+}
+
+#[derive(Clone, Default)]
+pub struct InMemoryUploadStore {
+    inner: Arc<RwLock<HashMap<UploadId, Upload>>>,
+}
+
+impl<const N: usize> From<[Upload; N]> for InMemoryUploadStore {
+    fn from(uploads: [Upload; N]) -> Self {
+        let uploads = HashMap::from_iter(uploads.into_iter().map(|u| (u.id, u)));
+        Self {
+            inner: Arc::new(RwLock::new(uploads)),
+        }
+    }
+}
+
+#[async_trait]
+impl UploadStore for InMemoryUploadStore {
+    async fn by_id(&self, id: UploadId) -> Result<Option<Upload>, UploadByIdError> {
+        let inner = self.inner.read().await;
+        Ok(inner.get(&id).cloned())
+    }
+
+    async fn save(&self, upload: Upload) -> Result<Upload, SaveUploadError> {
+        let mut inner = self.inner.write().await;
+        inner.insert(upload.id, upload.clone());
+        Ok(upload)
+    }
+
+    async fn advance(
+        &self,
+        id: UploadId,
+        from: usize,
+        to: usize,
+        expires_at: OffsetDateTime,
+    ) -> Result<bool, AdvanceUploadError> {
+        let mut inner = self.inner.write().await;
+
+        let Some(upload) = inner.get_mut(&id).filter(|upload| upload.offset == from) else {
+            return Ok(false);
+        };
+
+        upload.offset = to;
+        upload.expires_at = expires_at;
+
+        Ok(true)
+    }
+
+    async fn delete(&self, id: UploadId) -> Result<(), DeleteUploadError> {
+        let mut inner = self.inner.write().await;
+        inner.remove(&id);
+        Ok(())
+    }
+
+    async fn expired_before(
+        &self,
+        before: OffsetDateTime,
+        limit: usize,
+    ) -> Result<Vec<Upload>, ExpiredUploadsError> {
+        let inner = self.inner.read().await;
+
+        let mut uploads = inner
+            .values()
+            .filter(|u| u.expires_at < before)
+            .cloned()
+            .collect::<Vec<_>>();
+
+        uploads.sort_by_key(|u| u.expires_at);
+        uploads.truncate(limit);
+
+        Ok(uploads)
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,178 @@
+use async_trait::async_trait;
+use time::OffsetDateTime;
+
+use crate::upload::{Upload, UploadId};
+
+use super::{
+    AdvanceUploadError, DeleteUploadError, ExpiredUploadsError, SaveUploadError, UploadByIdError,
+    UploadStore,
+};
+
+pub struct SqliteUploadStore {
+    pool: sqlx::SqlitePool,
+}
+
+impl SqliteUploadStore {
+    pub fn new(pool: sqlx::SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl UploadStore for SqliteUploadStore {
+    async fn by_id(&self, id: UploadId) -> Result<Option<Upload>, UploadByIdError> {
+        let upload: Option<SqliteUpload> = sqlx::query_as(
+            r#"
+select
+  id,
+  owner_id,
+  file_name,
+  length,
+  current_offset,
+  expires_at,
+  digest
+from uploads
+where id = $1
+"#,
+        )
+        .bind(id.to_string())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(UploadByIdError::wrap)?;
+
+        Ok(upload.map(Upload::from))
+    }
+
+    async fn save(&self, upload: Upload) -> Result<Upload, SaveUploadError> {
+        sqlx::query(
+            r#"
+insert into uploads (
+  id,
+  owner_id,
+  file_name,
+  length,
+  current_offset,
+  expires_at,
+  digest
+) values (
+  $1,
+  $2,
+  $3,
+  $4,
+  $5,
+  $6,
+  $7
+) on conflict (id)
+do update set
+  file_name = excluded.file_name,
+  length = excluded.length,
+  current_offset = excluded.current_offset,
+  expires_at = excluded.expires_at,
+  digest = excluded.digest
+"#,
+        )
+        .bind(upload.id.to_string())
+        .bind(upload.owner_id.to_string())
+        .bind(&upload.file_name)
+        .bind(upload.length as i64)
+        .bind(upload.offset as i64)
+        .bind(upload.expires_at)
+        .bind(upload.digest.map(|digest| digest.to_string()))
+        .execute(&self.pool)
+        .await
+        .map_err(SaveUploadError::wrap)?;
+
+        Ok(upload)
+    }
+
+    async fn advance(
+        &self,
+        id: UploadId,
+        from: usize,
+        to: usize,
+        expires_at: OffsetDateTime,
+    ) -> Result<bool, AdvanceUploadError> {
+        let result = sqlx::query(
+            r#"
+update uploads
+set current_offset = $3,
+    expires_at = $4
+where id = $1
+  and current_offset = $2
+"#,
+        )
+        .bind(id.to_string())
+        .bind(from as i64)
+        .bind(to as i64)
+        .bind(expires_at)
+        .execute(&self.pool)
+        .await
+        .map_err(AdvanceUploadError::wrap)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn delete(&self, id: UploadId) -> Result<(), DeleteUploadError> {
+        sqlx::query("delete from uploads where id = $1")
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await
+            .map_err(DeleteUploadError::wrap)?;
+        Ok(())
+    }
+
+    async fn expired_before(
+        &self,
+        before: OffsetDateTime,
+        limit: usize,
+    ) -> Result<Vec<Upload>, ExpiredUploadsError> {
+        let uploads: Vec<SqliteUpload> = sqlx::query_as(
+            r#"
+select
+  id,
+  owner_id,
+  file_name,
+  length,
+  current_offset,
+  expires_at,
+  digest
+from uploads
+where expires_at < $1
+order by expires_at
+limit $2
+"#,
+        )
+        .bind(before)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(ExpiredUploadsError::wrap)?;
+
+        Ok(uploads.into_iter().map(Upload::from).collect())
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct SqliteUpload {
+    id: String,
+    owner_id: String,
+    file_name: String,
+    length: i64,
+    current_offset: i64,
+    expires_at: OffsetDateTime,
+    digest: Option<String>,
+}
+
+impl From<SqliteUpload> for Upload {
+    fn from(upload: SqliteUpload) -> Self {
+        Self {
+            id: upload.id.parse().unwrap(),
+            owner_id: upload.owner_id.parse().unwrap(),
+            file_name: upload.file_name,
+            length: upload.length.try_into().unwrap(),
+            offset: upload.current_offset.try_into().unwrap(),
+            expires_at: upload.expires_at,
+            digest: upload.digest.map(|digest| digest.parse().unwrap()),
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use assert2::{check, let_assert};
+use oxidrive_accounts::{account::AccountId, account_id};
+use time::{Duration, OffsetDateTime};
+
+use crate::file::Digest;
+use crate::upload::{Upload, UploadStore};
+
+const OWNER_ID: AccountId = account_id!("0194327d-becc-7ef3-809c-35dd09f62f45");
+
+fn upload(expires_in: Duration) -> Upload {
+    let expires_at = OffsetDateTime::now_utc().replace_nanosecond(0).unwrap() + expires_in;
+    Upload::new(OWNER_ID, "hello.txt", 1024, expires_at)
+}
+
+async fn store_and_load_upload<S: UploadStore>(store: S) {
+    let mut upload = upload(Duration::hours(1));
+    upload.digest = Some(Digest::Blake3(blake3::hash(b"hello world")));
+
+    store.save(upload.clone()).await.unwrap();
+
+    let_assert!(Some(found) = store.by_id(upload.id).await.unwrap());
+    check!(found.id == upload.id);
+    check!(found.owner_id == upload.owner_id);
+    check!(found.file_name == upload.file_name);
+    check!(found.length == upload.length);
+    check!(found.offset == 0);
+    check!(found.expires_at == upload.expires_at);
+    check!(found.digest == upload.digest);
+
+    store.delete(upload.id).await.unwrap();
+
+    let_assert!(None = store.by_id(upload.id).await.unwrap());
+}
+
+async fn advance_upload<S: UploadStore>(store: S) {
+    let upload = upload(Duration::hours(1));
+    let expires_at = upload.expires_at + Duration::hours(1);
+
+    store.save(upload.clone()).await.unwrap();
+
+    check!(store.advance(upload.id, 0, 512, expires_at).await.unwrap());
+    check!(!store.advance(upload.id, 0, 256, expires_at).await.unwrap());
+
+    let_assert!(Some(found) = store.by_id(upload.id).await.unwrap());
+    check!(found.offset == 512);
+    check!(found.expires_at == expires_at);
+}
+
+async fn find_expired_uploads<S: UploadStore>(store: S) {
+    let expired = upload(-Duration::hours(2));
+    let expiring = upload(-Duration::hours(1));
+    let active = upload(Duration::hours(1));
+
+    store.save(expired.clone()).await.unwrap();
+    store.save(expiring.clone()).await.unwrap();
+    store.save(active).await.unwrap();
+
+    let uploads = store
+        .expired_before(OffsetDateTime::now_utc(), 10)
+        .await
+        .unwrap();
+    check!(uploads.len() == 2);
+    check!(uploads[0].id == expired.id);
+    check!(uploads[1].id == expiring.id);
+
+    let uploads = store
+        .expired_before(OffsetDateTime::now_utc(), 1)
+        .await
+        .unwrap();
+    check!(uploads.len() == 1);
+    check!(uploads[0].id == expired.id);
+}
+
+mod inmemory {
+    use crate::upload::InMemoryUploadStore;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn it_stores_and_loads_an_upload() {
+        store_and_load_upload(InMemoryUploadStore::default()).await;
+    }
+
+    #[tokio::test]
+    async fn it_advances_an_upload() {
+        advance_upload(InMemoryUploadStore::default()).await;
+    }
+
+    #[tokio::test]
+    async fn it_finds_expired_uploads() {
+        find_expired_uploads(InMemoryUploadStore::default()).await;
+    }
+}
+
+mod pg {
+    use oxidrive_database::migrate::PG_MIGRATOR;
+
+    use crate::upload::PgUploadStore;
+
+    use super::*;
+
+    #[sqlx::test(
+        migrator = "PG_MIGRATOR",
+        fixtures("../../fixtures/postgres/accounts.sql")
+    )]
+    async fn it_stores_and_loads_an_upload(pool: sqlx::PgPool) {
+        store_and_load_upload(PgUploadStore::new(pool)).await;
+    }
+
+    #[sqlx::test(
+        migrator = "PG_MIGRATOR",
+        fixtures("../../fixtures/postgres/accounts.sql")
+    )]
+    async fn it_advances_an_upload(pool: sqlx::PgPool) {
+        advance_upload(PgUploadStore::new(pool)).await;
+    }
+
+    #[sqlx::test(
+        migrator = "PG_MIGRATOR",
+        fixtures("../../fixtures/postgres/accounts.sql")
+    )]
+    async fn it_finds_expired_uploads(pool: sqlx::PgPool) {
+        find_expired_uploads(PgUploadStore::new(pool)).await;
+    }
+}
+
+mod sqlite {
+    use oxidrive_database::migrate::SQLITE_MIGRATOR;
+
+    use crate::upload::SqliteUploadStore;
+
+    use super::*;
+
+    #[sqlx::test(
+        migrator = "SQLITE_MIGRATOR",
+        fixtures("../../fixtures/sqlite/accounts.sql")
+    )]
+    async fn it_stores_and_loads_an_upload(pool: sqlx::SqlitePool) {
+        store_and_load_upload(SqliteUploadStore::new(pool)).await;
+    }
+
+    #[sqlx::test(
+        migrator = "SQLITE_MIGRATOR",
+        fixtures("../../fixtures/sqlite/accounts.sql")
+    )]
+    async fn it_advances_an_upload(pool: sqlx::SqlitePool) {
+        advance_upload(SqliteUploadStore::new(pool)).await;
+    }
+
+    #[sqlx::test(
+        migrator = "SQLITE_MIGRATOR",
+        fixtures("../../fixtures/sqlite/accounts.sql")
+    )]
+    async fn it_finds_expired_uploads(pool: sqlx::SqlitePool) {
+        find_expired_uploads(SqliteUploadStore::new(pool)).await;
+    }
+}
@@ -0,0 +1,200 @@
+use std::{cmp::Ordering, fmt::Display};
+
+use bytesize::ByteSize;
+use time::{
+    Date, OffsetDateTime, UtcOffset, format_description::well_known::Rfc3339,
+    macros::format_description,
+};
+
+/// How the value of a tag is compared to an [Operand]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Cmp {
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+impl Cmp {
+    pub(crate) fn parse(s: &str) -> Self {
+        match s {
+            ">" => Self::Gt,
+            ">=" => Self::Ge,
+            "<" => Self::Lt,
+            "<=" => Self::Le,
+            unexpected => unreachable!("encountered unexpected comparison operator {unexpected}"),
+        }
+    }
+
+    /// Whether a value ordered this way relative to the operand passes the comparison
+    pub fn holds(self, ordering: Ordering) -> bool {
+        match self {
+            Self::Gt => ordering.is_gt(),
+            Self::Ge => ordering.is_ge(),
+            Self::Lt => ordering.is_lt(),
+            Self::Le => ordering.is_le(),
+        }
+    }
+}
+
+impl Display for Cmp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Gt => ">",
+            Self::Ge => ">=",
+            Self::Lt => "<",
+            Self::Le => "<=",
+        }
+        .fmt(f)
+    }
+}
+
+/// What the value of a tag is compared to
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Operand {
+    /// A plain number, or a size with a unit like `10MB` or `500KiB` in bytes
+    Number(u64),
+    /// A whole day, like `2025-01-01`
+    Date(Date),
+    /// An instant, like `2025-01-01T10:00:00Z`
+    DateTime(OffsetDateTime),
+    Text(String),
+}
+
+impl Operand {
+    pub fn parse(s: &str) -> Self {
+        if let Ok(number) = s.parse() {
+            return Self::Number(number);
+        }
+
+        if let Ok(date) = Date::parse(s, format_description!("[year]-[month]-[day]")) {
+            return Self::Date(date);
+        }
+
+        if let Ok(at) = OffsetDateTime::parse(s, &Rfc3339) {
+            return Self::DateTime(at);
+        }
+
+        if s.starts_with(|c: char| c.is_ascii_digit()) {
+            if let Ok(size) = s.parse::<ByteSize>() {
+                return Self::Number(size.as_u64());
+            }
+        }
+
+        Self::Text(s.into())
+    }
+
+    /// Turns the comparison into one against tag values as they are stored: numbers compare numerically,
+    /// dates as RFC 3339 instants in UTC and anything else as text.
+    /// Days include all of their hours, so that `created<=2025-01-01` matches files created during that day
+    pub fn comparable(&self, cmp: Cmp) -> (Cmp, Comparable) {
+        match self {
+            Self::Number(number) => (cmp, Comparable::Number(*number)),
+            Self::Date(date) => {
+                let next_day = date.next_day();
+                match (cmp, next_day) {
+                    (Cmp::Gt, Some(next_day)) => (Cmp::Ge, Comparable::start_of(next_day)),
+                    (Cmp::Le, Some(next_day)) => (Cmp::Lt, Comparable::start_of(next_day)),
+                    _ => (cmp, Comparable::start_of(*date)),
+                }
+            }
+            Self::DateTime(at) => (cmp, Comparable::instant(*at)),
+            Self::Text(text) => (cmp, Comparable::Text(text.clone())),
+        }
+    }
+}
+
+impl Display for Operand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Number(number) => number.fmt(f),
+            Self::Date(date) => date.fmt(f),
+            Self::DateTime(at) => match at.format(&Rfc3339) {
+                Ok(at) => at.fmt(f),
+                Err(_) => Err(std::fmt::Error),
+            },
+            Self::Text(text) => text.fmt(f),
+        }
+    }
+}
+
+/// An operand in the same form as the tag values it is compared to
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Comparable {
+    Number(u64),
+    Text(String),
+}
+
+impl Comparable {
+    fn start_of(date: Date) -> Self {
+        Self::instant(date.midnight().assume_utc())
+    }
+
+    /// Tag values are kept to the second in UTC, so that their text sorts chronologically
+    fn instant(at: OffsetDateTime) -> Self {
+        let at = at.to_offset(UtcOffset::UTC);
+        let at = at.replace_nanosecond(0).unwrap_or(at);
+        Self::Text(at.format(&Rfc3339).unwrap_or_else(|_| at.to_string()))
+    }
+
+    /// Whether the value of a tag passes the comparison. Values that are not numbers never pass numeric comparisons
+    pub fn is_satisfied_by(&self, cmp: Cmp, value: &str) -> bool {
+        match self {
+            Self::Number(number) => value
+                .parse::<u64>()
+                .is_ok_and(|value| cmp.holds(value.cmp(number))),
+            Self::Text(text) => cmp.holds(value.cmp(text.as_str())),
+        }
+    }
+}
+
+/// The comparisons the value of a tag must pass to fall within a range, both bounds included
+pub fn range_comparisons(from: Option<&Operand>, to: Option<&Operand>) -> Vec<(Cmp, Comparable)> {
+    from.map(|from| from.comparable(Cmp::Ge))
+        .into_iter()
+        .chain(to.map(|to| to.comparable(Cmp::Le)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use assert2::check;
+    use rstest::rstest;
+    use time::macros::{date, datetime};
+
+    use super::*;
+
+    #[rstest]
+    #[case("42", Operand::Number(42))]
+    #[case("10MB", Operand::Number(10_000_000))]
+    #[case("500KiB", Operand::Number(512_000))]
+    #[case("2025-01-01", Operand::Date(date!(2025-01-01)))]
+    #[case("2025-01-01T10:00:00Z", Operand::DateTime(datetime!(2025-01-01 10:00:00 UTC)))]
+    #[case("text/plain", Operand::Text("text/plain".into()))]
+    fn it_parses_an_operand(#[case] s: &str, #[case] expected: Operand) {
+        check!(Operand::parse(s) == expected);
+    }
+
+    #[rstest]
+    #[case(Cmp::Ge, "2025-01-01T00:00:00Z", true)]
+    #[case(Cmp::Ge, "2024-12-31T23:59:59Z", false)]
+    #[case(Cmp::Le, "2025-01-01T23:59:59Z", true)]
+    #[case(Cmp::Le, "2025-01-02T00:00:00Z", false)]
+    #[case(Cmp::Gt, "2025-01-01T12:00:00Z", false)]
+    #[case(Cmp::Gt, "2025-01-02T00:00:00Z", true)]
+    #[case(Cmp::Lt, "2024-12-31T23:59:59Z", true)]
+    #[case(Cmp::Lt, "2025-01-01T00:00:00Z", false)]
+    fn it_compares_a_whole_day(#[case] cmp: Cmp, #[case] value: &str, #[case] expected: bool) {
+        let (cmp, comparable) = Operand::parse("2025-01-01").comparable(cmp);
+        check!(comparable.is_satisfied_by(cmp, value) == expected);
+    }
+
+    #[test]
+    fn it_compares_numbers_numerically() {
+        let (cmp, comparable) = Operand::parse("1KB").comparable(Cmp::Gt);
+
+        check!(comparable.is_satisfied_by(cmp, "1001"));
+        check!(!comparable.is_satisfied_by(cmp, "999"));
+        check!(!comparable.is_satisfied_by(cmp, "not a number"));
+    }
+}
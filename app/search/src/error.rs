@@ -0,0 +1,238 @@
+use std::ops::Range;
+
+use miette::SourceSpan;
+use pest::error::{ErrorVariant, InputLocation};
+
+use crate::query::Rule;
+
+/// A query that could not be parsed, pointing at where it went wrong
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error, miette::Diagnostic)]
+#[error("invalid query: {message}")]
+#[diagnostic(code(oxidrive::search::invalid_query))]
+pub struct QueryParseError {
+    #[source_code]
+    query: String,
+    message: String,
+    #[label("here")]
+    span: SourceSpan,
+    expected: Vec<String>,
+    #[help]
+    hint: Option<String>,
+}
+
+impl QueryParseError {
+    pub(crate) fn new(query: &str, err: pest::error::Error<Rule>) -> Self {
+        let start = match err.location {
+            InputLocation::Pos(pos) => pos,
+            InputLocation::Span((start, _)) => start,
+        };
+
+        let expected = match &err.variant {
+            ErrorVariant::ParsingError { positives, .. } => {
+                positives.iter().fold(Vec::new(), |mut expected, rule| {
+                    let name = describe(*rule).to_string();
+                    if !expected.contains(&name) {
+                        expected.push(name);
+                    }
+                    expected
+                })
+            }
+            ErrorVariant::CustomError { .. } => Vec::new(),
+        };
+
+        let found = query[start..].chars().next();
+        let message = match (expected.as_slice(), found) {
+            ([], Some(found)) => format!("unexpected `{found}`"),
+            ([], None) => "unexpected end of query".into(),
+            (expected, _) => format!("expected {}", join(expected)),
+        };
+
+        let span = start..start + found.map(char::len_utf8).unwrap_or_default();
+
+        let mut err = Self {
+            query: query.into(),
+            message,
+            span: span.into(),
+            expected,
+            hint: None,
+        };
+        err.suggest();
+        err
+    }
+
+    /// A content term that parses, but cannot be searched for
+    pub(crate) fn invalid_content_term(query: &str, span: Range<usize>, cmp: bool) -> Self {
+        let message = if cmp {
+            "content cannot be compared"
+        } else {
+            "content cannot be searched with wildcards"
+        };
+
+        Self {
+            query: query.into(),
+            message: message.into(),
+            span: span.into(),
+            expected: Vec::new(),
+            hint: Some(
+                "search content for whole words, like `content:budget` or `\"quarterly report\"`"
+                    .into(),
+            ),
+        }
+    }
+
+    /// The query that failed to parse
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    /// What went wrong, without the position
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// The byte range of the query the error points at.
+    /// It is empty when the query ended too early
+    pub fn span(&self) -> Range<usize> {
+        self.span.offset()..self.span.offset() + self.span.len()
+    }
+
+    /// The tokens that would have been valid where the error is
+    pub fn expected(&self) -> &[String] {
+        &self.expected
+    }
+
+    /// A suggestion on how to fix the query, if one could be guessed
+    pub fn hint(&self) -> Option<&str> {
+        self.hint.as_deref()
+    }
+
+    fn suggest(&mut self) {
+        if let Some(quote) = unclosed_quote(&self.query) {
+            self.span = (quote..quote + 1).into();
+            self.hint = Some("unclosed quote, add a `\"` after the value".into());
+            return;
+        }
+
+        if let Some(paren) = unmatched_paren(&self.query) {
+            self.span = (paren..paren + 1).into();
+            self.hint = Some(if self.query[paren..].starts_with('(') {
+                "unclosed parenthesis, add a `)` to end the group".into()
+            } else {
+                "there is no group for this `)` to close".into()
+            });
+            return;
+        }
+
+        let start = self.span.offset();
+        let before = self.query[..start].trim_end();
+        let after = self.query[start..].trim_start();
+
+        let previous = before
+            .rsplit(char::is_whitespace)
+            .next()
+            .unwrap_or_default();
+        for op in ["AND", "OR"] {
+            if previous != op && previous.eq_ignore_ascii_case(op) {
+                self.hint = Some(format!("did you mean `{op}`? Operators are uppercase"));
+                return;
+            }
+        }
+
+        if after.is_empty() && (previous == "AND" || previous == "OR") {
+            self.hint = Some(format!("`{previous}` must be followed by a tag or a group"));
+            return;
+        }
+
+        if after.starts_with('(') && self.expects(Rule::and) {
+            self.hint = Some("join groups to the rest of the query with `AND` or `OR`".into());
+            return;
+        }
+
+        if after.starts_with(':') {
+            self.hint = Some("tags need a key before the `:`, like `name:report.pdf`".into());
+        }
+    }
+
+    fn expects(&self, rule: Rule) -> bool {
+        self.expected
+            .iter()
+            .any(|expected| expected == describe(rule))
+    }
+}
+
+fn describe(rule: Rule) -> &'static str {
+    match rule {
+        Rule::and => "`AND`",
+        Rule::or => "`OR`",
+        Rule::not => "`-`",
+        Rule::all | Rule::r#match => "`*`",
+        Rule::cmp => "a comparison",
+        Rule::range | Rule::bound => "a range",
+        Rule::value | Rule::text | Rule::quoted_value => "a value",
+        Rule::negation => "a negated group",
+        Rule::EOI => "the end of the query",
+        _ => "a tag",
+    }
+}
+
+fn join(items: &[String]) -> String {
+    match items {
+        [] => String::new(),
+        [item] => item.clone(),
+        [rest @ .., last] => format!("{} or {last}", rest.join(", ")),
+    }
+}
+
+fn unclosed_quote(query: &str) -> Option<usize> {
+    let quotes = query.match_indices('"').map(|(i, _)| i).collect::<Vec<_>>();
+    if quotes.len() % 2 == 0 {
+        return None;
+    }
+    quotes.last().copied()
+}
+
+fn unmatched_paren(query: &str) -> Option<usize> {
+    let mut open = Vec::new();
+    for (i, c) in query.char_indices() {
+        match c {
+            '(' => open.push(i),
+            ')' if open.pop().is_none() => return Some(i),
+            _ => {}
+        }
+    }
+    open.pop()
+}
+
+#[cfg(test)]
+mod tests {
+    use assert2::{check, let_assert};
+    use rstest::rstest;
+
+    use crate::parse_query;
+
+    #[rstest]
+    #[case("hello:\"test with", 6..7, Some("unclosed quote, add a `\"` after the value"))]
+    #[case("(a OR b", 0..1, Some("unclosed parenthesis, add a `)` to end the group"))]
+    #[case("a OR b)", 6..7, Some("there is no group for this `)` to close"))]
+    #[case("a and (b OR c)", 6..7, Some("did you mean `AND`? Operators are uppercase"))]
+    #[case("a AND", 5..5, Some("`AND` must be followed by a tag or a group"))]
+    #[case("a (b OR c)", 2..3, Some("join groups to the rest of the query with `AND` or `OR`"))]
+    #[case(":pdf", 0..1, Some("tags need a key before the `:`, like `name:report.pdf`"))]
+    fn it_points_at_the_error(
+        #[case] query: &str,
+        #[case] span: std::ops::Range<usize>,
+        #[case] hint: Option<&str>,
+    ) {
+        let_assert!(Err(err) = parse_query(query));
+        check!(err.query() == query);
+        check!(err.span() == span);
+        check!(err.hint() == hint);
+    }
+
+    #[test]
+    fn it_lists_the_expected_tokens() {
+        let_assert!(Err(err) = parse_query("a OR"));
+        check!(err.expected().contains(&"a tag".to_string()));
+        check!(err.message().starts_with("expected "));
+    }
+}
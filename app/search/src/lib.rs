@@ -1,3 +1,7 @@
+mod compare;
+mod error;
 mod query;
 
+pub use compare::*;
+pub use error::*;
 pub use query::*;
@@ -1,8 +1,13 @@
-use std::{fmt::Display, str::FromStr};
+use std::{fmt::Display, ops::Range, str::FromStr};
 
-use pest::{Parser, iterators::Pairs};
+use pest::{
+    Parser,
+    iterators::{Pair, Pairs},
+};
 use pest_derive::Parser;
 
+use crate::{Cmp, Operand, QueryParseError};
+
 #[derive(Parser)]
 #[grammar = "querylang.pest"]
 struct QueryParser;
@@ -13,8 +18,14 @@ pub fn parse_query(q: impl AsRef<str>) -> Result<Filter, QueryParseError> {
         return Ok(Filter::All);
     }
 
-    let mut query = QueryParser::parse(Rule::query, q).map_err(Box::new)?;
+    let mut query =
+        QueryParser::parse(Rule::query, q).map_err(|err| QueryParseError::new(q, err))?;
     let query = query.next().unwrap();
+
+    if let Some((span, cmp)) = invalid_content_term(query.clone()) {
+        return Err(QueryParseError::invalid_content_term(q, span, cmp));
+    }
+
     match query.as_rule() {
         Rule::all => Ok(Filter::All),
         Rule::filter => Ok(parse_filter(query.into_inner())),
@@ -26,10 +37,35 @@ pub fn parse_query(q: impl AsRef<str>) -> Result<Filter, QueryParseError> {
     }
 }
 
-#[derive(Debug, thiserror::Error)]
-pub enum QueryParseError {
-    #[error("query parse failed: {0}")]
-    Parse(#[from] Box<pest::error::Error<Rule>>),
+/// Finds a content term that cannot be searched for, because it has wildcards or compares content like a tag.
+/// Returns its span, and whether it is a comparison
+fn invalid_content_term(query: Pair<Rule>) -> Option<(Range<usize>, bool)> {
+    query
+        .into_inner()
+        .flatten()
+        .filter(|pair| pair.as_rule() == Rule::tag)
+        .find_map(|tag| {
+            let mut parts = tag
+                .clone()
+                .into_inner()
+                .skip_while(|p| p.as_rule() == Rule::not);
+            let is_content = parts.next().is_some_and(|part| match part.as_rule() {
+                Rule::quoted_value => true,
+                Rule::key => part.as_str() == CONTENT,
+                _ => false,
+            });
+            if !is_content {
+                return None;
+            }
+
+            let span = tag.as_span().start()..tag.as_span().end();
+            let mut rest = tag.into_inner().flatten();
+            if rest.clone().any(|p| p.as_rule() == Rule::cmp) {
+                return Some((span, true));
+            }
+            rest.any(|p| p.as_rule() == Rule::r#match)
+                .then_some((span, false))
+        })
 }
 
 fn parse_filter(mut pairs: Pairs<Rule>) -> Filter {
@@ -77,11 +113,25 @@ fn parse_filter(mut pairs: Pairs<Rule>) -> Filter {
     }
 }
 
+fn parse_negation(mut pairs: Pairs<Rule>) -> Filter {
+    let pair = pairs.next().unwrap();
+
+    match pair.as_rule() {
+        Rule::not => Filter::not(parse_filter(pairs.next().unwrap().into_inner())),
+        unexpected => unreachable!(
+            "encountered unexpected rule {:?}({}) while parsing negation",
+            unexpected,
+            pair.as_str()
+        ),
+    }
+}
+
 fn parse_tag(mut pairs: Pairs<Rule>) -> Filter {
     let pair = pairs.next().unwrap();
 
-    let key = match pair.as_rule() {
+    let key: String = match pair.as_rule() {
         Rule::not => return Filter::not(parse_tag(pairs)),
+        Rule::quoted_value => return Filter::Content(phrase(parse_values(pair))),
         Rule::key => pair.as_str().into(),
         unexpected => unreachable!(
             "encountered unexpected rule {:?}({}) while parsing tag",
@@ -90,35 +140,89 @@ fn parse_tag(mut pairs: Pairs<Rule>) -> Filter {
         ),
     };
 
+    match pairs.peek().map(|pair| pair.as_rule()) {
+        Some(Rule::cmp) => {
+            let cmp = Cmp::parse(pairs.next().unwrap().as_str());
+            let operand = Operand::parse(pairs.next().unwrap().as_str());
+            return Filter::Compare { key, cmp, operand };
+        }
+        // words of content are never ranges
+        Some(Rule::range) if key == CONTENT => {
+            return Filter::Content(pairs.next().unwrap().as_str().into());
+        }
+        Some(Rule::range) => return parse_range(key, pairs.next().unwrap()),
+        _ => {}
+    }
+
     let values = if pairs
         .peek()
         .is_some_and(|p| matches!(p.as_rule(), Rule::value | Rule::quoted_value))
     {
-        let value = pairs.next().unwrap();
-
-        value
-            .into_inner()
-            .map(|pair| match pair.as_rule() {
-                Rule::text | Rule::r#match => pair.as_str().into(),
-                unexpected => unreachable!(
-                    "encountered unexpected rule {:?}({}) while parsing tag value",
-                    unexpected,
-                    pair.as_str()
-                ),
-            })
-            .collect()
+        parse_values(pairs.next().unwrap())
     } else {
         Values::default()
     };
 
+    if key == CONTENT && !values.is_empty() {
+        return Filter::Content(phrase(values));
+    }
+
     Filter::Tag { key, values }
 }
 
+fn parse_values(value: Pair<Rule>) -> Values {
+    value
+        .into_inner()
+        .map(|pair| match pair.as_rule() {
+            Rule::text | Rule::r#match => pair.as_str().into(),
+            unexpected => unreachable!(
+                "encountered unexpected rule {:?}({}) while parsing tag value",
+                unexpected,
+                pair.as_str()
+            ),
+        })
+        .collect()
+}
+
+/// The words to look for in the content of files. Content terms with wildcards are rejected before getting here
+fn phrase(values: Values) -> String {
+    values
+        .into_iter()
+        .filter_map(|value| match value {
+            Value::Text(text) => Some(text),
+            Value::Match => None,
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Only numbers, sizes and dates make ranges, so that values like `name:v1..2` keep matching tags exactly
+fn parse_range(key: String, pair: Pair<Rule>) -> Filter {
+    let value = pair.as_str();
+    let unbounded_start = value.starts_with("..");
+    let mut bounds = pair
+        .into_inner()
+        .map(|bound| Operand::parse(bound.as_str()));
+
+    let from = if unbounded_start { None } else { bounds.next() };
+    let to = bounds.next();
+
+    let is_text = |bound: &Option<Operand>| matches!(bound, Some(Operand::Text(_)));
+    if is_text(&from) || is_text(&to) {
+        return Filter::Tag {
+            key,
+            values: Values(vec![Value::Text(value.into())]),
+        };
+    }
+
+    Filter::Range { key, from, to }
+}
+
 fn parse_tags(mut pairs: Pairs<Rule>) -> Filter {
-    let first = parse_tag(pairs.next().unwrap().into_inner());
+    let first = parse_tag_or_negation(pairs.next().unwrap());
 
     pairs.fold(first, |lhs, pair| {
-        let rhs = parse_tag(pair.into_inner());
+        let rhs = parse_tag_or_negation(pair);
         Filter::Op {
             lhs: Box::new(lhs),
             op: Op::And,
@@ -127,6 +231,18 @@ fn parse_tags(mut pairs: Pairs<Rule>) -> Filter {
     })
 }
 
+fn parse_tag_or_negation(pair: Pair<Rule>) -> Filter {
+    match pair.as_rule() {
+        Rule::tag => parse_tag(pair.into_inner()),
+        Rule::negation => parse_negation(pair.into_inner()),
+        unexpected => unreachable!(
+            "encountered unexpected rule {:?}({}) while parsing tags",
+            unexpected,
+            pair.as_str()
+        ),
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Filter {
     All,
@@ -134,6 +250,18 @@ pub enum Filter {
         key: String,
         values: Values,
     },
+    /// Compares the value of a tag, like `size>10MB`
+    Compare {
+        key: String,
+        cmp: Cmp,
+        operand: Operand,
+    },
+    /// Matches values of a tag within a range including both bounds, like `size:1MB..5MB`
+    Range {
+        key: String,
+        from: Option<Operand>,
+        to: Option<Operand>,
+    },
     Op {
         lhs: Box<Filter>,
         op: Op,
@@ -143,8 +271,14 @@ pub enum Filter {
         modifier: Mod,
         inner: Box<Filter>,
     },
+    /// Matches files whose content contains the words in this order, like `"quarterly report"` or `content:budget`
+    Content(String),
 }
 
+/// The key searching the content of files instead of their tags.
+/// Files cannot have tags with this key, so that it never clashes with them
+pub const CONTENT: &str = "content";
+
 impl FromStr for Filter {
     type Err = QueryParseError;
 
@@ -154,6 +288,48 @@ impl FromStr for Filter {
 }
 
 impl Filter {
+    /// Removes the tags with the given key that apply to the whole query, like `sort:size`,
+    /// so that they can direct how the query runs instead of filtering on them.
+    /// The values of the last one found are returned
+    pub fn take_directive(self, key: &str) -> (Self, Option<Values>) {
+        match self {
+            Self::Tag { key: k, values } if k == key => (Self::All, Some(values)),
+            Self::Op {
+                lhs,
+                op: Op::And,
+                rhs,
+            } => {
+                let (lhs, left) = lhs.take_directive(key);
+                let (rhs, right) = rhs.take_directive(key);
+
+                let filter = match (lhs, rhs) {
+                    (Self::All, filter) | (filter, Self::All) => filter,
+                    (lhs, rhs) => Self::Op {
+                        lhs: Box::new(lhs),
+                        op: Op::And,
+                        rhs: Box::new(rhs),
+                    },
+                };
+
+                (filter, right.or(left))
+            }
+            filter => (filter, None),
+        }
+    }
+
+    /// The phrases the content of matching files contains, leaving out the negated ones
+    pub fn content_phrases(&self) -> Vec<&str> {
+        match self {
+            Self::Content(phrase) => vec![phrase],
+            Self::Op { lhs, rhs, .. } => {
+                let mut phrases = lhs.content_phrases();
+                phrases.extend(rhs.content_phrases());
+                phrases
+            }
+            _ => Vec::new(),
+        }
+    }
+
     fn not(inner: Self) -> Self {
         Self::Mod {
             modifier: Mod::Not,
@@ -185,6 +361,20 @@ impl Display for Filter {
 
                 values.fmt(f)?;
             }
+            Self::Compare { key, cmp, operand } => write!(f, "{key}{cmp}{operand}")?,
+            Self::Range { key, from, to } => {
+                write!(f, "{key}:")?;
+
+                if let Some(from) = from {
+                    from.fmt(f)?;
+                }
+
+                write!(f, "..")?;
+
+                if let Some(to) = to {
+                    to.fmt(f)?;
+                }
+            }
             Self::Op { lhs, op, rhs } => {
                 write!(f, "(")?;
                 lhs.fmt(f)?;
@@ -192,7 +382,18 @@ impl Display for Filter {
                 rhs.fmt(f)?;
                 write!(f, ")")?;
             }
-            Self::Mod { modifier, inner } => write!(f, "{modifier}{inner}")?,
+            // `--a` would be read back as the tag `-a`, so nested negations keep their group
+            Self::Mod { modifier, inner } => match inner.as_ref() {
+                Self::Mod { .. } | Self::Content(_) => write!(f, "{modifier}({inner})")?,
+                _ => write!(f, "{modifier}{inner}")?,
+            },
+            Self::Content(phrase) => {
+                if phrase.contains(' ') {
+                    write!(f, r#""{phrase}""#)?;
+                } else {
+                    write!(f, "{CONTENT}:{phrase}")?;
+                }
+            }
         }
 
         Ok(())
@@ -384,11 +585,161 @@ mod tests {
         Filter::tag("hello", ["midd".into(), Value::Match, "le".into()]),
         "hello:midd*le"
     )]
+    #[case(
+        "created:2025-01-10T09:00:00Z",
+        Filter::tag("created", ["2025-01-10T09:00:00Z"]),
+        "created:2025-01-10T09:00:00Z"
+    )]
+    #[case(
+        "updated:2025-01*",
+        Filter::tag("updated", ["2025-01".into(), Value::Match]),
+        "updated:2025-01*"
+    )]
     #[case(
         "hello:*end",
         Filter::tag("hello", [Value::Match, "end".into()]),
         "hello:*end"
     )]
+    #[case(
+        "size>10MB",
+        Filter::Compare { key: "size".into(), cmp: Cmp::Gt, operand: Operand::Number(10_000_000) },
+        "size>10000000"
+    )]
+    #[case(
+        "-size<=500KiB",
+        Filter::not(Filter::Compare { key: "size".into(), cmp: Cmp::Le, operand: Operand::Number(512_000) }),
+        "-size<=512000"
+    )]
+    #[case(
+        "created>=2025-01-01 ext:pdf",
+        Filter::Op {
+            lhs: Box::new(Filter::Compare {
+                key: "created".into(),
+                cmp: Cmp::Ge,
+                operand: Operand::parse("2025-01-01"),
+            }),
+            op: Op::And,
+            rhs: Box::new(Filter::tag("ext", ["pdf"])),
+        },
+        "(created>=2025-01-01 AND ext:pdf)"
+    )]
+    #[case(
+        "size:1MB..5MB",
+        Filter::Range { key: "size".into(), from: Some(Operand::Number(1_000_000)), to: Some(Operand::Number(5_000_000)) },
+        "size:1000000..5000000"
+    )]
+    #[case(
+        "updated:..2025-01-31",
+        Filter::Range { key: "updated".into(), from: None, to: Some(Operand::parse("2025-01-31")) },
+        "updated:..2025-01-31"
+    )]
+    #[case("name:v1..2", Filter::tag("name", ["v1..2"]), "name:v1..2")]
+    #[case("version:1.0..beta", Filter::tag("version", ["1.0..beta"]), "version:1.0..beta")]
+    #[case("name:..draft", Filter::tag("name", ["..draft"]), "name:..draft")]
+    #[case(
+        "size:1KB..",
+        Filter::Range { key: "size".into(), from: Some(Operand::Number(1_000)), to: None },
+        "size:1000.."
+    )]
+    #[case(
+        "name:report.pdf",
+        Filter::tag("name", ["report.pdf"]),
+        "name:report.pdf"
+    )]
+    #[case(
+        "-(ext:jpg OR ext:png)",
+        Filter::not(Filter::Op {
+            lhs: Box::new(Filter::tag("ext", Some("jpg"))),
+            op: Op::Or,
+            rhs: Box::new(Filter::tag("ext", Some("png"))),
+        }),
+        "-(ext:jpg OR ext:png)"
+    )]
+    #[case(
+        "dir:/docs AND -(name:a* OR -(size>1KB))",
+        Filter::Op {
+            lhs: Box::new(Filter::tag("dir", Some("/docs"))),
+            op: Op::And,
+            rhs: Box::new(Filter::not(Filter::Op {
+                lhs: Box::new(Filter::tag("name", ["a".into(), Value::Match])),
+                op: Op::Or,
+                rhs: Box::new(Filter::not(Filter::Compare {
+                    key: "size".into(),
+                    cmp: Cmp::Gt,
+                    operand: Operand::Number(1_000),
+                })),
+            })),
+        },
+        "(dir:/docs AND -(name:a* OR -size>1000))"
+    )]
+    #[case(
+        "-(file1) OR -(a b)",
+        Filter::Op {
+            lhs: Box::new(Filter::not(Filter::tag("file1", None::<String>))),
+            op: Op::Or,
+            rhs: Box::new(Filter::not(Filter::Op {
+                lhs: Box::new(Filter::tag("a", None::<String>)),
+                op: Op::And,
+                rhs: Box::new(Filter::tag("b", None::<String>)),
+            })),
+        },
+        "(-file1 OR -(a AND b))"
+    )]
+    #[case(
+        r#""quarterly report""#,
+        Filter::Content("quarterly report".into()),
+        r#""quarterly report""#
+    )]
+    #[case(
+        "content:budget ext:pdf",
+        Filter::Op {
+            lhs: Box::new(Filter::Content("budget".into())),
+            op: Op::And,
+            rhs: Box::new(Filter::tag("ext", Some("pdf"))),
+        },
+        "(content:budget AND ext:pdf)"
+    )]
+    #[case(
+        r#"-"draft" OR content:"q3 budget""#,
+        Filter::Op {
+            lhs: Box::new(Filter::not(Filter::Content("draft".into()))),
+            op: Op::Or,
+            rhs: Box::new(Filter::Content("q3 budget".into())),
+        },
+        r#"(-(content:draft) OR "q3 budget")"#
+    )]
+    #[case("content", Filter::tag("content", None::<String>), "content")]
+    #[case(
+        "-(a) b",
+        Filter::Op {
+            lhs: Box::new(Filter::not(Filter::tag("a", None::<String>))),
+            op: Op::And,
+            rhs: Box::new(Filter::tag("b", None::<String>)),
+        },
+        "(-a AND b)"
+    )]
+    #[case(
+        "a -(b OR c) d",
+        Filter::Op {
+            lhs: Box::new(Filter::Op {
+                lhs: Box::new(Filter::tag("a", None::<String>)),
+                op: Op::And,
+                rhs: Box::new(Filter::not(Filter::Op {
+                    lhs: Box::new(Filter::tag("b", None::<String>)),
+                    op: Op::Or,
+                    rhs: Box::new(Filter::tag("c", None::<String>)),
+                })),
+            }),
+            op: Op::And,
+            rhs: Box::new(Filter::tag("d", None::<String>)),
+        },
+        "((a AND -(b OR c)) AND d)"
+    )]
+    #[case(
+        "-(-(file2))",
+        Filter::not(Filter::not(Filter::tag("file2", None::<String>))),
+        "-(-file2)"
+    )]
     fn it_parses_some_queries(
         #[case] q: &str,
         #[case] expected: Filter,
@@ -398,4 +749,62 @@ mod tests {
         check!(parsed == expected);
         check!(parsed.to_string() == to_string);
     }
+
+    #[rstest]
+    #[case("-(ext:jpg OR ext:png)")]
+    #[case("dir:/docs AND -(name:a* OR -(size>1KB))")]
+    #[case("-(a b) OR -(-(c) AND updated:2025-01-01..)")]
+    #[case("-(-(x))")]
+    #[case("-(-(-(x)))")]
+    #[case("-(a OR b)")]
+    #[case("name:*.txt AND -(-(file2))")]
+    #[case(r#"-("quarterly report") -(content:budget)"#)]
+    #[case("content:budget")]
+    #[case(r#""quarterly report" OR content:v1..2"#)]
+    fn it_round_trips_negated_groups(#[case] q: &str) {
+        let parsed = parse_query(q).unwrap();
+        check!(parse_query(parsed.to_string()).unwrap() == parsed);
+    }
+
+    #[rstest]
+    #[case("content:*", 0..9, false)]
+    #[case("content:foo*", 0..12, false)]
+    #[case(r#"ext:pdf "foo* bar""#, 8..18, false)]
+    #[case(r#"-content:"a * b""#, 0..16, false)]
+    #[case("a OR content>5", 5..14, true)]
+    fn it_rejects_invalid_content_terms(
+        #[case] q: &str,
+        #[case] span: Range<usize>,
+        #[case] cmp: bool,
+    ) {
+        let err = parse_query(q).unwrap_err();
+        check!(err.span() == span);
+        check!(err.message().contains("compared") == cmp);
+        check!(err.hint().is_some());
+    }
+
+    #[rstest]
+    #[case("sort:size", "*", Some("size"))]
+    #[case("ext:pdf sort:updated:desc", "ext:pdf", Some("updated:desc"))]
+    #[case("sort:name a AND b", "(a AND b)", Some("name"))]
+    #[case("sort:name a sort:size", "a", Some("size"))]
+    #[case("a OR sort:size", "(a OR sort:size)", None)]
+    #[case("-sort:size", "-sort:size", None)]
+    #[case("ext:pdf", "ext:pdf", None)]
+    fn it_takes_directives_out_of_the_query(
+        #[case] q: &str,
+        #[case] filter: &str,
+        #[case] directive: Option<&str>,
+    ) {
+        let (taken, values) = parse_query(q).unwrap().take_directive("sort");
+        check!(taken.to_string() == filter);
+        check!(values.map(|values| values.to_string()).as_deref() == directive);
+    }
+
+    #[test]
+    fn it_lists_the_content_phrases_to_look_for() {
+        let filter =
+            parse_query(r#""annual report" AND (content:budget OR -content:draft)"#).unwrap();
+        check!(filter.content_phrases() == ["annual report", "budget"]);
+    }
 }
@@ -1,15 +1,20 @@
 import { addToast, reportError } from "$lib/components/Toast.svelte";
 import type { SvelteFluent } from "@nubolab-ffwd/svelte-fluent";
-import type { SchemaApiError } from "./openapi";
+import type { SchemaApiError, SchemaUploadResult } from "./openapi";
 
-export async function uploadFile(
-	file: File,
+export async function uploadFiles(
+	files: File[],
 	localize: SvelteFluent["localize"],
 ): Promise<boolean> {
+	if (files.length === 0) {
+		return true;
+	}
+
 	addToast({
 		data: {
 			title: localize("files-upload-started", {
-				file: file.name,
+				file: files[0].name,
+				count: files.length,
 			}),
 			message: localize("files-upload-started.message"),
 			level: "info",
@@ -17,10 +22,12 @@ export async function uploadFile(
 	});
 	const form = new FormData();
 
-	if (file.type === "application/json") {
-		form.append("file", await escapeJSONFile(file));
-	} else {
-		form.append("file", file);
+	for (const file of files) {
+		if (file.type === "application/json") {
+			form.append("file", await escapeJSONFile(file));
+		} else {
+			form.append("file", file);
+		}
 	}
 
 	// can't use openapi-fetch here because it doesn't handle
@@ -33,29 +40,48 @@ export async function uploadFile(
 	if (!response.ok) {
 		try {
 			const error: SchemaApiError = await response.json();
-			reportError(error, localize("files-upload-failed", { file: file.name }));
+			reportError(
+				error,
+				localize("files-upload-failed", { file: files[0].name }),
+			);
 		} catch (e) {
 			const err = e as Error;
 
 			reportError(
 				{ error: "JSON_RESPONSE_ERROR", message: err.message },
-				localize("files-upload-failed", { file: file.name }),
+				localize("files-upload-failed", { file: files[0].name }),
 			);
 		}
 		return false;
 	}
 
-	addToast({
-		data: {
-			title: localize("files-upload-succeeded", {
-				file: file.name,
-			}),
-			message: localize("files-upload-succeeded.message"),
-			level: "info",
-		},
-	});
+	const { files: results }: { files: SchemaUploadResult[] } =
+		await response.json();
+
+	const failed = results.filter((result) => result.error);
+	for (const { name, error } of failed) {
+		if (error) {
+			reportError(error, localize("files-upload-failed", { file: name }));
+		}
+	}
+
+	const uploaded = results.length - failed.length;
+	if (uploaded > 0) {
+		addToast({
+			data: {
+				title: localize("files-upload-succeeded", {
+					file: results.find((result) => !result.error)?.name ?? "",
+					count: uploaded,
+				}),
+				message: localize("files-upload-succeeded.message", {
+					count: uploaded,
+				}),
+				level: "info",
+			},
+		});
+	}
 
-	return true;
+	return failed.length === 0;
 }
 
 async function escapeJSONFile(file: globalThis.File): Promise<globalThis.File> {
@@ -10,11 +10,20 @@ files-empty = No files in here
 
 files-upload-cta = Upload
 
-files-upload-started = Uploading { $file }
+files-upload-started = Uploading { $count ->
+        [one] { $file }
+       *[other] { $count } files
+    }
     .message = This could take a few minutes
 
-files-upload-succeeded = { $file } uploaded
-    .message = Your new file is now available
+files-upload-succeeded = { $count ->
+        [one] { $file } uploaded
+       *[other] { $count } files uploaded
+    }
+    .message = { $count ->
+        [one] Your new file is now available
+       *[other] Your new files are now available
+    }
 
 files-upload-failed = { $file } failed to upload
 
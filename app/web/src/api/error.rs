@@ -6,6 +6,7 @@ use std::{
 
 use axum::{Json, http::StatusCode, response::IntoResponse};
 use oxidrive_authorization::Authorized;
+use oxidrive_search::QueryParseError;
 use serde::Serialize;
 use utoipa::{ToResponse, ToSchema, openapi::Content};
 
@@ -29,6 +30,53 @@ pub struct ApiErrorBody {
     pub details: HashMap<String, serde_json::Value>,
 }
 
+/// Why a search query could not be parsed, with enough detail to point at the error
+#[derive(Debug, Serialize, ToSchema)]
+pub struct QueryErrorBody {
+    pub message: String,
+    /// Byte offset in the query where the error starts
+    pub start: usize,
+    /// Byte offset in the query where the error ends, equal to `start` if the query ended too early
+    pub end: usize,
+    /// What would have been valid at that position
+    pub expected: Vec<String>,
+    /// A suggestion on how to fix the query
+    pub hint: Option<String>,
+}
+
+/// The body of the error returned for a search query that could not be parsed, like [ApiErrorBody]
+/// with the details of the error typed
+#[derive(Debug, Serialize, ToSchema, ToResponse)]
+#[schema(as = InvalidQueryError)]
+#[response(
+    description = "The search query could not be parsed",
+    content_type = "application/json"
+)]
+pub struct InvalidQueryBody {
+    /// Always `INVALID_QUERY`
+    pub error: String,
+    pub message: String,
+    pub details: InvalidQueryDetails,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct InvalidQueryDetails {
+    pub query: QueryErrorBody,
+}
+
+impl From<&QueryParseError> for QueryErrorBody {
+    fn from(err: &QueryParseError) -> Self {
+        let span = err.span();
+        Self {
+            message: err.message().into(),
+            start: span.start,
+            end: span.end,
+            expected: err.expected().to_vec(),
+            hint: err.hint().map(Into::into),
+        }
+    }
+}
+
 impl ApiError {
     pub fn new<D: Display + Debug>(err: D) -> Self {
         Self {
@@ -154,6 +202,17 @@ impl From<Authorized> for ApiError {
     }
 }
 
+impl From<QueryParseError> for ApiError {
+    fn from(err: QueryParseError) -> Self {
+        let query = serde_json::to_value(QueryErrorBody::from(&err)).unwrap_or_default();
+
+        Self::new(err)
+            .status(StatusCode::BAD_REQUEST)
+            .error("INVALID_QUERY")
+            .detail("query", query)
+    }
+}
+
 pub fn handle_panic(err: Box<dyn Any + Send + 'static>) -> axum::response::Response {
     let details = if let Some(s) = err.downcast_ref::<String>() {
         s.clone()
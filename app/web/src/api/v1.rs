@@ -1,24 +1,36 @@
 use accounts::AccountsApi;
+use admin::AdminApi;
 use collections::CollectionsApi;
 use files::FilesApi;
+use folders::FoldersApi;
 use pats::PatsApi;
+use search::SearchApi;
+use trash::TrashApi;
 use utoipa::OpenApi;
 use utoipa_axum::router::OpenApiRouter;
 
 use crate::state::AppState;
 
 mod accounts;
+mod admin;
 mod collections;
 mod files;
+mod folders;
 mod pats;
+mod search;
+mod trash;
 
 #[derive(OpenApi)]
 #[openapi(
     nest(
         (path = "accounts", api = AccountsApi, tags = ["accounts"]),
+        (path = "admin", api = AdminApi, tags = ["admin"]),
         (path = "collections", api = CollectionsApi, tags = ["collections"]),
         (path = "files", api = FilesApi, tags = ["files"]),
+        (path = "folders", api = FoldersApi, tags = ["folders"]),
         (path = "pats", api = PatsApi, tags = ["pats"]),
+        (path = "search", api = SearchApi, tags = ["search"]),
+        (path = "trash", api = TrashApi, tags = ["trash"]),
     ),
 )]
 pub struct V1Api;
@@ -26,7 +38,11 @@ pub struct V1Api;
 pub fn routes() -> OpenApiRouter<AppState> {
     OpenApiRouter::new()
         .nest("/accounts", accounts::routes())
+        .nest("/admin", admin::routes())
         .nest("/collections", collections::routes())
         .nest("/files", files::routes())
+        .nest("/folders", folders::routes())
         .nest("/pats", pats::routes())
+        .nest("/search", search::routes())
+        .nest("/trash", trash::routes())
 }
@@ -5,9 +5,11 @@ use crate::state::AppState;
 mod password;
 
 mod get;
+mod usage;
 
 pub fn routes() -> OpenApiRouter<AppState> {
     OpenApiRouter::new()
         .routes(routes!(get::handler))
+        .routes(routes!(usage::handler))
         .nest("/password", password::routes())
 }
@@ -0,0 +1,47 @@
+use axum::{Json, extract::State};
+use oxidrive_files::{Files, quota::UsageByAccountError};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::{
+    api::error::{ApiError, ApiResult},
+    session::CurrentUser,
+};
+
+#[utoipa::path(get,
+    path = "/usage",
+    operation_id = "usage",
+    responses((status = OK, body = UsageInfo)),
+    tag = "accounts",
+)]
+#[axum::debug_handler(state = crate::state::AppState)]
+pub async fn handler(
+    State(files): State<Files>,
+    CurrentUser(account): CurrentUser,
+) -> ApiResult<Json<UsageInfo>> {
+    let usage = files.usage(account.id).await?;
+
+    Ok(Json(UsageInfo {
+        used: usage.used,
+        quota: usage.quota,
+        remaining: usage.remaining(),
+    }))
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UsageInfo {
+    /// How many bytes the account stores, including previous versions and trashed files
+    used: u64,
+    /// The most bytes the account can store. Absent if unlimited
+    #[serde(skip_serializing_if = "Option::is_none")]
+    quota: Option<u64>,
+    /// How many more bytes the account can store. Absent if unlimited
+    #[serde(skip_serializing_if = "Option::is_none")]
+    remaining: Option<u64>,
+}
+
+impl From<UsageByAccountError> for ApiError {
+    fn from(err: UsageByAccountError) -> Self {
+        Self::new(err)
+    }
+}
@@ -0,0 +1,15 @@
+use integrity_checks::IntegrityChecksApi;
+use utoipa::OpenApi;
+use utoipa_axum::router::OpenApiRouter;
+
+use crate::state::AppState;
+
+mod integrity_checks;
+
+#[derive(OpenApi)]
+#[openapi(nest((path = "integrity-checks", api = IntegrityChecksApi)))]
+pub struct AdminApi;
+
+pub fn routes() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new().nest("/integrity-checks", integrity_checks::routes())
+}
@@ -0,0 +1,96 @@
+use oxidrive_files::integrity::{IntegrityCheck, IntegrityIssue};
+use serde::Serialize;
+use time::OffsetDateTime;
+use utoipa::{OpenApi, ToSchema};
+use utoipa_axum::{router::OpenApiRouter, routes};
+use uuid::Uuid;
+
+use crate::state::AppState;
+
+mod get;
+mod list;
+
+#[derive(OpenApi)]
+pub struct IntegrityChecksApi;
+
+pub fn routes() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new()
+        .routes(routes!(list::handler))
+        .routes(routes!(get::handler))
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub(super) struct IntegrityCheckData {
+    id: Uuid,
+    #[serde(with = "time::serde::rfc3339")]
+    started_at: OffsetDateTime,
+    /// When all files were checked. Absent while the check is running, or if it was interrupted
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        with = "time::serde::rfc3339::option"
+    )]
+    finished_at: Option<OffsetDateTime>,
+    checked: usize,
+    /// How many files without a hash were given the one of their current content
+    backfilled: usize,
+    issues: Vec<IntegrityIssueData>,
+}
+
+impl From<IntegrityCheck> for IntegrityCheckData {
+    fn from(check: IntegrityCheck) -> Self {
+        Self {
+            id: check.id.as_uuid(),
+            started_at: check.started_at,
+            finished_at: check.finished_at,
+            checked: check.checked,
+            backfilled: check.backfilled,
+            issues: check.issues.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct IntegrityIssueData {
+    file_id: Uuid,
+    owner_id: Uuid,
+    kind: IntegrityIssueKind,
+    /// The digest recorded on the file, for mismatched content
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expected: Option<String>,
+    /// The digest of the stored content, for mismatched content
+    #[serde(skip_serializing_if = "Option::is_none")]
+    actual: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+enum IntegrityIssueKind {
+    /// The content of the file can't be found
+    Missing,
+    /// The content of the file does not match its hash
+    Mismatched,
+    /// The file has no hash to compare its content against
+    Unhashed,
+}
+
+impl From<IntegrityIssue> for IntegrityIssueData {
+    fn from(issue: IntegrityIssue) -> Self {
+        use oxidrive_files::integrity::IntegrityIssueKind as Kind;
+
+        let (kind, expected, actual) = match issue.kind {
+            Kind::Missing => (IntegrityIssueKind::Missing, None, None),
+            Kind::Mismatched { expected, actual } => {
+                (IntegrityIssueKind::Mismatched, Some(expected), Some(actual))
+            }
+            Kind::Unhashed => (IntegrityIssueKind::Unhashed, None, None),
+        };
+
+        Self {
+            file_id: issue.file_id.as_uuid(),
+            owner_id: issue.owner_id.as_uuid(),
+            kind,
+            expected,
+            actual,
+        }
+    }
+}
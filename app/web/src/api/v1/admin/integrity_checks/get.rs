@@ -0,0 +1,42 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+};
+use oxidrive_files::integrity::{Integrity, IntegrityCheckByIdError, IntegrityCheckId};
+
+use crate::{
+    api::error::{ApiError, ApiResult},
+    session::Admin,
+};
+
+use super::IntegrityCheckData;
+
+#[utoipa::path(
+    get,
+    path = "/{check_id}",
+    operation_id = "getIntegrityCheck",
+    params(("check_id" = String, Path, format = "uuid")),
+    responses(
+        (status = OK, body = IntegrityCheckData),
+        (status = FORBIDDEN, description = "The current user is not an admin"),
+    ),
+    tag = "admin",
+)]
+#[axum::debug_handler(state = crate::state::AppState)]
+pub async fn handler(
+    State(integrity): State<Integrity>,
+    _: Admin,
+    Path(check_id): Path<IntegrityCheckId>,
+) -> ApiResult<Json<IntegrityCheckData>> {
+    let Some(check) = integrity.by_id(check_id).await? else {
+        return Err(ApiError::not_found());
+    };
+
+    Ok(Json(check.into()))
+}
+
+impl From<IntegrityCheckByIdError> for ApiError {
+    fn from(err: IntegrityCheckByIdError) -> Self {
+        Self::new(err)
+    }
+}
@@ -0,0 +1,54 @@
+use axum::{
+    Json,
+    extract::{Query, State},
+};
+use oxidrive_files::integrity::{Integrity, RecentIntegrityChecksError};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use crate::{
+    api::error::{ApiError, ApiResult},
+    session::Admin,
+};
+
+use super::IntegrityCheckData;
+
+const DEFAULT_LIMIT: usize = 20;
+const MAX_LIMIT: usize = 100;
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ListQuery {
+    /// How many checks to return, the most recent first
+    #[param(maximum = 100)]
+    limit: Option<usize>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/",
+    operation_id = "listIntegrityChecks",
+    params(ListQuery),
+    responses(
+        (status = OK, body = Vec<IntegrityCheckData>),
+        (status = FORBIDDEN, description = "The current user is not an admin"),
+    ),
+    tag = "admin",
+)]
+#[axum::debug_handler(state = crate::state::AppState)]
+pub async fn handler(
+    State(integrity): State<Integrity>,
+    _: Admin,
+    Query(params): Query<ListQuery>,
+) -> ApiResult<Json<Vec<IntegrityCheckData>>> {
+    let limit = params.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+
+    let checks = integrity.recent(limit).await?;
+
+    Ok(Json(checks.into_iter().map(Into::into).collect()))
+}
+
+impl From<RecentIntegrityChecksError> for ApiError {
+    fn from(err: RecentIntegrityChecksError) -> Self {
+        Self::new(err)
+    }
+}
@@ -49,9 +49,7 @@ impl IntoResponse for CollectionCreated {
 impl From<CreateCollectionError> for ApiError {
     fn from(err: CreateCollectionError) -> Self {
         match err {
-            CreateCollectionError::FilterParse(err) => Self::new(err)
-                .status(StatusCode::BAD_REQUEST)
-                .error("INVALID_QUERY"),
+            CreateCollectionError::FilterParse(err) => err.into(),
             CreateCollectionError::SaveFailed(err) => Self::new(err),
         }
     }
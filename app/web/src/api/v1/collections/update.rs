@@ -1,7 +1,6 @@
 use axum::{
     Json,
     extract::{Path, State},
-    http::StatusCode,
     response::IntoResponse,
 };
 use oxidrive_accounts::auth::AccountEntity;
@@ -74,9 +73,7 @@ impl IntoResponse for CollectionUpdated {
 impl From<UpdateCollectionError> for ApiError {
     fn from(err: UpdateCollectionError) -> Self {
         match err {
-            UpdateCollectionError::FilterParse(err) => Self::new(err)
-                .status(StatusCode::BAD_REQUEST)
-                .error("INVALID_QUERY"),
+            UpdateCollectionError::FilterParse(err) => err.into(),
             UpdateCollectionError::SaveFailed(err) => Self::new(err),
         }
     }
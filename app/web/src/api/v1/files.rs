@@ -1,34 +1,80 @@
+use axum::http::StatusCode;
+use copy::FileCopied;
+use create::FileCreated;
 use delete::FileDeleted;
-use oxidrive_files::File;
+use move_file::FileMoved;
+use oxidrive_files::{ConflictError, File};
 use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
 use update::FileUpdated;
 use utoipa::{OpenApi, ToSchema};
 use utoipa_axum::{router::OpenApiRouter, routes};
+use versions::restore::VersionRestored;
 
-use crate::state::AppState;
+use crate::{api::error::ApiError, state::AppState};
 
+mod copy;
+mod create;
 mod delete;
 mod get;
 mod list;
+mod move_file;
 mod update;
+mod versions;
 
 #[derive(OpenApi)]
-#[openapi(components(responses(FileUpdated, FileDeleted)))]
+#[openapi(components(responses(
+    FileCreated,
+    FileUpdated,
+    FileDeleted,
+    FileCopied,
+    FileMoved,
+    VersionRestored
+)))]
 pub struct FilesApi;
 
 pub fn routes() -> OpenApiRouter<AppState> {
     OpenApiRouter::new()
-        .routes(routes!(list::handler))
+        .routes(routes!(create::handler, list::handler))
         .routes(routes!(get::handler, update::handler, delete::handler))
+        .routes(routes!(copy::handler))
+        .routes(routes!(move_file::handler))
+        .routes(routes!(versions::list::handler))
+        .routes(routes!(versions::restore::handler))
 }
 
 #[derive(Debug, Serialize, ToSchema)]
-struct FileData {
+pub(super) struct FileData {
     id: String,
     name: String,
+    /// The folder the file lives in, e.g. `/photos/2025`
+    folder: String,
+    /// The full path of the file, folder included
+    path: String,
     content_type: String,
     size: usize,
+    /// The hex encoded blake3 hash of the content, once it has been computed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hash: Option<String>,
     tags: Vec<Tag>,
+    /// When the file was first uploaded or created
+    #[serde(with = "time::serde::rfc3339")]
+    created_at: OffsetDateTime,
+    /// When the name, folder, tags or content of the file last changed
+    #[serde(with = "time::serde::rfc3339")]
+    updated_at: OffsetDateTime,
+    /// When the content of the file was last downloaded. Only present if it ever was
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        with = "time::serde::rfc3339::option"
+    )]
+    accessed_at: Option<OffsetDateTime>,
+    /// When the file was moved to the trash. Only present for trashed files
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        with = "time::serde::rfc3339::option"
+    )]
+    deleted_at: Option<OffsetDateTime>,
 }
 
 impl From<File> for FileData {
@@ -37,6 +83,13 @@ impl From<File> for FileData {
         tags.sort();
         Self {
             id: file.id.to_string(),
+            created_at: file.created_at(),
+            updated_at: file.updated_at(),
+            accessed_at: file.accessed_at(),
+            deleted_at: file.deleted_at(),
+            hash: file.hash().map(|hash| hash.to_string()),
+            path: file.path(),
+            folder: file.folder.into(),
             name: file.name,
             content_type: file.content_type,
             size: file.size,
@@ -69,3 +122,14 @@ impl From<Tag> for oxidrive_files::Tag {
         }
     }
 }
+
+impl From<ConflictError> for ApiError {
+    fn from(err: ConflictError) -> Self {
+        match err {
+            ConflictError::Taken(_) => Self::new(err)
+                .status(StatusCode::CONFLICT)
+                .error("FILE_NAME_TAKEN"),
+            ConflictError::LoadFailed(err) => Self::new(err),
+        }
+    }
+}
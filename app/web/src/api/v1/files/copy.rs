@@ -0,0 +1,89 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use oxidrive_accounts::auth::AccountEntity;
+use oxidrive_authorization::Authorizer;
+use oxidrive_files::{CopyError, Files, auth::FileEntity, file::FileId};
+use serde::Deserialize;
+use utoipa::{ToResponse, ToSchema};
+
+use crate::{
+    api::error::{ApiError, ApiResult, ApiResultExt},
+    files::OnConflict,
+    session::CurrentUser,
+};
+
+use super::FileData;
+
+#[utoipa::path(
+    post,
+    path = "/{file_id}/copy",
+    operation_id = "copy",
+    params(("file_id" = String, Path, format = "uuid")),
+    responses(
+        (status = CREATED, response = FileCopied),
+        (status = CONFLICT, description = "A file already exists at the destination path"),
+    ),
+    tag = "files",
+)]
+#[axum::debug_handler(state = crate::state::AppState)]
+pub async fn handler(
+    State(authorizer): State<Authorizer>,
+    State(files): State<Files>,
+    CurrentUser(account): CurrentUser,
+    Path(file_id): Path<FileId>,
+    Json(CopyFile { path, on_conflict }): Json<CopyFile>,
+) -> ApiResult<FileCopied> {
+    let Some(file) = files
+        .metadata()
+        .by_id(file_id)
+        .await?
+        .filter(|file| !file.is_trashed())
+    else {
+        return Err(ApiError::not_found());
+    };
+
+    authorizer
+        .authorize(
+            &AccountEntity::from(&account),
+            "download",
+            &FileEntity::from(&file),
+        )
+        .into_err::<ApiError>()
+        .hide_403_as_404()?;
+
+    let copy = files.copy(&file, &path, on_conflict.into()).await?;
+
+    Ok(FileCopied(copy.into()))
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CopyFile {
+    /// The full path of the copy, e.g. `photos/beach (copy).jpg`. Missing folders are created
+    path: String,
+    #[serde(default)]
+    on_conflict: OnConflict,
+}
+
+#[derive(ToResponse)]
+pub struct FileCopied(FileData);
+
+impl IntoResponse for FileCopied {
+    fn into_response(self) -> axum::response::Response {
+        (StatusCode::CREATED, Json(self.0)).into_response()
+    }
+}
+
+impl From<CopyError> for ApiError {
+    fn from(err: CopyError) -> Self {
+        match err {
+            CopyError::InvalidPath(err) => err.into(),
+            CopyError::Conflict(err) => err.into(),
+            CopyError::QuotaFailed(err) => err.into(),
+            _ => Self::new(err),
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use oxidrive_files::Files;
+use serde::Deserialize;
+use utoipa::{ToResponse, ToSchema};
+
+use crate::{
+    api::error::{ApiError, ApiResult},
+    session::CurrentUser,
+};
+
+use super::FileData;
+
+/// Creates a file, or overwrites the one with the same name, with content already stored for another file
+/// of the account. Clients can announce the hash of a file before uploading it, and skip the upload
+/// if the server already has its content
+#[utoipa::path(
+    post,
+    path = "/",
+    operation_id = "create",
+    responses(
+        (status = CREATED, response = FileCreated),
+        (status = NOT_FOUND, description = "None of the files of the account has content with the given hash, so it must be uploaded"),
+        (status = PAYLOAD_TOO_LARGE, description = "The file is larger than the whole storage quota of the account"),
+        (status = INSUFFICIENT_STORAGE, description = "There is not enough space left in the storage quota of the account"),
+    ),
+    tag = "files",
+)]
+#[axum::debug_handler(state = crate::state::AppState)]
+pub async fn handler(
+    State(files): State<Files>,
+    CurrentUser(account): CurrentUser,
+    Json(CreateFile { name, hash }): Json<CreateFile>,
+) -> ApiResult<FileCreated> {
+    let hash = blake3::Hash::from_hex(&hash).map_err(|err| {
+        ApiError::new(err)
+            .status(StatusCode::BAD_REQUEST)
+            .error("INVALID_HASH")
+    })?;
+
+    let Some(file) = files.upload_existing(account.id, name, hash).await? else {
+        return Err(ApiError::new("no content with the given hash was found")
+            .status(StatusCode::NOT_FOUND)
+            .error("CONTENT_NOT_FOUND"));
+    };
+
+    Ok(FileCreated(file.into()))
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateFile {
+    name: String,
+    /// The hex encoded blake3 hash of the content
+    hash: String,
+}
+
+#[derive(Debug, ToResponse)]
+#[response(content_type = "application/json")]
+pub struct FileCreated(FileData);
+
+impl IntoResponse for FileCreated {
+    fn into_response(self) -> axum::response::Response {
+        (StatusCode::CREATED, Json(self.0)).into_response()
+    }
+}
@@ -5,7 +5,7 @@ use axum::{
 };
 use oxidrive_accounts::auth::AccountEntity;
 use oxidrive_authorization::Authorizer;
-use oxidrive_files::{FileId, Files, auth::FileEntity, file::DeleteFileError};
+use oxidrive_files::{DeleteError, FileId, Files, auth::FileEntity};
 use utoipa::ToResponse;
 
 use crate::{
@@ -30,7 +30,12 @@ pub async fn handler(
     CurrentUser(account): CurrentUser,
     Path(file_id): Path<FileId>,
 ) -> ApiResult<FileDeleted> {
-    let Some(file) = files.metadata().by_id(file_id).await? else {
+    let Some(file) = files
+        .metadata()
+        .by_id(file_id)
+        .await?
+        .filter(|file| !file.is_trashed())
+    else {
         return Err(ApiError::not_found());
     };
 
@@ -57,8 +62,10 @@ impl IntoResponse for FileDeleted {
     }
 }
 
-impl From<DeleteFileError> for ApiError {
-    fn from(err: DeleteFileError) -> Self {
-        Self::new(err)
+impl From<DeleteError> for ApiError {
+    fn from(err: DeleteError) -> Self {
+        match err {
+            DeleteError::TrashFailed(err) => Self::new(err),
+        }
     }
 }
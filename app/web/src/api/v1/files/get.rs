@@ -33,7 +33,12 @@ pub async fn handler(
     CurrentUser(account): CurrentUser,
     Path(file_id): Path<FileId>,
 ) -> ApiResult<Json<FileData>> {
-    let Some(file) = files.metadata().by_id(file_id).await? else {
+    let Some(file) = files
+        .metadata()
+        .by_id(file_id)
+        .await?
+        .filter(|file| !file.is_trashed())
+    else {
         return Err(ApiError::not_found());
     };
 
@@ -4,7 +4,7 @@ use axum::{Json, extract::State, http::StatusCode};
 use axum_extra::extract::Query;
 use oxidrive_files::{
     Files, SearchError,
-    file::{AllOwnedByError, AllOwnedByInError},
+    file::{AllOwnedByInError, InvalidSortError, Sort},
 };
 use serde::{Deserialize, Deserializer};
 use utoipa::IntoParams;
@@ -12,7 +12,7 @@ use uuid::Uuid;
 
 use crate::{
     api::{
-        error::{ApiError, ApiResult},
+        error::{ApiError, ApiResult, InvalidQueryBody},
         v1::files::FileData,
     },
     paginate::{Page, PageParams},
@@ -24,14 +24,17 @@ use crate::{
     path = "/",
     operation_id = "list",
     params(ListQuery),
-    responses((status = OK, body = Page<FileData>)),
+    responses(
+        (status = OK, body = Page<FileData>),
+        (status = BAD_REQUEST, response = InvalidQueryBody),
+    ),
     tag = "files",
 )]
 #[axum::debug_handler(state = crate::state::AppState)]
 pub async fn handler(
     State(files): State<Files>,
     CurrentUser(account): CurrentUser,
-    Query(ListQuery { search, ids }): Query<ListQuery>,
+    Query(ListQuery { search, sort, ids }): Query<ListQuery>,
     PageParams(params): PageParams,
 ) -> ApiResult<Json<Page<FileData>>> {
     if search.is_some() && !ids.is_empty() {
@@ -42,26 +45,25 @@ pub async fn handler(
         );
     }
 
-    let files = match search {
-        Some(filter) => files.search(account.id, filter, params).await?,
-        None => {
-            if ids.is_empty() {
-                files.metadata().all_owned_by(account.id, params).await?
-            } else {
-                let ids = ids.into_iter().map(Into::into).collect::<Vec<_>>();
-                files
-                    .metadata()
-                    .all_owned_by_in(account.id, &ids, params)
-                    .await?
-            }
-        }
+    let sort = sort.map(|sort| sort.parse::<Sort>()).transpose()?;
+
+    let files = if ids.is_empty() {
+        files
+            .search(account.id, search.unwrap_or_default(), sort, params)
+            .await?
+    } else {
+        let ids = ids.into_iter().map(Into::into).collect::<Vec<_>>();
+        files
+            .metadata()
+            .all_owned_by_in(account.id, &ids, params)
+            .await?
     };
     Ok(Json(files.map(FileData::from).into()))
 }
 
 #[derive(Debug, Deserialize, IntoParams)]
 pub struct ListQuery {
-    /// The OxiQL filter to search files for.
+    /// The OxiQL filter to search files for, by tags or by content.
     /// Mutually exclusive with `ids`
     #[serde(
         alias = "q",
@@ -71,6 +73,13 @@ pub struct ListQuery {
     )]
     search: Option<String>,
 
+    /// How files are ordered: `name`, `size`, `content_type`, `created`, `updated` or `relevance`,
+    /// optionally followed by `:asc` or `:desc`. Defaults to `relevance:desc` when `search`
+    /// matches phrases of file contents, like `"quarterly report"`, and to `name:asc` otherwise.
+    /// A `sort:` directive in `search` takes precedence over it. Ignored when listing `ids`
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    sort: Option<String>,
+
     /// The list of File IDs to load. Non-existent IDs will be ignored.
     /// Mutually exclusive with `search`
     #[serde(rename = "id", default)]
@@ -80,17 +89,21 @@ pub struct ListQuery {
 impl From<SearchError> for ApiError {
     fn from(err: SearchError) -> Self {
         match err {
-            SearchError::QueryParse(err) => Self::new(err)
+            SearchError::QueryParse(err) => err.into(),
+            SearchError::InvalidSort(err) => err.into(),
+            SearchError::InvalidCursor(err) => Self::new(err)
                 .status(StatusCode::BAD_REQUEST)
-                .error("INVALID_QUERY"),
+                .error("INVALID_CURSOR"),
             SearchError::SearchFailed(err) => Self::new(err),
         }
     }
 }
 
-impl From<AllOwnedByError> for ApiError {
-    fn from(err: AllOwnedByError) -> Self {
+impl From<InvalidSortError> for ApiError {
+    fn from(err: InvalidSortError) -> Self {
         Self::new(err)
+            .status(StatusCode::BAD_REQUEST)
+            .error("INVALID_SORT")
     }
 }
 
@@ -0,0 +1,88 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    response::IntoResponse,
+};
+use oxidrive_accounts::auth::AccountEntity;
+use oxidrive_authorization::Authorizer;
+use oxidrive_files::{Files, MoveError, auth::FileEntity, file::FileId};
+use serde::Deserialize;
+use utoipa::{ToResponse, ToSchema};
+
+use crate::{
+    api::error::{ApiError, ApiResult, ApiResultExt},
+    files::OnConflict,
+    session::CurrentUser,
+};
+
+use super::FileData;
+
+#[utoipa::path(
+    post,
+    path = "/{file_id}/move",
+    operation_id = "move",
+    params(("file_id" = String, Path, format = "uuid")),
+    responses(
+        (status = OK, response = FileMoved),
+        (status = CONFLICT, description = "A file already exists at the destination path"),
+    ),
+    tag = "files",
+)]
+#[axum::debug_handler(state = crate::state::AppState)]
+pub async fn handler(
+    State(authorizer): State<Authorizer>,
+    State(files): State<Files>,
+    CurrentUser(account): CurrentUser,
+    Path(file_id): Path<FileId>,
+    Json(MoveFile { path, on_conflict }): Json<MoveFile>,
+) -> ApiResult<FileMoved> {
+    let Some(file) = files
+        .metadata()
+        .by_id(file_id)
+        .await?
+        .filter(|file| !file.is_trashed())
+    else {
+        return Err(ApiError::not_found());
+    };
+
+    authorizer
+        .authorize(
+            &AccountEntity::from(&account),
+            "update",
+            &FileEntity::from(&file),
+        )
+        .into_err::<ApiError>()
+        .hide_403_as_404()?;
+
+    let file = files.move_file(file, &path, on_conflict.into()).await?;
+
+    Ok(FileMoved(file.into()))
+}
+
+/// Moves or renames a file. Overwritten files are moved to the trash
+#[derive(Deserialize, ToSchema)]
+pub struct MoveFile {
+    /// The new full path of the file, e.g. `archive/report.pdf`. Missing folders are created
+    path: String,
+    #[serde(default)]
+    on_conflict: OnConflict,
+}
+
+#[derive(ToResponse)]
+pub struct FileMoved(FileData);
+
+impl IntoResponse for FileMoved {
+    fn into_response(self) -> axum::response::Response {
+        Json(self.0).into_response()
+    }
+}
+
+impl From<MoveError> for ApiError {
+    fn from(err: MoveError) -> Self {
+        match err {
+            MoveError::InvalidPath(err) => err.into(),
+            MoveError::Conflict(err) => err.into(),
+            _ => Self::new(err),
+        }
+    }
+}
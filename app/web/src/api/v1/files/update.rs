@@ -22,7 +22,10 @@ use super::FileData;
     path = "/{file_id}",
     operation_id = "update",
     params(("file_id" = String, format = "uuid")),
-    responses((status = OK, response = FileUpdated)),
+    responses(
+        (status = OK, response = FileUpdated),
+        (status = CONFLICT, description = "Another file in the same folder already has the new name"),
+    ),
     tag = "files",
 )]
 #[axum::debug_handler(state = crate::state::AppState)]
@@ -33,7 +36,12 @@ pub async fn handler(
     Path(file_id): Path<FileId>,
     Json(body): Json<UpdateFile>,
 ) -> ApiResult<FileUpdated> {
-    let Some(file) = files.metadata().by_id(file_id).await? else {
+    let Some(file) = files
+        .metadata()
+        .by_id(file_id)
+        .await?
+        .filter(|file| !file.is_trashed())
+    else {
         return Err(ApiError::not_found());
     };
 
@@ -92,6 +100,7 @@ impl From<ParseError> for ApiError {
 impl From<UpdateError> for ApiError {
     fn from(err: UpdateError) -> Self {
         match err {
+            UpdateError::Conflict(err) => err.into(),
             UpdateError::SaveFileFailed(err) => Self::new(err),
         }
     }
@@ -0,0 +1,30 @@
+use oxidrive_files::file::version::FileVersion;
+use serde::Serialize;
+use time::OffsetDateTime;
+use utoipa::ToSchema;
+
+pub(super) mod list;
+pub(super) mod restore;
+
+#[derive(Debug, Serialize, ToSchema)]
+pub(super) struct FileVersionData {
+    number: u32,
+    content_type: String,
+    size: usize,
+    hash: Option<String>,
+    /// When this version was replaced by a newer one
+    #[serde(with = "time::serde::rfc3339")]
+    created_at: OffsetDateTime,
+}
+
+impl From<FileVersion> for FileVersionData {
+    fn from(version: FileVersion) -> Self {
+        Self {
+            hash: version.hash().map(|hash| hash.to_string()),
+            number: version.number,
+            content_type: version.content_type,
+            size: version.size,
+            created_at: version.created_at,
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+};
+use oxidrive_accounts::auth::AccountEntity;
+use oxidrive_authorization::Authorizer;
+use oxidrive_files::{
+    Files,
+    auth::FileEntity,
+    file::{FileId, version::AllVersionsError},
+};
+
+use crate::{
+    api::error::{ApiError, ApiResult, ApiResultExt},
+    session::CurrentUser,
+};
+
+use super::FileVersionData;
+
+#[utoipa::path(
+    get,
+    path = "/{file_id}/versions",
+    operation_id = "listVersions",
+    params(("file_id" = String, Path, format = "uuid")),
+    responses((status = OK, body = Vec<FileVersionData>)),
+    tag = "files",
+)]
+#[axum::debug_handler(state = crate::state::AppState)]
+pub async fn handler(
+    State(authorizer): State<Authorizer>,
+    State(files): State<Files>,
+    CurrentUser(account): CurrentUser,
+    Path(file_id): Path<FileId>,
+) -> ApiResult<Json<Vec<FileVersionData>>> {
+    let Some(file) = files
+        .metadata()
+        .by_id(file_id)
+        .await?
+        .filter(|file| !file.is_trashed())
+    else {
+        return Err(ApiError::not_found());
+    };
+
+    authorizer
+        .authorize(
+            &AccountEntity::from(&account),
+            "get",
+            &FileEntity::from(&file),
+        )
+        .into_err::<ApiError>()
+        .hide_403_as_404()?;
+
+    let versions = files.versions(&file).await?;
+
+    Ok(Json(versions.into_iter().map(Into::into).collect()))
+}
+
+impl From<AllVersionsError> for ApiError {
+    fn from(err: AllVersionsError) -> Self {
+        Self::new(err)
+    }
+}
@@ -0,0 +1,77 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    response::IntoResponse,
+};
+use oxidrive_accounts::auth::AccountEntity;
+use oxidrive_authorization::Authorizer;
+use oxidrive_files::{Files, RestoreVersionError, auth::FileEntity, file::FileId};
+use utoipa::ToResponse;
+
+use crate::{
+    api::{
+        error::{ApiError, ApiResult, ApiResultExt},
+        v1::files::FileData,
+    },
+    session::CurrentUser,
+};
+
+#[utoipa::path(
+    post,
+    path = "/{file_id}/versions/{version}/restore",
+    operation_id = "restoreVersion",
+    params(
+        ("file_id" = String, Path, format = "uuid"),
+        ("version" = u32, Path),
+    ),
+    responses((status = OK, response = VersionRestored)),
+    tag = "files",
+)]
+#[axum::debug_handler(state = crate::state::AppState)]
+pub async fn handler(
+    State(authorizer): State<Authorizer>,
+    State(files): State<Files>,
+    CurrentUser(account): CurrentUser,
+    Path((file_id, version)): Path<(FileId, u32)>,
+) -> ApiResult<VersionRestored> {
+    let Some(file) = files
+        .metadata()
+        .by_id(file_id)
+        .await?
+        .filter(|file| !file.is_trashed())
+    else {
+        return Err(ApiError::not_found());
+    };
+
+    authorizer
+        .authorize(
+            &AccountEntity::from(&account),
+            "update",
+            &FileEntity::from(&file),
+        )
+        .into_err::<ApiError>()
+        .hide_403_as_404()?;
+
+    let Some(version) = files.version(&file, version).await? else {
+        return Err(ApiError::not_found());
+    };
+
+    let file = files.restore_version(file, &version).await?;
+
+    Ok(VersionRestored(file.into()))
+}
+
+#[derive(ToResponse)]
+pub struct VersionRestored(FileData);
+
+impl IntoResponse for VersionRestored {
+    fn into_response(self) -> axum::response::Response {
+        Json(self.0).into_response()
+    }
+}
+
+impl From<RestoreVersionError> for ApiError {
+    fn from(err: RestoreVersionError) -> Self {
+        Self::new(err)
+    }
+}
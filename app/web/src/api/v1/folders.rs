@@ -0,0 +1,72 @@
+use axum::http::StatusCode;
+use create::FolderCreated;
+use delete::FolderDeleted;
+use oxidrive_files::{
+    FolderError,
+    folder::{FolderPath, InvalidPathError},
+};
+use serde::Serialize;
+use update::FolderMoved;
+use utoipa::{OpenApi, ToSchema};
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+use crate::{api::error::ApiError, state::AppState};
+
+mod create;
+mod delete;
+mod list;
+mod update;
+
+#[derive(OpenApi)]
+#[openapi(components(responses(FolderCreated, FolderMoved, FolderDeleted)))]
+pub struct FoldersApi;
+
+pub fn routes() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new().routes(routes!(
+        list::handler,
+        create::handler,
+        update::handler,
+        delete::handler
+    ))
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct FolderData {
+    /// The absolute path of the folder, e.g. `/photos/2025`
+    path: String,
+    name: String,
+}
+
+impl From<FolderPath> for FolderData {
+    fn from(path: FolderPath) -> Self {
+        Self {
+            name: path.name().into(),
+            path: path.into(),
+        }
+    }
+}
+
+impl From<InvalidPathError> for ApiError {
+    fn from(err: InvalidPathError) -> Self {
+        Self::new(err)
+            .status(StatusCode::BAD_REQUEST)
+            .error("INVALID_PATH")
+    }
+}
+
+impl From<FolderError> for ApiError {
+    fn from(err: FolderError) -> Self {
+        match err {
+            FolderError::NotFound(_) => Self::new(err)
+                .status(StatusCode::NOT_FOUND)
+                .error("NOT_FOUND"),
+            FolderError::AlreadyExists(_) => Self::new(err)
+                .status(StatusCode::CONFLICT)
+                .error("FOLDER_EXISTS"),
+            FolderError::IntoItself(_) | FolderError::Root => Self::new(err)
+                .status(StatusCode::BAD_REQUEST)
+                .error("INVALID_FOLDER_MOVE"),
+            _ => Self::new(err),
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use oxidrive_files::{Files, folder::FolderPath};
+use serde::Deserialize;
+use utoipa::{ToResponse, ToSchema};
+
+use crate::{api::error::ApiResult, session::CurrentUser};
+
+use super::FolderData;
+
+#[utoipa::path(
+    post,
+    path = "/",
+    operation_id = "create",
+    responses(
+        (status = CREATED, response = FolderCreated),
+        (status = CONFLICT, description = "The folder already exists"),
+    ),
+    tag = "folders",
+)]
+#[axum::debug_handler(state = crate::state::AppState)]
+pub async fn handler(
+    State(files): State<Files>,
+    CurrentUser(account): CurrentUser,
+    Json(CreateFolder { path }): Json<CreateFolder>,
+) -> ApiResult<FolderCreated> {
+    let path = FolderPath::parse(path)?;
+
+    files.create_folder(account.id, &path).await?;
+
+    Ok(FolderCreated(path.into()))
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateFolder {
+    /// The absolute path of the folder. Missing parent folders are created too
+    path: String,
+}
+
+#[derive(Debug, ToResponse)]
+#[response(content_type = "application/json")]
+pub struct FolderCreated(FolderData);
+
+impl IntoResponse for FolderCreated {
+    fn into_response(self) -> axum::response::Response {
+        (StatusCode::CREATED, Json(self.0)).into_response()
+    }
+}
@@ -0,0 +1,50 @@
+use axum::{Json, extract::State, response::IntoResponse};
+use axum_extra::extract::Query;
+use oxidrive_files::{Files, folder::FolderPath};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToResponse};
+
+use crate::{api::error::ApiResult, session::CurrentUser};
+
+#[utoipa::path(
+    delete,
+    path = "/",
+    operation_id = "delete",
+    params(DeleteQuery),
+    responses(
+        (status = OK, response = FolderDeleted),
+        (status = NOT_FOUND, description = "The folder does not exist"),
+    ),
+    tag = "folders",
+)]
+#[axum::debug_handler(state = crate::state::AppState)]
+pub async fn handler(
+    State(files): State<Files>,
+    CurrentUser(account): CurrentUser,
+    Query(DeleteQuery { path }): Query<DeleteQuery>,
+) -> ApiResult<FolderDeleted> {
+    let path = FolderPath::parse(path)?;
+
+    let trashed = files.delete_folder(account.id, &path).await?;
+
+    Ok(FolderDeleted { trashed })
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct DeleteQuery {
+    /// The folder to delete, along with everything nested in it
+    path: String,
+}
+
+#[derive(Debug, Serialize, ToResponse)]
+#[response(content_type = "application/json")]
+pub struct FolderDeleted {
+    /// How many files were moved to the trash
+    trashed: usize,
+}
+
+impl IntoResponse for FolderDeleted {
+    fn into_response(self) -> axum::response::Response {
+        Json(self).into_response()
+    }
+}
@@ -0,0 +1,55 @@
+use axum::{Json, extract::State};
+use axum_extra::extract::Query;
+use oxidrive_files::{Files, folder::FolderPath};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{
+    api::{error::ApiResult, v1::files::FileData},
+    paginate::{Page, PageParams},
+    session::CurrentUser,
+};
+
+use super::FolderData;
+
+#[utoipa::path(
+    get,
+    path = "/",
+    operation_id = "list",
+    params(ListQuery),
+    responses((status = OK, body = FolderListing)),
+    tag = "folders",
+)]
+#[axum::debug_handler(state = crate::state::AppState)]
+pub async fn handler(
+    State(files): State<Files>,
+    CurrentUser(account): CurrentUser,
+    Query(ListQuery { path }): Query<ListQuery>,
+    PageParams(params): PageParams,
+) -> ApiResult<Json<FolderListing>> {
+    let path = match path {
+        Some(path) => FolderPath::parse(path)?,
+        None => FolderPath::root(),
+    };
+
+    let listing = files.list_folder(account.id, &path, params).await?;
+
+    Ok(Json(FolderListing {
+        folders: listing.folders.into_iter().map(FolderData::from).collect(),
+        files: listing.files.map(FileData::from).into(),
+    }))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ListQuery {
+    /// The folder to list. Defaults to the root folder
+    path: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct FolderListing {
+    /// The folders directly nested in this one
+    folders: Vec<FolderData>,
+    /// The files directly inside this folder. Pagination only applies to them
+    files: Page<FileData>,
+}
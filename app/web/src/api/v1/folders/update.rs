@@ -0,0 +1,52 @@
+use axum::{Json, extract::State, response::IntoResponse};
+use oxidrive_files::{Files, folder::FolderPath};
+use serde::Deserialize;
+use utoipa::{ToResponse, ToSchema};
+
+use crate::{api::error::ApiResult, session::CurrentUser};
+
+use super::FolderData;
+
+#[utoipa::path(
+    patch,
+    path = "/",
+    operation_id = "move",
+    responses(
+        (status = OK, response = FolderMoved),
+        (status = NOT_FOUND, description = "The folder does not exist"),
+        (status = CONFLICT, description = "A folder already exists at the new path"),
+    ),
+    tag = "folders",
+)]
+#[axum::debug_handler(state = crate::state::AppState)]
+pub async fn handler(
+    State(files): State<Files>,
+    CurrentUser(account): CurrentUser,
+    Json(MoveFolder { path, new_path }): Json<MoveFolder>,
+) -> ApiResult<FolderMoved> {
+    let from = FolderPath::parse(path)?;
+    let to = FolderPath::parse(new_path)?;
+
+    files.move_folder(account.id, &from, &to).await?;
+
+    Ok(FolderMoved(to.into()))
+}
+
+/// Renames a folder, or moves it under another one, along with everything nested in it
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct MoveFolder {
+    /// The current path of the folder
+    path: String,
+    /// The path the folder is moved to. Missing parent folders are created
+    new_path: String,
+}
+
+#[derive(Debug, ToResponse)]
+#[response(content_type = "application/json")]
+pub struct FolderMoved(FolderData);
+
+impl IntoResponse for FolderMoved {
+    fn into_response(self) -> axum::response::Response {
+        Json(self.0).into_response()
+    }
+}
@@ -0,0 +1,13 @@
+use utoipa::OpenApi;
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+use crate::state::AppState;
+
+mod validate;
+
+#[derive(OpenApi)]
+pub struct SearchApi;
+
+pub fn routes() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new().routes(routes!(validate::handler))
+}
@@ -0,0 +1,52 @@
+use axum::Json;
+use axum_extra::extract::Query;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{api::error::QueryErrorBody, session::CurrentUser};
+
+/// Check an OxiQL query without running it, so that errors can be shown while it is typed
+#[utoipa::path(
+    get,
+    path = "/validate",
+    operation_id = "validate",
+    params(ValidateQuery),
+    responses((status = OK, body = QueryValidation)),
+    tag = "search",
+)]
+#[axum::debug_handler(state = crate::state::AppState)]
+pub async fn handler(
+    CurrentUser(_): CurrentUser,
+    Query(ValidateQuery { search }): Query<ValidateQuery>,
+) -> Json<QueryValidation> {
+    let validation = match oxidrive_search::parse_query(&search) {
+        Ok(filter) => QueryValidation {
+            valid: true,
+            normalized: Some(filter.to_string()),
+            error: None,
+        },
+        Err(err) => QueryValidation {
+            valid: false,
+            normalized: None,
+            error: Some(QueryErrorBody::from(&err)),
+        },
+    };
+
+    Json(validation)
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ValidateQuery {
+    /// The OxiQL query to validate
+    #[serde(alias = "q", alias = "query", default)]
+    search: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct QueryValidation {
+    valid: bool,
+    /// The query as it was understood, with explicit operators and groups
+    normalized: Option<String>,
+    /// Where and why the query is invalid
+    error: Option<QueryErrorBody>,
+}
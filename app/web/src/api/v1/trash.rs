@@ -0,0 +1,23 @@
+use empty::TrashEmptied;
+use purge::FilePurged;
+use restore::FileRestored;
+use utoipa::OpenApi;
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+use crate::state::AppState;
+
+mod empty;
+mod list;
+mod purge;
+mod restore;
+
+#[derive(OpenApi)]
+#[openapi(components(responses(TrashEmptied, FilePurged, FileRestored)))]
+pub struct TrashApi;
+
+pub fn routes() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new()
+        .routes(routes!(list::handler, empty::handler))
+        .routes(routes!(purge::handler))
+        .routes(routes!(restore::handler))
+}
@@ -0,0 +1,48 @@
+use axum::{Json, extract::State, response::IntoResponse};
+use oxidrive_files::{EmptyTrashError, Files};
+use serde::Serialize;
+use utoipa::ToResponse;
+
+use crate::{
+    api::error::{ApiError, ApiResult},
+    session::CurrentUser,
+};
+
+#[utoipa::path(
+    delete,
+    path = "/",
+    operation_id = "empty",
+    responses((status = OK, response = TrashEmptied)),
+    tag = "trash",
+)]
+#[axum::debug_handler(state = crate::state::AppState)]
+pub async fn handler(
+    State(files): State<Files>,
+    CurrentUser(account): CurrentUser,
+) -> ApiResult<TrashEmptied> {
+    let purged = files.empty_trash(account.id).await?;
+
+    Ok(TrashEmptied { purged })
+}
+
+#[derive(Debug, Serialize, ToResponse)]
+#[response(content_type = "application/json")]
+pub struct TrashEmptied {
+    /// How many files were permanently deleted
+    purged: usize,
+}
+
+impl IntoResponse for TrashEmptied {
+    fn into_response(self) -> axum::response::Response {
+        Json(self).into_response()
+    }
+}
+
+impl From<EmptyTrashError> for ApiError {
+    fn from(err: EmptyTrashError) -> Self {
+        match err {
+            EmptyTrashError::LoadFailed(err) => Self::new(err),
+            EmptyTrashError::DeleteFailed(err) => Self::new(err),
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use axum::{Json, extract::State};
+use oxidrive_files::{Files, file::TrashedByError};
+
+use crate::{
+    api::{
+        error::{ApiError, ApiResult},
+        v1::files::FileData,
+    },
+    paginate::{Page, PageParams},
+    session::CurrentUser,
+};
+
+#[utoipa::path(
+    get,
+    path = "/",
+    operation_id = "list",
+    responses((status = OK, body = Page<FileData>)),
+    tag = "trash",
+)]
+#[axum::debug_handler(state = crate::state::AppState)]
+pub async fn handler(
+    State(files): State<Files>,
+    CurrentUser(account): CurrentUser,
+    PageParams(params): PageParams,
+) -> ApiResult<Json<Page<FileData>>> {
+    let files = files.trashed(account.id, params).await?;
+    Ok(Json(files.map(FileData::from).into()))
+}
+
+impl From<TrashedByError> for ApiError {
+    fn from(err: TrashedByError) -> Self {
+        Self::new(err)
+    }
+}
@@ -0,0 +1,70 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    response::IntoResponse,
+};
+use oxidrive_accounts::auth::AccountEntity;
+use oxidrive_authorization::Authorizer;
+use oxidrive_files::{FileId, Files, PurgeError, auth::FileEntity};
+use utoipa::ToResponse;
+
+use crate::{
+    api::{
+        error::{ApiError, ApiResult, ApiResultExt},
+        v1::files::FileData,
+    },
+    session::CurrentUser,
+};
+
+#[utoipa::path(
+    delete,
+    path = "/{file_id}",
+    operation_id = "purge",
+    params(("file_id" = String, Path, format = "uuid")),
+    responses((status = OK, response = FilePurged)),
+    tag = "trash",
+)]
+#[axum::debug_handler(state = crate::state::AppState)]
+pub async fn handler(
+    State(authorizer): State<Authorizer>,
+    State(files): State<Files>,
+    CurrentUser(account): CurrentUser,
+    Path(file_id): Path<FileId>,
+) -> ApiResult<FilePurged> {
+    let Some(file) = files
+        .metadata()
+        .by_id(file_id)
+        .await?
+        .filter(|file| file.is_trashed())
+    else {
+        return Err(ApiError::not_found());
+    };
+
+    authorizer
+        .authorize(
+            &AccountEntity::from(&account),
+            "delete",
+            &FileEntity::from(&file),
+        )
+        .into_err::<ApiError>()
+        .hide_403_as_404()?;
+
+    files.purge(&file).await?;
+
+    Ok(FilePurged(file.into()))
+}
+
+#[derive(ToResponse)]
+pub struct FilePurged(FileData);
+
+impl IntoResponse for FilePurged {
+    fn into_response(self) -> axum::response::Response {
+        Json(self.0).into_response()
+    }
+}
+
+impl From<PurgeError> for ApiError {
+    fn from(err: PurgeError) -> Self {
+        Self::new(err)
+    }
+}
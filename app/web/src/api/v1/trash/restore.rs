@@ -0,0 +1,78 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use oxidrive_accounts::auth::AccountEntity;
+use oxidrive_authorization::Authorizer;
+use oxidrive_files::{FileId, Files, RestoreError, auth::FileEntity};
+use utoipa::ToResponse;
+
+use crate::{
+    api::{
+        error::{ApiError, ApiResult, ApiResultExt},
+        v1::files::FileData,
+    },
+    session::CurrentUser,
+};
+
+#[utoipa::path(
+    post,
+    path = "/{file_id}/restore",
+    operation_id = "restore",
+    params(("file_id" = String, Path, format = "uuid")),
+    responses((status = OK, response = FileRestored)),
+    tag = "trash",
+)]
+#[axum::debug_handler(state = crate::state::AppState)]
+pub async fn handler(
+    State(authorizer): State<Authorizer>,
+    State(files): State<Files>,
+    CurrentUser(account): CurrentUser,
+    Path(file_id): Path<FileId>,
+) -> ApiResult<FileRestored> {
+    let Some(file) = files
+        .metadata()
+        .by_id(file_id)
+        .await?
+        .filter(|file| file.is_trashed())
+    else {
+        return Err(ApiError::not_found());
+    };
+
+    authorizer
+        .authorize(
+            &AccountEntity::from(&account),
+            "restore",
+            &FileEntity::from(&file),
+        )
+        .into_err::<ApiError>()
+        .hide_403_as_404()?;
+
+    let file = files.restore(file).await?;
+
+    Ok(FileRestored(file.into()))
+}
+
+#[derive(ToResponse)]
+pub struct FileRestored(FileData);
+
+impl IntoResponse for FileRestored {
+    fn into_response(self) -> axum::response::Response {
+        Json(self.0).into_response()
+    }
+}
+
+impl From<RestoreError> for ApiError {
+    fn from(err: RestoreError) -> Self {
+        match err {
+            RestoreError::NameTaken(_) => Self::new(err)
+                .status(StatusCode::CONFLICT)
+                .error("FILE_NAME_TAKEN"),
+            RestoreError::LoadFailed(err) => Self::new(err),
+            RestoreError::CreateFolderFailed(err) => Self::new(err),
+            RestoreError::SaveFailed(err) => Self::new(err),
+        }
+    }
+}
@@ -1,12 +1,17 @@
 use axum::extract::DefaultBodyLimit;
+use oxidrive_files::ConflictPolicy;
+use serde::Deserialize;
 use upload::UploadCompleted;
-use utoipa::OpenApi;
+use utoipa::{OpenApi, ToSchema};
 use utoipa_axum::{router::OpenApiRouter, routes};
 
 use crate::{Config, state::AppState};
 
+mod digest;
 mod download;
+mod range;
 mod upload;
+mod uploads;
 
 #[derive(OpenApi)]
 #[openapi(components(responses(UploadCompleted)))]
@@ -19,4 +24,28 @@ pub fn routes(cfg: &Config) -> OpenApiRouter<AppState> {
             cfg.upload_body_limit.as_u64() as usize
         ))
         .routes(routes!(download::handler))
+        .nest("/uploads", uploads::routes())
+}
+
+/// What happens when a file is uploaded, copied or moved where another one already exists
+#[derive(Clone, Copy, Debug, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OnConflict {
+    /// Nothing is changed and `409 Conflict` is returned
+    #[default]
+    Fail,
+    /// The existing file is replaced
+    Overwrite,
+    /// The file gets a free name, numbered like `report (1).pdf`
+    Rename,
+}
+
+impl From<OnConflict> for ConflictPolicy {
+    fn from(on_conflict: OnConflict) -> Self {
+        match on_conflict {
+            OnConflict::Fail => Self::Fail,
+            OnConflict::Overwrite => Self::Overwrite,
+            OnConflict::Rename => Self::Rename,
+        }
+    }
 }
@@ -0,0 +1,69 @@
+//! Digests of file content, exchanged through the `Repr-Digest` and `Content-Digest` headers
+//! defined in [RFC 9530](https://www.rfc-editor.org/rfc/rfc9530)
+
+use axum::http::{HeaderMap, HeaderName, HeaderValue, StatusCode};
+use base64::{Engine, engine::general_purpose::STANDARD as ENGINE};
+use oxidrive_files::file::{Digest, DigestAlgorithm};
+
+use crate::api::error::ApiError;
+
+pub const REPR_DIGEST: HeaderName = HeaderName::from_static("repr-digest");
+pub const CONTENT_DIGEST: HeaderName = HeaderName::from_static("content-digest");
+
+/// Reads the digest declared in the given header, a list of algorithms paired with their base64 encoded digest.
+/// Algorithms other than `blake3` and `sha-256` are ignored, but at least one of them must be present
+pub fn from_headers(headers: &HeaderMap, name: &HeaderName) -> Result<Option<Digest>, ApiError> {
+    let invalid = || {
+        ApiError::new(format!("header '{name}' is invalid"))
+            .error("INVALID_HEADER_VALUE")
+            .status(StatusCode::BAD_REQUEST)
+    };
+
+    let mut declared = false;
+
+    for value in headers.get_all(name) {
+        let value = value.to_str().map_err(|_| invalid())?;
+
+        for member in value.split(',').map(str::trim).filter(|m| !m.is_empty()) {
+            declared = true;
+
+            let (algorithm, digest) = member.split_once('=').ok_or_else(invalid)?;
+
+            let Ok(algorithm) = algorithm.trim().parse::<DigestAlgorithm>() else {
+                continue;
+            };
+
+            // parameters are allowed after the value, but none are defined for digests
+            let digest = digest.split(';').next().unwrap_or_default().trim();
+
+            let digest = digest
+                .strip_prefix(':')
+                .and_then(|digest| digest.strip_suffix(':'))
+                .ok_or_else(invalid)?;
+
+            let digest = ENGINE.decode(digest).map_err(|_| invalid())?;
+            let digest = Digest::from_bytes(algorithm, &digest).map_err(|_| invalid())?;
+
+            return Ok(Some(digest));
+        }
+    }
+
+    if declared {
+        return Err(ApiError::new(format!(
+            "header '{name}' must contain a 'blake3' or 'sha-256' digest"
+        ))
+        .error("UNSUPPORTED_DIGEST_ALGORITHM")
+        .status(StatusCode::BAD_REQUEST));
+    }
+
+    Ok(None)
+}
+
+pub fn header_value(digest: &Digest) -> HeaderValue {
+    let value = format!(
+        "{}=:{}:",
+        digest.algorithm(),
+        ENGINE.encode(digest.as_bytes())
+    );
+    HeaderValue::from_str(&value).unwrap()
+}
@@ -1,7 +1,12 @@
-use std::{collections::HashSet, marker::PhantomData};
+use std::{
+    collections::HashSet,
+    future::ready,
+    marker::PhantomData,
+    ops::{Range, RangeBounds},
+};
 
 use axum::{
-    body::Bytes,
+    body::{Body, Bytes},
     extract::{Path, Query, State},
     http::{
         HeaderMap, HeaderValue, StatusCode,
@@ -10,30 +15,63 @@ use axum::{
     response::{IntoResponse, Response},
 };
 use axum_extra::response::FileStream;
-use futures::Stream;
+use futures::{
+    Stream, StreamExt, TryStreamExt,
+    stream::{self, BoxStream},
+};
 use oxidrive_accounts::auth::AccountEntity;
 use oxidrive_authorization::Authorizer;
-use oxidrive_files::{DownloadError, File, Files, auth::FileEntity, file::ByNameError};
+use oxidrive_files::{
+    DownloadError, File, Files,
+    auth::FileEntity,
+    file::{
+        ByPathError,
+        version::{FileVersion, VersionByNumberError},
+    },
+    folder::FolderPath,
+};
 use serde::Deserialize;
 use utoipa::ToSchema;
+use uuid::Uuid;
 
 use crate::{
     api::error::{ApiError, ApiResult},
     session::CurrentUser,
 };
 
+use super::{
+    digest::{self, REPR_DIGEST},
+    range::RequestedRange,
+};
+
 #[utoipa::path(
     get,
-    path = "/{file_name}",
+    path = "/{*file_path}",
     operation_id = "download",
-    params(("file_name" = String, Path), ("force" = bool, Query)),
-    responses((
-        status = OK,
-        description = "Raw content of the file. The actual content type varies based on the detected format",
-        content_type = "application/octet-stream",
-        body = inline(BinaryFile),
-        example = "hello world",
-    )),
+    params(
+        ("file_path" = String, Path, description = "The full path of the file, e.g. `photos/beach.jpg`"),
+        ("force" = bool, Query),
+        ("version" = Option<u32>, Query, description = "Download a previous version of the file instead of the current one"),
+        ("Range" = Option<String>, Header, description = "Only download the given byte ranges of the file, e.g. `bytes=0-499,1000-`"),
+        ("If-Range" = Option<String>, Header, description = "Only honor `Range` if the file still has the given ETag"),
+    ),
+    responses(
+        (
+            status = OK,
+            description = "Raw content of the file. The actual content type varies based on the detected format",
+            content_type = "application/octet-stream",
+            body = inline(BinaryFile),
+            example = "hello world",
+            headers(("Repr-Digest" = String, description = "The `blake3` digest of the whole file, once it has been computed")),
+        ),
+        (
+            status = PARTIAL_CONTENT,
+            description = "The requested range of the file. When multiple ranges are requested, they are sent as `multipart/byteranges`",
+            content_type = "application/octet-stream",
+            body = inline(BinaryFile),
+        ),
+        (status = RANGE_NOT_SATISFIABLE, description = "None of the requested ranges are within the file"),
+    ),
     tags = ["files", "content"],
 )]
 #[axum::debug_handler(state = crate::state::AppState)]
@@ -41,13 +79,17 @@ pub async fn handler(
     State(authorizer): State<Authorizer>,
     State(files): State<Files>,
     CurrentUser(account): CurrentUser,
-    Path(file_name): Path<String>,
-    Query(DownloadQuery { force }): Query<DownloadQuery>,
+    Path(file_path): Path<String>,
+    Query(DownloadQuery { force, version }): Query<DownloadQuery>,
     headers: HeaderMap,
 ) -> ApiResult<Response> {
+    let Ok((folder, file_name)) = FolderPath::split_file_path(&file_path) else {
+        return Err(ApiError::not_found());
+    };
+
     let Some(file) = files
         .metadata()
-        .by_owner_and_name(account.id, &file_name)
+        .by_owner_and_path(account.id, &folder, &file_name)
         .await?
     else {
         return Err(ApiError::not_found());
@@ -61,17 +103,77 @@ pub async fn handler(
         )
         .into_err::<ApiError>()?;
 
-    let body = match etag_matches(&file, headers) {
-        Some(_) => None,
-        None => {
-            let Some(body) = files.download(&file).await? else {
+    let (file, version) = match version {
+        Some(number) => {
+            let Some(version) = files.version(&file, number).await? else {
                 return Err(ApiError::not_found());
             };
-            Some(body)
+
+            (file.at_version(&version), Some(version))
         }
+        None => (file, None),
     };
 
-    Ok(DownloadResponse { file, force, body }.into_response())
+    if etag_matches(&file, &headers).is_some() {
+        let content = Content::NotModified;
+        return Ok(DownloadResponse {
+            file,
+            force,
+            content,
+        }
+        .into_response());
+    }
+
+    let etag = file.hash().map(|hash| hash.to_string());
+
+    let content = match RequestedRange::from_headers(&headers, etag.as_deref(), file.size as u64) {
+        RequestedRange::Full => Content::Full(download(&files, &file, version.as_ref(), ..).await?),
+        RequestedRange::Unsatisfiable => Content::Unsatisfiable,
+        RequestedRange::Partial(ranges) => {
+            let mut parts = Vec::with_capacity(ranges.len());
+
+            for range in ranges {
+                let body = download(&files, &file, version.as_ref(), range.clone()).await?;
+                parts.push((range, body));
+            }
+
+            Content::Partial(parts)
+        }
+    };
+
+    Ok(DownloadResponse {
+        file,
+        force,
+        content,
+    }
+    .into_response())
+}
+
+async fn download(
+    files: &Files,
+    file: &File,
+    version: Option<&FileVersion>,
+    range: impl RangeBounds<u64>,
+) -> ApiResult<DownloadStream> {
+    let body = match version {
+        Some(version) => files
+            .download_version(file, version, range)
+            .await?
+            .map(boxed),
+        None => files.download(file, range).await?.map(boxed),
+    };
+
+    body.ok_or_else(ApiError::not_found)
+}
+
+type DownloadStream = BoxStream<'static, Result<Bytes, std::io::Error>>;
+
+fn boxed<S, E>(body: S) -> DownloadStream
+where
+    S: Stream<Item = Result<Bytes, E>> + Send + 'static,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    body.map_err(std::io::Error::other).boxed()
 }
 
 #[derive(ToSchema)]
@@ -83,19 +185,24 @@ struct BinaryFile(PhantomData<Vec<u8>>);
 pub struct DownloadQuery {
     #[serde(default)]
     force: bool,
+    version: Option<u32>,
 }
 
-pub struct DownloadResponse<S> {
+pub struct DownloadResponse {
     file: File,
     force: bool,
-    body: Option<S>,
+    content: Content,
 }
 
-impl<S, E> IntoResponse for DownloadResponse<S>
-where
-    S: Stream<Item = Result<Bytes, E>> + Send + 'static,
-    E: std::error::Error + Send + Sync + 'static,
-{
+enum Content {
+    /// The client already has the current content, as told by `If-None-Match`
+    NotModified,
+    Full(DownloadStream),
+    Partial(Vec<(Range<u64>, DownloadStream)>),
+    Unsatisfiable,
+}
+
+impl IntoResponse for DownloadResponse {
     fn into_response(self) -> Response {
         let content_disposition = if self.force {
             attachment(&self.file)
@@ -107,24 +214,110 @@ where
             (header::CONTENT_DISPOSITION, content_disposition),
             (header::CONTENT_TYPE, header_value(&self.file.content_type)),
             (header::CACHE_CONTROL, HeaderValue::from_static("private")),
+            (header::ACCEPT_RANGES, HeaderValue::from_static("bytes")),
         ]);
 
         if let Some(hash) = self.file.hash() {
             headers.insert(header::ETAG, header_value(hash.to_string()));
         }
 
-        let Some(body) = self.body else {
-            return (StatusCode::NOT_MODIFIED, headers).into_response();
-        };
+        // the digest is of the whole file, even when only some ranges of it are sent
+        if let Some(digest) = self.file.digest() {
+            headers.insert(REPR_DIGEST, digest::header_value(&digest));
+        }
+
+        let size = self.file.size as u64;
+
+        match self.content {
+            Content::NotModified => (StatusCode::NOT_MODIFIED, headers).into_response(),
+            Content::Full(body) => {
+                let body = FileStream::new(body).file_name(self.file.name);
+                (headers, body).into_response()
+            }
+            Content::Partial(mut parts) if parts.len() == 1 => {
+                let (range, body) = parts.remove(0);
+
+                headers.insert(header::CONTENT_RANGE, content_range(&range, size));
+                headers.insert(
+                    header::CONTENT_LENGTH,
+                    HeaderValue::from(range.end - range.start),
+                );
+
+                (
+                    StatusCode::PARTIAL_CONTENT,
+                    headers,
+                    Body::from_stream(body),
+                )
+                    .into_response()
+            }
+            Content::Partial(parts) => {
+                let boundary = Uuid::now_v7().simple().to_string();
 
-        let body = FileStream::new(body).file_name(self.file.name);
+                headers.insert(
+                    header::CONTENT_TYPE,
+                    header_value(format!("multipart/byteranges; boundary={boundary}")),
+                );
 
-        (headers, body).into_response()
+                let body = byteranges(parts, boundary, self.file.content_type, size);
+
+                (
+                    StatusCode::PARTIAL_CONTENT,
+                    headers,
+                    Body::from_stream(body),
+                )
+                    .into_response()
+            }
+            Content::Unsatisfiable => {
+                headers.remove(header::CONTENT_TYPE);
+                headers.insert(
+                    header::CONTENT_RANGE,
+                    header_value(format!("bytes */{size}")),
+                );
+
+                (StatusCode::RANGE_NOT_SATISFIABLE, headers).into_response()
+            }
+        }
+    }
+}
+
+fn content_range(range: &Range<u64>, size: u64) -> HeaderValue {
+    header_value(format!("bytes {}-{}/{size}", range.start, range.end - 1))
+}
+
+/// Sends each range as its own part, preceded by its content type and position in the file
+fn byteranges(
+    parts: Vec<(Range<u64>, DownloadStream)>,
+    boundary: String,
+    content_type: String,
+    size: u64,
+) -> impl Stream<Item = Result<Bytes, std::io::Error>> + Send + 'static {
+    let end = Bytes::from(format!("--{boundary}--\r\n"));
+
+    let parts = parts.into_iter().map(move |(range, body)| {
+        let head = Bytes::from(format!(
+            "--{boundary}\r\nContent-Type: {content_type}\r\nContent-Range: bytes {}-{}/{size}\r\n\r\n",
+            range.start,
+            range.end - 1,
+        ));
+
+        stream::once(ready(Ok(head)))
+            .chain(body)
+            .chain(stream::once(ready(Ok(Bytes::from_static(b"\r\n")))))
+    });
+
+    stream::iter(parts)
+        .flatten()
+        .chain(stream::once(ready(Ok(end))))
+}
+
+impl From<ByPathError> for ApiError {
+    fn from(err: ByPathError) -> Self {
+        Self::new(err)
     }
 }
 
-impl From<ByNameError> for ApiError {
-    fn from(err: ByNameError) -> Self {
+impl From<VersionByNumberError> for ApiError {
+    fn from(err: VersionByNumberError) -> Self {
         Self::new(err)
     }
 }
@@ -163,7 +356,7 @@ fn header_value(value: impl AsRef<str>) -> HeaderValue {
     HeaderValue::from_str(value.as_ref()).unwrap()
 }
 
-fn etag_matches(file: &File, headers: HeaderMap) -> Option<()> {
+fn etag_matches(file: &File, headers: &HeaderMap) -> Option<()> {
     let matching_etags = headers.get(IF_NONE_MATCH).and_then(|h| h.to_str().ok())?;
 
     // * matches anything
@@ -0,0 +1,102 @@
+//! Partial downloads through the `Range` and `If-Range` headers, as described in
+//! [RFC 9110](https://www.rfc-editor.org/rfc/rfc9110#name-range-requests)
+
+use std::ops::Range;
+
+use axum::http::{HeaderMap, header};
+
+/// Requests asking for more ranges than this are served the whole content instead,
+/// as they are more likely to be abusive than useful
+const MAX_RANGES: usize = 16;
+
+/// The part of the content requested by the client
+#[derive(Debug, PartialEq, Eq)]
+pub enum RequestedRange {
+    Full,
+    Partial(Vec<Range<u64>>),
+    /// None of the requested ranges overlap with the content
+    Unsatisfiable,
+}
+
+impl RequestedRange {
+    /// Resolves the ranges requested in the headers against the size of the content.
+    /// Ranges are ignored when they can't be parsed, or when `If-Range` does not match the current entity tag
+    pub fn from_headers(headers: &HeaderMap, etag: Option<&str>, size: u64) -> Self {
+        let Some(range) = headers.get(header::RANGE).and_then(|h| h.to_str().ok()) else {
+            return Self::Full;
+        };
+
+        if let Some(if_range) = headers.get(header::IF_RANGE) {
+            // only entity tags are supported, since no Last-Modified date is ever sent
+            let matches = if_range
+                .to_str()
+                .is_ok_and(|if_range| Some(if_range.trim_matches('"')) == etag);
+
+            if !matches {
+                return Self::Full;
+            }
+        }
+
+        Self::parse(range, size)
+    }
+
+    fn parse(range: &str, size: u64) -> Self {
+        let Some(specs) = range.trim().strip_prefix("bytes=") else {
+            return Self::Full;
+        };
+
+        let specs: Vec<&str> = specs
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .collect();
+
+        if specs.is_empty() || specs.len() > MAX_RANGES {
+            return Self::Full;
+        }
+
+        let mut ranges = Vec::with_capacity(specs.len());
+
+        for spec in specs {
+            let Some((start, end)) = spec.split_once('-') else {
+                return Self::Full;
+            };
+
+            let range = match (start.trim(), end.trim()) {
+                ("", suffix) => {
+                    let Ok(suffix) = suffix.parse::<u64>() else {
+                        return Self::Full;
+                    };
+                    size.saturating_sub(suffix)..size
+                }
+                (start, "") => {
+                    let Ok(start) = start.parse::<u64>() else {
+                        return Self::Full;
+                    };
+                    start..size
+                }
+                (start, end) => {
+                    let (Ok(start), Ok(end)) = (start.parse::<u64>(), end.parse::<u64>()) else {
+                        return Self::Full;
+                    };
+
+                    if end < start {
+                        return Self::Full;
+                    }
+
+                    start..end.saturating_add(1).min(size)
+                }
+            };
+
+            if !range.is_empty() {
+                ranges.push(range);
+            }
+        }
+
+        if ranges.is_empty() {
+            return Self::Unsatisfiable;
+        }
+
+        Self::Partial(ranges)
+    }
+}
@@ -1,70 +1,237 @@
 use axum::{
-    extract::{Multipart, State, multipart::MultipartError},
-    http::StatusCode,
+    Json,
+    extract::{
+        Multipart, State,
+        multipart::{Field, MultipartError},
+    },
+    http::{
+        HeaderMap, StatusCode,
+        header::{IF_MATCH, IF_NONE_MATCH},
+    },
     response::IntoResponse,
 };
-use oxidrive_files::{Files, UploadMetadata};
-use utoipa::{ToResponse, ToSchema};
+use axum_extra::extract::Query;
+use oxidrive_files::{
+    ConflictPolicy, File, Files, Precondition, QuotaError, Tag, UploadMetadata, tag::ParseError,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToResponse, ToSchema};
 
 use crate::{
-    api::error::{ApiError, ApiResult},
+    api::error::{ApiError, ApiErrorBody, ApiResult},
     session::CurrentUser,
 };
 
+use super::{
+    OnConflict,
+    digest::{self, CONTENT_DIGEST, REPR_DIGEST},
+};
+
 #[utoipa::path(
     post,
     path = "/",
     operation_id = "upload",
+    params(
+        UploadQuery,
+        ("If-Match" = Option<String>, Header, description = "Only upload each file if a file exists at the same path with one of the given ETags, or with any for `*`"),
+        ("If-None-Match" = Option<String>, Header, description = "Only upload each file if no file exists at the same path. Only `*` is supported"),
+        ("Repr-Digest" = Option<String>, Header, description = "Digest of the uploaded file, using `blake3` or `sha-256`, e.g. `sha-256=:<base64>:`. It applies to every file part, so when uploading several files each part should declare its own in a `Content-Digest` header instead"),
+    ),
     request_body(content = inline(UploadForm), content_type = "multipart/form-data"),
-    responses((status = CREATED, response = UploadCompleted)),
+    responses(
+        (status = CREATED, response = UploadCompleted, description = "Every file was uploaded"),
+        (status = MULTI_STATUS, response = UploadCompleted, description = "Some files were not uploaded, e.g. because they do not match their digest (`DIGEST_MISMATCH`), another file exists at the same path and `on_conflict` is `fail` (`FILE_NAME_TAKEN`), the file at the same path does not satisfy `If-Match` or `If-None-Match` (`PRECONDITION_FAILED`) or the storage quota of the account is exceeded (`FILE_EXCEEDS_QUOTA`, `QUOTA_EXCEEDED`)"),
+        (status = BAD_REQUEST, description = "The request does not contain any file"),
+    ),
     tags = ["files", "content"],
 )]
 #[axum::debug_handler(state = crate::state::AppState)]
 pub async fn handler(
     State(files): State<Files>,
     CurrentUser(account): CurrentUser,
+    Query(UploadQuery { on_conflict }): Query<UploadQuery>,
+    headers: HeaderMap,
     mut body: Multipart,
 ) -> ApiResult<UploadCompleted> {
-    let Some(field) = body.next_field().await? else {
-        return Err(UploadError::InvalidBody.into());
-    };
+    let precondition = precondition(&headers)?;
+    let on_conflict = on_conflict
+        .map(ConflictPolicy::from)
+        .unwrap_or(ConflictPolicy::Overwrite);
+
+    let mut results = Vec::new();
+    let mut tags = Vec::new();
 
-    match field.name() {
-        Some("file") => {
-            let file_name = field
-                .file_name()
-                .ok_or_else(|| UploadError::MissingFileName)?
-                .to_string();
-            files
-                .upload(
+    // parts are uploaded as they arrive, so that the whole request never has to be buffered
+    while let Some(field) = body.next_field().await? {
+        match field.name() {
+            Some("tag") => {
+                let tag = field.text().await?;
+                tags.push(Tag::parse_public(tag));
+            }
+            Some("file") => {
+                let file_name = field.file_name().unwrap_or_default().to_string();
+
+                let meta = upload_metadata(
+                    &field,
+                    &headers,
                     UploadMetadata {
-                        file_name,
+                        file_name: file_name.clone(),
                         owner_id: account.id,
+                        digest: None,
+                        tags: Vec::new(),
+                        on_conflict,
+                        precondition: precondition.clone(),
                     },
-                    field,
-                )
-                .await?;
-        }
-        Some(name) => Err(UploadError::UnexpectedField(name.into()))?,
-        None => Err(UploadError::FieldMissingName)?,
+                    std::mem::take(&mut tags),
+                );
+
+                let result = match meta {
+                    Ok(meta) => files.upload(meta, field).await.map_err(ApiError::from),
+                    Err(err) => Err(err),
+                };
+
+                results.push(UploadResult::new(file_name, result));
+            }
+            Some(name) => Err(UploadError::UnexpectedField(name.into()))?,
+            None => Err(UploadError::FieldMissingName)?,
+        };
+    }
+
+    if results.is_empty() {
+        return Err(UploadError::InvalidBody.into());
+    }
+
+    Ok(UploadCompleted { files: results })
+}
+
+/// Completes the metadata of a file part with its digest and the tags sent right before it
+fn upload_metadata(
+    field: &Field<'_>,
+    headers: &HeaderMap,
+    mut meta: UploadMetadata,
+    tags: Vec<Result<Tag, ParseError>>,
+) -> ApiResult<UploadMetadata> {
+    if meta.file_name.is_empty() {
+        return Err(UploadError::MissingFileName.into());
+    }
+
+    meta.digest = match digest::from_headers(field.headers(), &CONTENT_DIGEST)? {
+        Some(digest) => Some(digest),
+        None => digest::from_headers(headers, &REPR_DIGEST)?,
+    };
+
+    meta.tags = tags.into_iter().collect::<Result<_, _>>()?;
+
+    Ok(meta)
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct UploadQuery {
+    /// What happens when a file already exists at the same path. Defaults to `overwrite`
+    on_conflict: Option<OnConflict>,
+}
+
+/// Reads the conditional request headers, comparing ETags as sent by downloads, with or without quotes
+fn precondition(headers: &HeaderMap) -> Result<Option<Precondition>, UploadError> {
+    if let Some(value) = headers.get(IF_NONE_MATCH) {
+        return match value.to_str() {
+            Ok("*") => Ok(Some(Precondition::NoneMatch)),
+            _ => Err(UploadError::UnsupportedPrecondition),
+        };
+    }
+
+    let Some(value) = headers.get(IF_MATCH) else {
+        return Ok(None);
     };
 
-    Ok(UploadCompleted)
+    let value = value
+        .to_str()
+        .map_err(|_| UploadError::UnsupportedPrecondition)?
+        .trim();
+
+    if value == "*" {
+        return Ok(Some(Precondition::Match(Vec::new())));
+    }
+
+    // If-Match uses the strong comparison, so weak ETags are kept as sent and never match a hash
+    let etags = value
+        .split(',')
+        .map(str::trim)
+        .map(|etag| {
+            if etag.starts_with("W/") {
+                etag
+            } else {
+                etag.trim_matches('"')
+            }
+        })
+        .filter(|etag| !etag.is_empty())
+        .map(String::from)
+        .collect();
+
+    Ok(Some(Precondition::Match(etags)))
 }
 
 #[derive(ToSchema)]
 #[allow(unused)] // only used for utoipa schema generation
 struct UploadForm {
+    /// The file name can include the path of the folder to store the file in, e.g. `photos/beach.jpg`.
+    /// Missing folders are created. The field can be repeated to upload several files at once
     #[schema(format = Binary, content_media_type = "application/octet-stream")]
-    file: String,
+    file: Vec<String>,
+    /// A tag like `key:value` given to the file sent right after it. The field can be repeated
+    tag: Option<Vec<String>>,
 }
 
-#[derive(ToResponse)]
-pub struct UploadCompleted;
+#[derive(Debug, Serialize, ToResponse)]
+#[response(content_type = "application/json")]
+pub struct UploadCompleted {
+    /// The outcome of each file, in the order they were sent
+    files: Vec<UploadResult>,
+}
 
 impl IntoResponse for UploadCompleted {
     fn into_response(self) -> axum::response::Response {
-        (StatusCode::CREATED).into_response()
+        let status = if self.files.iter().all(|result| result.error.is_none()) {
+            StatusCode::CREATED
+        } else {
+            StatusCode::MULTI_STATUS
+        };
+
+        (status, Json(self)).into_response()
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UploadResult {
+    /// The name of the file part, including the folder if any
+    name: String,
+    /// The id of the file, if it was uploaded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(format = "uuid")]
+    id: Option<String>,
+    /// Why the file was not uploaded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<ApiErrorBody>,
+}
+
+impl UploadResult {
+    fn new(name: String, result: ApiResult<File>) -> Self {
+        match result {
+            Ok(file) => Self {
+                name,
+                id: Some(file.id.to_string()),
+                error: None,
+            },
+            Err(err) => {
+                tracing::warn!(error = %err, error.details = ?err, file_name = %name, "failed to upload file");
+
+                Self {
+                    name,
+                    id: None,
+                    error: Some(err.into_body()),
+                }
+            }
+        }
     }
 }
 
@@ -78,6 +245,8 @@ pub enum UploadError {
     InvalidBody,
     #[error("unexpected field '{0}'")]
     UnexpectedField(String),
+    #[error("If-None-Match only supports '*'")]
+    UnsupportedPrecondition,
     #[error(transparent)]
     Multipart(#[from] MultipartError),
     #[error(transparent)]
@@ -89,7 +258,10 @@ impl From<UploadError> for ApiError {
         match err {
             UploadError::FieldMissingName
             | UploadError::MissingFileName
-            | UploadError::InvalidBody => Self::new(err).status(StatusCode::BAD_REQUEST),
+            | UploadError::InvalidBody
+            | UploadError::UnsupportedPrecondition => {
+                Self::new(err).status(StatusCode::BAD_REQUEST)
+            }
             UploadError::UnexpectedField(err) => Self::new(err).status(StatusCode::BAD_REQUEST),
             UploadError::Multipart(err) => err.into(),
             UploadError::UploadFailed(err) => err.into(),
@@ -108,6 +280,36 @@ impl From<MultipartError> for ApiError {
 
 impl From<oxidrive_files::UploadError> for ApiError {
     fn from(err: oxidrive_files::UploadError) -> Self {
-        Self::new(err)
+        match err {
+            oxidrive_files::UploadError::DigestMismatch { .. } => Self::new(err)
+                .status(StatusCode::BAD_REQUEST)
+                .error("DIGEST_MISMATCH"),
+            oxidrive_files::UploadError::InvalidPath(_) => Self::new(err)
+                .status(StatusCode::BAD_REQUEST)
+                .error("INVALID_PATH"),
+            oxidrive_files::UploadError::PreconditionFailed => Self::new(err)
+                .status(StatusCode::PRECONDITION_FAILED)
+                .error("PRECONDITION_FAILED"),
+            oxidrive_files::UploadError::Conflict(err) => err.into(),
+            oxidrive_files::UploadError::QuotaFailed(err) => err.into(),
+            _ => Self::new(err),
+        }
+    }
+}
+
+impl From<QuotaError> for ApiError {
+    fn from(err: QuotaError) -> Self {
+        match err {
+            QuotaError::TooLarge { .. } => Self::new(err)
+                .status(StatusCode::PAYLOAD_TOO_LARGE)
+                .error("FILE_EXCEEDS_QUOTA"),
+            QuotaError::Exceeded => Self::new(err)
+                .status(StatusCode::INSUFFICIENT_STORAGE)
+                .error("QUOTA_EXCEEDED"),
+            QuotaError::InvalidPath(_) => Self::new(err)
+                .status(StatusCode::BAD_REQUEST)
+                .error("INVALID_PATH"),
+            _ => Self::new(err),
+        }
     }
 }
@@ -0,0 +1,149 @@
+//! Resumable uploads, following the [tus](https://tus.io/protocols/resumable-upload) 1.0 protocol
+//! with the `creation`, `termination` and `expiration` extensions
+
+use std::{collections::HashMap, str::FromStr};
+
+use axum::{
+    extract::Request,
+    http::{HeaderMap, HeaderName, HeaderValue, Method, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+};
+use base64::{Engine, engine::general_purpose::STANDARD as ENGINE};
+use oxidrive_accounts::account::Account;
+use oxidrive_files::upload::{Upload, UploadByIdError, UploadId, Uploads};
+use time::{
+    OffsetDateTime, UtcOffset, format_description::BorrowedFormatItem, macros::format_description,
+};
+use tower_http::set_header::SetResponseHeaderLayer;
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+use crate::{api::error::ApiError, state::AppState};
+
+mod append;
+mod create;
+mod offset;
+mod options;
+mod terminate;
+
+const TUS_VERSION: &str = "1.0.0";
+const TUS_EXTENSIONS: &str = "creation,termination,expiration";
+
+const TUS_RESUMABLE_HEADER: HeaderName = HeaderName::from_static("tus-resumable");
+const TUS_VERSION_HEADER: HeaderName = HeaderName::from_static("tus-version");
+const TUS_EXTENSION_HEADER: HeaderName = HeaderName::from_static("tus-extension");
+const UPLOAD_LENGTH: HeaderName = HeaderName::from_static("upload-length");
+const UPLOAD_DEFER_LENGTH: HeaderName = HeaderName::from_static("upload-defer-length");
+const UPLOAD_OFFSET: HeaderName = HeaderName::from_static("upload-offset");
+const UPLOAD_METADATA: HeaderName = HeaderName::from_static("upload-metadata");
+const UPLOAD_EXPIRES: HeaderName = HeaderName::from_static("upload-expires");
+
+const HTTP_DATE: &[BorrowedFormatItem<'_>] = format_description!(
+    "[weekday repr:short], [day] [month repr:short] [year] [hour]:[minute]:[second] GMT"
+);
+
+pub fn routes() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new()
+        .routes(routes!(options::handler, create::handler))
+        .routes(routes!(
+            offset::handler,
+            append::handler,
+            terminate::handler
+        ))
+        .layer(middleware::from_fn(require_tus_version))
+        .layer(SetResponseHeaderLayer::overriding(
+            TUS_RESUMABLE_HEADER,
+            HeaderValue::from_static(TUS_VERSION),
+        ))
+}
+
+/// Every request but OPTIONS must declare the version of the protocol used by the client
+async fn require_tus_version(request: Request, next: Next) -> Response {
+    if request.method() == Method::OPTIONS {
+        return next.run(request).await;
+    }
+
+    let supported = request
+        .headers()
+        .get(TUS_RESUMABLE_HEADER)
+        .is_some_and(|version| version == TUS_VERSION);
+
+    if supported {
+        return next.run(request).await;
+    }
+
+    let err = ApiError::new(format!("only version {TUS_VERSION} of tus is supported"))
+        .status(StatusCode::PRECONDITION_FAILED)
+        .error("UNSUPPORTED_VERSION");
+
+    (
+        [(TUS_VERSION_HEADER, HeaderValue::from_static(TUS_VERSION))],
+        err,
+    )
+        .into_response()
+}
+
+/// Loads an upload of the current account that can still be resumed
+async fn find(uploads: &Uploads, account: &Account, id: UploadId) -> Result<Upload, ApiError> {
+    uploads
+        .by_id(id)
+        .await?
+        .filter(|upload| upload.owner_id == account.id)
+        .ok_or_else(ApiError::not_found)
+}
+
+fn parse_header<T: FromStr>(headers: &HeaderMap, name: HeaderName) -> Result<T, ApiError> {
+    headers
+        .get(&name)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok())
+        .ok_or_else(|| {
+            ApiError::new(format!("header '{name}' is missing or invalid"))
+                .error("INVALID_HEADER_VALUE")
+                .status(StatusCode::BAD_REQUEST)
+        })
+}
+
+/// Parses `Upload-Metadata`, made of comma separated pairs of a key and a base64 encoded value.
+/// The value may be omitted, in which case it is empty
+fn parse_metadata(header: &HeaderValue) -> Result<HashMap<String, String>, ApiError> {
+    let invalid = || {
+        ApiError::new(format!("header '{UPLOAD_METADATA}' is invalid"))
+            .error("INVALID_HEADER_VALUE")
+            .status(StatusCode::BAD_REQUEST)
+    };
+
+    let header = header.to_str().map_err(|_| invalid())?;
+
+    header
+        .split(',')
+        .map(str::trim)
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once(' ').unwrap_or((pair, ""));
+
+            let value = ENGINE.decode(value.trim()).map_err(|_| invalid())?;
+            let value = String::from_utf8(value).map_err(|_| invalid())?;
+
+            Ok((key.to_string(), value))
+        })
+        .collect()
+}
+
+fn upload_headers(upload: &Upload) -> [(HeaderName, HeaderValue); 2] {
+    [
+        (UPLOAD_OFFSET, HeaderValue::from(upload.offset)),
+        (UPLOAD_EXPIRES, http_date(upload.expires_at)),
+    ]
+}
+
+fn http_date(at: OffsetDateTime) -> HeaderValue {
+    let date = at.to_offset(UtcOffset::UTC).format(HTTP_DATE).unwrap();
+    HeaderValue::from_str(&date).unwrap()
+}
+
+impl From<UploadByIdError> for ApiError {
+    fn from(err: UploadByIdError) -> Self {
+        Self::new(err)
+    }
+}
@@ -0,0 +1,103 @@
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode, header},
+    response::IntoResponse,
+};
+use oxidrive_files::{
+    UploadError,
+    upload::{AppendError, CompleteUploadError, Upload, UploadId, Uploads},
+};
+
+use crate::{
+    api::error::{ApiError, ApiResult},
+    session::CurrentUser,
+};
+
+use super::{UPLOAD_OFFSET, find, parse_header, upload_headers};
+
+const OFFSET_OCTET_STREAM: &str = "application/offset+octet-stream";
+
+#[utoipa::path(
+    patch,
+    path = "/{upload_id}",
+    operation_id = "append",
+    params(
+        ("upload_id" = String, Path, format = "uuid"),
+        ("Tus-Resumable" = String, Header, example = "1.0.0"),
+        ("Upload-Offset" = usize, Header, description = "Where the content starts, which must be the offset reached so far"),
+    ),
+    request_body(content = Vec<u8>, content_type = "application/offset+octet-stream"),
+    responses(
+        (
+            status = NO_CONTENT,
+            description = "The content was appended. Once all of it has been received, the file is created",
+            headers(
+                ("Upload-Offset" = usize),
+                ("Upload-Expires" = String),
+            ),
+        ),
+        (status = BAD_REQUEST, description = "The complete file does not match the digest declared when creating the upload, which is terminated"),
+        (status = INSUFFICIENT_STORAGE, description = "There is not enough space left in the storage quota of the account for the complete file"),
+    ),
+    tags = ["files", "uploads"],
+)]
+#[axum::debug_handler(state = crate::state::AppState)]
+pub async fn handler(
+    State(uploads): State<Uploads>,
+    CurrentUser(account): CurrentUser,
+    Path(upload_id): Path<UploadId>,
+    headers: HeaderMap,
+    body: Body,
+) -> ApiResult<UploadAppended> {
+    if headers
+        .get(header::CONTENT_TYPE)
+        .is_none_or(|content_type| content_type != OFFSET_OCTET_STREAM)
+    {
+        return Err(
+            ApiError::new(format!("content type must be {OFFSET_OCTET_STREAM}"))
+                .status(StatusCode::UNSUPPORTED_MEDIA_TYPE)
+                .error("UNSUPPORTED_MEDIA_TYPE"),
+        );
+    }
+
+    let offset = parse_header(&headers, UPLOAD_OFFSET)?;
+
+    let upload = find(&uploads, &account, upload_id).await?;
+
+    let upload = uploads
+        .append(upload, offset, body.into_data_stream())
+        .await?;
+
+    Ok(UploadAppended(upload))
+}
+
+pub struct UploadAppended(Upload);
+
+impl IntoResponse for UploadAppended {
+    fn into_response(self) -> axum::response::Response {
+        (StatusCode::NO_CONTENT, upload_headers(&self.0)).into_response()
+    }
+}
+
+impl From<AppendError> for ApiError {
+    fn from(err: AppendError) -> Self {
+        match err {
+            AppendError::OffsetMismatch => Self::new(err)
+                .status(StatusCode::CONFLICT)
+                .error("OFFSET_MISMATCH"),
+            AppendError::ExceedsLength(_) => Self::new(err)
+                .status(StatusCode::PAYLOAD_TOO_LARGE)
+                .error("EXCEEDS_UPLOAD_LENGTH"),
+            AppendError::CompleteFailed(CompleteUploadError::UploadFailed(
+                UploadError::DigestMismatch { .. },
+            )) => Self::new(err)
+                .status(StatusCode::BAD_REQUEST)
+                .error("DIGEST_MISMATCH"),
+            AppendError::CompleteFailed(CompleteUploadError::UploadFailed(
+                UploadError::QuotaFailed(err),
+            )) => err.into(),
+            _ => Self::new(err),
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use axum::{
+    extract::State,
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    response::IntoResponse,
+};
+use oxidrive_files::{
+    UploadError,
+    upload::{CompleteUploadError, CreateUploadError, Upload, Uploads},
+};
+
+use crate::{
+    api::error::{ApiError, ApiResult},
+    files::digest::{self, REPR_DIGEST},
+    session::CurrentUser,
+};
+
+use super::{UPLOAD_DEFER_LENGTH, UPLOAD_LENGTH, UPLOAD_METADATA, parse_header, parse_metadata};
+
+#[utoipa::path(
+    post,
+    path = "/",
+    operation_id = "create",
+    params(
+        ("Tus-Resumable" = String, Header, example = "1.0.0"),
+        ("Upload-Length" = usize, Header, description = "Total size of the file, in bytes"),
+        ("Upload-Metadata" = String, Header, description = "Comma separated keys and base64 encoded values. The file name is read from `filename`, or `name`"),
+        ("Repr-Digest" = Option<String>, Header, description = "Digest of the complete file, using `blake3` or `sha-256`, e.g. `sha-256=:<base64>:`. The upload is terminated if the content received does not match it"),
+    ),
+    responses(
+        (
+            status = CREATED,
+            description = "The upload was created, and its content can be sent to the returned location",
+            headers(
+                ("Location" = String, description = "Where to send the content of the upload"),
+                ("Upload-Expires" = String, description = "When the upload will be abandoned if no content is received"),
+            ),
+        ),
+        (status = PAYLOAD_TOO_LARGE, description = "The file is larger than the whole storage quota of the account"),
+        (status = INSUFFICIENT_STORAGE, description = "There is not enough space left in the storage quota of the account for the declared length"),
+    ),
+    tags = ["files", "uploads"],
+)]
+#[axum::debug_handler(state = crate::state::AppState)]
+pub async fn handler(
+    State(uploads): State<Uploads>,
+    CurrentUser(account): CurrentUser,
+    headers: HeaderMap,
+) -> ApiResult<UploadCreated> {
+    if headers.contains_key(UPLOAD_DEFER_LENGTH) {
+        return Err(CreateError::DeferredLength.into());
+    }
+
+    let length = parse_header(&headers, UPLOAD_LENGTH)?;
+
+    let metadata = headers
+        .get(UPLOAD_METADATA)
+        .map(parse_metadata)
+        .transpose()?
+        .unwrap_or_default();
+
+    let Some(file_name) = metadata
+        .get("filename")
+        .or_else(|| metadata.get("name"))
+        .filter(|name| !name.is_empty())
+    else {
+        return Err(CreateError::MissingFileName.into());
+    };
+
+    let digest = digest::from_headers(&headers, &REPR_DIGEST)?;
+
+    let upload = uploads
+        .create(account.id, file_name, length, digest)
+        .await?;
+
+    Ok(UploadCreated(upload))
+}
+
+pub struct UploadCreated(Upload);
+
+impl IntoResponse for UploadCreated {
+    fn into_response(self) -> axum::response::Response {
+        let location = format!("/files/uploads/{}", self.0.id);
+
+        (
+            StatusCode::CREATED,
+            [(header::LOCATION, HeaderValue::from_str(&location).unwrap())],
+            super::upload_headers(&self.0),
+        )
+            .into_response()
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CreateError {
+    #[error("uploads must declare their length upfront")]
+    DeferredLength,
+    #[error("'filename' must be provided in the upload metadata")]
+    MissingFileName,
+}
+
+impl From<CreateError> for ApiError {
+    fn from(err: CreateError) -> Self {
+        Self::new(err).status(StatusCode::BAD_REQUEST)
+    }
+}
+
+impl From<CreateUploadError> for ApiError {
+    fn from(err: CreateUploadError) -> Self {
+        match err {
+            // empty uploads are completed right away
+            CreateUploadError::CompleteFailed(CompleteUploadError::UploadFailed(
+                UploadError::DigestMismatch { .. },
+            )) => Self::new(err)
+                .status(StatusCode::BAD_REQUEST)
+                .error("DIGEST_MISMATCH"),
+            CreateUploadError::QuotaFailed(err) => err.into(),
+            _ => Self::new(err),
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderValue, StatusCode, header},
+    response::IntoResponse,
+};
+use oxidrive_files::upload::{Upload, UploadId, Uploads};
+
+use crate::{api::error::ApiResult, session::CurrentUser};
+
+use super::{UPLOAD_LENGTH, find, upload_headers};
+
+#[utoipa::path(
+    head,
+    path = "/{upload_id}",
+    operation_id = "offset",
+    params(
+        ("upload_id" = String, Path, format = "uuid"),
+        ("Tus-Resumable" = String, Header, example = "1.0.0"),
+    ),
+    responses((
+        status = OK,
+        description = "How much of the upload has been received so far, to resume it from there",
+        headers(
+            ("Upload-Offset" = usize),
+            ("Upload-Length" = usize),
+            ("Upload-Expires" = String),
+        ),
+    )),
+    tags = ["files", "uploads"],
+)]
+#[axum::debug_handler(state = crate::state::AppState)]
+pub async fn handler(
+    State(uploads): State<Uploads>,
+    CurrentUser(account): CurrentUser,
+    Path(upload_id): Path<UploadId>,
+) -> ApiResult<UploadProgress> {
+    let upload = find(&uploads, &account, upload_id).await?;
+    Ok(UploadProgress(upload))
+}
+
+pub struct UploadProgress(Upload);
+
+impl IntoResponse for UploadProgress {
+    fn into_response(self) -> axum::response::Response {
+        (
+            StatusCode::OK,
+            [
+                (UPLOAD_LENGTH, HeaderValue::from(self.0.length)),
+                (header::CACHE_CONTROL, HeaderValue::from_static("no-store")),
+            ],
+            upload_headers(&self.0),
+        )
+            .into_response()
+    }
+}
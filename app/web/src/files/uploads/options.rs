@@ -0,0 +1,28 @@
+use axum::{http::StatusCode, response::IntoResponse};
+
+use super::{TUS_EXTENSION_HEADER, TUS_EXTENSIONS, TUS_VERSION, TUS_VERSION_HEADER};
+
+#[utoipa::path(
+    options,
+    path = "/",
+    operation_id = "options",
+    responses((
+        status = NO_CONTENT,
+        description = "The tus protocol versions and extensions supported by the server",
+        headers(
+            ("Tus-Version" = String, example = "1.0.0"),
+            ("Tus-Extension" = String, example = "creation,termination,expiration"),
+        ),
+    )),
+    tags = ["files", "uploads"],
+)]
+#[axum::debug_handler(state = crate::state::AppState)]
+pub async fn handler() -> impl IntoResponse {
+    (
+        StatusCode::NO_CONTENT,
+        [
+            (TUS_VERSION_HEADER, TUS_VERSION),
+            (TUS_EXTENSION_HEADER, TUS_EXTENSIONS),
+        ],
+    )
+}
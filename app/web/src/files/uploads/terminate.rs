@@ -0,0 +1,51 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use oxidrive_files::upload::{TerminateUploadError, UploadId, Uploads};
+
+use crate::{
+    api::error::{ApiError, ApiResult},
+    session::CurrentUser,
+};
+
+use super::find;
+
+#[utoipa::path(
+    delete,
+    path = "/{upload_id}",
+    operation_id = "terminate",
+    params(
+        ("upload_id" = String, Path, format = "uuid"),
+        ("Tus-Resumable" = String, Header, example = "1.0.0"),
+    ),
+    responses((status = NO_CONTENT, description = "The upload was abandoned and its content discarded")),
+    tags = ["files", "uploads"],
+)]
+#[axum::debug_handler(state = crate::state::AppState)]
+pub async fn handler(
+    State(uploads): State<Uploads>,
+    CurrentUser(account): CurrentUser,
+    Path(upload_id): Path<UploadId>,
+) -> ApiResult<UploadTerminated> {
+    let upload = find(&uploads, &account, upload_id).await?;
+
+    uploads.terminate(&upload).await?;
+
+    Ok(UploadTerminated)
+}
+
+pub struct UploadTerminated;
+
+impl IntoResponse for UploadTerminated {
+    fn into_response(self) -> axum::response::Response {
+        StatusCode::NO_CONTENT.into_response()
+    }
+}
+
+impl From<TerminateUploadError> for ApiError {
+    fn from(err: TerminateUploadError) -> Self {
+        Self::new(err)
+    }
+}
@@ -45,6 +45,10 @@ pub struct Config {
 
     #[serde(default)]
     csrf: CsrfConfig,
+
+    /// Usernames of the accounts allowed to use the admin API
+    #[serde(default)]
+    admins: Vec<String>,
 }
 
 impl Config {
@@ -72,6 +76,7 @@ impl Config {
             upload_body_limit: default_upload_body_limit(),
             cors: Default::default(),
             csrf: Default::default(),
+            admins: Default::default(),
         }
     }
 }
@@ -95,8 +95,8 @@ impl<T> From<Slice<T>> for Page<T> {
     fn from(slice: Slice<T>) -> Self {
         Self {
             items: slice.items,
-            next: slice.next.map(Cursor::from),
-            previous: slice.previous.map(Cursor::from),
+            next: slice.next.map(Cursor::encode),
+            previous: slice.previous.map(Cursor::encode),
         }
     }
 }
@@ -108,6 +108,11 @@ pub enum Cursor {
 }
 
 impl Cursor {
+    /// Cursors are opaque to clients, as stores may put anything in them
+    pub fn encode(cursor: impl AsRef<[u8]>) -> Self {
+        Self::Encoded(ENGINE.encode(cursor))
+    }
+
     pub fn unwrap(self) -> Result<String, InvalidCursor> {
         let s = match self {
             Cursor::Plain(s) => return Ok(s),
@@ -215,6 +215,27 @@ impl OptionalFromRequestParts<AppState> for CurrentUser {
     }
 }
 
+/// The current user, only if they are one of the configured admins
+#[derive(Debug)]
+pub struct Admin(pub Account);
+
+impl FromRequestParts<AppState> for Admin {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let CurrentUser(account) = CurrentUser::from_request_parts(parts, state).await?;
+
+        if !state.is_admin(&account) {
+            return Err(ApiError::unauthorized());
+        }
+
+        Ok(Self(account))
+    }
+}
+
 async fn extract_account_id(parts: &mut Parts, state: &AppState) -> ApiResult<Option<AccountId>> {
     if let Some(session) =
         <WebSession as OptionalFromRequestParts<AppState>>::from_request_parts(parts, state).await?
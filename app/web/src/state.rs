@@ -1,8 +1,10 @@
+use std::sync::Arc;
+
 use axum::extract::FromRef;
 use axum_extra::extract::cookie::Key;
-use oxidrive_accounts::AccountService;
+use oxidrive_accounts::{AccountService, account::Account};
 use oxidrive_authorization::Authorizer;
-use oxidrive_files::{Files, collection::Collections};
+use oxidrive_files::{Files, collection::Collections, integrity::Integrity, upload::Uploads};
 
 use crate::Config;
 
@@ -12,8 +14,12 @@ pub struct AppState {
     pub authorizer: Authorizer,
     pub files: Files,
     pub collections: Collections,
+    pub uploads: Uploads,
+    pub integrity: Integrity,
 
     key: Key,
+    #[from_ref(skip)]
+    admins: Arc<[String]>,
 }
 
 impl AppState {
@@ -23,13 +29,22 @@ impl AppState {
         authorizer: Authorizer,
         files: Files,
         collections: Collections,
+        uploads: Uploads,
+        integrity: Integrity,
     ) -> Self {
         Self {
             accounts,
             authorizer,
             files,
             collections,
+            uploads,
+            integrity,
             key: Key::from(cfg.secret_key.as_bytes()),
+            admins: cfg.admins.into(),
         }
     }
+
+    pub fn is_admin(&self, account: &Account) -> bool {
+        self.admins.contains(&account.username)
+    }
 }
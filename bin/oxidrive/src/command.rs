@@ -3,12 +3,17 @@ use oxidrive_accounts::AccountService;
 use oxidrive_database::Database;
 
 mod account;
+mod search;
+mod storage;
 
 #[derive(Debug, Subcommand)]
 pub enum Command {
     Migrate,
     CreateDefaultAdmin,
     Account(account::Args),
+    Storage(storage::Args),
+    /// List the files of an account matching a query, pointing at what is wrong with it if it is invalid
+    Search(search::Args),
     Server,
     Worker,
 }
@@ -37,6 +42,8 @@ impl Command {
                 Ok(())
             }
             Command::Account(cmd) => cmd.run(ctx, c).await,
+            Command::Storage(cmd) => cmd.run(ctx, c).await,
+            Command::Search(cmd) => cmd.run(ctx, c).await,
             Command::Server => unreachable!(),
             Command::Worker => {
                 todo!("workers")
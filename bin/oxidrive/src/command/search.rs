@@ -0,0 +1,55 @@
+use oxidrive_accounts::AccountService;
+use oxidrive_files::{Files, SearchError, file::Sort};
+use oxidrive_paginate::Paginate;
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    username: String,
+    /// The OxiQL query to search files for, e.g. `ext:pdf AND -(size>10MB)`
+    query: String,
+    /// How files are ordered, e.g. `size:desc`. A `sort:` directive in the query takes precedence.
+    /// Defaults to `relevance` when searching content, `name` otherwise
+    #[arg(long)]
+    sort: Option<Sort>,
+    /// The most files to list
+    #[arg(long, default_value_t = oxidrive_paginate::DEFAULT_LIMIT)]
+    limit: usize,
+}
+
+impl Args {
+    pub async fn run(
+        &self,
+        _ctx: app::context::Context,
+        c: &app::di::Container,
+    ) -> app::eyre::Result<()> {
+        let accounts = c.get::<AccountService>();
+        let files = c.get::<Files>();
+
+        let Some(account) = accounts.accounts().by_username(&self.username).await? else {
+            app::eyre::bail!("no account found by username {}", self.username);
+        };
+
+        let found = match files
+            .search(
+                account.id,
+                &self.query,
+                self.sort,
+                Paginate::first(self.limit),
+            )
+            .await
+        {
+            Ok(found) => found,
+            Err(SearchError::QueryParse(err)) => {
+                eprintln!("{:?}", miette::Report::new(err));
+                app::eyre::bail!("invalid query");
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        for file in found {
+            println!("{}\t{}", file.id, file.path());
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,173 @@
+use std::path::PathBuf;
+
+use bytesize::ByteSize;
+use clap::Subcommand;
+use oxidrive_accounts::AccountService;
+use oxidrive_files::{
+    Files, StorageConfig, file::FileStorage, integrity::Integrity, lifecycle::Lifecycle,
+};
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    #[clap(subcommand)]
+    command: Command,
+}
+
+impl Args {
+    pub async fn run(
+        &self,
+        _ctx: app::context::Context,
+        c: &app::di::Container,
+    ) -> app::eyre::Result<()> {
+        match &self.command {
+            Command::Deduplicate => deduplicate(c).await,
+            Command::Verify(args) => verify(c, args).await,
+            Command::Quota(args) => quota(c, args).await,
+            Command::RotateKeys => rotate_keys(c).await,
+            Command::Migrate(args) => migrate(c, args).await,
+            Command::Lifecycle => lifecycle(c).await,
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Convert an existing store to the content addressed layout, moving content stored per file under its hash.
+    /// Requires `storage.layout` to be set to `content_addressed`
+    Deduplicate,
+    /// Check the content of every file against its hash, reporting files that are missing, corrupted or have no hash.
+    /// Exits with an error if any issue is found
+    Verify(Verify),
+    /// Set how much content an account can store, overriding the configured default quota
+    Quota(Quota),
+    /// Wrap the data key of every account with the current master key, after it was changed.
+    /// Requires the replaced keys to be listed in `storage.encryption.previous_master_keys`,
+    /// which can be removed once this completes
+    RotateKeys,
+    /// Copy the content of every file, versions included, from one storage backend to another,
+    /// verifying it once copied. Content already copied is skipped, so it can be run again if interrupted.
+    /// Point `storage` to the new backend once it completes
+    Migrate(Migrate),
+    /// Apply the lifecycle rules right away, moving the content of the files they match to their storage tier
+    Lifecycle,
+}
+
+#[derive(Debug, clap::Args)]
+struct Verify {
+    /// Give files without a hash the one of their current content, instead of reporting them
+    #[arg(long)]
+    backfill: bool,
+}
+
+#[derive(Debug, clap::Args)]
+struct Quota {
+    username: String,
+    /// The most content the account can store, e.g. `10GB`. Omit it to go back to the configured default
+    size: Option<ByteSize>,
+}
+
+#[derive(Debug, clap::Args)]
+struct Migrate {
+    /// The configuration file whose `storage` section describes where content is copied from
+    #[arg(long)]
+    from: PathBuf,
+    /// The configuration file whose `storage` section describes where content is copied to
+    #[arg(long)]
+    to: PathBuf,
+}
+
+async fn deduplicate(c: &app::di::Container) -> app::eyre::Result<()> {
+    let files = c.get::<Files>();
+
+    let moved = files.deduplicate().await?;
+
+    tracing::info!(moved, "file content deduplicated");
+
+    Ok(())
+}
+
+async fn verify(c: &app::di::Container, Verify { backfill }: &Verify) -> app::eyre::Result<()> {
+    let integrity = c.get::<Integrity>();
+
+    let check = integrity.verify(*backfill).await?;
+
+    if !check.issues.is_empty() {
+        app::eyre::bail!(
+            "{} of {} files failed the integrity check",
+            check.issues.len(),
+            check.checked
+        );
+    }
+
+    Ok(())
+}
+
+async fn quota(c: &app::di::Container, Quota { username, size }: &Quota) -> app::eyre::Result<()> {
+    let accounts = c.get::<AccountService>();
+    let files = c.get::<Files>();
+
+    let Some(account) = accounts.accounts().by_username(username).await? else {
+        app::eyre::bail!("no account found by username {username}");
+    };
+
+    files
+        .set_quota(account.id, size.map(|size| size.as_u64()))
+        .await?;
+
+    let usage = files.usage(account.id).await?;
+
+    tracing::info!(
+        id = %account.id,
+        username,
+        used = usage.used,
+        quota = usage.quota,
+        "account quota set",
+    );
+
+    Ok(())
+}
+
+async fn rotate_keys(c: &app::di::Container) -> app::eyre::Result<()> {
+    let storage = c.get::<FileStorage>();
+
+    let rotated = storage.rotate_keys().await?;
+
+    tracing::info!(rotated, "data keys wrapped with the current master key");
+
+    Ok(())
+}
+
+async fn migrate(c: &app::di::Container, Migrate { from, to }: &Migrate) -> app::eyre::Result<()> {
+    let files = c.get::<Files>();
+    let storage = c.get::<FileStorage>();
+
+    let from: StorageConfig = oxidrive_config::load_section(from, "storage")?;
+    let to: StorageConfig = oxidrive_config::load_section(to, "storage")?;
+
+    // both backends are read and written the same way as the configured storage, encryption and compression included
+    let source = storage.with_backend(from.into());
+    let target = storage.with_backend(to.into());
+
+    let report = files.migrate_storage(&source, &target).await?;
+
+    if report.corrupted > 0 {
+        app::eyre::bail!(
+            "{} objects did not match their hash once copied, run the migration again to retry them",
+            report.corrupted
+        );
+    }
+
+    Ok(())
+}
+
+async fn lifecycle(c: &app::di::Container) -> app::eyre::Result<()> {
+    let lifecycle = c.get::<Lifecycle>();
+
+    if !lifecycle.has_rules() {
+        app::eyre::bail!("no lifecycle rules are configured in `storage.lifecycle.rules`");
+    }
+
+    lifecycle.apply().await?;
+
+    Ok(())
+}
@@ -43,3 +43,20 @@ where
         Ok(cfg)
     }
 }
+
+/// Loads a single section of a configuration file, e.g. `storage`.
+/// Unlike [Config::load_from], environment variables are ignored, so that several files can be loaded side by side
+pub fn load_section<T>(path: impl AsRef<Path>, section: &str) -> eyre::Result<T>
+where
+    T: for<'a> Deserialize<'a>,
+{
+    let path = path.as_ref();
+
+    let cfg = Figment::new()
+        .merge(Toml::file(path.with_extension("toml")))
+        .merge(Yaml::file(path.with_extension("yaml")))
+        .merge(Yaml::file(path.with_extension("yml")))
+        .extract_inner(section)?;
+
+    Ok(cfg)
+}
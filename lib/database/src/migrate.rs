@@ -16,3 +16,22 @@ pub async fn migrate(_ctx: app::context::Context, db: &Database) -> app::eyre::R
     tracing::debug!("database migrated successfully");
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[sqlx::test(migrations = false)]
+    async fn it_runs_sqlite_migrations(pool: sqlx::SqlitePool) {
+        SQLITE_MIGRATOR.run(&pool).await.unwrap();
+
+        for table in ["files", "file_versions"] {
+            sqlx::query(&format!("select compressed, encrypted from {table}"))
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        }
+
+        SQLITE_MIGRATOR.undo(&pool, 0).await.unwrap();
+    }
+}
@@ -1,4 +1,7 @@
+use std::fmt::Display;
+
 use oxidrive_paginate::{Paginate, Slice};
+use sqlx::types::Uuid;
 
 pub fn to_slice<T, F>(items: Vec<T>, cursor: F, paginate: &Paginate) -> Slice<T>
 where
@@ -13,10 +16,122 @@ where
     }
 }
 
+/// Like [to_slice], for rows loaded with `push_sorted_query`, which come in reverse order when paginating backward
+pub fn to_sorted_slice<T, F>(mut items: Vec<T>, cursor: F, paginate: &Paginate) -> Slice<T>
+where
+    F: FnOnce(&T) -> String,
+{
+    if paginate.is_forward() {
+        let next = items.last().map(cursor);
+        Slice::new(items, next, None)
+    } else {
+        items.reverse();
+        let previous = items.first().map(cursor);
+        Slice::new(items, None, previous)
+    }
+}
+
+/// An SQL expression whose values are bound as parameters instead of being written into it
+#[derive(Clone, Debug, Default)]
+pub struct Expression(Vec<Fragment>);
+
+#[derive(Clone, Debug)]
+enum Fragment {
+    Sql(String),
+    Bind(String),
+}
+
+impl Expression {
+    pub fn new(sql: impl Into<String>) -> Self {
+        Self(vec![Fragment::Sql(sql.into())])
+    }
+
+    pub fn push(&mut self, sql: impl Into<String>) -> &mut Self {
+        self.0.push(Fragment::Sql(sql.into()));
+        self
+    }
+
+    pub fn push_bind(&mut self, value: impl Into<String>) -> &mut Self {
+        self.0.push(Fragment::Bind(value.into()));
+        self
+    }
+}
+
+/// Orders rows by an expression, then by their id so that rows sharing the same value keep a stable order.
+/// Cursors carry the id of a row and the value it is sorted by, so that pages resume right after its position
+#[derive(Clone, Copy, Debug)]
+pub struct OrderBy<'a> {
+    /// The SQL expression rows are sorted by
+    pub by: &'a Expression,
+    /// The SQL type of the expression, which cursor values are cast back to
+    pub kind: &'a str,
+    pub descending: bool,
+}
+
+impl OrderBy<'_> {
+    /// How rows compare to the cursor and in which direction they are loaded.
+    /// Backward pages are loaded in reverse, starting from the cursor
+    fn keyset(&self, paginate: &Paginate) -> (&'static str, &'static str) {
+        match (paginate.is_forward(), self.descending) {
+            (true, false) | (false, true) => (">", "asc"),
+            (true, true) | (false, false) => ("<", "desc"),
+        }
+    }
+}
+
+/// The position of a row in a sorted query: its id and the value it is sorted by, as text
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SortCursor {
+    pub id: Uuid,
+    pub value: String,
+}
+
+impl SortCursor {
+    pub fn new(id: Uuid, value: impl Into<String>) -> Self {
+        Self {
+            id,
+            value: value.into(),
+        }
+    }
+
+    /// The cursor pages resume from, or none when starting from the first or last row
+    pub fn parse(paginate: &Paginate) -> Result<Option<Self>, InvalidCursor> {
+        let (cursor, _) = cursor_and_limit(paginate);
+        if cursor.is_empty() {
+            return Ok(None);
+        }
+
+        cursor
+            .split_once(':')
+            .and_then(|(id, value)| Some(Self::new(id.parse().ok()?, value)))
+            .map(Some)
+            .ok_or_else(|| InvalidCursor(cursor.clone()))
+    }
+}
+
+impl Display for SortCursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.id, self.value)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("invalid cursor '{0}'")]
+pub struct InvalidCursor(pub String);
+
+fn cursor_and_limit(paginate: &Paginate) -> (&String, i64) {
+    match paginate {
+        Paginate::Forward { after, first } => (after, *first as i64),
+        Paginate::Backward { before, last } => (before, *last as i64),
+    }
+}
+
 pub mod postgres {
     use oxidrive_paginate::Paginate;
     use sqlx::{Postgres, QueryBuilder, types::Uuid};
 
+    use super::{Expression, Fragment, InvalidCursor, OrderBy, SortCursor};
+
     pub fn push_query<'a>(
         qb: &mut QueryBuilder<'a, Postgres>,
         paginate: &'a Paginate,
@@ -54,12 +169,57 @@ pub mod postgres {
             }
         }
     }
+
+    /// Selects the value rows are sorted by as `sort_key`, to build their cursor
+    pub fn push_sort_key(qb: &mut QueryBuilder<'_, Postgres>, order: OrderBy<'_>) {
+        qb.push("cast(");
+        push_expression(qb, order.by);
+        qb.push(" as text) as sort_key");
+    }
+
+    pub fn push_sorted_query(
+        qb: &mut QueryBuilder<'_, Postgres>,
+        paginate: &Paginate,
+        order: OrderBy<'_>,
+    ) -> Result<(), InvalidCursor> {
+        let OrderBy { by, kind, .. } = order;
+        let (cmp, direction) = order.keyset(paginate);
+        let (_, limit) = super::cursor_and_limit(paginate);
+
+        if let Some(SortCursor { id, value }) = SortCursor::parse(paginate)? {
+            qb.push(" and (");
+            push_expression(qb, by);
+            qb.push(format!(", id) {cmp} (cast("))
+                .push_bind(value)
+                .push(format!(" as {kind}), "))
+                .push_bind(id)
+                .push(")");
+        }
+
+        qb.push(" order by ");
+        push_expression(qb, by);
+        qb.push(format!(" {direction}, id {direction} limit "))
+            .push_bind(limit);
+
+        Ok(())
+    }
+
+    fn push_expression(qb: &mut QueryBuilder<'_, Postgres>, expression: &Expression) {
+        for fragment in &expression.0 {
+            match fragment {
+                Fragment::Sql(sql) => qb.push(sql),
+                Fragment::Bind(value) => qb.push_bind(value.clone()),
+            };
+        }
+    }
 }
 
 pub mod sqlite {
     use oxidrive_paginate::Paginate;
     use sqlx::{QueryBuilder, Sqlite, types::Uuid};
 
+    use super::{Expression, Fragment, InvalidCursor, OrderBy, SortCursor};
+
     pub fn push_query<'a>(
         qb: &mut QueryBuilder<'a, Sqlite>,
         paginate: &'a Paginate,
@@ -97,4 +257,53 @@ pub mod sqlite {
             }
         }
     }
+
+    /// Selects the value rows are sorted by as `sort_key`, to build their cursor.
+    /// Real numbers are printed with enough digits to be read back exactly
+    pub fn push_sort_key(qb: &mut QueryBuilder<'_, Sqlite>, order: OrderBy<'_>) {
+        let (open, close) = match order.kind {
+            "real" => ("printf('%.17g', ", ")"),
+            _ => ("cast(", " as text)"),
+        };
+
+        qb.push(open);
+        push_expression(qb, order.by);
+        qb.push(format!("{close} as sort_key"));
+    }
+
+    pub fn push_sorted_query(
+        qb: &mut QueryBuilder<'_, Sqlite>,
+        paginate: &Paginate,
+        order: OrderBy<'_>,
+    ) -> Result<(), InvalidCursor> {
+        let OrderBy { by, kind, .. } = order;
+        let (cmp, direction) = order.keyset(paginate);
+        let (_, limit) = super::cursor_and_limit(paginate);
+
+        if let Some(SortCursor { id, value }) = SortCursor::parse(paginate)? {
+            qb.push(" and (");
+            push_expression(qb, by);
+            qb.push(format!(", id) {cmp} (cast("))
+                .push_bind(value)
+                .push(format!(" as {kind}), "))
+                .push_bind(id.to_string())
+                .push(")");
+        }
+
+        qb.push(" order by ");
+        push_expression(qb, by);
+        qb.push(format!(" {direction}, id {direction} limit "))
+            .push_bind(limit);
+
+        Ok(())
+    }
+
+    fn push_expression(qb: &mut QueryBuilder<'_, Sqlite>, expression: &Expression) {
+        for fragment in &expression.0 {
+            match fragment {
+                Fragment::Sql(sql) => qb.push(sql),
+                Fragment::Bind(value) => qb.push_bind(value.clone()),
+            };
+        }
+    }
 }
@@ -0,0 +1,8 @@
+drop index idx_files_deleted_at;
+drop index idx_files_owner_name;
+
+delete from files where deleted_at is not null;
+
+alter table files add constraint files_owner_id_name_key unique (owner_id, name);
+
+alter table files drop column deleted_at;
@@ -0,0 +1,7 @@
+alter table files add column deleted_at timestamptz;
+
+alter table files drop constraint files_owner_id_name_key;
+
+create unique index idx_files_owner_name on files (owner_id, name) where deleted_at is null;
+
+create index idx_files_deleted_at on files (deleted_at) where deleted_at is not null;
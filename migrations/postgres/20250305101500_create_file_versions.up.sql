@@ -0,0 +1,9 @@
+create table file_versions (
+    file_id uuid not null references files(id) on delete cascade,
+    number integer not null,
+    content_type text not null,
+    size bigint not null,
+    hash bytea,
+    created_at timestamptz not null,
+    primary key (file_id, number)
+);
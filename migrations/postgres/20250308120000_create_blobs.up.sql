@@ -0,0 +1,5 @@
+create table blobs (
+    hash bytea primary key,
+    size bigint not null,
+    refcount bigint not null check (refcount >= 0)
+);
@@ -0,0 +1,10 @@
+create table uploads (
+    id uuid primary key,
+    owner_id uuid not null references accounts(id) on delete cascade,
+    file_name text not null,
+    length bigint not null,
+    current_offset bigint not null default 0,
+    expires_at timestamptz not null
+);
+
+create index idx_uploads_expires_at on uploads (expires_at);
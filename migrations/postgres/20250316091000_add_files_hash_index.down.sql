@@ -0,0 +1 @@
+drop index idx_files_owner_hash;
@@ -0,0 +1,10 @@
+create table integrity_checks (
+    id uuid primary key,
+    started_at timestamptz not null,
+    finished_at timestamptz,
+    checked bigint not null default 0,
+    backfilled bigint not null default 0,
+    issues jsonb not null default '[]'
+);
+
+create index idx_integrity_checks_started_at on integrity_checks (started_at);
@@ -0,0 +1,14 @@
+create table account_usage (
+    account_id uuid primary key references accounts(id) on delete cascade,
+    used bigint not null default 0,
+    quota bigint
+);
+
+insert into account_usage (account_id, used)
+select owner_id, sum(size)
+from (
+    select owner_id, size from files
+    union all
+    select f.owner_id, v.size from file_versions v join files f on f.id = v.file_id
+) stored
+group by owner_id;
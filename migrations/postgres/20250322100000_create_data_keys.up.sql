@@ -0,0 +1,7 @@
+create table data_keys (
+    account_id uuid primary key references accounts(id) on delete cascade,
+    master_key_id text not null,
+    ciphertext bytea not null
+);
+
+create index data_keys_master_key_id_idx on data_keys(master_key_id);
@@ -0,0 +1,2 @@
+alter table file_versions drop column stored_size;
+alter table files drop column stored_size;
@@ -0,0 +1,2 @@
+-- content moved out of the default storage backend by a lifecycle rule records the tier holding it
+alter table files add column tier text;
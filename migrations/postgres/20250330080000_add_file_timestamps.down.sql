@@ -0,0 +1,7 @@
+drop index idx_files_owner_updated_at;
+
+update files set tags = delete(tags, array['created', 'updated', 'accessed']);
+
+alter table files drop column accessed_at;
+alter table files drop column updated_at;
+alter table files drop column created_at;
@@ -0,0 +1,15 @@
+-- files uploaded before timestamps were tracked count as created and updated now
+alter table files add column created_at timestamptz not null default date_trunc('second', now());
+alter table files add column updated_at timestamptz not null default date_trunc('second', now());
+-- only set once the content is downloaded
+alter table files add column accessed_at timestamptz;
+
+update files set tags = tags || hstore(
+    array['created', 'updated'],
+    array[
+        to_char(created_at at time zone 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS"Z"'),
+        to_char(updated_at at time zone 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS"Z"')
+    ]
+);
+
+create index idx_files_owner_updated_at on files (owner_id, updated_at) where deleted_at is null;
@@ -0,0 +1,10 @@
+-- words extracted from the content of files, for full-text search.
+-- The `simple` configuration neither stems nor drops stop words, as files can be in any language
+create table file_texts (
+    file_id uuid primary key references files(id) on delete cascade,
+    -- the hash of the content the words were extracted from, so that it is only indexed again once it changes
+    hash bytea,
+    content tsvector not null
+);
+
+create index idx_file_texts_content on file_texts using gin (content);
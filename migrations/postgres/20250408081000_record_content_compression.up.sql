@@ -0,0 +1,4 @@
+-- whether content was compressed before being stored, so that it is never guessed from the stored bytes
+alter table files add column compressed boolean not null default false;
+
+alter table file_versions add column compressed boolean not null default false;
@@ -0,0 +1,29 @@
+-- no-transaction
+
+pragma foreign_keys = off;
+
+begin;
+
+create table files_old (
+    id text primary key not null,
+    owner_id text not null,
+    name text not null,
+    content_type text not null,
+    size integer not null default 0,
+    tags text not null default '{}',
+    hash blob,
+    unique(owner_id, name),
+    foreign key (owner_id) references accounts(id)
+) strict;
+
+insert into files_old (id, owner_id, name, content_type, size, tags, hash)
+select id, owner_id, name, content_type, size, tags, hash from files
+where deleted_at is null;
+
+drop table files;
+
+alter table files_old rename to files;
+
+commit;
+
+pragma foreign_keys = on;
@@ -0,0 +1,34 @@
+-- no-transaction
+
+-- SQLite cannot drop a table constraint in place, so the table is rebuilt
+-- following https://www.sqlite.org/lang_altertable.html#otheralter
+pragma foreign_keys = off;
+
+begin;
+
+create table files_new (
+    id text primary key not null,
+    owner_id text not null,
+    name text not null,
+    content_type text not null,
+    size integer not null default 0,
+    tags text not null default '{}',
+    hash blob,
+    deleted_at text,
+    foreign key (owner_id) references accounts(id)
+) strict;
+
+insert into files_new (id, owner_id, name, content_type, size, tags, hash)
+select id, owner_id, name, content_type, size, tags, hash from files;
+
+drop table files;
+
+alter table files_new rename to files;
+
+create unique index idx_files_owner_name on files (owner_id, name) where deleted_at is null;
+
+create index idx_files_deleted_at on files (deleted_at) where deleted_at is not null;
+
+commit;
+
+pragma foreign_keys = on;
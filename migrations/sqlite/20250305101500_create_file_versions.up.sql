@@ -0,0 +1,10 @@
+create table file_versions (
+    file_id text not null,
+    number integer not null,
+    content_type text not null,
+    size integer not null,
+    hash blob,
+    created_at text not null,
+    foreign key (file_id) references files(id) on delete cascade,
+    primary key (file_id, number)
+) strict;
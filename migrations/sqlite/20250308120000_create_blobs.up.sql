@@ -0,0 +1,5 @@
+create table blobs (
+    hash blob not null primary key,
+    size integer not null,
+    refcount integer not null check (refcount >= 0)
+) strict;
@@ -0,0 +1,2 @@
+drop index idx_uploads_expires_at;
+drop table uploads;
@@ -0,0 +1,11 @@
+create table uploads (
+    id text not null primary key,
+    owner_id text not null,
+    file_name text not null,
+    length integer not null,
+    current_offset integer not null default 0,
+    expires_at text not null,
+    foreign key (owner_id) references accounts(id) on delete cascade
+) strict;
+
+create index idx_uploads_expires_at on uploads (expires_at);
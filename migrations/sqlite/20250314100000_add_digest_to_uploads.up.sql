@@ -0,0 +1 @@
+alter table uploads add column digest text;
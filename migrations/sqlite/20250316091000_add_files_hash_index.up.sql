@@ -0,0 +1 @@
+create index idx_files_owner_hash on files (owner_id, hash) where deleted_at is null;
@@ -0,0 +1,2 @@
+drop index idx_integrity_checks_started_at;
+drop table integrity_checks;
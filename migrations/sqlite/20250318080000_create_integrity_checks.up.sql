@@ -0,0 +1,10 @@
+create table integrity_checks (
+    id text not null primary key,
+    started_at text not null,
+    finished_at text,
+    checked integer not null default 0,
+    backfilled integer not null default 0,
+    issues text not null default '[]'
+) strict;
+
+create index idx_integrity_checks_started_at on integrity_checks (started_at);
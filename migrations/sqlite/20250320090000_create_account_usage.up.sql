@@ -0,0 +1,15 @@
+create table account_usage (
+    account_id text not null primary key,
+    used integer not null default 0,
+    quota integer,
+    foreign key (account_id) references accounts(id) on delete cascade
+) strict;
+
+insert into account_usage (account_id, used)
+select owner_id, sum(size)
+from (
+    select owner_id, size from files
+    union all
+    select f.owner_id, v.size from file_versions v join files f on f.id = v.file_id
+) stored
+group by owner_id;
@@ -0,0 +1,8 @@
+create table data_keys (
+    account_id text not null primary key,
+    master_key_id text not null,
+    ciphertext blob not null,
+    foreign key (account_id) references accounts(id) on delete cascade
+) strict;
+
+create index data_keys_master_key_id_idx on data_keys(master_key_id);
@@ -0,0 +1,5 @@
+alter table files add column stored_size integer not null default 0;
+update files set stored_size = size;
+
+alter table file_versions add column stored_size integer not null default 0;
+update file_versions set stored_size = size;
@@ -0,0 +1 @@
+alter table files drop column tier;
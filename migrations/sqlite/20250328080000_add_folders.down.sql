@@ -0,0 +1,9 @@
+drop table folders;
+
+drop index idx_files_owner_folder_name;
+
+update files set tags = json_remove(tags, '$.dir', '$.path');
+
+alter table files drop column folder;
+
+create unique index idx_files_owner_name on files (owner_id, name) where deleted_at is null;
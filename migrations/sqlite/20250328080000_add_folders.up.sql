@@ -0,0 +1,19 @@
+-- files live in a folder, the root one unless uploaded with a path
+alter table files add column folder text not null default '/';
+
+drop index idx_files_owner_name;
+
+create unique index idx_files_owner_folder_name on files (owner_id, folder, name) where deleted_at is null;
+
+update files set tags = json_set(tags, '$.dir', '/', '$.path', '/' || name);
+
+-- folders are tracked on their own so that they can exist while empty
+create table folders (
+    owner_id text not null,
+    path text not null,
+    parent text not null,
+    primary key (owner_id, path),
+    foreign key (owner_id) references accounts(id) on delete cascade
+) strict;
+
+create index idx_folders_owner_parent on folders (owner_id, parent);
@@ -0,0 +1,14 @@
+-- SQLite only allows constant defaults when adding a column, so existing files are backfilled right after.
+-- Files uploaded before timestamps were tracked count as created and updated now
+alter table files add column created_at text not null default '1970-01-01T00:00:00Z';
+alter table files add column updated_at text not null default '1970-01-01T00:00:00Z';
+-- only set once the content is downloaded
+alter table files add column accessed_at text;
+
+update files set
+    created_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now'),
+    updated_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now');
+
+update files set tags = json_set(tags, '$.created', created_at, '$.updated', updated_at);
+
+create index idx_files_owner_updated_at on files (owner_id, updated_at) where deleted_at is null;
@@ -0,0 +1,4 @@
+-- text extracted from the content of files, for full-text search.
+-- FTS5 tables have no foreign keys, rows are removed along with their file.
+-- `hash` is the one of the content the text was extracted from, so that it is only indexed again once it changes
+create virtual table file_texts using fts5(file_id unindexed, hash unindexed, content, tokenize = 'unicode61 remove_diacritics 2');
@@ -0,0 +1,2 @@
+alter table file_versions drop column encrypted;
+alter table files drop column encrypted;
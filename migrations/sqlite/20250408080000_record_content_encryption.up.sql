@@ -0,0 +1,4 @@
+-- whether content was sealed with the data key of its owner, so that it is never guessed from the stored bytes
+alter table files add column encrypted integer not null default 0;
+
+alter table file_versions add column encrypted integer not null default 0;
@@ -0,0 +1,2 @@
+alter table file_versions drop column compressed;
+alter table files drop column compressed;
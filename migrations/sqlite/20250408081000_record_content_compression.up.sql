@@ -0,0 +1,4 @@
+-- whether content was compressed before being stored, so that it is never guessed from the stored bytes
+alter table files add column compressed integer not null default 0;
+
+alter table file_versions add column compressed integer not null default 0;